@@ -0,0 +1,709 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use anyhow::{Context, Result};
+use futures_util::{stream, SinkExt, StreamExt};
+use http_body_util::{BodyExt, Full};
+use hyper::{header, Request as HttpRequest, Uri};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use prost::Message;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStream};
+use tonic::{
+    codegen::{BoxStream, Bytes},
+    Request, Response, Status,
+};
+
+use crate::transcoder::{RpcError, Transcoder};
+
+/// Stream of responses returned by server streaming rpcs
+pub type ResponseStream<T> = BoxStream<T>;
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Serves the generated gRPC services by forwarding each call to a JSON-RPC node over HTTP.
+///
+/// The services are implemented for this type by the `bridge.rs` file `generate --bridge`
+/// writes. Requests are converted to the params of their method, by name or by position as its
+/// `paramStructure` asks, and the result or JSON-RPC error of the node is converted back into
+/// the response message. Subscriptions are made over a WebSocket connection of their own.
+#[derive(Clone)]
+pub struct JsonRpcBridge {
+    inner: Arc<Inner>,
+    ws_endpoint: Uri,
+}
+
+struct Inner {
+    endpoint: Uri,
+    transcoder: Transcoder,
+    client: Client<HttpConnector, Full<Bytes>>,
+    next_id: AtomicU64,
+}
+
+/// JSON-RPC response object
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+impl JsonRpcBridge {
+    /// Bridge to the node at the `http://` URL `endpoint`, whose methods `transcoder` converts.
+    /// Subscriptions go to the same URL with the `ws://` scheme.
+    pub fn new(endpoint: &str, transcoder: Transcoder) -> Result<Self> {
+        let endpoint: Uri =
+            endpoint.parse().with_context(|| format!("invalid URL {}", endpoint))?;
+        if endpoint.scheme_str() != Some("http") {
+            anyhow::bail!("only http:// endpoints are supported, got {}", endpoint);
+        }
+        let ws_endpoint = format!("ws{}", &endpoint.to_string()["http".len()..]).parse()?;
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        Ok(Self {
+            inner: Arc::new(Inner {
+                endpoint,
+                transcoder,
+                client,
+                next_id: AtomicU64::new(1),
+            }),
+            ws_endpoint,
+        })
+    }
+
+    /// Makes subscriptions to the `ws://` URL `endpoint` instead
+    pub fn with_ws_endpoint(mut self, endpoint: &str) -> Result<Self> {
+        let endpoint: Uri =
+            endpoint.parse().with_context(|| format!("invalid URL {}", endpoint))?;
+        if endpoint.scheme_str() != Some("ws") {
+            anyhow::bail!("only ws:// endpoints are supported, got {}", endpoint);
+        }
+        self.ws_endpoint = endpoint;
+        Ok(self)
+    }
+
+    pub fn transcoder(&self) -> &Transcoder {
+        &self.inner.transcoder
+    }
+
+    /// Calls `method` with the params of `request` and converts its result or error into the
+    /// response message
+    pub async fn call<Req, Resp>(
+        &self,
+        method: &str,
+        request: Request<Req>,
+    ) -> Result<Response<Resp>, Status>
+    where
+        Req: Message,
+        Resp: Message + Default,
+    {
+        let transcoder = self.transcoder();
+        let params = self.request_params(method, request)?;
+        let bytes = match self.send(method, params).await? {
+            Ok(result) => transcoder.encode_result(method, &result),
+            Err(error) => transcoder.encode_error(method, &error),
+        }
+        .map_err(|err| Status::internal(format!("invalid response from the node: {:#}", err)))?;
+        Ok(Response::new(decode_response(&bytes)?))
+    }
+
+    /// Server streaming variant of [`Self::call`], streaming the single response
+    pub async fn call_streaming<Req, Resp>(
+        &self,
+        method: &str,
+        request: Request<Req>,
+    ) -> Result<Response<ResponseStream<Resp>>, Status>
+    where
+        Req: Message,
+        Resp: Message + Default + 'static,
+    {
+        let response = self.call(method, request).await?.into_inner();
+        let stream: ResponseStream<Resp> = Box::pin(stream::iter([Ok(response)]));
+        Ok(Response::new(stream))
+    }
+
+    /// Subscribes to `method` over a new WebSocket connection, streaming the response carrying
+    /// the subscription id, or the error, followed by one for every notification. Dropping the
+    /// stream unsubscribes and closes the connection.
+    pub async fn subscribe<Req, Resp>(
+        &self,
+        method: &str,
+        request: Request<Req>,
+    ) -> Result<Response<ResponseStream<Resp>>, Status>
+    where
+        Req: Message,
+        Resp: Message + Default + 'static,
+    {
+        let params = self.request_params(method, request)?;
+        let (mut socket, _) = connect_async(self.ws_endpoint.to_string())
+            .await
+            .map_err(|err| Status::unavailable(format!("failed to reach the node: {}", err)))?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        send_json(&mut socket, &body).await?;
+
+        // Notifications can't come before the subscription id
+        let reply = loop {
+            match next_json(&mut socket).await? {
+                Some(reply) if reply.get("id") == Some(&json!(id)) => break reply,
+                Some(_) => continue,
+                None => return Err(Status::unavailable("the node closed the connection")),
+            }
+        };
+        let reply: JsonRpcResponse = serde_json::from_value(reply)
+            .map_err(|err| Status::unavailable(format!("invalid response: {}", err)))?;
+        let transcoder = self.transcoder();
+        let (bytes, subscription_id) = match (reply.result, reply.error) {
+            (_, Some(error)) => (transcoder.encode_error(method, &error), None),
+            (result, None) => {
+                let result = result.unwrap_or_default();
+                (transcoder.encode_result(method, &result), Some(result))
+            }
+        };
+        let response = bytes
+            .map_err(|err| Status::internal(format!("invalid response from the node: {:#}", err)))
+            .and_then(|bytes| decode_response::<Resp>(&bytes));
+
+        let (sender, receiver) = mpsc::channel(16);
+        let _ = sender.send(response).await;
+        if let Some(subscription_id) = subscription_id {
+            let bridge = self.clone();
+            let method = method.to_owned();
+            tokio::spawn(async move {
+                bridge.forward_notifications(&method, socket, subscription_id, sender).await
+            });
+        }
+        let stream = stream::unfold(receiver, |mut receiver| async move {
+            let item = receiver.recv().await?;
+            Some((item, receiver))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    /// Sends a JSON-RPC request for `method`, returning its result or error
+    pub async fn send(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Result<Value, RpcError>, Status> {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let request = HttpRequest::post(self.inner.endpoint.clone())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(body.to_string())))
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let response = self
+            .inner
+            .client
+            .request(request)
+            .await
+            .map_err(|err| Status::unavailable(format!("failed to reach the node: {}", err)))?;
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|err| Status::unavailable(format!("failed to read the response: {}", err)))?
+            .to_bytes();
+
+        let response: JsonRpcResponse = serde_json::from_slice(&body).map_err(|err| {
+            Status::unavailable(format!("the node answered with HTTP {}: {}", status, err))
+        })?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Ok(Err(error)),
+            (Some(result), None) => Ok(Ok(result)),
+            (None, None) => Ok(Ok(Value::Null)),
+        }
+    }
+
+    /// Forwards the notifications of the subscription `method` to `sender` until either side is
+    /// closed, unsubscribing if the stream was dropped
+    async fn forward_notifications<Resp: Message + Default>(
+        &self,
+        method: &str,
+        mut socket: WebSocket,
+        subscription_id: Value,
+        sender: mpsc::Sender<Result<Resp, Status>>,
+    ) {
+        loop {
+            let message = tokio::select! {
+                _ = sender.closed() => break,
+                message = next_json(&mut socket) => message,
+            };
+            let notification = match message {
+                Ok(Some(notification)) => notification,
+                Ok(None) => return,
+                Err(status) => {
+                    let _ = sender.send(Err(status)).await;
+                    return;
+                }
+            };
+            let (Some(name), Some(params)) =
+                (notification["method"].as_str(), notification.get("params"))
+            else {
+                continue;
+            };
+            if params["subscription_id"] != subscription_id
+                || !self.transcoder().notifications(method).any(|notification| notification == name)
+            {
+                continue;
+            }
+            let response = self
+                .transcoder()
+                .encode_notification(method, name, &params["result"])
+                .map_err(|err| Status::internal(format!("invalid notification: {:#}", err)))
+                .and_then(|bytes| decode_response(&bytes));
+            if sender.send(response).await.is_err() {
+                break;
+            }
+        }
+
+        let Some((namespace, _)) = method.split_once("_subscribe") else {
+            return;
+        };
+        let unsubscribe = format!("{}_unsubscribe", namespace);
+        let params = Map::from_iter([("subscription_id".to_owned(), subscription_id)]);
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let params = self.shape_params(&unsubscribe, params);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": unsubscribe, "params": params });
+        let _ = send_json(&mut socket, &body).await;
+        let _ = socket.close(None).await;
+    }
+
+    /// Params by name of `method` called with `request`, in the form its `paramStructure` asks
+    /// for
+    fn request_params<Req: Message>(
+        &self,
+        method: &str,
+        request: Request<Req>,
+    ) -> Result<Value, Status> {
+        let params = self
+            .transcoder()
+            .decode_params(method, &request.into_inner().encode_to_vec())
+            .map_err(|err| Status::invalid_argument(format!("{:#}", err)))?;
+        Ok(self.shape_params(method, params))
+    }
+
+    /// Params of `method` in the form its `paramStructure` asks for: an array for `by-position`,
+    /// an object otherwise. Absent optional params before a present one are passed as `null`.
+    fn shape_params(&self, method: &str, mut params: Map<String, Value>) -> Value {
+        let Some(spec_method) = self.transcoder().method(method) else {
+            return Value::Object(params);
+        };
+        if spec_method.param_structure.as_deref() != Some("by-position") {
+            return Value::Object(params);
+        }
+        let mut values = spec_method
+            .params
+            .iter()
+            .map(|param| params.remove(&param.name).unwrap_or(Value::Null))
+            .collect::<Vec<_>>();
+        while values.last() == Some(&Value::Null) {
+            values.pop();
+        }
+        Value::Array(values)
+    }
+}
+
+fn decode_response<Resp: Message + Default>(bytes: &[u8]) -> Result<Resp, Status> {
+    Resp::decode(bytes)
+        .map_err(|err| Status::internal(format!("invalid response message: {}", err)))
+}
+
+async fn send_json(socket: &mut WebSocket, value: &Value) -> Result<(), Status> {
+    socket
+        .send(tungstenite::Message::text(value.to_string()))
+        .await
+        .map_err(|err| Status::unavailable(format!("failed to send to the node: {}", err)))
+}
+
+/// Next JSON message of `socket`, `None` once the node closed it
+async fn next_json(socket: &mut WebSocket) -> Result<Option<Value>, Status> {
+    while let Some(message) = socket.next().await {
+        let message = message
+            .map_err(|err| Status::unavailable(format!("the connection failed: {}", err)))?;
+        let text = match message {
+            tungstenite::Message::Text(text) => text,
+            tungstenite::Message::Close(_) => return Ok(None),
+            _ => continue,
+        };
+        return serde_json::from_str(text.as_str())
+            .map(Some)
+            .map_err(|err| Status::unavailable(format!("invalid message: {}", err)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use hyper::{server::conn::http1, service::service_fn};
+    use hyper_util::rt::TokioIo;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{proto_gen::ProtoConfig, spec::Specification};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetStorageAtRequest {
+        #[prost(string, tag = "1")]
+        contract_address: String,
+        #[prost(string, tag = "2")]
+        key: String,
+        #[prost(string, optional, tag = "3")]
+        block_tag: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetStorageAtResponse {
+        #[prost(string, tag = "1")]
+        result: String,
+        #[prost(message, optional, tag = "2")]
+        error: Option<Error>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Error {
+        #[prost(int32, tag = "1")]
+        code: i32,
+        #[prost(string, tag = "2")]
+        message: String,
+        #[prost(string, optional, tag = "3")]
+        data: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct SubscribeNewHeadsResponse {
+        #[prost(string, tag = "1")]
+        result: String,
+        #[prost(message, optional, tag = "2")]
+        error: Option<Error>,
+        #[prost(oneof = "Notification", tags = "3, 4")]
+        notification: Option<Notification>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    enum Notification {
+        #[prost(message, tag = "3")]
+        NewHeads(BlockHeader),
+        #[prost(int64, tag = "4")]
+        Reorg(i64),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct BlockHeader {
+        #[prost(string, tag = "1")]
+        block_hash: String,
+        #[prost(int64, tag = "2")]
+        block_number: i64,
+    }
+
+    fn create_test_transcoder() -> Transcoder {
+        let specs: Specification = serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [
+                    {
+                        "name": "starknet_getStorageAt",
+                        "summary": "Storage value",
+                        "paramStructure": "by-position",
+                        "params": [
+                            {
+                                "name": "contract_address",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            { "name": "key", "required": true, "schema": { "type": "string" } },
+                            {
+                                "name": "block_tag",
+                                "required": false,
+                                "schema": { "type": "string" }
+                            }
+                        ],
+                        "result": { "name": "result", "schema": { "type": "string" } }
+                    },
+                    {
+                        "name": "starknet_subscribeNewHeads",
+                        "summary": "New block headers subscription",
+                        "params": [],
+                        "result": { "name": "subscription_id", "schema": { "type": "string" } }
+                    },
+                    {
+                        "name": "starknet_subscriptionNewHeads",
+                        "summary": "New block headers notification",
+                        "params": [
+                            {
+                                "name": "subscription_id",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            {
+                                "name": "result",
+                                "required": true,
+                                "schema": { "$ref": "#/components/schemas/BLOCK_HEADER" }
+                            }
+                        ]
+                    },
+                    {
+                        "name": "starknet_subscriptionReorg",
+                        "summary": "Reorganization notification",
+                        "params": [
+                            {
+                                "name": "subscription_id",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            { "name": "result", "required": true, "schema": { "type": "integer" } }
+                        ]
+                    },
+                    {
+                        "name": "starknet_unsubscribe",
+                        "summary": "Closes a subscription",
+                        "params": [
+                            {
+                                "name": "subscription_id",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            }
+                        ],
+                        "result": { "name": "result", "schema": { "type": "boolean" } }
+                    }
+                ],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "BLOCK_HEADER": {
+                            "type": "object",
+                            "properties": {
+                                "block_hash": { "type": "string" },
+                                "block_number": { "type": "integer" }
+                            },
+                            "required": ["block_hash", "block_number"]
+                        }
+                    },
+                    "errors": {}
+                }
+            }"##,
+        )
+        .unwrap();
+        Transcoder::for_spec(&specs, &ProtoConfig::new("v0_8_1")).unwrap()
+    }
+
+    /// JSON-RPC node on a local port answering every request with `reply`, along with the
+    /// requests it received
+    async fn mock_node(reply: fn(&Value) -> Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let received = received.clone();
+                let service = service_fn(move |request: hyper::Request<hyper::body::Incoming>| {
+                    let received = received.clone();
+                    async move {
+                        let body = request.into_body().collect().await?.to_bytes();
+                        let request: Value = serde_json::from_slice(&body).unwrap();
+                        let mut response = reply(&request);
+                        response["jsonrpc"] = json!("2.0");
+                        response["id"] = request["id"].clone();
+                        received.lock().unwrap().push(request);
+                        let body = Full::new(Bytes::from(response.to_string()));
+                        Ok::<_, hyper::Error>(hyper::Response::new(body))
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        (url, requests)
+    }
+
+    /// WebSocket JSON-RPC node on a local port sending the messages `reply` returns for each
+    /// request, the first one being its response, along with the requests it receives
+    async fn mock_ws_node(
+        reply: fn(&Value) -> Vec<Value>,
+    ) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    while let Some(Ok(tungstenite::Message::Text(text))) = socket.next().await {
+                        let request: Value = serde_json::from_str(text.as_str()).unwrap();
+                        let mut messages = reply(&request);
+                        messages[0]["jsonrpc"] = json!("2.0");
+                        messages[0]["id"] = request["id"].clone();
+                        let _ = sender.send(request);
+                        for message in messages {
+                            let message = tungstenite::Message::text(message.to_string());
+                            socket.send(message).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_forwarded_by_position() {
+        let (url, requests) = mock_node(|_| json!({ "result": "0x2a" })).await;
+        let bridge = JsonRpcBridge::new(&url, create_test_transcoder()).unwrap();
+
+        let request = GetStorageAtRequest {
+            contract_address: "0x1".to_owned(),
+            key: "0x2".to_owned(),
+            block_tag: None,
+        };
+        let response: GetStorageAtResponse = bridge
+            .call("starknet_getStorageAt", Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.result, "0x2a");
+        assert_eq!(response.error, None);
+
+        // Absent optional params are left out of the array
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["method"], "starknet_getStorageAt");
+        assert_eq!(requests[0]["params"], json!(["0x1", "0x2"]));
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc_errors_are_set_on_the_response() {
+        let (url, _) = mock_node(|_| {
+            json!({ "error": { "code": 20, "message": "Contract not found", "data": [1] } })
+        })
+        .await;
+        let bridge = JsonRpcBridge::new(&url, create_test_transcoder()).unwrap();
+
+        let request = GetStorageAtRequest {
+            contract_address: "0x1".to_owned(),
+            key: "0x2".to_owned(),
+            block_tag: Some("latest".to_owned()),
+        };
+        let response: GetStorageAtResponse = bridge
+            .call("starknet_getStorageAt", Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        let error = Error {
+            code: 20,
+            message: "Contract not found".to_owned(),
+            data: Some("[1]".to_owned()),
+        };
+        assert_eq!(response.error, Some(error));
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_stream_notifications_until_dropped() {
+        fn notification(method: &str, subscription_id: &str, result: Value) -> Value {
+            json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": { "subscription_id": subscription_id, "result": result },
+            })
+        }
+        let (url, mut requests) = mock_ws_node(|request| match request["method"].as_str() {
+            Some("starknet_subscribeNewHeads") => vec![
+                json!({ "result": "0x42" }),
+                notification("starknet_subscriptionNewHeads", "0x1", json!({})),
+                notification(
+                    "starknet_subscriptionNewHeads",
+                    "0x42",
+                    json!({ "block_hash": "0x7", "block_number": 7 }),
+                ),
+                notification("starknet_subscriptionReorg", "0x42", json!(3)),
+            ],
+            _ => vec![json!({ "result": true })],
+        })
+        .await;
+        let bridge = JsonRpcBridge::new(&url, create_test_transcoder()).unwrap();
+
+        let mut stream = bridge
+            .subscribe::<_, SubscribeNewHeadsResponse>(
+                "starknet_subscribeNewHeads",
+                Request::new(()),
+            )
+            .await
+            .unwrap()
+            .into_inner();
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(response.result, "0x42");
+        assert_eq!(response.notification, None);
+
+        // Notifications of other subscriptions are skipped
+        let header = BlockHeader {
+            block_hash: "0x7".to_owned(),
+            block_number: 7,
+        };
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(response.notification, Some(Notification::NewHeads(header)));
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(response.notification, Some(Notification::Reorg(3)));
+
+        drop(stream);
+        let subscribe = requests.recv().await.unwrap();
+        assert_eq!(subscribe["method"], "starknet_subscribeNewHeads");
+        let unsubscribe = requests.recv().await.unwrap();
+        assert_eq!(unsubscribe["method"], "starknet_unsubscribe");
+        assert_eq!(unsubscribe["params"], json!({ "subscription_id": "0x42" }));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_errors_end_the_stream() {
+        let (url, _) = mock_ws_node(|_| {
+            let error = json!({ "code": 68, "message": "Cannot go back more than 1024 blocks" });
+            vec![json!({ "error": error })]
+        })
+        .await;
+        let bridge = JsonRpcBridge::new(&url, create_test_transcoder()).unwrap();
+
+        let mut stream = bridge
+            .subscribe::<_, SubscribeNewHeadsResponse>(
+                "starknet_subscribeNewHeads",
+                Request::new(()),
+            )
+            .await
+            .unwrap()
+            .into_inner();
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(response.error.unwrap().code, 68);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_nodes_are_unavailable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let bridge = JsonRpcBridge::new(&url, create_test_transcoder()).unwrap();
+
+        let status = bridge
+            .call::<_, GetStorageAtResponse>(
+                "starknet_getStorageAt",
+                Request::new(GetStorageAtRequest::default()),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert!(JsonRpcBridge::new("https://example.com", create_test_transcoder()).is_err());
+        assert!(bridge.with_ws_endpoint("wss://example.com").is_err());
+    }
+}
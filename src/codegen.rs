@@ -0,0 +1,339 @@
+//! Generation from build scripts and other programs: a spec source, passes transforming the
+//! definitions resolved from it, and a backend rendering them into an output directory, the way
+//! the `generate` subcommand does.
+//!
+//! ```no_run
+//! use starknet_grpc_codegen::{codegen::passes::Rename, Builder, SpecVersion};
+//!
+//! let generated = Builder::bundled(SpecVersion::V0_8_1)
+//!     .with_pass(Rename::new("starknet.v0_8_1.common.BlockId", "BlockSelector"))
+//!     .with_output_dir(std::env::var("OUT_DIR").unwrap())
+//!     .generate()
+//!     .unwrap();
+//! for warning in &generated.warnings {
+//!     println!("cargo:warning={}", warning);
+//! }
+//! ```
+
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    profile::ProfileOptions,
+    proto_gen::{
+        backend::Backend,
+        descriptor::file_descriptor_set,
+        layout::{file_header, proto_sources, write_buf_files},
+        shared_types::SharedTypes,
+        validate::validate,
+        ProtoConfig, ProtoGenerator, ProtoIr,
+    },
+    source::{
+        bundled_profiles, GenerationProfile, RawExtension, RawSpecs, SpecVersion, MAIN_DOCUMENT,
+        TRACE_DOCUMENT, WRITE_DOCUMENT, WS_DOCUMENT,
+    },
+    spec::{ParseMode, Specification},
+};
+use passes::Pass;
+
+pub mod passes;
+
+/// Generates the files of a spec version into a directory
+pub struct Builder {
+    profile: GenerationProfile,
+    extensions: Vec<RawExtension>,
+    mode: ParseMode,
+    passes: Vec<Box<dyn Pass>>,
+    shared_types: Option<SharedTypes>,
+    /// Proto files with buf configurations when unset, like `generate --target proto`
+    backend: Option<Box<dyn Backend>>,
+    /// Backends rendering files next to the proto files, e.g. `BridgeBackend`
+    outputs: Vec<Box<dyn Backend>>,
+    descriptor_set: Option<PathBuf>,
+    check: bool,
+    force: bool,
+    output_dir: PathBuf,
+}
+
+/// What `Builder::generate` wrote
+#[derive(Debug)]
+pub struct Generated {
+    /// Directory of the spec version holding the files
+    pub dir: PathBuf,
+    /// Files written, without the buf configurations written next to proto files
+    pub files: Vec<PathBuf>,
+    /// Problems with the spec that didn't prevent generating, e.g. unknown keywords
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl Builder {
+    pub fn new(profile: GenerationProfile) -> Self {
+        Self {
+            profile,
+            extensions: vec![],
+            mode: ParseMode::Lenient,
+            passes: vec![],
+            shared_types: None,
+            backend: None,
+            outputs: vec![],
+            descriptor_set: None,
+            check: false,
+            force: false,
+            output_dir: PathBuf::from("proto"),
+        }
+    }
+
+    /// Generates from the spec documents of `version` bundled with the crate
+    pub fn bundled(version: SpecVersion) -> Self {
+        let profile = bundled_profiles()
+            .into_iter()
+            .find(|profile| profile.version == version)
+            .expect("Unable to find profile");
+        Self::new(profile)
+    }
+
+    /// Generates from the documents in `dir`, e.g. a patched copy of the spec of `version`, named
+    /// the way `$ref`s point to them. The WebSocket document is optional, as before 0.8.
+    pub fn from_dir(version: SpecVersion, dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let read = |document: &str| -> Result<Cow<'static, str>> {
+            let path = dir.join(document);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            Ok(Cow::Owned(content))
+        };
+        let raw_specs = RawSpecs {
+            main: read(MAIN_DOCUMENT)?,
+            write: read(WRITE_DOCUMENT)?,
+            trace: read(TRACE_DOCUMENT)?,
+            ws: match dir.join(WS_DOCUMENT).exists() {
+                true => Some(read(WS_DOCUMENT)?),
+                false => None,
+            },
+        };
+        let profile = Self::bundled(version).profile;
+        Ok(Self::new(GenerationProfile {
+            raw_specs,
+            ..profile
+        }))
+    }
+
+    /// Adds an extension document generating its own service and package
+    pub fn with_extension(mut self, extension: RawExtension) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// With `ParseMode::Strict`, keywords the generator doesn't understand fail instead of warning
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Adds a pass, applied after the ones added before it
+    pub fn with_pass(mut self, pass: impl Pass + 'static) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Imports the types of `shared_types` from their package instead of defining them, after
+    /// the passes. The file defining them is written by `write_workspace`.
+    pub fn with_shared_types(mut self, shared_types: SharedTypes) -> Self {
+        self.shared_types = Some(shared_types);
+        self
+    }
+
+    /// Renders the files with `backend` instead of writing proto files
+    pub fn with_backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Also writes the files `backend` renders next to the proto files, e.g. a `BridgeBackend`
+    pub fn with_output(mut self, backend: impl Backend + 'static) -> Self {
+        self.outputs.push(Box::new(backend));
+        self
+    }
+
+    /// Also writes the proto files as a binary `FileDescriptorSet` to `path`
+    pub fn with_descriptor_set(mut self, path: impl Into<PathBuf>) -> Self {
+        self.descriptor_set = Some(path.into());
+        self
+    }
+
+    /// Only validates the proto files, without writing anything
+    pub fn with_check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    /// Writes the proto files even if they fail validation, their problems becoming warnings
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Directory the directory of the spec version is created in, `proto` by default
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    /// Spec version the files are generated from
+    pub fn version(&self) -> SpecVersion {
+        self.profile.version
+    }
+
+    /// Configuration of the generated packages, e.g. to create a `RustSerdeBackend`
+    pub fn config(&self) -> ProtoConfig {
+        ProtoConfig::new(&self.profile.version.to_version_string())
+    }
+
+    /// Options of the profile, e.g. to create a `RustSerdeBackend`
+    pub fn options(&self) -> Result<ProfileOptions> {
+        ProfileOptions::parse(&self.profile.raw_options)
+    }
+
+    /// Parses the spec, with the extensions. Problems with it are reported to `diagnostics`;
+    /// `None` is returned if any of them is an error.
+    pub fn parse(&self, diagnostics: &mut Diagnostics) -> Option<Specification> {
+        self.profile
+            .raw_specs
+            .parse_full(&self.extensions, self.mode, diagnostics)
+    }
+
+    /// Resolves the definitions of `specs`, transformed by the passes and then the shared types
+    pub fn resolve_specs(&self, specs: &Specification) -> Result<ProtoIr> {
+        let mut ir = ProtoGenerator::new(self.config()).resolve(specs)?;
+        for pass in &self.passes {
+            pass.apply(specs, &mut ir)?;
+        }
+        if let Some(shared_types) = &self.shared_types {
+            shared_types.apply(&mut ir)?;
+        }
+        Ok(ir)
+    }
+
+    /// Parses the spec and resolves its definitions, transformed by the passes.
+    ///
+    /// Problems with the spec are reported to `diagnostics`; `None` is returned if any of them is
+    /// an error.
+    pub fn resolve(
+        &self,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<(Specification, ProtoIr)>> {
+        let Some(specs) = self.parse(diagnostics) else {
+            return Ok(None);
+        };
+        let ir = self.resolve_specs(&specs)?;
+        Ok(Some((specs, ir)))
+    }
+
+    /// Writes the files rendered from `ir`, resolved from `specs`, to `<output dir>/<version>`.
+    ///
+    /// Proto files are validated first, their problems being reported to `diagnostics`. `None`
+    /// is returned instead of writing invalid ones unless forced; when only checking, nothing is
+    /// written and the files are left out of what is returned.
+    pub fn write(
+        &self,
+        specs: &Specification,
+        ir: &ProtoIr,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Option<Generated>> {
+        let dir = self.output_dir.join(self.config().version);
+        let generated = |files| Generated {
+            dir: dir.clone(),
+            files,
+            warnings: vec![],
+        };
+        let mut files = match &self.backend {
+            Some(backend) => backend.render(specs, ir)?,
+            None => {
+                // Invalid output would only fail later in protoc, e.g. after passes broke it
+                let mut problems = Diagnostics::new();
+                let mut protos = ir.files.clone();
+                protos.extend(self.shared_types.iter().map(|shared| shared.file.clone()));
+                validate(&protos, &mut problems);
+                let invalid = problems.has_errors();
+                for mut problem in problems.iter().cloned() {
+                    if self.force {
+                        problem.severity = Severity::Warning;
+                    }
+                    diagnostics.push(problem);
+                }
+                if invalid && !self.force {
+                    return Ok(None);
+                }
+                if self.check {
+                    return Ok(Some(generated(vec![])));
+                }
+                proto_sources(specs, ir)?
+            }
+        };
+        if self.backend.is_none() {
+            for output in &self.outputs {
+                files.extend(output.render(specs, ir)?);
+            }
+        }
+
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let mut written = vec![];
+        for (filename, content) in files {
+            let path = dir.join(filename);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            written.push(path);
+        }
+        if self.backend.is_none() {
+            if let Some(path) = &self.descriptor_set {
+                // Source locations point into the written files, below their header and blank line
+                let set = file_descriptor_set(&ir.files, |file| {
+                    file_header(&file.name, specs).lines().count() + 1
+                })?;
+                fs::write(path, prost::Message::encode_to_vec(&set))
+                    .with_context(|| format!("failed to write descriptor set {}", path.display()))?;
+                written.push(path.clone());
+            }
+            write_buf_files(&dir, &ir.package_info)?;
+        }
+
+        Ok(Some(generated(written)))
+    }
+
+    /// Writes the files to `<output dir>/<version>`, e.g. `proto/v0_8_1`
+    pub fn generate(&self) -> Result<Generated> {
+        let mut diagnostics = Diagnostics::new();
+        let Some((specs, ir)) = self.resolve(&mut diagnostics)? else {
+            bail!("invalid specification:\n{}", errors(&diagnostics));
+        };
+        let mut problems = Diagnostics::new();
+        let Some(generated) = self.write(&specs, &ir, &mut problems)? else {
+            bail!("generated proto files are invalid:\n{}", errors(&problems));
+        };
+
+        Ok(Generated {
+            warnings: diagnostics.iter().chain(problems.iter()).cloned().collect(),
+            ..generated
+        })
+    }
+}
+
+/// Errors of `diagnostics`, one per line
+fn errors(diagnostics: &Diagnostics) -> String {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
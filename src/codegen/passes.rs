@@ -0,0 +1,385 @@
+//! Transformations of the definitions resolved from a spec, applied before a backend renders
+//! them. Types are named by their fully qualified name, e.g. `starknet.v0_8_1.common.BlockId`.
+
+use anyhow::{bail, Result};
+
+use crate::{
+    proto_gen::{
+        qualify, shared_types::SharedTypes, subset::Subset, ProtoField, ProtoFieldType, ProtoIr,
+        ProtoMessage,
+    },
+    spec::Specification,
+};
+
+/// Transformation of the definitions `ProtoGenerator` resolves from a spec. Closures taking the
+/// same arguments are passes too.
+pub trait Pass {
+    fn apply(&self, specs: &Specification, ir: &mut ProtoIr) -> Result<()>;
+}
+
+impl<F> Pass for F
+where
+    F: Fn(&Specification, &mut ProtoIr) -> Result<()>,
+{
+    fn apply(&self, specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        self(specs, ir)
+    }
+}
+
+/// Keeps the selected methods and services, and the definitions they use
+impl Pass for Subset {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        Subset::apply(self, ir)
+    }
+}
+
+/// Imports the types shared with other versions from their package instead of defining them
+impl Pass for SharedTypes {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        SharedTypes::apply(self, ir)
+    }
+}
+
+/// Renames a message or enum, along with the fields and rpcs referring to it
+pub struct Rename {
+    from: String,
+    to: String,
+}
+
+impl Rename {
+    /// Renames the type named `from` to `to`, in the same package
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+impl Pass for Rename {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        let Some((package, name)) = self.from.rsplit_once('.') else {
+            bail!("{} isn't a fully qualified name", self.from);
+        };
+        let renamed = qualify(package, &self.to);
+        if defining_file(ir, &renamed).is_some() {
+            bail!("cannot rename {} to {}, which is already defined", self.from, renamed);
+        }
+
+        let mut found = false;
+        for file in ir.files.iter_mut().filter(|file| file.package == package) {
+            let names = file
+                .messages
+                .iter_mut()
+                .map(|message| &mut message.name)
+                .chain(file.enums.iter_mut().map(|proto_enum| &mut proto_enum.name));
+            for definition in names.filter(|definition| *definition == name) {
+                *definition = self.to.clone();
+                found = true;
+            }
+        }
+        if !found {
+            bail!("no message or enum named {}", self.from);
+        }
+
+        for file in &mut ir.files {
+            let package = file.package.clone();
+            let rename = |reference: &mut String| {
+                if qualify(&package, reference) == self.from {
+                    // References keep their form, relative to their package or not
+                    *reference = match reference.contains('.') {
+                        true => renamed.clone(),
+                        false => self.to.clone(),
+                    };
+                }
+            };
+            for message in &mut file.messages {
+                for field in fields_mut(message) {
+                    if let ProtoFieldType::Message(reference) | ProtoFieldType::Enum(reference) =
+                        &mut field.field_type
+                    {
+                        rename(reference);
+                    }
+                }
+            }
+            for rpc in file.services.iter_mut().flat_map(|service| &mut service.rpcs) {
+                rename(&mut rpc.request_type);
+                rename(&mut rpc.response_type);
+            }
+        }
+
+        if let Some(schema) = ir.schemas.remove(&self.from) {
+            ir.schemas.insert(renamed, schema);
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the rpcs calling the JSON-RPC methods a predicate accepts. Their request and response
+/// messages are kept.
+pub struct FilterMethods {
+    keep: Box<dyn Fn(&str) -> bool>,
+}
+
+impl FilterMethods {
+    /// Keeps the rpcs whose JSON-RPC method, e.g. `starknet_getBlockWithTxHashes`, `keep` accepts
+    pub fn new(keep: impl Fn(&str) -> bool + 'static) -> Self {
+        Self {
+            keep: Box::new(keep),
+        }
+    }
+}
+
+impl Pass for FilterMethods {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        ir.methods.retain(|_, method| (self.keep)(method));
+        let methods = &ir.methods;
+        for file in &mut ir.files {
+            for service in &mut file.services {
+                let prefix = format!("/{}.{}/", file.package, service.name);
+                service
+                    .rpcs
+                    .retain(|rpc| methods.contains_key(&format!("{}{}", prefix, rpc.name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds a field to a message, e.g. one a proxy fills in along with the fields of the spec
+pub struct InjectField {
+    message: String,
+    field: ProtoField,
+}
+
+impl InjectField {
+    /// Adds `field` to `message`. A `number` of 0 is replaced with the first number after the
+    /// ones of the message.
+    pub fn new(message: impl Into<String>, field: ProtoField) -> Self {
+        Self {
+            message: message.into(),
+            field,
+        }
+    }
+}
+
+impl Pass for InjectField {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        let Some(index) = defining_file(ir, &self.message) else {
+            bail!("no message named {}", self.message);
+        };
+        let file = &mut ir.files[index];
+        let package = file.package.clone();
+        let Some(message) = file
+            .messages
+            .iter_mut()
+            .find(|message| qualify(&package, &message.name) == self.message)
+        else {
+            bail!("{} is an enum, not a message", self.message);
+        };
+
+        let mut field = self.field.clone();
+        let fields = fields_mut(message).map(|field| (field.name.clone(), field.number));
+        let (names, numbers): (Vec<_>, Vec<_>) = fields.unzip();
+        if names.contains(&field.name) {
+            bail!("{} already has a field named {}", self.message, field.name);
+        }
+        if field.number == 0 {
+            field.number = numbers.iter().max().map_or(1, |number| number + 1);
+        } else if numbers.contains(&field.number) {
+            bail!("{} already has a field numbered {}", self.message, field.number);
+        }
+        message.fields.push(field);
+
+        import_definition(ir, index, &self.field.field_type);
+        Ok(())
+    }
+}
+
+/// Changes the type of the fields of a type to another, e.g. a message wrapping a scalar to the
+/// scalar itself
+pub struct RemapType {
+    from: ProtoFieldType,
+    to: ProtoFieldType,
+}
+
+impl RemapType {
+    /// Fields of type `from` become of type `to`. A message or enum `to` is imported by the files
+    /// using it when one of the files defines it, as other imports are up to the backend.
+    pub fn new(from: ProtoFieldType, to: ProtoFieldType) -> Self {
+        Self { from, to }
+    }
+}
+
+impl Pass for RemapType {
+    fn apply(&self, _specs: &Specification, ir: &mut ProtoIr) -> Result<()> {
+        let mut remapped = vec![];
+        for (index, file) in ir.files.iter_mut().enumerate() {
+            let package = file.package.clone();
+            for message in &mut file.messages {
+                for field in fields_mut(message) {
+                    let field_type = match &field.field_type {
+                        ProtoFieldType::Message(name) => ProtoFieldType::Message(qualify(&package, name)),
+                        ProtoFieldType::Enum(name) => ProtoFieldType::Enum(qualify(&package, name)),
+                        field_type => field_type.clone(),
+                    };
+                    if field_type == self.from {
+                        field.field_type = self.to.clone();
+                        remapped.push(index);
+                    }
+                }
+            }
+        }
+        remapped.dedup();
+        for index in remapped {
+            import_definition(ir, index, &self.to);
+        }
+        Ok(())
+    }
+}
+
+/// Regular and oneof fields of `message`
+fn fields_mut(message: &mut ProtoMessage) -> impl Iterator<Item = &mut ProtoField> {
+    message
+        .fields
+        .iter_mut()
+        .chain(message.oneofs.iter_mut().flat_map(|oneof| &mut oneof.fields))
+}
+
+/// Index of the file defining the message or enum named `name`
+fn defining_file(ir: &ProtoIr, name: &str) -> Option<usize> {
+    ir.files.iter().position(|file| {
+        let mut names = file
+            .messages
+            .iter()
+            .map(|message| &message.name)
+            .chain(file.enums.iter().map(|proto_enum| &proto_enum.name));
+        names.any(|definition| qualify(&file.package, definition) == name)
+    })
+}
+
+/// Imports the file defining `field_type`, if any, into the file at `index`
+fn import_definition(ir: &mut ProtoIr, index: usize, field_type: &ProtoFieldType) {
+    let (ProtoFieldType::Message(name) | ProtoFieldType::Enum(name)) = field_type else {
+        return;
+    };
+    let name = qualify(&ir.files[index].package, name);
+    let Some(defining) = defining_file(ir, &name) else {
+        return;
+    };
+    let import = ir.files[defining].name.clone();
+    let file = &mut ir.files[index];
+    if defining != index && !file.imports.contains(&import) {
+        file.imports.push(import);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::{validate::validate, ProtoConfig, ProtoGenerator};
+    use crate::diagnostics::Diagnostics;
+
+    fn resolve() -> (Specification, ProtoIr) {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let ir = ProtoGenerator::new(ProtoConfig::new("v0_8_1")).resolve(&specs).unwrap();
+        (specs, ir)
+    }
+
+    fn message<'a>(ir: &'a ProtoIr, name: &str) -> &'a ProtoMessage {
+        let index = defining_file(ir, name).unwrap();
+        let file = &ir.files[index];
+        file.messages
+            .iter()
+            .find(|message| qualify(&file.package, &message.name) == name)
+            .unwrap()
+    }
+
+    fn assert_valid(ir: &ProtoIr) {
+        let mut problems = Diagnostics::new();
+        validate(&ir.files, &mut problems);
+        assert!(!problems.has_errors(), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_rename_updates_references() {
+        let (specs, mut ir) = resolve();
+        Rename::new("starknet.v0_8_1.common.BlockId", "BlockSelector")
+            .apply(&specs, &mut ir)
+            .unwrap();
+        assert_valid(&ir);
+
+        let request = message(&ir, "starknet.v0_8_1.main.GetBlockWithTxHashesRequest");
+        assert_eq!(
+            request.fields[0].field_type,
+            ProtoFieldType::Message("starknet.v0_8_1.common.BlockSelector".to_owned())
+        );
+        assert_eq!(ir.schemas["starknet.v0_8_1.common.BlockSelector"], "BLOCK_ID");
+        assert!(!ir.schemas.contains_key("starknet.v0_8_1.common.BlockId"));
+
+        // Renaming an rpc's request updates the rpc
+        Rename::new("starknet.v0_8_1.main.SpecVersionRequest", "VersionRequest")
+            .apply(&specs, &mut ir)
+            .unwrap();
+        let rpc = &ir.files[1].services[0].rpcs[0];
+        assert_eq!(rpc.request_type, "VersionRequest");
+
+        let error = Rename::new("starknet.v0_8_1.common.Felt", "BlockSelector").apply(&specs, &mut ir);
+        assert!(error.is_err());
+        assert!(Rename::new("starknet.v0_8_1.common.Missing", "Other").apply(&specs, &mut ir).is_err());
+    }
+
+    #[test]
+    fn test_filter_methods() {
+        let (specs, mut ir) = resolve();
+        FilterMethods::new(|method| method.starts_with("starknet_getBlock"))
+            .apply(&specs, &mut ir)
+            .unwrap();
+        assert_valid(&ir);
+
+        let rpcs = ir.files[1].services[0].rpcs.iter().map(|rpc| rpc.name.as_str()).collect::<Vec<_>>();
+        assert!(rpcs.contains(&"GetBlockWithTxHashes"));
+        assert!(!rpcs.contains(&"SpecVersion"));
+        assert!(ir.methods.values().all(|method| method.starts_with("starknet_getBlock")));
+    }
+
+    #[test]
+    fn test_inject_field() {
+        let (specs, mut ir) = resolve();
+        let field = ProtoField {
+            name: "reference_block".to_owned(),
+            field_type: ProtoFieldType::Message("starknet.v0_8_1.common.BlockId".to_owned()),
+            number: 0,
+            json_name: None,
+            comment: Some("Block the proxy answers from".to_owned()),
+            optional: true,
+            repeated: false,
+            oneof_name: None,
+        };
+        let name = "starknet.v0_8_1.main.GetBlockWithTxHashesRequest";
+        let inject = InjectField::new(name, field);
+        inject.apply(&specs, &mut ir).unwrap();
+        assert_valid(&ir);
+
+        let injected = message(&ir, name).fields.last().unwrap();
+        assert_eq!((injected.name.as_str(), injected.number), ("reference_block", 2));
+        // Injected twice, the field clashes with itself
+        assert!(inject.apply(&specs, &mut ir).is_err());
+    }
+
+    #[test]
+    fn test_remap_type() {
+        let (specs, mut ir) = resolve();
+        RemapType::new(
+            ProtoFieldType::Message("starknet.v0_8_1.common.BlockId".to_owned()),
+            ProtoFieldType::String,
+        )
+        .apply(&specs, &mut ir)
+        .unwrap();
+        assert_valid(&ir);
+
+        let request = message(&ir, "starknet.v0_8_1.main.GetBlockWithTxHashesRequest");
+        assert_eq!(request.fields[0].field_type, ProtoFieldType::String);
+    }
+}
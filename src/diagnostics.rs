@@ -0,0 +1,237 @@
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Problem found while processing the input documents, located by file and JSON pointer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Short kebab-case identifier of the kind of problem, e.g. `dangling-ref`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Spec version being processed when the problem was found, in runs over several versions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Diagnostics collected over a whole run instead of failing on the first problem
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Error caused by the command line rather than by the spec or a bug, e.g. an option that doesn't
+/// apply along with the others. The CLI exits with the usage exit code for it, like clap does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageError(pub String);
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: None,
+            message: message.into(),
+            document: None,
+            path: None,
+            version: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    pub fn code(self, code: &str) -> Self {
+        Self {
+            code: Some(code.to_owned()),
+            ..self
+        }
+    }
+
+    /// Locates the diagnostic at `path` (a JSON pointer fragment like `#/methods/0`) of `document`.
+    pub fn at(self, document: &str, path: &str) -> Self {
+        Self {
+            document: Some(document.to_owned()),
+            path: Some(path.to_owned()),
+            ..self
+        }
+    }
+
+    /// Locates the diagnostic in `document` as a whole, e.g. a generated proto file.
+    pub fn in_document(self, document: &str) -> Self {
+        Self {
+            document: Some(document.to_owned()),
+            ..self
+        }
+    }
+
+    /// Attributes the diagnostic to spec `version`, unless it already names one.
+    pub fn for_version(self, version: &str) -> Self {
+        Self {
+            version: self.version.or_else(|| Some(version.to_owned())),
+            ..self
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Adds the diagnostics of `other`, found while processing spec `version`.
+    pub fn append_for_version(&mut self, other: Diagnostics, version: &str) {
+        let diagnostics = other.diagnostics.into_iter();
+        self.diagnostics.extend(diagnostics.map(|diagnostic| diagnostic.for_version(version)));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    /// Renders all diagnostics as a single JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "diagnostics": self.diagnostics,
+            "errors": self.error_count(),
+            "warnings": self.warning_count(),
+        })
+        .to_string()
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{}[{}]: {}", self.severity, code, self.message)?,
+            None => write!(f, "{}: {}", self.severity, self.message)?,
+        }
+        match (&self.document, &self.path) {
+            (Some(document), Some(path)) => write!(f, " at {}{}", document, path)?,
+            (Some(document), None) => write!(f, " in {}", document)?,
+            _ => {}
+        }
+        match &self.version {
+            Some(version) => write!(f, " (spec {})", version),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_rendering() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(
+            Diagnostic::error("dangling $ref: #/components/schemas/FOO")
+                .code("dangling-ref")
+                .at("starknet_api_openrpc.json", "#/components/schemas/TXN/oneOf/3"),
+        );
+        diagnostics.push(Diagnostic::warning("something odd"));
+
+        assert!(diagnostics.has_errors());
+        assert_eq!(diagnostics.warning_count(), 1);
+
+        let rendered = diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            vec![
+                "error[dangling-ref]: dangling $ref: #/components/schemas/FOO at \
+                 starknet_api_openrpc.json#/components/schemas/TXN/oneOf/3",
+                "warning: something odd",
+            ]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&diagnostics.to_json()).unwrap();
+        assert_eq!(json["errors"], 1);
+        assert_eq!(json["diagnostics"][0]["severity"], "error");
+        assert_eq!(json["diagnostics"][0]["code"], "dangling-ref");
+        assert_eq!(json["diagnostics"][0]["path"], "#/components/schemas/TXN/oneOf/3");
+        assert!(json["diagnostics"][1].get("document").is_none());
+        assert!(json["diagnostics"][1].get("version").is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_name_their_version() {
+        let mut found = Diagnostics::new();
+        found.push(
+            Diagnostic::warning("fixed up")
+                .code("spec-fixup")
+                .at("starknet_api_openrpc.json", "#/methods/3"),
+        );
+        found.push(Diagnostic::warning("odd").for_version("0.7.1"));
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.append_for_version(found, "0.8.1");
+
+        let rendered = diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            vec![
+                "warning[spec-fixup]: fixed up at starknet_api_openrpc.json#/methods/3 \
+                 (spec 0.8.1)",
+                "warning: odd (spec 0.7.1)",
+            ]
+        );
+        let json: serde_json::Value = serde_json::from_str(&diagnostics.to_json()).unwrap();
+        assert_eq!(json["diagnostics"][0]["version"], "0.8.1");
+    }
+}
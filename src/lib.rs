@@ -1,6 +1,24 @@
+#[cfg(feature = "bridge")]
+pub mod bridge;
+pub mod codegen;
+pub mod diagnostics;
+pub mod profile;
+pub mod refs;
+#[cfg(feature = "bridge")]
+pub mod server;
+pub mod source;
 pub mod spec;
+pub mod spec_diff;
 pub mod proto_gen;
+pub mod transcoder;
+
+mod built_info {
+    include!(concat!(env!("OUT_DIR"), "/built.rs"));
+}
 
 // Re-export commonly used types for convenience
 pub use spec::*;
-pub use proto_gen::{ProtoConfig, ProtoGenerator}; 
\ No newline at end of file
+pub use codegen::{passes::Pass, Builder};
+pub use proto_gen::{ProtoConfig, ProtoGenerator};
+pub use source::SpecVersion;
+pub use transcoder::Transcoder; 
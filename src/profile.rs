@@ -0,0 +1,185 @@
+//! Options of the bundled generation profiles (`src/profiles/<version>.json`), which tailor the
+//! Rust serde types generated from a spec version.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// Options of a generation profile. Type names are the ones starknet-rs uses, with
+/// `Transaction` where the spec has `TXN`, e.g. `DeclareTransactionV0` for `DECLARE_TXN_V0`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProfileOptions {
+    #[serde(default)]
+    pub fixed_field_types: FixedFieldsOptions,
+    #[serde(default)]
+    pub arc_wrapped_types: ArcWrappingOptions,
+    #[serde(default)]
+    pub additional_derives_types: AdditionalDerivesOptions,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FixedFieldsOptions {
+    pub fixed_field_types: Vec<FixedFieldType>,
+}
+
+/// Fields of a type the spec restricts to a single value, left out of the Rust type and only
+/// checked and written in JSON
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixedFieldType {
+    pub name: String,
+    pub fields: Vec<FixedField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FixedField {
+    pub name: String,
+    /// Rust expression of the value, e.g. `"DECLARE"`, `&1`, `&Felt::ONE` or
+    /// `&TransactionFinalityStatus::AcceptedOnL2`
+    pub value: String,
+    /// Whether the value is a transaction version that can also be its query version, the
+    /// version plus 2^128
+    pub is_query_version: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArcWrappingOptions {
+    pub arc_wrapped_types: Vec<ArcWrappedType>,
+}
+
+/// Fields of a type held in an `Arc`, for large values cloned along with their parent
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArcWrappedType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdditionalDerivesOptions {
+    pub additional_derives_types: Vec<AdditionalDerivesType>,
+}
+
+/// Traits derived for a type on top of the default ones
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdditionalDerivesType {
+    pub name: String,
+    pub derives: Vec<String>,
+}
+
+/// Value of a fixed field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixedValue {
+    String(String),
+    Number(u64),
+    /// Variant of an enum, by its Rust name
+    Variant(String),
+}
+
+impl ProfileOptions {
+    pub fn parse(content: &str) -> Result<Self> {
+        serde_json::from_str(content).context("invalid generation profile")
+    }
+
+    /// Fixed fields of the type named `name`, if any
+    pub fn fixed_fields(&self, name: &str) -> &[FixedField] {
+        self.fixed_field_types
+            .fixed_field_types
+            .iter()
+            .find(|fixed| fixed.name == name)
+            .map_or(&[], |fixed| fixed.fields.as_slice())
+    }
+
+    /// Fields of the type named `name` held in an `Arc`
+    pub fn arc_wrapped_fields(&self, name: &str) -> &[String] {
+        self.arc_wrapped_types
+            .arc_wrapped_types
+            .iter()
+            .find(|wrapped| wrapped.name == name)
+            .map_or(&[], |wrapped| wrapped.fields.as_slice())
+    }
+
+    /// Traits derived for the type named `name` on top of the default ones
+    pub fn additional_derives(&self, name: &str) -> &[String] {
+        self.additional_derives_types
+            .additional_derives_types
+            .iter()
+            .find(|derives| derives.name == name)
+            .map_or(&[], |derives| derives.derives.as_slice())
+    }
+}
+
+impl FixedField {
+    pub fn parse_value(&self) -> Result<FixedValue> {
+        if self.value.starts_with('"') {
+            let value = serde_json::from_str(&self.value)
+                .with_context(|| format!("invalid string {} for `{}`", self.value, self.name))?;
+            return Ok(FixedValue::String(value));
+        }
+        let Some(value) = self.value.strip_prefix('&') else {
+            bail!("unsupported value {} for `{}`", self.value, self.name);
+        };
+        if let Ok(number) = value.parse() {
+            return Ok(FixedValue::Number(number));
+        }
+        let number = match value {
+            "Felt::ZERO" => 0,
+            "Felt::ONE" => 1,
+            "Felt::TWO" => 2,
+            "Felt::THREE" => 3,
+            _ => match value.split_once("::") {
+                Some((_, variant)) => return Ok(FixedValue::Variant(variant.to_owned())),
+                None => bail!("unsupported value {} for `{}`", self.value, self.name),
+            },
+        };
+        Ok(FixedValue::Number(number))
+    }
+}
+
+/// Name a profile uses for the type generated as `name`
+pub fn profile_type_name(name: &str) -> String {
+    name.replace("Txn", "Transaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_profiles_parse() {
+        for content in [
+            include_str!("./profiles/0.1.0.json"),
+            include_str!("./profiles/0.2.1.json"),
+            include_str!("./profiles/0.3.0.json"),
+            include_str!("./profiles/0.4.0.json"),
+            include_str!("./profiles/0.5.1.json"),
+            include_str!("./profiles/0.6.0.json"),
+            include_str!("./profiles/0.7.1.json"),
+            include_str!("./profiles/0.8.1.json"),
+        ] {
+            let options = ProfileOptions::parse(content).unwrap();
+            for fixed in &options.fixed_field_types.fixed_field_types {
+                for field in &fixed.fields {
+                    field.parse_value().unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_values() {
+        let field = |value: &str| FixedField {
+            name: "version".to_owned(),
+            value: value.to_owned(),
+            is_query_version: false,
+        };
+        assert_eq!(
+            field("\"DECLARE\"").parse_value().unwrap(),
+            FixedValue::String("DECLARE".to_owned())
+        );
+        assert_eq!(field("&2").parse_value().unwrap(), FixedValue::Number(2));
+        assert_eq!(field("&Felt::THREE").parse_value().unwrap(), FixedValue::Number(3));
+        assert_eq!(
+            field("&TransactionFinalityStatus::AcceptedOnL2").parse_value().unwrap(),
+            FixedValue::Variant("AcceptedOnL2".to_owned())
+        );
+        assert!(field("DECLARE").parse_value().is_err());
+    }
+}
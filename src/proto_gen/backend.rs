@@ -0,0 +1,26 @@
+use anyhow::Result;
+
+use crate::{
+    proto_gen::{ProtoIr, ProtoWriter},
+    spec::Specification,
+};
+
+/// Renders the definitions `ProtoGenerator` resolves from a spec into the files of a target
+pub trait Backend {
+    /// Files rendered from `ir`, resolved from `specs`, by path relative to the directory of the
+    /// spec version
+    fn render(&self, specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>>;
+}
+
+/// Renders each file as `.proto` source
+pub struct ProtoBackend;
+
+impl Backend for ProtoBackend {
+    fn render(&self, _specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>> {
+        Ok(ir
+            .files
+            .iter()
+            .map(|file| (file.name.clone(), ProtoWriter::from(file).to_string()))
+            .collect())
+    }
+}
@@ -0,0 +1,791 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+};
+use serde::Serialize;
+
+/// Kind of client a change breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Breakage {
+    /// Binary-encoded messages or gRPC calls no longer decode or route the same way
+    Wire,
+    /// The proto3 JSON mapping of a message changes
+    Json,
+    /// Code generated from the old files no longer compiles against the new ones
+    Source,
+}
+
+/// Single difference between two sets of proto files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProtoChange {
+    /// File and fully qualified element the change is about, e.g. `main.proto: Block.hash`
+    pub location: String,
+    pub description: String,
+    /// Empty for compatible changes
+    pub breaks: Vec<Breakage>,
+}
+
+/// Changes between two sets of proto files, e.g. two generated versions of the API
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtoDiff {
+    pub changes: Vec<ProtoChange>,
+}
+
+/// Whether a named type is a message or an enum, which decides its wire and JSON encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeKind {
+    Message,
+    Enum,
+}
+
+/// Packages of the files of one side, to name types relative to their file
+struct TypeIndex {
+    /// Package to the name of the file defining it
+    packages: HashMap<String, String>,
+}
+
+struct Differ<'a> {
+    old: &'a TypeIndex,
+    new: &'a TypeIndex,
+    changes: Vec<ProtoChange>,
+}
+
+impl ProtoDiff {
+    /// Compares files by name; types are compared by their name relative to the package of their
+    /// file, so that two versions generated into different packages line up. Type names have to
+    /// be resolved, as in the descriptors protox compiles.
+    pub fn new(old: &[FileDescriptorProto], new: &[FileDescriptorProto]) -> Self {
+        let old_index = TypeIndex::new(old);
+        let new_index = TypeIndex::new(new);
+        let mut differ = Differ {
+            old: &old_index,
+            new: &new_index,
+            changes: vec![],
+        };
+
+        for old_file in old {
+            match new.iter().find(|file| file.name == old_file.name) {
+                Some(new_file) => differ.file(old_file, new_file),
+                None => {
+                    let mut breaks = vec![Breakage::Source];
+                    if !old_file.service.is_empty() {
+                        breaks.insert(0, Breakage::Wire);
+                    }
+                    differ.push(old_file.name(), "file removed".to_owned(), breaks);
+                }
+            }
+        }
+        for new_file in new {
+            if !old.iter().any(|file| file.name == new_file.name) {
+                differ.push(new_file.name(), "file added".to_owned(), vec![]);
+            }
+        }
+
+        Self {
+            changes: differ.changes,
+        }
+    }
+
+    /// Changes breaking clients of the given kind
+    pub fn breaking(&self, breakage: Breakage) -> impl Iterator<Item = &ProtoChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.breaks.contains(&breakage))
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Proto changes\n");
+
+        if self.changes.is_empty() {
+            markdown.push_str("\nNo changes.\n");
+        }
+
+        let (breaking, compatible): (Vec<_>, Vec<_>) = self
+            .changes
+            .iter()
+            .partition(|change| !change.breaks.is_empty());
+        for (changes, title) in [(breaking, "Breaking"), (compatible, "Compatible")] {
+            if changes.is_empty() {
+                continue;
+            }
+
+            markdown.push_str(&format!("\n## {}\n\n", title));
+            for change in changes {
+                markdown.push_str(&format!("- {}\n", change));
+            }
+        }
+
+        markdown
+    }
+}
+
+impl Display for Breakage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wire => write!(f, "wire"),
+            Self::Json => write!(f, "json"),
+            Self::Source => write!(f, "source"),
+        }
+    }
+}
+
+impl Display for ProtoChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {}", self.location, self.description)?;
+        if !self.breaks.is_empty() {
+            let breaks = self
+                .breaks
+                .iter()
+                .map(|breakage| breakage.to_string())
+                .collect::<Vec<_>>();
+            write!(f, " (breaks {})", breaks.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl TypeIndex {
+    fn new(files: &[FileDescriptorProto]) -> Self {
+        Self {
+            packages: files
+                .iter()
+                .map(|file| (file.package().to_owned(), file.name().to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Resolved type name made relative to the file defining its package, e.g.
+    /// `.starknet.v0_8_1.common.Block` becomes `common.proto:Block`.
+    fn normalize(&self, file: &str, type_name: &str) -> String {
+        let reference = type_name.trim_start_matches('.');
+        let qualified = self
+            .packages
+            .iter()
+            .filter_map(|(package, name)| {
+                let rest = reference.strip_prefix(package.as_str())?.strip_prefix('.')?;
+                Some((package.len(), format!("{}:{}", name, rest)))
+            })
+            .max_by_key(|(length, _)| *length);
+        match qualified {
+            Some((_, normalized)) => normalized,
+            None if reference.starts_with("google.protobuf.") => reference.to_owned(),
+            None => format!("{}:{}", file, reference),
+        }
+    }
+}
+
+/// Field type with named types resolved against one side
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ResolvedType {
+    Scalar(&'static str),
+    Named(String, TypeKind),
+}
+
+impl ResolvedType {
+    fn new(index: &TypeIndex, file: &str, field: &FieldDescriptorProto) -> Self {
+        let named = |kind| Self::Named(index.normalize(file, field.type_name()), kind);
+        match field.r#type() {
+            Type::String => Self::Scalar("string"),
+            Type::Int32 => Self::Scalar("int32"),
+            Type::Int64 => Self::Scalar("int64"),
+            Type::Uint32 => Self::Scalar("uint32"),
+            Type::Uint64 => Self::Scalar("uint64"),
+            Type::Sint32 => Self::Scalar("sint32"),
+            Type::Sint64 => Self::Scalar("sint64"),
+            Type::Fixed32 => Self::Scalar("fixed32"),
+            Type::Fixed64 => Self::Scalar("fixed64"),
+            Type::Sfixed32 => Self::Scalar("sfixed32"),
+            Type::Sfixed64 => Self::Scalar("sfixed64"),
+            Type::Bool => Self::Scalar("bool"),
+            Type::Bytes => Self::Scalar("bytes"),
+            Type::Double => Self::Scalar("double"),
+            Type::Float => Self::Scalar("float"),
+            Type::Enum => named(TypeKind::Enum),
+            Type::Message | Type::Group => named(TypeKind::Message),
+        }
+    }
+
+    /// Group of types that can be decoded from each other's binary encoding
+    fn wire_group(&self) -> &str {
+        match self {
+            Self::Scalar("int32" | "int64" | "uint32" | "uint64" | "bool")
+            | Self::Named(_, TypeKind::Enum) => "varint",
+            Self::Scalar("sint32" | "sint64") => "zigzag",
+            Self::Scalar("fixed32" | "sfixed32") => "fixed32",
+            Self::Scalar("fixed64" | "sfixed64") => "fixed64",
+            Self::Scalar("string" | "bytes") => "length-delimited",
+            Self::Scalar(name) => name,
+            Self::Named(name, TypeKind::Message) => name,
+        }
+    }
+
+    /// Group of types sharing a proto3 JSON representation
+    fn json_group(&self) -> &str {
+        match self {
+            Self::Scalar(
+                "int32" | "uint32" | "sint32" | "fixed32" | "sfixed32" | "double" | "float",
+            ) => "number",
+            Self::Scalar("int64" | "uint64" | "sint64" | "fixed64" | "sfixed64" | "string") => {
+                "string"
+            }
+            Self::Scalar(name) => name,
+            Self::Named(name, _) => name,
+        }
+    }
+}
+
+impl Display for ResolvedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar(name) => write!(f, "{}", name),
+            Self::Named(name, _) => match name.split_once(':') {
+                Some((_, name)) => write!(f, "{}", name),
+                None => write!(f, "{}", name),
+            },
+        }
+    }
+}
+
+/// Name of the real oneof `field` belongs to, leaving out the synthetic ones of proto3
+/// `optional` fields
+fn oneof_name<'a>(message: &'a DescriptorProto, field: &FieldDescriptorProto) -> Option<&'a str> {
+    if field.proto3_optional() {
+        return None;
+    }
+    let index = usize::try_from(field.oneof_index?).ok()?;
+    Some(message.oneof_decl.get(index)?.name())
+}
+
+impl Differ<'_> {
+    fn push(&mut self, location: &str, description: String, breaks: Vec<Breakage>) {
+        self.changes.push(ProtoChange {
+            location: location.to_owned(),
+            description,
+            breaks,
+        });
+    }
+
+    fn file(&mut self, old: &FileDescriptorProto, new: &FileDescriptorProto) {
+        if old.package != new.package {
+            self.push(
+                old.name(),
+                format!("package renamed from {} to {}", old.package(), new.package()),
+                vec![Breakage::Wire, Breakage::Source],
+            );
+        }
+
+        let location = |name: &str| format!("{}: {}", old.name(), name);
+        self.enums(&old.enum_type, &new.enum_type, &location);
+        self.messages(old.name(), new.name(), "", &old.message_type, &new.message_type);
+
+        for old_service in &old.service {
+            match new.service.iter().find(|service| service.name == old_service.name) {
+                Some(new_service) => self.service(old.name(), new.name(), old_service, new_service),
+                None => self.push(
+                    &location(old_service.name()),
+                    "service removed".to_owned(),
+                    vec![Breakage::Wire, Breakage::Source],
+                ),
+            }
+        }
+        for new_service in &new.service {
+            if !old.service.iter().any(|service| service.name == new_service.name) {
+                self.push(&location(new_service.name()), "service added".to_owned(), vec![]);
+            }
+        }
+    }
+
+    fn enums(
+        &mut self,
+        old: &[EnumDescriptorProto],
+        new: &[EnumDescriptorProto],
+        location: &dyn Fn(&str) -> String,
+    ) {
+        for old_enum in old {
+            let Some(new_enum) = new.iter().find(|proto_enum| proto_enum.name == old_enum.name)
+            else {
+                self.push(
+                    &location(old_enum.name()),
+                    "enum removed".to_owned(),
+                    vec![Breakage::Source],
+                );
+                continue;
+            };
+
+            for old_value in &old_enum.value {
+                let value_location = location(&format!("{}.{}", old_enum.name(), old_value.name()));
+                let by_number = new_enum
+                    .value
+                    .iter()
+                    .find(|value| value.number == old_value.number);
+                let by_name = new_enum
+                    .value
+                    .iter()
+                    .find(|value| value.name == old_value.name);
+                // JSON uses value names, so a name keeps its identity even when renumbered
+                match (by_name, by_number) {
+                    (Some(value), _) if value.number == old_value.number => {}
+                    (Some(value), _) => self.push(
+                        &value_location,
+                        format!(
+                            "enum value renumbered from {} to {}",
+                            old_value.number(),
+                            value.number()
+                        ),
+                        vec![Breakage::Wire],
+                    ),
+                    (None, Some(value))
+                        if !old_enum.value.iter().any(|old| old.name == value.name) =>
+                    {
+                        self.push(
+                            &value_location,
+                            format!(
+                                "enum value {} renamed to {}",
+                                old_value.number(),
+                                value.name()
+                            ),
+                            vec![Breakage::Json, Breakage::Source],
+                        )
+                    }
+                    _ => self.push(
+                        &value_location,
+                        "enum value removed".to_owned(),
+                        vec![Breakage::Json, Breakage::Source],
+                    ),
+                }
+            }
+            for new_value in &new_enum.value {
+                let renamed = old_enum.value.iter().any(|value| {
+                    value.number == new_value.number
+                        && !new_enum.value.iter().any(|new| new.name == value.name)
+                });
+                if !renamed && !old_enum.value.iter().any(|value| value.name == new_value.name) {
+                    self.push(
+                        &location(&format!("{}.{}", new_enum.name(), new_value.name())),
+                        "enum value added".to_owned(),
+                        vec![],
+                    );
+                }
+            }
+        }
+        for new_enum in new {
+            if !old.iter().any(|proto_enum| proto_enum.name == new_enum.name) {
+                self.push(&location(new_enum.name()), "enum added".to_owned(), vec![]);
+            }
+        }
+    }
+
+    fn messages(
+        &mut self,
+        old_file: &str,
+        new_file: &str,
+        prefix: &str,
+        old: &[DescriptorProto],
+        new: &[DescriptorProto],
+    ) {
+        let location = |name: &str| format!("{}: {}{}", old_file, prefix, name);
+
+        for old_message in old {
+            let Some(new_message) = new.iter().find(|message| message.name == old_message.name)
+            else {
+                self.push(
+                    &location(old_message.name()),
+                    "message removed".to_owned(),
+                    vec![Breakage::Source],
+                );
+                continue;
+            };
+
+            let name = format!("{}{}", prefix, old_message.name());
+            self.fields(old_file, new_file, &name, old_message, new_message);
+
+            let nested_location = |nested: &str| format!("{}: {}.{}", old_file, name, nested);
+            self.enums(
+                &old_message.enum_type,
+                &new_message.enum_type,
+                &nested_location,
+            );
+            self.messages(
+                old_file,
+                new_file,
+                &format!("{}.", name),
+                &old_message.nested_type,
+                &new_message.nested_type,
+            );
+        }
+        for new_message in new {
+            if !old.iter().any(|message| message.name == new_message.name) {
+                self.push(&location(new_message.name()), "message added".to_owned(), vec![]);
+            }
+        }
+    }
+
+    fn fields(
+        &mut self,
+        old_file: &str,
+        new_file: &str,
+        message: &str,
+        old: &DescriptorProto,
+        new: &DescriptorProto,
+    ) {
+        let location = |field: &str| format!("{}: {}.{}", old_file, message, field);
+
+        for old_field in &old.field {
+            let by_number = new.field.iter().find(|field| field.number == old_field.number);
+            let by_name = new.field.iter().find(|field| field.name == old_field.name);
+            let field_location = location(old_field.name());
+
+            match (by_number, by_name) {
+                (Some(new_field), _) if new_field.name == old_field.name => {
+                    self.field(
+                        &field_location,
+                        (old_file, old, old_field),
+                        (new_file, new, new_field),
+                    );
+                }
+                (Some(new_field), Some(moved)) => self.push(
+                    &field_location,
+                    format!(
+                        "field number {} reused by {} while the field moved to {}",
+                        old_field.number(),
+                        new_field.name(),
+                        moved.number()
+                    ),
+                    vec![Breakage::Wire, Breakage::Json, Breakage::Source],
+                ),
+                (Some(new_field), None) => {
+                    let mut breaks = vec![Breakage::Source];
+                    if old_field.json_name() != new_field.json_name() {
+                        breaks.insert(0, Breakage::Json);
+                    }
+                    self.push(
+                        &field_location,
+                        format!("field {} renamed to {}", old_field.number(), new_field.name()),
+                        breaks,
+                    );
+                    self.field(
+                        &field_location,
+                        (old_file, old, old_field),
+                        (new_file, new, new_field),
+                    );
+                }
+                (None, Some(new_field)) => self.push(
+                    &field_location,
+                    format!(
+                        "field renumbered from {} to {}",
+                        old_field.number(),
+                        new_field.number()
+                    ),
+                    vec![Breakage::Wire],
+                ),
+                (None, None) => self.push(
+                    &field_location,
+                    "field removed".to_owned(),
+                    vec![Breakage::Wire, Breakage::Json, Breakage::Source],
+                ),
+            }
+        }
+        for new_field in &new.field {
+            if !old.field.iter().any(|field| {
+                field.number == new_field.number || field.name == new_field.name
+            }) {
+                self.push(&location(new_field.name()), "field added".to_owned(), vec![]);
+            }
+        }
+    }
+
+    /// Compares a field kept by name or number, each given with its file and message
+    fn field(
+        &mut self,
+        location: &str,
+        (old_file, old_message, old): (&str, &DescriptorProto, &FieldDescriptorProto),
+        (new_file, new_message, new): (&str, &DescriptorProto, &FieldDescriptorProto),
+    ) {
+        let old_type = ResolvedType::new(self.old, old_file, old);
+        let new_type = ResolvedType::new(self.new, new_file, new);
+        if old_type != new_type {
+            let mut breaks = vec![];
+            if old_type.wire_group() != new_type.wire_group() {
+                breaks.push(Breakage::Wire);
+            }
+            if old_type.json_group() != new_type.json_group() {
+                breaks.push(Breakage::Json);
+            }
+            breaks.push(Breakage::Source);
+            self.push(
+                location,
+                format!("type changed from {} to {}", old_type, new_type),
+                breaks,
+            );
+        }
+
+        let old_repeated = old.label() == Label::Repeated;
+        let new_repeated = new.label() == Label::Repeated;
+        if old_repeated != new_repeated {
+            let description = if new_repeated {
+                "field became repeated"
+            } else {
+                "field is no longer repeated"
+            };
+            self.push(
+                location,
+                description.to_owned(),
+                vec![Breakage::Wire, Breakage::Json, Breakage::Source],
+            );
+        }
+
+        if old.proto3_optional() != new.proto3_optional() {
+            let description = if new.proto3_optional() {
+                "field became optional"
+            } else {
+                "field is no longer optional"
+            };
+            self.push(location, description.to_owned(), vec![Breakage::Source]);
+        }
+
+        let old_oneof = oneof_name(old_message, old);
+        let new_oneof = oneof_name(new_message, new);
+        if old_oneof != new_oneof {
+            let describe = |oneof: Option<&str>| match oneof {
+                Some(oneof) => format!("oneof {}", oneof),
+                None => "no oneof".to_owned(),
+            };
+            self.push(
+                location,
+                format!(
+                    "field moved from {} to {}",
+                    describe(old_oneof),
+                    describe(new_oneof)
+                ),
+                vec![Breakage::Wire, Breakage::Json, Breakage::Source],
+            );
+        }
+
+        // Renames already account for their JSON name
+        if old.name == new.name && old.json_name() != new.json_name() {
+            self.push(
+                location,
+                format!("JSON name changed from {} to {}", old.json_name(), new.json_name()),
+                vec![Breakage::Json],
+            );
+        }
+    }
+
+    fn service(
+        &mut self,
+        old_file: &str,
+        new_file: &str,
+        old: &ServiceDescriptorProto,
+        new: &ServiceDescriptorProto,
+    ) {
+        for old_rpc in &old.method {
+            let location = format!("{}: {}.{}", old_file, old.name(), old_rpc.name());
+            let Some(new_rpc) = new.method.iter().find(|rpc| rpc.name == old_rpc.name) else {
+                self.push(
+                    &location,
+                    "RPC removed".to_owned(),
+                    vec![Breakage::Wire, Breakage::Source],
+                );
+                continue;
+            };
+
+            for (what, old_type, new_type) in [
+                ("request", old_rpc.input_type(), new_rpc.input_type()),
+                ("response", old_rpc.output_type(), new_rpc.output_type()),
+            ] {
+                let old_type = self.old.normalize(old_file, old_type);
+                let new_type = self.new.normalize(new_file, new_type);
+                if old_type != new_type {
+                    self.push(
+                        &location,
+                        format!("{} type changed from {} to {}", what, old_type, new_type),
+                        vec![Breakage::Source],
+                    );
+                }
+            }
+
+            if old_rpc.client_streaming() != new_rpc.client_streaming()
+                || old_rpc.server_streaming() != new_rpc.server_streaming()
+            {
+                self.push(
+                    &location,
+                    "streaming changed".to_owned(),
+                    vec![Breakage::Wire, Breakage::Source],
+                );
+            }
+        }
+        for new_rpc in &new.method {
+            if !old.method.iter().any(|rpc| rpc.name == new_rpc.name) {
+                self.push(
+                    &format!("{}: {}.{}", old_file, new.name(), new_rpc.name()),
+                    "RPC added".to_owned(),
+                    vec![],
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::descriptor::compile_proto_dir;
+
+    /// Compiles the files of one side with protox
+    fn compile(files: &[(&str, &str)]) -> Vec<FileDescriptorProto> {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+        compile_proto_dir(dir.path()).unwrap()
+    }
+
+    fn diff(old: &str, new: &str) -> Vec<(String, String, Vec<Breakage>)> {
+        let old = compile(&[("main.proto", old)]);
+        let new = compile(&[("main.proto", new)]);
+        ProtoDiff::new(&old, &new)
+            .changes
+            .into_iter()
+            .map(|change| (change.location, change.description, change.breaks))
+            .collect()
+    }
+
+    #[test]
+    fn test_field_changes_are_classified() {
+        use Breakage::*;
+
+        let changes = diff(
+            r#"
+            syntax = "proto3";
+            package starknet.v0_7_1.main;
+            enum Status { PENDING = 0; ACCEPTED = 1; REJECTED = 2; }
+            message Block {
+              uint64 number = 1;
+              string hash = 2 [json_name = "block_hash"];
+              Status status = 3;
+              repeated string txs = 4;
+              bytes data = 5;
+              string parent = 6;
+              string old = 7;
+            }
+            service Main {
+              rpc GetBlock(Block) returns (Block);
+              rpc Subscribe(Block) returns (Block);
+            }
+            "#,
+            r#"
+            syntax = "proto3";
+            package starknet.v0_8_1.main;
+            enum Status { PENDING = 0; ACCEPTED = 2; DROPPED = 1; }
+            message Block {
+              uint32 number = 1;
+              string hash = 2 [json_name = "hash"];
+              int32 status = 3;
+              string txs = 4;
+              double data = 5;
+              string parent_hash = 6;
+              string old = 8;
+              string extra = 9;
+            }
+            service Main {
+              rpc Subscribe(Block) returns (stream Block);
+            }
+            "#,
+        );
+
+        let expected = [
+            ("main.proto", "package renamed from starknet.v0_7_1.main to starknet.v0_8_1.main",
+             vec![Wire, Source]),
+            ("main.proto: Status.ACCEPTED", "enum value renumbered from 1 to 2", vec![Wire]),
+            ("main.proto: Status.REJECTED", "enum value removed", vec![Json, Source]),
+            ("main.proto: Status.DROPPED", "enum value added", vec![]),
+            ("main.proto: Block.number", "type changed from uint64 to uint32", vec![Json, Source]),
+            ("main.proto: Block.hash", "JSON name changed from block_hash to hash", vec![Json]),
+            ("main.proto: Block.status", "type changed from Status to int32", vec![Json, Source]),
+            ("main.proto: Block.txs", "field is no longer repeated", vec![Wire, Json, Source]),
+            ("main.proto: Block.data", "type changed from bytes to double",
+             vec![Wire, Json, Source]),
+            ("main.proto: Block.parent", "field 6 renamed to parent_hash", vec![Json, Source]),
+            ("main.proto: Block.old", "field renumbered from 7 to 8", vec![Wire]),
+            ("main.proto: Block.extra", "field added", vec![]),
+            ("main.proto: Main.GetBlock", "RPC removed", vec![Wire, Source]),
+            ("main.proto: Main.Subscribe", "streaming changed", vec![Wire, Source]),
+        ];
+        assert_eq!(
+            changes,
+            expected
+                .into_iter()
+                .map(|(location, description, breaks)| {
+                    (location.to_owned(), description.to_owned(), breaks)
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_types_are_compared_across_packages() {
+        let felt = |package: &str| {
+            format!(
+                "syntax = \"proto3\"; package {}.common; message Felt {{ string value = 1; }}",
+                package
+            )
+        };
+        let main = |package: &str, import: &str, felt: &str| {
+            format!(
+                "syntax = \"proto3\"; package {}.main; import \"{}\"; \
+                 message Block {{ {}.common.Felt hash = 1; }}",
+                package, import, felt
+            )
+        };
+
+        let old = compile(&[
+            ("common.proto", &felt("v1")),
+            ("main.proto", &main("v1", "common.proto", "v1")),
+        ]);
+        let new = compile(&[
+            ("common.proto", &felt("v2")),
+            ("main.proto", &main("v2", "common.proto", "v2")),
+        ]);
+        let diff = ProtoDiff::new(&old, &new);
+        assert!(diff.changes.iter().all(|change| change.description.starts_with("package")));
+        assert_eq!(diff.breaking(Breakage::Json).count(), 0);
+
+        // Still pointing at the old package is a different type
+        let new = compile(&[
+            ("common.proto", &felt("v2")),
+            ("legacy.proto", &felt("v1")),
+            ("main.proto", &main("v2", "legacy.proto", "v1")),
+        ]);
+        let diff = ProtoDiff::new(&old, &new);
+        assert_eq!(diff.breaking(Breakage::Json).count(), 1);
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let message = |field: &str| {
+            compile(&[(
+                "main.proto",
+                &format!("syntax = \"proto3\"; package a; message A {{ {} a = 1; }}", field),
+            )])
+        };
+        let old = message("string");
+        assert_eq!(
+            ProtoDiff::new(&old, &old).to_markdown(),
+            "# Proto changes\n\nNo changes.\n"
+        );
+
+        let new = message("bytes");
+        assert_eq!(
+            ProtoDiff::new(&old, &new).to_markdown(),
+            "# Proto changes\n\n## Breaking\n\n\
+             - `main.proto: A.a`: type changed from string to bytes (breaks json, source)\n"
+        );
+    }
+}
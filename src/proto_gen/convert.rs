@@ -0,0 +1,842 @@
+//! Rust conversions between the prost messages of two spec versions, e.g. from
+//! `starknet.v0_7_1.common.BlockHeader` to `starknet.v0_8_1.common.BlockHeader`, for services
+//! translating the messages of a node that upgraded.
+//!
+//! Messages are paired by the schema they're generated from, or by their name within their
+//! package for messages without one, like requests and responses. Fields are paired by name:
+//! those holding the same type, or types with a conversion of their own, are converted, the
+//! others are dropped or left empty. A pair converts with `From` unless it can fail, in which
+//! case it converts with `TryFrom`: when the new version requires a field the old one doesn't
+//! have, or removed a variant of a union or an enum value.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::proto_gen::{
+    qualify,
+    rust::{rust_type_path, to_snake_case, to_upper_camel_case},
+    shared_types::SharedTypes,
+    ProtoEnum, ProtoField, ProtoFieldType, ProtoFile, ProtoIr, ProtoMessage, ProtoOneof,
+};
+
+/// Directory of the conversions in a workspace of several versions
+pub const CONVERTERS_DIR: &str = "convert";
+
+/// Rust module converting the messages of a version into those of another
+#[derive(Debug, Clone)]
+pub struct Converters {
+    /// Name of the module, e.g. `v0_7_1_to_v0_8_1`
+    pub module: String,
+    pub source: String,
+    /// Pairs of messages converted with `From`
+    pub infallible: usize,
+    /// Pairs of messages converted with `TryFrom`
+    pub fallible: usize,
+}
+
+/// Conversions from the messages of `from` into the messages of `to`, meant to be a module next
+/// to the modules of both versions in a crate compiling their proto files with prost-build, e.g.
+/// `starknet::v0_7_1_to_v0_8_1` next to `starknet::v0_7_1` and `starknet::v0_8_1`. Types both
+/// versions take from `shared_types` are moved as they are.
+pub fn generate_converters(
+    from: &ProtoIr,
+    to: &ProtoIr,
+    shared_types: Option<&SharedTypes>,
+) -> Converters {
+    let shared = shared_types.map(|shared| &shared.file);
+    let old = Side::new(from, shared);
+    let new = Side::new(to, shared);
+    let (prefix, from_version) = split_version(&from.package_info.common_package);
+    let (_, to_version) = split_version(&to.package_info.common_package);
+    let module = format!("{}_to_{}", from_version, to_version);
+
+    let mut converter = Converter {
+        old: &old,
+        new: &new,
+        module_package: format!("{}.{}", prefix, module),
+        messages: pairs(&old, &new, &old.messages.keys().cloned().collect::<Vec<_>>()),
+        enums: BTreeMap::new(),
+        fallible: HashSet::new(),
+        functions: BTreeSet::new(),
+    };
+    converter.enums = pairs(&old, &new, &old.enums.keys().cloned().collect::<Vec<_>>());
+
+    // A message converts fallibly if a message it holds does, which only spreads
+    let plans = loop {
+        let messages = converter.messages.clone();
+        let plans = messages
+            .iter()
+            .map(|(old_name, new_name)| converter.plan(old_name, new_name))
+            .collect::<Vec<_>>();
+        let fallible = plans
+            .iter()
+            .filter(|plan| plan.fallible())
+            .map(|plan| plan.old.clone())
+            .collect::<HashSet<_>>();
+        if fallible == converter.fallible {
+            break plans;
+        }
+        converter.fallible = fallible;
+    };
+
+    let mut source = String::new();
+    source.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+    let _ = writeln!(
+        source,
+        "// Generated from Starknet JSON-RPC specifications {} and {}",
+        from.spec_version, to.spec_version
+    );
+    source.push_str("//\n");
+    let _ = writeln!(
+        source,
+        "// Converts the messages of {} into the messages of {} generated from the same schema.",
+        from.spec_version, to.spec_version
+    );
+    let _ = writeln!(
+        source,
+        "// It is the module `{}` of a crate compiling the proto files of both",
+        converter.module_package.replace('.', "::")
+    );
+    source.push_str(
+        "// versions with prost-build. Conversions that can fail implement `TryFrom`, the others\n",
+    );
+    source.push_str("// `From`.\n");
+    source.push('\n');
+    source.push_str("use std::fmt;\n");
+    source.push('\n');
+    write_error(&mut source, &from.spec_version, &to.spec_version);
+
+    for plan in &plans {
+        source.push('\n');
+        converter.write_impl(&mut source, plan);
+    }
+    for (old_name, new_name) in &converter.enums {
+        let function = enum_function(old_name);
+        if converter.functions.contains(&function) {
+            source.push('\n');
+            converter.write_enum_function(&mut source, &function, old_name, new_name);
+        }
+    }
+
+    let fallible = plans.iter().filter(|plan| plan.fallible()).count();
+    Converters {
+        module,
+        source,
+        infallible: plans.len() - fallible,
+        fallible,
+    }
+}
+
+/// Definitions of a version, by qualified name
+struct Side<'a> {
+    ir: &'a ProtoIr,
+    messages: BTreeMap<String, (&'a ProtoFile, &'a ProtoMessage)>,
+    enums: BTreeMap<String, &'a ProtoEnum>,
+    /// Enums of the shared types, which are never converted but stored like the others
+    shared_enums: HashSet<String>,
+    /// Message types of the singular fields of each message, to find the ones prost-build boxes
+    nesting: HashMap<String, Vec<String>>,
+}
+
+impl<'a> Side<'a> {
+    fn new(ir: &'a ProtoIr, shared: Option<&'a ProtoFile>) -> Self {
+        let mut side = Self {
+            ir,
+            messages: BTreeMap::new(),
+            enums: BTreeMap::new(),
+            shared_enums: HashSet::new(),
+            nesting: HashMap::new(),
+        };
+        for file in &ir.files {
+            for message in &file.messages {
+                side.messages.insert(qualify(&file.package, &message.name), (file, message));
+            }
+            for proto_enum in &file.enums {
+                side.enums.insert(qualify(&file.package, &proto_enum.name), proto_enum);
+            }
+        }
+        if let Some(file) = shared {
+            for proto_enum in &file.enums {
+                side.shared_enums.insert(qualify(&file.package, &proto_enum.name));
+            }
+        }
+        for file in ir.files.iter().chain(shared) {
+            for message in &file.messages {
+                let nested = message
+                    .all_fields()
+                    .iter()
+                    .filter(|field| !field.repeated)
+                    .filter_map(|field| match side.element(&file.package, field) {
+                        Element::Message(name) => Some(name),
+                        _ => None,
+                    })
+                    .collect();
+                side.nesting.insert(qualify(&file.package, &message.name), nested);
+            }
+        }
+        side
+    }
+
+    /// What a field of `package` holds
+    fn element(&self, package: &str, field: &ProtoField) -> Element {
+        match &field.field_type {
+            ProtoFieldType::Enum(name) => Element::Enum(qualify(package, name)),
+            ProtoFieldType::Message(name) => {
+                let name = qualify(package, name);
+                match self.enums.contains_key(&name) || self.shared_enums.contains(&name) {
+                    true => Element::Enum(name),
+                    false => Element::Message(name),
+                }
+            }
+            scalar => Element::Scalar(scalar.clone()),
+        }
+    }
+
+    /// How prost-build stores a field of `package`
+    fn container(&self, package: &str, field: &ProtoField) -> Container {
+        if field.repeated {
+            return Container::Vec;
+        }
+        match self.element(package, field) {
+            Element::Message(_) => Container::Option,
+            _ if field.optional => Container::Option,
+            _ => Container::Plain,
+        }
+    }
+
+    /// Whether prost-build boxes a singular field of `message` holding `element`, which it does
+    /// when the type contains the message
+    fn boxed(&self, message: &str, element: &Element) -> bool {
+        let Element::Message(field_type) = element else {
+            return false;
+        };
+        let mut seen = HashSet::new();
+        let mut pending = vec![field_type.as_str()];
+        while let Some(name) = pending.pop() {
+            if name == message {
+                return true;
+            }
+            if seen.insert(name) {
+                pending.extend(self.nesting.get(name).into_iter().flatten().map(String::as_str));
+            }
+        }
+        false
+    }
+}
+
+/// What a field holds
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Scalar(ProtoFieldType),
+    Message(String),
+    Enum(String),
+}
+
+/// How prost-build stores a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Plain,
+    Option,
+    Vec,
+}
+
+/// Conversion of a single value of a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Conversion {
+    Move,
+    Into,
+    TryInto,
+    /// Function mapping the numbers of an enum
+    Function(String),
+}
+
+impl Conversion {
+    fn fallible(&self) -> bool {
+        matches!(self, Conversion::TryInto | Conversion::Function(_))
+    }
+}
+
+/// Conversion of a pair of messages
+struct Plan {
+    old: String,
+    new: String,
+    /// Rust expression of each field of the new message, by Rust name
+    fields: Vec<(String, String)>,
+    /// Whether any of `fields` can fail
+    fallible_fields: bool,
+    /// Fields and unions the new message requires without a value to take it from
+    missing: Vec<String>,
+    /// Fields and unions of the old message that are lost
+    dropped: Vec<String>,
+}
+
+impl Plan {
+    fn fallible(&self) -> bool {
+        self.fallible_fields || !self.missing.is_empty()
+    }
+}
+
+struct Converter<'a> {
+    old: &'a Side<'a>,
+    new: &'a Side<'a>,
+    /// Package the module would have, e.g. `starknet.v0_7_1_to_v0_8_1`, to write Rust paths from
+    module_package: String,
+    /// Pairs of messages, by qualified name
+    messages: BTreeMap<String, String>,
+    /// Pairs of enums, by qualified name
+    enums: BTreeMap<String, String>,
+    /// Old messages converting with `TryFrom`
+    fallible: HashSet<String>,
+    /// Enum functions the conversions call
+    functions: BTreeSet<String>,
+}
+
+impl Converter<'_> {
+    fn plan(&mut self, old_name: &str, new_name: &str) -> Plan {
+        let (old_file, old_message) = self.old.messages[old_name];
+        let (new_file, new_message) = self.new.messages[new_name];
+        let mut plan = Plan {
+            old: old_name.to_owned(),
+            new: new_name.to_owned(),
+            fields: vec![],
+            fallible_fields: false,
+            missing: vec![],
+            dropped: vec![],
+        };
+
+        let regular = |message: &'_ ProtoMessage| -> Vec<ProtoField> {
+            let fields = message.fields.iter().filter(|field| field.oneof_name.is_none());
+            fields.cloned().collect()
+        };
+        let old_fields = regular(old_message);
+        for new_field in regular(new_message) {
+            let old_field = old_fields.iter().find(|field| field.name == new_field.name);
+            let converted = old_field.and_then(|old_field| {
+                self.field(old_name, &old_file.package, old_field, new_name, &new_file.package, &new_field)
+            });
+            let expression = match converted {
+                Some((expression, fallible)) => {
+                    plan.fallible_fields |= fallible;
+                    expression
+                }
+                None => {
+                    if let Some(old_field) = old_field {
+                        plan.dropped.push(old_field.name.clone());
+                    }
+                    if !new_field.repeated && !new_field.optional {
+                        plan.missing.push(new_field.name.clone());
+                    }
+                    "Default::default()".to_owned()
+                }
+            };
+            plan.fields.push((to_snake_case(&new_field.name), expression));
+        }
+        for old_field in &old_fields {
+            if !new_message.fields.iter().any(|field| field.name == old_field.name) {
+                plan.dropped.push(old_field.name.clone());
+            }
+        }
+
+        // Unions without variants aren't written
+        let oneofs = |message: &'_ ProtoMessage| -> Vec<ProtoOneof> {
+            let oneofs = message.oneofs.iter().filter(|oneof| !oneof.fields.is_empty());
+            oneofs.cloned().collect()
+        };
+        let old_oneofs = oneofs(old_message);
+        let new_oneofs = oneofs(new_message);
+        for new_oneof in &new_oneofs {
+            let old_oneof = old_oneofs.iter().find(|oneof| oneof.name == new_oneof.name);
+            let expression = match old_oneof {
+                Some(old_oneof) => {
+                    let (expression, fallible) = self.oneof(
+                        (old_file, old_message, old_oneof),
+                        (new_file, new_message, new_oneof),
+                    );
+                    plan.fallible_fields |= fallible;
+                    expression
+                }
+                // Unions stand for a value of the spec, which the old message doesn't have
+                None => {
+                    plan.missing.push(new_oneof.name.clone());
+                    "None".to_owned()
+                }
+            };
+            plan.fields.push((to_snake_case(&new_oneof.name), expression));
+        }
+        for old_oneof in &old_oneofs {
+            if !new_oneofs.iter().any(|oneof| oneof.name == old_oneof.name) {
+                plan.dropped.push(old_oneof.name.clone());
+            }
+        }
+        plan
+    }
+
+    /// Rust expression converting `old_field` of `value` into `new_field`, and whether it can
+    /// fail, if their types convert
+    fn field(
+        &mut self,
+        old_message: &str,
+        old_package: &str,
+        old_field: &ProtoField,
+        new_message: &str,
+        new_package: &str,
+        new_field: &ProtoField,
+    ) -> Option<(String, bool)> {
+        let old_element = self.old.element(old_package, old_field);
+        let new_element = self.new.element(new_package, new_field);
+        let conversion = self.conversion(&old_element, &new_element)?;
+        let old_boxed = self.old.boxed(old_message, &old_element);
+        let new_boxed = self.new.boxed(new_message, &new_element);
+        let fallible = conversion.fallible();
+        let source = format!("value.{}", to_snake_case(&old_field.name));
+        let question = if fallible { "?" } else { "" };
+
+        let each = || map_function(&convert_value(&conversion, "v", old_boxed, new_boxed));
+        let expression = match (
+            self.old.container(old_package, old_field),
+            self.new.container(new_package, new_field),
+        ) {
+            (Container::Vec, Container::Vec) => match (each(), fallible) {
+                (None, _) => source,
+                (Some(each), false) => format!("{}.into_iter().map({}).collect()", source, each),
+                (Some(each), true) => {
+                    format!("{}.into_iter().map({}).collect::<Result<_, _>>()?", source, each)
+                }
+            },
+            (Container::Option, Container::Option) => match (each(), fallible) {
+                (None, _) => source,
+                (Some(each), false) => format!("{}.map({})", source, each),
+                (Some(each), true) => format!("{}.map({}).transpose()?", source, each),
+            },
+            (Container::Plain, Container::Plain) => {
+                let value = convert_value(&conversion, &source, old_boxed, new_boxed);
+                format!("{}{}", value, question)
+            }
+            (Container::Plain, Container::Option) => {
+                let value = convert_value(&conversion, &source, old_boxed, new_boxed);
+                format!("Some({}{})", value, question)
+            }
+            // The new version requires what the old one may leave out
+            (Container::Option, Container::Plain) => {
+                let present = format!(
+                    "{}.ok_or(ConversionError::MissingField {{ message: \"{}\", field: \"{}\" }})?",
+                    source, new_message, new_field.name
+                );
+                let value = convert_value(&conversion, &present, old_boxed, new_boxed);
+                return Some((format!("{}{}", value, question), true));
+            }
+            _ => return None,
+        };
+        Some((expression, fallible))
+    }
+
+    /// Rust expression converting the union `old` of `value` into `new`, and whether it can fail
+    fn oneof(
+        &mut self,
+        (old_file, old_message, old_oneof): (&ProtoFile, &ProtoMessage, &ProtoOneof),
+        (new_file, new_message, new_oneof): (&ProtoFile, &ProtoMessage, &ProtoOneof),
+    ) -> (String, bool) {
+        let old_name = qualify(&old_file.package, &old_message.name);
+        let new_name = qualify(&new_file.package, &new_message.name);
+        let old_enum = self.oneof_path(&old_file.package, old_message, old_oneof);
+        let new_enum = self.oneof_path(&new_file.package, new_message, new_oneof);
+
+        let mut arms = vec![];
+        let mut fallible = false;
+        for old_variant in &old_oneof.fields {
+            let pattern = format!("{}::{}", old_enum, to_upper_camel_case(&old_variant.name));
+            let new_variant = new_oneof.fields.iter().find(|field| field.name == old_variant.name);
+            let converted = new_variant.and_then(|new_variant| {
+                let old_element = self.old.element(&old_file.package, old_variant);
+                let new_element = self.new.element(&new_file.package, new_variant);
+                let conversion = self.conversion(&old_element, &new_element)?;
+                let old_boxed = self.old.boxed(&old_name, &old_element);
+                let new_boxed = self.new.boxed(&new_name, &new_element);
+                let value = convert_value(&conversion, "v", old_boxed, new_boxed);
+                let variant = format!("{}::{}", new_enum, to_upper_camel_case(&new_variant.name));
+                Some(match conversion.fallible() {
+                    true => (format!("{}.map({})", value, variant), true),
+                    false => (format!("{}({})", variant, value), false),
+                })
+            });
+            match converted {
+                Some((value, variant_fallible)) => {
+                    fallible |= variant_fallible;
+                    arms.push((format!("{}(v)", pattern), value, variant_fallible));
+                }
+                None => {
+                    fallible = true;
+                    let error = format!(
+                        "Err(ConversionError::RemovedVariant {{ message: \"{}\", variant: \"{}\" }})",
+                        new_name, old_variant.name
+                    );
+                    arms.push((format!("{}(_)", pattern), error, true));
+                }
+            }
+        }
+
+        let source = format!("value.{}", to_snake_case(&old_oneof.name));
+        let mut expression = format!("{}.map(|oneof| match oneof {{\n", source);
+        for (pattern, value, arm_fallible) in arms {
+            let value = match fallible && !arm_fallible {
+                true => format!("Ok({})", value),
+                false => value,
+            };
+            let _ = writeln!(expression, "    {} => {},", pattern, value);
+        }
+        expression.push_str("})");
+        if fallible {
+            expression.push_str(".transpose()?");
+        }
+        (expression, fallible)
+    }
+
+    /// Conversion of a value of `old` into a value of `new`, if they convert
+    fn conversion(&mut self, old: &Element, new: &Element) -> Option<Conversion> {
+        match (old, new) {
+            (Element::Scalar(old), Element::Scalar(new)) if old == new => Some(Conversion::Move),
+            (Element::Scalar(old), Element::Scalar(new)) => {
+                use ProtoFieldType::*;
+                let widened = matches!(
+                    (old, new),
+                    (Int32, Int64) | (Uint32, Uint64) | (Uint32, Int64) | (Float, Double)
+                );
+                widened.then_some(Conversion::Into)
+            }
+            (Element::Message(old), Element::Message(new)) if old == new => Some(Conversion::Move),
+            (Element::Message(old), Element::Message(new)) => {
+                if self.messages.get(old) != Some(new) {
+                    return None;
+                }
+                match self.fallible.contains(old) {
+                    true => Some(Conversion::TryInto),
+                    false => Some(Conversion::Into),
+                }
+            }
+            (Element::Enum(old), Element::Enum(new)) if old == new => Some(Conversion::Move),
+            (Element::Enum(old), Element::Enum(new)) => {
+                if self.enums.get(old) != Some(new) {
+                    return None;
+                }
+                let (old_enum, new_enum) = (self.old.enums[old], self.new.enums[new]);
+                let same_numbers = old_enum.values.iter().all(|value| {
+                    new_enum
+                        .values
+                        .iter()
+                        .any(|other| other.name == value.name && other.number == value.number)
+                });
+                if same_numbers {
+                    return Some(Conversion::Move);
+                }
+                let function = enum_function(old);
+                self.functions.insert(function.clone());
+                Some(Conversion::Function(function))
+            }
+            _ => None,
+        }
+    }
+
+    /// Path of the enum prost-build generates for `oneof` of `message`, in `package`
+    fn oneof_path(&self, package: &str, message: &ProtoMessage, oneof: &ProtoOneof) -> String {
+        let message_path = rust_type_path(&self.module_package, &qualify(package, &message.name));
+        let (package_path, _) = message_path.rsplit_once("::").unwrap_or(("", &message_path));
+        format!(
+            "{}::{}::{}",
+            package_path,
+            to_snake_case(&message.name),
+            to_upper_camel_case(&oneof.name)
+        )
+    }
+
+    fn write_impl(&self, out: &mut String, plan: &Plan) {
+        let old_type = rust_type_path(&self.module_package, &plan.old);
+        let new_type = rust_type_path(&self.module_package, &plan.new);
+        let mut notes = vec![];
+        if !plan.missing.is_empty() {
+            notes.push(format!(
+                "Always fails: `{}` requires {}, which `{}` doesn't have.",
+                plan.new,
+                code_list(&plan.missing),
+                plan.old
+            ));
+        }
+        if !plan.dropped.is_empty() {
+            notes.push(format!("Drops {}.", code_list(&plan.dropped)));
+        }
+        let _ = writeln!(out, "/// Converts `{}` into `{}`", plan.old, plan.new);
+        for note in notes {
+            out.push_str("///\n");
+            let _ = writeln!(out, "/// {}", note);
+        }
+
+        // Messages whose fields are all new or changed take nothing from the old one
+        let value = match plan.fields.iter().any(|(_, expression)| expression.contains("value.")) {
+            true => "value",
+            false => "_value",
+        };
+        if !plan.fallible() {
+            let _ = writeln!(out, "impl From<{}> for {} {{", old_type, new_type);
+            let _ = writeln!(out, "    fn from({}: {}) -> Self {{", value, old_type);
+            write_fields(out, &plan.fields, "        Self {");
+            out.push_str("    }\n");
+            out.push_str("}\n");
+            return;
+        }
+
+        let _ = writeln!(out, "impl TryFrom<{}> for {} {{", old_type, new_type);
+        out.push_str("    type Error = ConversionError;\n");
+        out.push('\n');
+        if let Some(missing) = plan.missing.first() {
+            let _ = writeln!(
+                out,
+                "    fn try_from(_value: {}) -> Result<Self, Self::Error> {{",
+                old_type
+            );
+            let _ = writeln!(
+                out,
+                "        Err(ConversionError::MissingField {{ message: \"{}\", field: \"{}\" }})",
+                plan.new, missing
+            );
+        } else {
+            let _ = writeln!(
+                out,
+                "    fn try_from({}: {}) -> Result<Self, Self::Error> {{",
+                value, old_type
+            );
+            write_fields(out, &plan.fields, "        Ok(Self {");
+            out.pop();
+            out.push_str(")\n");
+        }
+        out.push_str("    }\n");
+        out.push_str("}\n");
+    }
+
+    fn write_enum_function(&self, out: &mut String, function: &str, old: &str, new: &str) {
+        let (old_enum, new_enum) = (self.old.enums[old], self.new.enums[new]);
+        let _ = writeln!(
+            out,
+            "/// Number of the value of `{}` named like the value of `{}` numbered `value`",
+            new, old
+        );
+        let _ = writeln!(out, "fn {}(value: i32) -> Result<i32, ConversionError> {{", function);
+        out.push_str("    match value {\n");
+        for value in &old_enum.values {
+            match new_enum.values.iter().find(|other| other.name == value.name) {
+                Some(other) => {
+                    let _ = writeln!(out, "        {} => Ok({}),", value.number, other.number);
+                }
+                None => {
+                    let _ = writeln!(
+                        out,
+                        "        {} => Err(ConversionError::RemovedVariant {{ message: \"{}\", \
+                         variant: \"{}\" }}),",
+                        value.number, new, value.name
+                    );
+                }
+            }
+        }
+        let _ = writeln!(
+            out,
+            "        _ => Err(ConversionError::UnknownValue {{ name: \"{}\", value }}),",
+            old
+        );
+        out.push_str("    }\n");
+        out.push_str("}\n");
+    }
+}
+
+/// Pairs of the definitions named `names` of `old` with the definitions of `new` generated from
+/// the same schema, or with the same name within their package for definitions without one
+fn pairs(old: &Side, new: &Side, names: &[String]) -> BTreeMap<String, String> {
+    let defined = |side: &Side, name: &str| {
+        side.messages.contains_key(name) || side.enums.contains_key(name)
+    };
+    let mut by_schema = HashMap::new();
+    for (name, schema) in &new.ir.schemas {
+        if defined(new, name) {
+            by_schema.entry(schema).or_insert(name);
+        }
+    }
+    let relative = |name: &str| {
+        let mut segments = name.rsplitn(3, '.');
+        let name = segments.next().unwrap_or_default();
+        format!("{}.{}", segments.next().unwrap_or_default(), name)
+    };
+    let by_relative = new
+        .messages
+        .keys()
+        .chain(new.enums.keys())
+        .filter(|name| !new.ir.schemas.contains_key(*name))
+        .map(|name| (relative(name), name))
+        .collect::<HashMap<_, _>>();
+
+    let mut pairs = BTreeMap::new();
+    let mut taken = HashSet::new();
+    for name in names {
+        let paired = match old.ir.schemas.get(name) {
+            Some(schema) => by_schema.get(schema).copied(),
+            None => by_relative.get(&relative(name)).copied(),
+        };
+        // Shared types are the same in both versions
+        if let Some(paired) = paired.filter(|paired| *paired != name) {
+            if taken.insert(paired) {
+                pairs.insert(name.clone(), paired.clone());
+            }
+        }
+    }
+    pairs
+}
+
+/// Rust expression converting `value`, stored boxed or not, into a value stored boxed or not
+fn convert_value(conversion: &Conversion, value: &str, old_boxed: bool, new_boxed: bool) -> String {
+    let inner = match old_boxed {
+        true => format!("(*{})", value),
+        false => value.to_owned(),
+    };
+    let converted = match conversion {
+        Conversion::Move if old_boxed => format!("*{}", value),
+        Conversion::Move => value.to_owned(),
+        Conversion::Into => format!("{}.into()", inner),
+        Conversion::TryInto => format!("{}.try_into()", inner),
+        Conversion::Function(function) => format!("{}({})", function, inner),
+    };
+    match (new_boxed, conversion.fallible()) {
+        (false, _) => converted,
+        (true, false) => format!("Box::new({})", converted),
+        (true, true) => format!("{}.map(Box::new)", converted),
+    }
+}
+
+/// Function converting each value of a collection, from the expression converting `v`, or `None`
+/// if values are moved as they are
+fn map_function(each: &str) -> Option<String> {
+    match each {
+        "v" => None,
+        "v.into()" => Some("Into::into".to_owned()),
+        "v.try_into()" => Some("TryInto::try_into".to_owned()),
+        _ => Some(format!("|v| {}", each)),
+    }
+}
+
+/// Name of the function converting the numbers of the enum `name`, e.g. `common_block_status`
+fn enum_function(name: &str) -> String {
+    let mut segments = name.rsplit('.');
+    let name = segments.next().unwrap_or_default();
+    format!("{}_{}", segments.next().unwrap_or_default(), to_snake_case(name))
+}
+
+/// Package prefix and version of a common package, e.g. `starknet` and `v0_8_1`
+fn split_version(common_package: &str) -> (&str, &str) {
+    let package = common_package.rsplit_once('.').map_or(common_package, |(package, _)| package);
+    package.rsplit_once('.').unwrap_or(("", package))
+}
+
+fn code_list(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Struct expression opening with `open`, with a field per line
+fn write_fields(out: &mut String, fields: &[(String, String)], open: &str) {
+    let _ = writeln!(out, "{}", open);
+    for (name, expression) in fields {
+        let expression = expression.replace('\n', "\n            ");
+        let _ = writeln!(out, "            {}: {},", name, expression);
+    }
+    out.push_str("        }\n");
+}
+
+fn write_error(out: &mut String, from: &str, to: &str) {
+    let _ = writeln!(out, "/// Why a message of {} has no equivalent in {}", from, to);
+    out.push_str("#[derive(Debug, Clone, PartialEq, Eq)]\n");
+    out.push_str("pub enum ConversionError {\n");
+    let _ = writeln!(out, "    /// A field {} requires has no value", to);
+    out.push_str("    MissingField {\n");
+    out.push_str("        message: &'static str,\n");
+    out.push_str("        field: &'static str,\n");
+    out.push_str("    },\n");
+    let _ = writeln!(out, "    /// A variant of a union or an enum value {} removed", to);
+    out.push_str("    RemovedVariant {\n");
+    out.push_str("        message: &'static str,\n");
+    out.push_str("        variant: &'static str,\n");
+    out.push_str("    },\n");
+    let _ = writeln!(out, "    /// A number no value of an enum of {} has", from);
+    out.push_str("    UnknownValue { name: &'static str, value: i32 },\n");
+    out.push_str("}\n");
+    out.push('\n');
+    out.push_str("impl fmt::Display for ConversionError {\n");
+    out.push_str("    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n");
+    out.push_str("        match self {\n");
+    out.push_str("            ConversionError::MissingField { message, field } => {\n");
+    out.push_str("                write!(f, \"{} requires {}, which has no value\", message, field)\n");
+    out.push_str("            }\n");
+    out.push_str("            ConversionError::RemovedVariant { message, variant } => {\n");
+    out.push_str("                write!(f, \"{} has no {}\", message, variant)\n");
+    out.push_str("            }\n");
+    out.push_str("            ConversionError::UnknownValue { name, value } => {\n");
+    out.push_str("                write!(f, \"{} has no value numbered {}\", name, value)\n");
+    out.push_str("            }\n");
+    out.push_str("        }\n");
+    out.push_str("    }\n");
+    out.push_str("}\n");
+    out.push('\n');
+    out.push_str("impl std::error::Error for ConversionError {}\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostics::Diagnostics,
+        proto_gen::{ProtoConfig, ProtoGenerator},
+        source::{bundled_profiles, SpecVersion},
+        spec::ParseMode,
+    };
+
+    fn resolve(version: SpecVersion) -> ProtoIr {
+        let profile = bundled_profiles()
+            .into_iter()
+            .find(|profile| profile.version == version)
+            .unwrap();
+        let specs = profile
+            .raw_specs
+            .parse_full(&[], ParseMode::Lenient, &mut Diagnostics::new())
+            .unwrap();
+        let config = ProtoConfig::new(&version.to_version_string());
+        ProtoGenerator::new(config).resolve(&specs).unwrap()
+    }
+
+    #[test]
+    fn test_generate_converters() {
+        let from = resolve(SpecVersion::V0_7_1);
+        let mut to = resolve(SpecVersion::V0_8_1);
+        let converters = generate_converters(&from, &to, None);
+        assert_eq!(converters.module, "v0_7_1_to_v0_8_1");
+        assert!(converters.fallible > 0 && converters.infallible > 0);
+        // 0.8 headers require the L2 gas price
+        assert!(converters.source.contains(
+            "impl TryFrom<super::v0_7_1::common::BlockHeader> for super::v0_8_1::common::BlockHeader {"
+        ));
+        assert!(converters.source.contains(
+            "impl From<super::v0_7_1::main::GetBlockWithTxHashesRequest> for \
+             super::v0_8_1::main::GetBlockWithTxHashesRequest {"
+        ));
+        assert!(!converters.source.contains("fn common_block_status("));
+
+        // Enum values the new version removed fail to convert
+        let common = to.files.iter_mut().find(|file| file.name == "common.proto").unwrap();
+        let status = common.enums.iter_mut().find(|e| e.name == "BlockStatus").unwrap();
+        status.values.retain(|value| value.name != "BLOCK_STATUS_REJECTED");
+        let converters = generate_converters(&from, &to, None);
+        assert!(converters.source.contains("fn common_block_status(value: i32)"));
+        assert!(converters.source.contains(
+            "3 => Err(ConversionError::RemovedVariant { message: \"starknet.v0_8_1.common.BlockStatus\", \
+             variant: \"BLOCK_STATUS_REJECTED\" }),"
+        ));
+    }
+}
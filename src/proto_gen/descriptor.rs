@@ -0,0 +1,512 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    file_options::OptimizeMode,
+    source_code_info::Location,
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet, FileOptions, MethodDescriptorProto,
+    OneofDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
+};
+
+use crate::proto_gen::{
+    parser::{parse_option, OptionValue},
+    types::{ProtoEnum, ProtoFieldType, ProtoMessage, ProtoService},
+    validate::{SymbolKind, SymbolTable},
+    well_known::well_known_file,
+    ProtoFile, ProtoWriter,
+};
+
+/// Encodes `files` and the well-known files they import as a `FileDescriptorSet`, dependencies
+/// first. Source info locates definitions in the text `ProtoWriter` renders, starting at the
+/// zero-based line `first_line` returns for each file.
+pub fn file_descriptor_set(
+    files: &[ProtoFile],
+    first_line: impl Fn(&ProtoFile) -> usize,
+) -> Result<FileDescriptorSet> {
+    let table = SymbolTable::new(files);
+    let mut ordered = vec![];
+    for file in files {
+        add_with_imports(file, files, &mut ordered, &mut vec![])?;
+    }
+
+    let file = ordered
+        .into_iter()
+        .map(|file| {
+            let is_input = files.iter().any(|input| input.name == file.name);
+            let mut descriptor = file_descriptor(&table, &file)?;
+            if is_input {
+                let offset = first_line(&file) as i32;
+                let location = ProtoWriter::from(&file)
+                    .render()
+                    .locations
+                    .into_iter()
+                    .map(|mut location| {
+                        location.span[0] += offset;
+                        if location.span.len() == 4 {
+                            location.span[2] += offset;
+                        }
+                        Location {
+                            path: location.path,
+                            span: location.span,
+                            leading_comments: location.leading_comments,
+                            trailing_comments: None,
+                            leading_detached_comments: vec![],
+                        }
+                    })
+                    .collect();
+                descriptor.source_code_info = Some(SourceCodeInfo { location });
+            }
+            Ok(descriptor)
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(FileDescriptorSet { file })
+}
+
+/// Encodes `files` without source info, leaving out the well-known files they import.
+pub fn file_descriptors(files: &[ProtoFile]) -> Result<Vec<FileDescriptorProto>> {
+    let table = SymbolTable::new(files);
+    files.iter().map(|file| file_descriptor(&table, file)).collect()
+}
+
+/// Compiles every `.proto` file directly inside `dir`, e.g. the files of a previous release, with
+/// protox. Well-known imports resolve to the files bundled with protox.
+pub fn compile_proto_dir(dir: &Path) -> Result<Vec<FileDescriptorProto>> {
+    let mut names = fs::read_dir(dir)
+        .with_context(|| format!("unable to read directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("unable to read directory {}", dir.display()))?
+        .into_iter()
+        .filter(|name| Path::new(name).extension().is_some_and(|extension| extension == "proto"))
+        .map(|name| {
+            name.into_string()
+                .map_err(|name| anyhow!("invalid file name {}", name.to_string_lossy()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    names.sort();
+
+    let mut compiler = protox::Compiler::new([dir])?;
+    compiler.open_files(&names)?;
+    Ok(compiler.file_descriptor_set().file)
+}
+
+/// Appends `file` to `ordered` after the files it imports.
+fn add_with_imports(
+    file: &ProtoFile,
+    files: &[ProtoFile],
+    ordered: &mut Vec<ProtoFile>,
+    visiting: &mut Vec<String>,
+) -> Result<()> {
+    if ordered.iter().any(|added| added.name == file.name) {
+        return Ok(());
+    }
+    if visiting.contains(&file.name) {
+        bail!("{} imports itself through {}", file.name, visiting.join(" -> "));
+    }
+
+    visiting.push(file.name.clone());
+    for import in &file.imports {
+        let imported = match files.iter().find(|file| &file.name == import) {
+            Some(imported) => imported.clone(),
+            None => well_known_file(import)
+                .ok_or_else(|| anyhow!("{}: imported file {} does not exist", file.name, import))?,
+        };
+        add_with_imports(&imported, files, ordered, visiting)?;
+    }
+    visiting.pop();
+
+    ordered.push(file.clone());
+    Ok(())
+}
+
+fn file_descriptor(table: &SymbolTable, file: &ProtoFile) -> Result<FileDescriptorProto> {
+    let resolve = |scope: &str, reference: &str, user: &dyn Fn() -> String| {
+        table
+            .resolve(scope, reference)
+            .map(|(full_name, kind, _)| (format!(".{}", full_name), kind))
+            .ok_or_else(|| {
+                anyhow!("{}: {} refers to undefined type {}", file.name, user(), reference)
+            })
+    };
+
+    Ok(FileDescriptorProto {
+        name: Some(file.name.clone()),
+        package: (!file.package.is_empty()).then(|| file.package.clone()),
+        dependency: file.imports.clone(),
+        message_type: file
+            .messages
+            .iter()
+            .map(|message| message_descriptor(&resolve, &file.package, message))
+            .collect::<Result<_>>()?,
+        enum_type: file.enums.iter().map(enum_descriptor).collect(),
+        service: file
+            .services
+            .iter()
+            .map(|service| service_descriptor(&resolve, &file.package, service))
+            .collect::<Result<_>>()?,
+        options: file_options(&file.options)?,
+        syntax: Some("proto3".to_owned()),
+        ..Default::default()
+    })
+}
+
+type Resolve<'a> = dyn Fn(&str, &str, &dyn Fn() -> String) -> Result<(String, SymbolKind)> + 'a;
+
+fn message_descriptor(
+    resolve: &Resolve,
+    scope: &str,
+    message: &ProtoMessage,
+) -> Result<DescriptorProto> {
+    let full_name = if scope.is_empty() {
+        message.name.clone()
+    } else {
+        format!("{}.{}", scope, message.name)
+    };
+
+    let mut oneof_decl = message
+        .declared_oneofs()
+        .map(|oneof| OneofDescriptorProto {
+            name: Some(oneof.name.clone()),
+            options: None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut field = vec![];
+    for (proto_field, oneof) in message.declared_fields() {
+        let (r#type, type_name) = match &proto_field.field_type {
+            ProtoFieldType::String => (Type::String, None),
+            ProtoFieldType::Int32 => (Type::Int32, None),
+            ProtoFieldType::Int64 => (Type::Int64, None),
+            ProtoFieldType::Uint32 => (Type::Uint32, None),
+            ProtoFieldType::Uint64 => (Type::Uint64, None),
+            ProtoFieldType::Bool => (Type::Bool, None),
+            ProtoFieldType::Bytes => (Type::Bytes, None),
+            ProtoFieldType::Double => (Type::Double, None),
+            ProtoFieldType::Float => (Type::Float, None),
+            ProtoFieldType::Message(reference) | ProtoFieldType::Enum(reference) => {
+                let user = || format!("field {}.{}", full_name, proto_field.name);
+                match resolve(&full_name, reference, &user)? {
+                    (type_name, SymbolKind::Enum) => (Type::Enum, Some(type_name)),
+                    (type_name, _) => (Type::Message, Some(type_name)),
+                }
+            }
+            ProtoFieldType::Any => {
+                let user = || format!("field {}.{}", full_name, proto_field.name);
+                (Type::Message, Some(resolve("", "google.protobuf.Any", &user)?.0))
+            }
+        };
+
+        // proto3 `optional` fields get a synthetic oneof, declared after the real ones
+        let proto3_optional = oneof.is_none() && proto_field.optional && !proto_field.repeated;
+        let oneof_index = match oneof {
+            Some(index) => Some(index as i32),
+            None if proto3_optional => {
+                oneof_decl.push(OneofDescriptorProto {
+                    name: Some(format!("_{}", proto_field.name)),
+                    options: None,
+                });
+                Some(oneof_decl.len() as i32 - 1)
+            }
+            None => None,
+        };
+
+        field.push(FieldDescriptorProto {
+            name: Some(proto_field.name.clone()),
+            number: Some(proto_field.number as i32),
+            label: Some(if proto_field.repeated {
+                Label::Repeated
+            } else {
+                Label::Optional
+            } as i32),
+            r#type: Some(r#type as i32),
+            type_name,
+            oneof_index,
+            json_name: Some(proto_field.json_name()),
+            proto3_optional: proto3_optional.then_some(true),
+            ..Default::default()
+        });
+    }
+
+    Ok(DescriptorProto {
+        name: Some(message.name.clone()),
+        field,
+        nested_type: message
+            .nested_messages
+            .iter()
+            .map(|nested| message_descriptor(resolve, &full_name, nested))
+            .collect::<Result<_>>()?,
+        enum_type: message.nested_enums.iter().map(enum_descriptor).collect(),
+        oneof_decl,
+        ..Default::default()
+    })
+}
+
+fn enum_descriptor(proto_enum: &ProtoEnum) -> EnumDescriptorProto {
+    EnumDescriptorProto {
+        name: Some(proto_enum.name.clone()),
+        value: proto_enum
+            .values
+            .iter()
+            .map(|value| EnumValueDescriptorProto {
+                name: Some(value.name.clone()),
+                number: Some(value.number),
+                options: None,
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn service_descriptor(
+    resolve: &Resolve,
+    package: &str,
+    service: &ProtoService,
+) -> Result<ServiceDescriptorProto> {
+    let method = service
+        .rpcs
+        .iter()
+        .map(|rpc| {
+            let user = |what: &str| format!("{} type of rpc {}.{}", what, service.name, rpc.name);
+            let input_type = resolve(package, &rpc.request_type, &|| user("request"))?.0;
+            let output_type = resolve(package, &rpc.response_type, &|| user("response"))?.0;
+            Ok(MethodDescriptorProto {
+                name: Some(rpc.name.clone()),
+                input_type: Some(input_type),
+                output_type: Some(output_type),
+                options: None,
+                client_streaming: rpc.client_streaming.then_some(true),
+                server_streaming: rpc.server_streaming.then_some(true),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(ServiceDescriptorProto {
+        name: Some(service.name.clone()),
+        method,
+        options: None,
+    })
+}
+
+fn file_options(options: &[String]) -> Result<Option<FileOptions>> {
+    if options.is_empty() {
+        return Ok(None);
+    }
+
+    let mut file_options = FileOptions::default();
+    for option in options {
+        let (name, value) = parse_option(option)?;
+        let string = || match &value {
+            OptionValue::String(value) => Ok(Some(value.clone())),
+            _ => Err(anyhow!("option {} must be a string", name)),
+        };
+        let bool = || match &value {
+            OptionValue::Identifier(value) if value == "true" => Ok(Some(true)),
+            OptionValue::Identifier(value) if value == "false" => Ok(Some(false)),
+            _ => Err(anyhow!("option {} must be true or false", name)),
+        };
+
+        match name.as_str() {
+            "java_package" => file_options.java_package = string()?,
+            "java_outer_classname" => file_options.java_outer_classname = string()?,
+            "java_multiple_files" => file_options.java_multiple_files = bool()?,
+            "java_string_check_utf8" => file_options.java_string_check_utf8 = bool()?,
+            "go_package" => file_options.go_package = string()?,
+            "cc_enable_arenas" => file_options.cc_enable_arenas = bool()?,
+            "objc_class_prefix" => file_options.objc_class_prefix = string()?,
+            "csharp_namespace" => file_options.csharp_namespace = string()?,
+            "swift_prefix" => file_options.swift_prefix = string()?,
+            "php_class_prefix" => file_options.php_class_prefix = string()?,
+            "php_namespace" => file_options.php_namespace = string()?,
+            "php_metadata_namespace" => file_options.php_metadata_namespace = string()?,
+            "ruby_package" => file_options.ruby_package = string()?,
+            "deprecated" => file_options.deprecated = bool()?,
+            "optimize_for" => {
+                let mode = match &value {
+                    OptionValue::Identifier(mode) => OptimizeMode::from_str_name(mode),
+                    _ => None,
+                };
+                let mode = mode.ok_or_else(|| anyhow!("invalid optimize_for mode"))?;
+                file_options.optimize_for = Some(mode as i32);
+            }
+            _ => bail!("unsupported file option {}", name),
+        }
+    }
+
+    Ok(Some(file_options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::types::{ProtoEnumValue, ProtoField, ProtoOneof, ProtoRpc};
+
+    fn field(name: &str, field_type: ProtoFieldType, number: u32) -> ProtoField {
+        ProtoField {
+            name: name.to_string(),
+            field_type,
+            number,
+            json_name: None,
+            comment: None,
+            optional: false,
+            repeated: false,
+            oneof_name: None,
+        }
+    }
+
+    fn files() -> Vec<ProtoFile> {
+        let mut common = ProtoWriter::new("starknet.v0_8_1.common");
+        common.add_import("google/protobuf/timestamp.proto");
+        common.add_enum(&ProtoEnum {
+            name: "TxnStatus".to_string(),
+            values: vec![
+                ProtoEnumValue {
+                    name: "TXN_STATUS_UNSPECIFIED".to_string(),
+                    number: 0,
+                    comment: None,
+                },
+                ProtoEnumValue {
+                    name: "ACCEPTED_ON_L2".to_string(),
+                    number: 1,
+                    comment: Some("Accepted by the sequencer".to_string()),
+                },
+            ],
+            comment: Some("Status of a transaction".to_string()),
+        });
+        common.add_message(&ProtoMessage {
+            name: "Block".to_string(),
+            fields: vec![
+                ProtoField {
+                    json_name: Some("block_hash".to_string()),
+                    comment: Some("Hash of the block\nas a felt".to_string()),
+                    ..field("block_hash", ProtoFieldType::String, 3)
+                },
+                ProtoField {
+                    repeated: true,
+                    ..field("statuses", ProtoFieldType::Enum("TxnStatus".to_string()), 4)
+                },
+                ProtoField {
+                    optional: true,
+                    ..field(
+                        "timestamp",
+                        ProtoFieldType::Message("google.protobuf.Timestamp".to_string()),
+                        5,
+                    )
+                },
+            ],
+            nested_messages: vec![],
+            nested_enums: vec![],
+            oneofs: vec![
+                ProtoOneof {
+                    name: "id".to_string(),
+                    fields: vec![
+                        field("hash", ProtoFieldType::String, 1),
+                        field("number", ProtoFieldType::Uint64, 2),
+                    ],
+                    comment: None,
+                },
+                ProtoOneof {
+                    name: "empty".to_string(),
+                    fields: vec![],
+                    comment: None,
+                },
+            ],
+            comment: Some("A block".to_string()),
+            options: vec![],
+        });
+
+        let mut main = ProtoWriter::new("starknet.v0_8_1.main");
+        main.add_import("common.proto");
+        main.add_service(&ProtoService {
+            name: "StarknetMainService".to_string(),
+            rpcs: vec![ProtoRpc {
+                name: "SubscribeBlocks".to_string(),
+                request_type: "starknet.v0_8_1.common.Block".to_string(),
+                response_type: "BlockResponse".to_string(),
+                comment: Some("Streams blocks".to_string()),
+                client_streaming: false,
+                server_streaming: true,
+            }],
+            comment: None,
+        });
+        main.add_message(&ProtoMessage {
+            name: "BlockResponse".to_string(),
+            fields: vec![field(
+                "result",
+                ProtoFieldType::Message("starknet.v0_8_1.common.Block".to_string()),
+                1,
+            )],
+            nested_messages: vec![],
+            nested_enums: vec![],
+            oneofs: vec![],
+            comment: None,
+            options: vec![],
+        });
+
+        vec![common.to_file("common.proto"), main.to_file("main.proto")]
+    }
+
+    #[test]
+    fn test_descriptor_set_matches_compiled_proto_text() {
+        let files = files();
+        let header = "// Generated file\n\n";
+
+        let dir = tempfile::tempdir().unwrap();
+        for file in &files {
+            let text = format!("{}{}", header, ProtoWriter::from(file));
+            std::fs::write(dir.path().join(&file.name), text).unwrap();
+        }
+        let mut compiler = protox::Compiler::new([dir.path()]).unwrap();
+        compiler.include_source_info(true);
+        compiler.open_files(["common.proto", "main.proto"]).unwrap();
+        let expected = compiler.file_descriptor_set();
+
+        let set = file_descriptor_set(&files, |_| header.lines().count()).unwrap();
+        let names = set.file.iter().map(|file| file.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["google/protobuf/timestamp.proto", "common.proto", "main.proto"]);
+
+        for expected in expected.file {
+            let mut actual = set
+                .file
+                .iter()
+                .find(|file| file.name == expected.name)
+                .unwrap()
+                .clone();
+
+            // Every location we emit has to match the one protox found for the same element
+            let locations = actual.source_code_info.take().unwrap().location;
+            let expected_locations = expected.source_code_info.clone().unwrap().location;
+            for location in locations {
+                assert!(
+                    expected_locations.iter().any(|expected| expected.path == location.path
+                        && expected.span == location.span
+                        && expected.leading_comments == location.leading_comments),
+                    "{:?} not found in {}",
+                    location,
+                    expected.name()
+                );
+            }
+
+            let expected = FileDescriptorProto {
+                source_code_info: None,
+                ..expected
+            };
+            pretty_assertions::assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_unresolved_types_are_rejected() {
+        let mut files = files();
+        files[1].messages[0].fields[0].field_type = ProtoFieldType::Message("AllOf".to_string());
+
+        let error = file_descriptor_set(&files, |_| 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "main.proto: field starknet.v0_8_1.main.BlockResponse.result refers to undefined type \
+             AllOf"
+        );
+    }
+}
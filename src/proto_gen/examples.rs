@@ -0,0 +1,493 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    proto_gen::{
+        json_mapping::{JsonMapping, MAX_DEPTH},
+        service::find_rpc,
+        types::{ProtoMessage, ProtoRpc, ProtoService},
+        ProtoConfig, ProtoFile,
+    },
+    spec::{Method, Primitive, Schema, Specification},
+};
+
+/// References followed before optional properties are left out and arrays are synthesized
+/// empty, so that recursive schemas end
+const SHALLOW_DEPTH: usize = 8;
+
+/// Strings tried in order for a `pattern`, covering the hex and base64 formats of the spec
+const PATTERN_CANDIDATES: &[&str] = &[
+    "0x1",
+    "0x01",
+    "0x0000000000000000000000000000000000000001",
+    "YQ==",
+    "1",
+    "a",
+];
+
+/// Where the values of an example come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExampleSource {
+    /// An OpenRPC example of the method
+    Spec,
+    /// Synthesized from the schemas of the params and result
+    Synthesized,
+}
+
+/// Call of a method in the JSON of the spec, next to the proto3 JSON of its generated request
+/// and response messages
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodExample {
+    pub name: String,
+    pub source: ExampleSource,
+    /// Params by name
+    pub params: Map<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Proto3 JSON of the request message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<Value>,
+    /// Proto3 JSON of the response message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    /// Why the params or result have no proto3 JSON form
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub proto_errors: Vec<String>,
+}
+
+/// Examples of a method along with the rpc generated for it
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodExamples {
+    /// gRPC path of the rpc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc: Option<String>,
+    pub examples: Vec<MethodExample>,
+}
+
+/// Choices made while synthesizing a value
+#[derive(Debug, Clone, Copy)]
+struct Choice {
+    /// Variant of each union and value of each enum, modulo their number
+    branch: usize,
+    /// Whether optional params and properties are included
+    optional: bool,
+}
+
+/// Produces examples of the methods of a spec along with their proto3 JSON form in the files
+/// generated from it
+pub struct ExampleGenerator<'a> {
+    specs: &'a Specification,
+    config: &'a ProtoConfig,
+    files: &'a [ProtoFile],
+    mapping: JsonMapping,
+}
+
+impl<'a> ExampleGenerator<'a> {
+    pub fn new(
+        specs: &'a Specification,
+        config: &'a ProtoConfig,
+        files: &'a [ProtoFile],
+    ) -> Result<Self> {
+        Ok(Self {
+            specs,
+            config,
+            files,
+            mapping: JsonMapping::new(specs, files)?,
+        })
+    }
+
+    /// OpenRPC examples of `method`, followed by synthesized ones: one with every optional
+    /// value for each variant of the largest union it reaches, and one with only required values.
+    /// Examples that can't be synthesized, e.g. for a union without variants, are errors.
+    pub fn examples(&self, method: &'a Method) -> Vec<Result<MethodExample>> {
+        let mut examples = vec![];
+
+        for pairing in &method.examples {
+            let params = pairing
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), param.value.clone()))
+                .collect();
+            let result = pairing.result.as_ref().map(|result| result.value.clone());
+            let example = self.example(method, &pairing.name, ExampleSource::Spec, params, result);
+            examples.push(Ok(example));
+        }
+
+        let branches = self.branch_count(method);
+        let choices = (0..branches)
+            .map(|branch| {
+                let name = match branches {
+                    1 => "all".to_owned(),
+                    _ => format!("all-branch-{}", branch + 1),
+                };
+                (name, Choice { branch, optional: true })
+            })
+            .chain([("required".to_owned(), Choice { branch: 0, optional: false })]);
+        let mut synthesized = vec![];
+        for (name, choice) in choices {
+            let example = match self.synthesize_example(method, choice) {
+                // Choices that don't affect the method give the same values
+                Ok(values) if synthesized.contains(&values) => continue,
+                Ok((params, result)) => {
+                    synthesized.push((params.clone(), result.clone()));
+                    Ok(self.example(method, &name, ExampleSource::Synthesized, params, result))
+                }
+                Err(err) => Err(err.context(format!("{} example of {}", name, method.name))),
+            };
+            examples.push(example);
+        }
+
+        examples
+    }
+
+    fn synthesize_example(
+        &self,
+        method: &'a Method,
+        choice: Choice,
+    ) -> Result<(Map<String, Value>, Option<Value>)> {
+        let mut params = Map::new();
+        for param in &method.params {
+            if choice.optional || param.required {
+                let value = self
+                    .synthesize(&param.schema, choice, 0)
+                    .with_context(|| format!("param `{}`", param.name))?;
+                params.insert(param.name.clone(), value);
+            }
+        }
+        let result = match &method.result {
+            Some(result) => Some(self.synthesize(&result.schema, choice, 0).context("result")?),
+            None => None,
+        };
+        Ok((params, result))
+    }
+
+    fn example(
+        &self,
+        method: &'a Method,
+        name: &str,
+        source: ExampleSource,
+        params: Map<String, Value>,
+        result: Option<Value>,
+    ) -> MethodExample {
+        let mut proto_errors = vec![];
+        let (request, response) = match self.rpc_messages(method) {
+            Some((request_message, response_message)) => {
+                let mut convert = |converted: Result<Value>| {
+                    converted
+                        .map_err(|err| proto_errors.push(format!("{:#}", err)))
+                        .ok()
+                };
+                let request =
+                    convert(self.mapping.request_to_proto_json(request_message, method, &params));
+                let response = convert(self.mapping.response_to_proto_json(
+                    response_message,
+                    method,
+                    result.as_ref(),
+                ));
+                (request, response)
+            }
+            None => {
+                proto_errors.push(format!("no rpc is generated for {}", method.name));
+                (None, None)
+            }
+        };
+
+        MethodExample {
+            name: name.to_owned(),
+            source,
+            params,
+            result,
+            request,
+            response,
+            proto_errors,
+        }
+    }
+
+    /// gRPC path of the rpc generated for `method`, e.g.
+    /// `/starknet.v0_8_1.main.StarknetMainService/BlockNumber`
+    pub fn rpc_path(&self, method: &Method) -> Option<String> {
+        let (file, service, rpc) = self.rpc(method)?;
+        Some(format!("/{}.{}/{}", file.package, service.name, rpc.name))
+    }
+
+    fn rpc(&self, method: &Method) -> Option<(&'a ProtoFile, &'a ProtoService, &'a ProtoRpc)> {
+        find_rpc(self.files, self.config, &method.name)
+    }
+
+    /// Request and response messages of the rpc generated for `method`
+    fn rpc_messages(&self, method: &Method) -> Option<(&'a ProtoMessage, &'a ProtoMessage)> {
+        let (file, _, rpc) = self.rpc(method)?;
+        let find = |name: &str| file.messages.iter().find(|message| message.name == name);
+        Some((find(&rpc.request_type)?, find(&rpc.response_type)?))
+    }
+
+    /// Number of variants of the largest union the params or result of `method` reach
+    fn branch_count(&self, method: &'a Method) -> usize {
+        let mut seen = HashSet::new();
+        let mut count = 1;
+        let schemas = method.params.iter().map(|param| &param.schema);
+        for schema in schemas.chain(method.result.iter().map(|result| &result.schema)) {
+            self.count_branches(schema, &mut seen, &mut count);
+        }
+        count
+    }
+
+    fn count_branches(&self, schema: &'a Schema, seen: &mut HashSet<&'a str>, count: &mut usize) {
+        match schema {
+            Schema::Ref(reference) => {
+                if !seen.insert(reference.name()) {
+                    return;
+                }
+                if let Some(target) = self.specs.components.schemas.get(reference.name()) {
+                    self.count_branches(target, seen, count);
+                }
+            }
+            Schema::OneOf(one_of) => {
+                *count = (*count).max(one_of.one_of.len());
+                for variant in &one_of.one_of {
+                    self.count_branches(variant, seen, count);
+                }
+            }
+            Schema::AllOf(all_of) => {
+                for member in &all_of.all_of {
+                    self.count_branches(member, seen, count);
+                }
+            }
+            Schema::Primitive(Primitive::Array(array)) => {
+                self.count_branches(&array.items, seen, count)
+            }
+            Schema::Primitive(Primitive::Object(object)) => {
+                for property in object.properties.values() {
+                    self.count_branches(property, seen, count);
+                }
+            }
+            Schema::Primitive(_) => {}
+        }
+    }
+
+    /// Value valid against `schema`, `depth` being the number of references followed to it
+    fn synthesize(&self, schema: &'a Schema, choice: Choice, depth: usize) -> Result<Value> {
+        let shallow = depth >= SHALLOW_DEPTH;
+        match schema {
+            Schema::Ref(reference) => {
+                if depth >= MAX_DEPTH {
+                    bail!("{} nests more than {} references deep", reference.name(), MAX_DEPTH);
+                }
+                let target = self
+                    .specs
+                    .components
+                    .schemas
+                    .get(reference.name())
+                    .ok_or_else(|| anyhow!("dangling $ref: {}", reference.ref_field))?;
+                self.synthesize(target, choice, depth + 1)
+            }
+            Schema::OneOf(one_of) => {
+                if one_of.one_of.is_empty() {
+                    bail!("oneOf without variants");
+                }
+                let variant = &one_of.one_of[choice.branch % one_of.one_of.len()];
+                self.synthesize(variant, choice, depth)
+            }
+            Schema::AllOf(all_of) => {
+                let mut object = Map::new();
+                for member in &all_of.all_of {
+                    match self.synthesize(member, choice, depth)? {
+                        Value::Object(properties) => object.extend(properties),
+                        _ => bail!("allOf member is not an object"),
+                    }
+                }
+                Ok(Value::Object(object))
+            }
+            Schema::Primitive(Primitive::Object(object)) => {
+                let mut properties = Map::new();
+                for (name, property) in &object.properties {
+                    if object.required.contains(name) || (choice.optional && !shallow) {
+                        properties.insert(name.clone(), self.synthesize(property, choice, depth)?);
+                    }
+                }
+                Ok(Value::Object(properties))
+            }
+            Schema::Primitive(Primitive::Array(array)) => match shallow {
+                true => Ok(json!([])),
+                false => Ok(json!([self.synthesize(&array.items, choice, depth)?])),
+            },
+            Schema::Primitive(Primitive::Boolean(_)) => Ok(Value::Bool(true)),
+            Schema::Primitive(Primitive::Integer(integer)) => {
+                Ok(json!(integer.minimum.unwrap_or(0).max(1)))
+            }
+            Schema::Primitive(Primitive::String(string)) => {
+                if let Some(values) = string.r#enum.as_ref().filter(|values| !values.is_empty()) {
+                    return Ok(json!(values[choice.branch % values.len()]));
+                }
+                match &string.pattern {
+                    Some(pattern) => {
+                        let regex = self
+                            .mapping
+                            .regex(pattern)
+                            .ok_or_else(|| anyhow!("unknown pattern `{}`", pattern))?;
+                        PATTERN_CANDIDATES
+                            .iter()
+                            .find(|candidate| regex.is_match(candidate))
+                            .map(|candidate| json!(candidate))
+                            .ok_or_else(|| anyhow!("no example string matches `{}`", pattern))
+                    }
+                    None => Ok(json!("example")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoGenerator;
+
+    fn create_test_specs() -> Specification {
+        serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [{
+                    "name": "starknet_getBlock",
+                    "summary": "Block by id",
+                    "params": [
+                        {
+                            "name": "block_id",
+                            "required": true,
+                            "schema": { "$ref": "#/components/schemas/BLOCK_ID" }
+                        },
+                        {
+                            "name": "status",
+                            "schema": { "$ref": "#/components/schemas/STATUS" }
+                        }
+                    ],
+                    "result": {
+                        "name": "result",
+                        "schema": { "$ref": "#/components/schemas/BLOCK" }
+                    },
+                    "examples": [{
+                        "name": "latest",
+                        "params": [{ "name": "block_id", "value": { "block_number": 7 } }],
+                        "result": {
+                            "name": "result",
+                            "value": { "hash": "0xabc", "number": 7, "status": "ACCEPTED" }
+                        }
+                    }]
+                }],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "BLOCK_NUMBER": { "type": "integer", "minimum": 0 },
+                        "FELT": {
+                            "type": "string",
+                            "pattern": "^0x(0|[a-fA-F1-9]{1}[a-fA-F0-9]{0,62})$"
+                        },
+                        "ETH_ADDRESS": { "type": "string", "pattern": "^0x[a-fA-F0-9]{40}$" },
+                        "STATUS": { "type": "string", "enum": ["ACCEPTED", "REJECTED"] },
+                        "BLOCK": {
+                            "type": "object",
+                            "properties": {
+                                "hash": { "$ref": "#/components/schemas/FELT" },
+                                "number": { "$ref": "#/components/schemas/BLOCK_NUMBER" },
+                                "status": { "$ref": "#/components/schemas/STATUS" },
+                                "sequencer": { "$ref": "#/components/schemas/ETH_ADDRESS" }
+                            },
+                            "required": ["hash", "number", "status"]
+                        },
+                        "BLOCK_ID": {
+                            "oneOf": [
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "block_hash": { "$ref": "#/components/schemas/FELT" }
+                                    },
+                                    "required": ["block_hash"]
+                                },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "block_number": {
+                                            "$ref": "#/components/schemas/BLOCK_NUMBER"
+                                        }
+                                    },
+                                    "required": ["block_number"]
+                                }
+                            ]
+                        }
+                    },
+                    "errors": {}
+                }
+            }"##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_examples_are_valid_and_converted_to_proto_json() {
+        let specs = create_test_specs();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+        let method = &specs.methods[0];
+
+        let generator = ExampleGenerator::new(&specs, &config, &result.protos).unwrap();
+        assert_eq!(
+            generator.rpc_path(method).unwrap(),
+            "/starknet.v0_8_1.main.StarknetMainService/GetBlock"
+        );
+        let examples = generator
+            .examples(method)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let names = examples.iter().map(|example| example.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["latest", "all-branch-1", "all-branch-2", "required"]);
+        assert_eq!(examples[0].source, ExampleSource::Spec);
+        assert_eq!(
+            examples[0].request.as_ref().unwrap(),
+            &json!({ "block_id": { "variant2": { "block_number": "7" } } })
+        );
+
+        // Every synthesized value is valid, and each branch of the union is used once
+        for example in &examples[1..] {
+            assert_eq!(example.source, ExampleSource::Synthesized);
+            assert!(example.proto_errors.is_empty(), "{:?}", example.proto_errors);
+            for param in &method.params {
+                if let Some(value) = example.params.get(&param.name) {
+                    assert!(generator.mapping.matches(value, &param.schema), "{}", value);
+                }
+            }
+            let result_schema = &method.result.as_ref().unwrap().schema;
+            assert!(generator.mapping.matches(example.result.as_ref().unwrap(), result_schema));
+        }
+        assert_eq!(examples[1].params["block_id"], json!({ "block_hash": "0x1" }));
+        assert_eq!(examples[2].params["block_id"], json!({ "block_number": 1 }));
+        assert_eq!(
+            examples[1].result.as_ref().unwrap()["sequencer"],
+            "0x0000000000000000000000000000000000000001"
+        );
+
+        // Optional params and properties are only in the examples with every value
+        assert!(!examples[3].params.contains_key("status"));
+        assert!(examples[3].result.as_ref().unwrap().get("sequencer").is_none());
+
+        // Defaults are left out, integers written as strings and enums by their proto names
+        assert_eq!(
+            examples[2].response.as_ref().unwrap(),
+            &json!({
+                "result": {
+                    "hash": "0x1",
+                    "number": "1",
+                    "status": "STATUS_REJECTED",
+                    "sequencer": "0x0000000000000000000000000000000000000001"
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    proto_gen::{
+        json_shape::{object_properties, Property},
+        types::{ProtoEnum, ProtoField, ProtoFieldType, ProtoMessage},
+        writer::to_proto_name,
+        ProtoFile,
+    },
+    refs::resolve_schema,
+    spec::{Method, Primitive, Schema, Specification},
+};
+
+/// References followed before giving up on a schema
+pub(crate) const MAX_DEPTH: usize = 32;
+
+/// Maps the JSON of a spec to the proto3 JSON of the messages generated from it
+#[derive(Debug, Clone)]
+pub struct JsonMapping {
+    schemas: IndexMap<String, Schema>,
+    /// Types by their unqualified name; generated names are unique across files
+    messages: HashMap<String, ProtoMessage>,
+    enums: HashMap<String, ProtoEnum>,
+    /// Compiled `pattern`s of the spec
+    patterns: HashMap<String, Regex>,
+}
+
+impl JsonMapping {
+    /// Mapping between `specs` and `files`, generated from it. Fails if a `pattern` of the spec
+    /// isn't a valid regular expression.
+    pub fn new(specs: &Specification, files: &[ProtoFile]) -> Result<Self> {
+        let mut messages = HashMap::new();
+        let mut enums = HashMap::new();
+        for file in files {
+            for message in &file.messages {
+                messages.insert(message.name.clone(), message.clone());
+            }
+            for proto_enum in &file.enums {
+                enums.insert(proto_enum.name.clone(), proto_enum.clone());
+            }
+        }
+
+        let mut pattern_list = HashSet::new();
+        let methods = specs
+            .methods
+            .iter()
+            .chain(specs.extensions.iter().flat_map(|extension| &extension.methods));
+        for method in methods {
+            for param in &method.params {
+                collect_patterns(&param.schema, &mut pattern_list);
+            }
+            if let Some(result) = &method.result {
+                collect_patterns(&result.schema, &mut pattern_list);
+            }
+        }
+        for schema in specs.components.schemas.values() {
+            collect_patterns(schema, &mut pattern_list);
+        }
+        let patterns = pattern_list
+            .into_iter()
+            .map(|pattern| {
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("invalid pattern `{}`", pattern))?;
+                Ok((pattern.to_owned(), regex))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            schemas: specs.components.schemas.clone(),
+            messages,
+            enums,
+            patterns,
+        })
+    }
+
+    /// Follows references to the schema they name, leaving dangling ones as they are
+    pub(crate) fn resolve<'s>(&'s self, schema: &'s Schema) -> &'s Schema {
+        resolve_schema(&self.schemas, schema)
+    }
+
+    pub(crate) fn schema(&self, name: &str) -> Option<&Schema> {
+        self.schemas.get(name)
+    }
+
+    /// Properties of an object or `allOf` schema along with its `allOf` members referencing other
+    /// schemas
+    pub(crate) fn properties<'s>(
+        &'s self,
+        schema: &'s Schema,
+    ) -> Option<(Vec<Property<'s>>, Vec<&'s str>)> {
+        object_properties(&self.schemas, schema)
+    }
+
+    /// Message or enum named by a field type, qualified or not
+    pub(crate) fn message(&self, name: &str) -> Option<&ProtoMessage> {
+        self.messages.get(name.rsplit('.').next().unwrap_or(name))
+    }
+
+    pub(crate) fn proto_enum(&self, name: &str) -> Option<&ProtoEnum> {
+        self.enums.get(name.rsplit('.').next().unwrap_or(name))
+    }
+
+    pub(crate) fn regex(&self, pattern: &str) -> Option<&Regex> {
+        self.patterns.get(pattern)
+    }
+
+    /// Whether `value` is valid against `schema`, taking `oneOf` as `anyOf`
+    pub fn matches(&self, value: &Value, schema: &Schema) -> bool {
+        match self.resolve(schema) {
+            Schema::Ref(_) => false,
+            Schema::OneOf(one_of) => {
+                one_of.one_of.iter().any(|variant| self.matches(value, variant))
+            }
+            Schema::AllOf(all_of) => all_of.all_of.iter().all(|member| self.matches(value, member)),
+            Schema::Primitive(Primitive::Object(object)) => {
+                let Some(properties) = value.as_object() else {
+                    return false;
+                };
+                object.required.iter().all(|name| properties.contains_key(name))
+                    && object.properties.iter().all(|(name, property)| {
+                        properties
+                            .get(name)
+                            .is_none_or(|value| self.matches(value, property))
+                    })
+            }
+            Schema::Primitive(Primitive::Array(array)) => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|item| self.matches(item, &array.items))),
+            Schema::Primitive(Primitive::Boolean(_)) => value.is_boolean(),
+            Schema::Primitive(Primitive::Integer(integer)) => {
+                let minimum = integer.minimum.unwrap_or(i32::MIN);
+                value.as_i64().map_or(value.is_u64(), |value| value >= minimum.into())
+            }
+            Schema::Primitive(Primitive::String(string)) => {
+                let Some(value) = value.as_str() else {
+                    return false;
+                };
+                let in_enum = string
+                    .r#enum
+                    .as_ref()
+                    .is_none_or(|values| values.iter().any(|allowed| allowed == value));
+                let matches_pattern = match &string.pattern {
+                    Some(pattern) => self.regex(pattern).is_some_and(|regex| regex.is_match(value)),
+                    None => true,
+                };
+                in_enum && matches_pattern
+            }
+        }
+    }
+
+    /// Names of the properties objects valid against `schema` can have, in any variant
+    pub(crate) fn property_names<'s>(
+        &'s self,
+        schema: &'s Schema,
+        names: &mut HashSet<&'s str>,
+        depth: usize,
+    ) {
+        if depth >= MAX_DEPTH {
+            return;
+        }
+        match self.resolve(schema) {
+            Schema::Primitive(Primitive::Object(object)) => {
+                names.extend(object.properties.keys().map(String::as_str))
+            }
+            Schema::OneOf(one_of) => {
+                for variant in &one_of.one_of {
+                    self.property_names(variant, names, depth + 1);
+                }
+            }
+            Schema::AllOf(all_of) => {
+                for member in &all_of.all_of {
+                    self.property_names(member, names, depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Proto3 JSON of the request `message` of `method` called with `params` by name
+    pub fn request_to_proto_json(
+        &self,
+        message: &ProtoMessage,
+        method: &Method,
+        params: &Map<String, Value>,
+    ) -> Result<Value> {
+        let mut request = Map::new();
+        for (name, value) in params {
+            let param = method
+                .params
+                .iter()
+                .find(|param| &param.name == name)
+                .ok_or_else(|| anyhow!("`{}` is not a param of {}", name, method.name))?;
+            let field = message
+                .fields
+                .iter()
+                .find(|field| field.json_name() == *name)
+                .ok_or_else(|| anyhow!("param `{}` has no field in {}", name, message.name))?;
+            let converted = self
+                .field_to_proto_json(field, &param.schema, value)
+                .with_context(|| format!("{}.{}", message.name, field.name))?;
+            self.insert_unless_default(&mut request, field, converted);
+        }
+        Ok(Value::Object(request))
+    }
+
+    /// Proto3 JSON of the response `message` of `method` returning `result`
+    pub fn response_to_proto_json(
+        &self,
+        message: &ProtoMessage,
+        method: &Method,
+        result: Option<&Value>,
+    ) -> Result<Value> {
+        let mut response = Map::new();
+        if let (Some(spec_result), Some(value)) = (&method.result, result) {
+            let field = message
+                .fields
+                .iter()
+                .find(|field| field.name == "result")
+                .ok_or_else(|| anyhow!("the result has no field in {}", message.name))?;
+            let converted = self
+                .field_to_proto_json(field, &spec_result.schema, value)
+                .with_context(|| format!("{}.{}", message.name, field.name))?;
+            self.insert_unless_default(&mut response, field, converted);
+        }
+        Ok(Value::Object(response))
+    }
+
+    /// Proto3 JSON of `message` holding `value`, valid against `schema`
+    pub fn message_to_proto_json(
+        &self,
+        message: &ProtoMessage,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<Value> {
+        if message.name == "Object" {
+            return Ok(json!({ "data": value.to_string() }));
+        }
+
+        let schema = self.resolve(schema);
+        if let (Schema::OneOf(one_of), Some(oneof)) = (schema, message.declared_oneofs().next()) {
+            // Variants can overlap, the first one the value converts to is taken
+            let mut first_error = None;
+            for (field, variant) in oneof.fields.iter().zip(&one_of.one_of) {
+                if !self.matches(value, variant) {
+                    continue;
+                }
+                match self.field_to_proto_json(field, variant, value) {
+                    Ok(converted) => return Ok(json!({ field.json_name(): converted })),
+                    Err(err) => {
+                        let err = err.context(format!("{}.{}", message.name, field.name));
+                        first_error.get_or_insert(err);
+                    }
+                }
+            }
+            return Err(first_error.unwrap_or_else(|| {
+                anyhow!("the value matches no variant of {}", message.name)
+            }));
+        }
+
+        let Some((properties, members)) = self.properties(schema) else {
+            // Scalars and arrays are wrapped in a message with a single field
+            let field = match message.fields.as_slice() {
+                [field] => field,
+                _ => bail!("{} doesn't wrap a single value", message.name),
+            };
+            let converted = self
+                .field_to_proto_json(field, schema, value)
+                .with_context(|| format!("{}.{}", message.name, field.name))?;
+            let mut wrapper = Map::new();
+            self.insert_unless_default(&mut wrapper, field, converted);
+            return Ok(Value::Object(wrapper));
+        };
+
+        let object = value.as_object().context("expected an object")?;
+        let mut converted_object = Map::new();
+        let mut converted_properties = HashSet::new();
+        for field in &message.fields {
+            let json_name = field.json_name();
+            let converted = if let Some(property) =
+                properties.iter().find(|property| property.name == json_name)
+            {
+                let Some(value) = object.get(property.name) else {
+                    continue;
+                };
+                converted_properties.insert(property.name.to_owned());
+                self.field_to_proto_json(field, property.schema, value)
+            } else if let Some(member) =
+                members.iter().find(|member| to_proto_name(member) == field.name)
+            {
+                // The member's message takes the properties its schema can have
+                let Some(member_schema) = self.schema(member) else {
+                    continue;
+                };
+                let mut names = HashSet::new();
+                self.property_names(member_schema, &mut names, 0);
+                let member_object = object
+                    .iter()
+                    .filter(|(name, _)| names.contains(name.as_str()))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect::<Map<_, _>>();
+                converted_properties.extend(member_object.keys().cloned());
+                self.field_to_proto_json(field, member_schema, &Value::Object(member_object))
+            } else {
+                continue;
+            };
+            let converted = converted.with_context(|| format!("{}.{}", message.name, field.name))?;
+            self.insert_unless_default(&mut converted_object, field, converted);
+        }
+
+        if let Some(name) = object.keys().find(|name| !converted_properties.contains(*name)) {
+            bail!("property `{}` has no field in {}", name, message.name);
+        }
+        Ok(Value::Object(converted_object))
+    }
+
+    /// Proto3 JSON of `field` holding `value`, valid against `schema`
+    pub(crate) fn field_to_proto_json(
+        &self,
+        field: &ProtoField,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<Value> {
+        match (self.resolve(schema), field.repeated) {
+            (Schema::Primitive(Primitive::Array(array)), true) => {
+                if let Schema::Primitive(Primitive::Array(_)) = self.resolve(&array.items) {
+                    bail!("the list of lists is generated as a single list");
+                }
+                let items = value.as_array().context("expected an array")?;
+                let items = items
+                    .iter()
+                    .map(|item| self.typed_to_proto_json(&field.field_type, &array.items, item))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Value::Array(items))
+            }
+            // Arrays with a schema of their own are wrapped in a message
+            (Schema::Primitive(Primitive::Array(_)), false)
+                if !matches!(field.field_type, ProtoFieldType::Message(_)) =>
+            {
+                bail!("the array is generated as a single {}", field.field_type)
+            }
+            (_, true) => bail!("the value is generated as a list"),
+            (_, false) => self.typed_to_proto_json(&field.field_type, schema, value),
+        }
+    }
+
+    /// Proto3 JSON of a single value of `field_type`
+    fn typed_to_proto_json(
+        &self,
+        field_type: &ProtoFieldType,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<Value> {
+        match field_type {
+            ProtoFieldType::String if value.is_string() => Ok(value.clone()),
+            ProtoFieldType::Bool if value.is_boolean() => Ok(value.clone()),
+            ProtoFieldType::Int32
+            | ProtoFieldType::Uint32
+            | ProtoFieldType::Double
+            | ProtoFieldType::Float
+                if value.is_number() =>
+            {
+                Ok(value.clone())
+            }
+            ProtoFieldType::Int64 | ProtoFieldType::Uint64 => match value {
+                Value::Number(number) if number.is_i64() || number.is_u64() => {
+                    Ok(json!(number.to_string()))
+                }
+                Value::String(hex) if hex.starts_with("0x") => {
+                    let number = u64::from_str_radix(&hex[2..], 16)
+                        .with_context(|| format!("{} doesn't fit in {}", hex, field_type))?;
+                    Ok(json!(number.to_string()))
+                }
+                _ => bail!("{} can't be written as {}", value, field_type),
+            },
+            ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                if let Some(message) = self.message(name) {
+                    return self.message_to_proto_json(message, schema, value);
+                }
+                let proto_enum = self
+                    .proto_enum(name)
+                    .ok_or_else(|| anyhow!("{} is not defined", name))?;
+                let index = self
+                    .spec_enum_values(schema)
+                    .and_then(|values| values.iter().position(|allowed| value == allowed))
+                    .ok_or_else(|| anyhow!("{} is not a value of {}", value, proto_enum.name))?;
+                let proto_value = proto_enum
+                    .values
+                    .get(index)
+                    .ok_or_else(|| anyhow!("{} has no value for {}", proto_enum.name, value))?;
+                Ok(json!(proto_value.name))
+            }
+            _ => bail!("{} can't be written as {}", value, field_type),
+        }
+    }
+
+    /// Values of a string enum schema, in the order of the generated enum values
+    pub(crate) fn spec_enum_values<'s>(&'s self, schema: &'s Schema) -> Option<&'s [String]> {
+        match self.resolve(schema) {
+            Schema::Primitive(Primitive::String(string)) => string.r#enum.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Sets `field` in `object` unless canonical proto3 JSON leaves it out, being a field
+    /// without presence set to its default
+    fn insert_unless_default(
+        &self,
+        object: &mut Map<String, Value>,
+        field: &ProtoField,
+        value: Value,
+    ) {
+        let has_presence = field.optional || field.oneof_name.is_some();
+        let is_default = match (&field.field_type, &value) {
+            _ if field.repeated => value.as_array().is_some_and(Vec::is_empty),
+            (ProtoFieldType::Int64 | ProtoFieldType::Uint64, Value::String(string)) => {
+                string == "0"
+            }
+            (ProtoFieldType::String | ProtoFieldType::Bytes, Value::String(string)) => {
+                string.is_empty()
+            }
+            (_, Value::Number(number)) => number.as_f64() == Some(0.0),
+            (_, Value::Bool(value)) => !value,
+            (ProtoFieldType::Enum(_) | ProtoFieldType::Message(_), Value::String(_)) => {
+                self.default_proto_json(field).as_ref() == Some(&value)
+            }
+            _ => false,
+        };
+        if has_presence || !is_default {
+            object.insert(field.json_name(), value);
+        }
+    }
+
+    /// Proto3 JSON of the default value of `field`, `None` for message fields that have no
+    /// value when unset
+    pub(crate) fn default_proto_json(&self, field: &ProtoField) -> Option<Value> {
+        if field.repeated {
+            return Some(json!([]));
+        }
+        match &field.field_type {
+            ProtoFieldType::String | ProtoFieldType::Bytes => Some(json!("")),
+            ProtoFieldType::Int64 | ProtoFieldType::Uint64 => Some(json!("0")),
+            ProtoFieldType::Int32
+            | ProtoFieldType::Uint32
+            | ProtoFieldType::Double
+            | ProtoFieldType::Float => Some(json!(0)),
+            ProtoFieldType::Bool => Some(json!(false)),
+            ProtoFieldType::Enum(name) | ProtoFieldType::Message(name) => {
+                let proto_enum = self.proto_enum(name)?;
+                let first = proto_enum.values.iter().find(|value| value.number == 0)?;
+                Some(json!(first.name))
+            }
+            ProtoFieldType::Any => None,
+        }
+    }
+}
+
+/// Adds the `pattern`s of `schema` and the inline schemas it contains to `patterns`
+fn collect_patterns<'s>(schema: &'s Schema, patterns: &mut HashSet<&'s str>) {
+    match schema {
+        Schema::Ref(_) => {}
+        Schema::OneOf(one_of) => {
+            for variant in &one_of.one_of {
+                collect_patterns(variant, patterns);
+            }
+        }
+        Schema::AllOf(all_of) => {
+            for member in &all_of.all_of {
+                collect_patterns(member, patterns);
+            }
+        }
+        Schema::Primitive(Primitive::Array(array)) => collect_patterns(&array.items, patterns),
+        Schema::Primitive(Primitive::Object(object)) => {
+            for property in object.properties.values() {
+                collect_patterns(property, patterns);
+            }
+        }
+        Schema::Primitive(Primitive::String(string)) => {
+            patterns.extend(string.pattern.as_deref());
+        }
+        Schema::Primitive(_) => {}
+    }
+}
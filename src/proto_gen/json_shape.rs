@@ -0,0 +1,660 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::{
+    proto_gen::{
+        service::ServiceGenerator,
+        types::{ProtoField, ProtoFieldType, ProtoMessage},
+        writer::{to_proto_name, to_proto_type_name},
+        ProtoConfig, ProtoFile,
+    },
+    refs::resolve_schema,
+    spec::{Method, Primitive, Schema, Specification},
+};
+
+/// Way the proto3 JSON mapping of a generated field differs from the JSON the spec describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShapeMismatch {
+    /// `int64` and `uint64` values are written as JSON strings
+    Int64AsString,
+    /// Enum values are written by their prefixed proto names
+    EnumName,
+    /// A union is written as an object keyed by the field of its variant
+    OneofWrapper,
+    /// Required values equal to their proto3 default (`0`, `false`, the first enum value or an
+    /// empty list) are left out
+    DefaultOmitted,
+    /// An array is generated as a single value
+    NotRepeated,
+    /// A scalar is generated as a message and written as an object
+    WrappedScalar,
+    /// The value is written with another JSON type or format
+    TypeMismatch,
+    /// An `allOf` member is nested under its own key instead of being merged into the object
+    NestedAllOf,
+    /// A property of the schema has no field
+    MissingField,
+    /// A field has no property in the schema
+    ExtraField,
+}
+
+/// Single place where proto3 JSON differs from the JSON of the spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShapeIssue {
+    /// File and element of the generated proto, e.g. `common.proto: BlockHeader.timestamp`
+    pub location: String,
+    /// Schema or method the element was generated from, e.g. `BLOCK_HEADER.timestamp`
+    pub source: String,
+    pub mismatch: ShapeMismatch,
+    pub description: String,
+}
+
+/// Differences between the proto3 JSON mapping of generated files and the spec they were
+/// generated from
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonShapeReport {
+    pub issues: Vec<ShapeIssue>,
+}
+
+/// Property of an object schema, flattened out of `allOf`s
+pub(crate) struct Property<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) schema: &'a Schema,
+    pub(crate) required: bool,
+    /// `allOf` member referencing the schema the property comes from, if any
+    pub(crate) member: Option<&'a str>,
+}
+
+/// Named type a generated field can refer to
+enum TypeDefinition<'a> {
+    Message(&'a ProtoMessage),
+    Enum(Vec<&'a str>),
+}
+
+struct Analyzer<'a> {
+    specs: &'a Specification,
+    /// Types by their unqualified name; generated names are unique across files
+    types: HashMap<&'a str, TypeDefinition<'a>>,
+    issues: Vec<ShapeIssue>,
+}
+
+impl JsonShapeReport {
+    /// Compares the messages of `files`, generated from `specs` with `config`, with the schemas
+    /// and methods they stand for.
+    pub fn new(specs: &Specification, config: &ProtoConfig, files: &[ProtoFile]) -> Self {
+        let mut types = HashMap::new();
+        for file in files {
+            for message in &file.messages {
+                types.insert(message.name.as_str(), TypeDefinition::Message(message));
+            }
+            for proto_enum in &file.enums {
+                let values = proto_enum.values.iter().map(|value| value.name.as_str()).collect();
+                types.insert(proto_enum.name.as_str(), TypeDefinition::Enum(values));
+            }
+        }
+        let mut analyzer = Analyzer {
+            specs,
+            types,
+            issues: vec![],
+        };
+
+        let common_file = files.iter().find(|file| file.package == config.common_package());
+        for (name, schema) in &specs.components.schemas {
+            let message = common_file.and_then(|file| {
+                let message_name = to_proto_type_name(name);
+                file.messages.iter().find(|message| message.name == message_name)
+            });
+            if let (Some(file), Some(message)) = (common_file, message) {
+                analyzer.message(&file.name, message, name, schema);
+            }
+        }
+
+        let rpc_names = ServiceGenerator::new("", config);
+        let methods = specs
+            .methods
+            .iter()
+            .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+            .collect::<Vec<_>>();
+        for file in files {
+            for rpc in file.services.iter().flat_map(|service| &service.rpcs) {
+                let Some(method) = methods
+                    .iter()
+                    .find(|method| rpc_names.method_name_to_rpc_name(&method.name) == rpc.name)
+                else {
+                    continue;
+                };
+                let find = |name: &str| file.messages.iter().find(|message| message.name == name);
+                if let Some(request) = find(&rpc.request_type) {
+                    analyzer.request(&file.name, request, method);
+                }
+                if let Some(response) = find(&rpc.response_type) {
+                    analyzer.response(&file.name, response, method);
+                }
+            }
+        }
+
+        Self {
+            issues: analyzer.issues,
+        }
+    }
+
+    /// Issues of the given kind
+    pub fn issues(&self, mismatch: ShapeMismatch) -> impl Iterator<Item = &ShapeIssue> {
+        self.issues
+            .iter()
+            .filter(move |issue| issue.mismatch == mismatch)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Proto3 JSON differences\n");
+
+        if self.issues.is_empty() {
+            markdown.push_str("\nNo differences.\n");
+        }
+
+        let mut mismatches = self.issues.iter().map(|issue| issue.mismatch).collect::<Vec<_>>();
+        mismatches.sort();
+        mismatches.dedup();
+        for mismatch in mismatches {
+            let issues = self.issues(mismatch).collect::<Vec<_>>();
+            markdown.push_str(&format!("\n## {} ({})\n\n", mismatch, issues.len()));
+            for issue in issues {
+                markdown.push_str(&format!("- {}\n", issue));
+            }
+        }
+
+        markdown
+    }
+}
+
+impl<'a> Analyzer<'a> {
+    fn resolve(&self, schema: &'a Schema) -> &'a Schema {
+        resolve_schema(&self.specs.components.schemas, schema)
+    }
+
+    fn properties(&self, schema: &'a Schema) -> Option<(Vec<Property<'a>>, Vec<&'a str>)> {
+        object_properties(&self.specs.components.schemas, schema)
+    }
+
+    fn push(
+        &mut self,
+        location: String,
+        source: String,
+        mismatch: ShapeMismatch,
+        description: String,
+    ) {
+        self.issues.push(ShapeIssue {
+            location,
+            source,
+            mismatch,
+            description,
+        });
+    }
+
+    fn message(&mut self, file: &str, message: &ProtoMessage, name: &str, schema: &'a Schema) {
+        let location = format!("{}: {}", file, message.name);
+        match self.resolve(schema) {
+            Schema::OneOf(one_of) => {
+                for oneof in message.declared_oneofs() {
+                    let keys = quoted(oneof.fields.iter().map(|field| field.json_name()));
+                    self.push(
+                        location.clone(),
+                        name.to_owned(),
+                        ShapeMismatch::OneofWrapper,
+                        format!("the value is wrapped in one of {}", keys),
+                    );
+
+                    for (field, variant) in oneof.fields.iter().zip(&one_of.one_of) {
+                        let location = format!("{}.{}", location, field.name);
+                        let source = format!("{}.oneOf[{}]", name, field.number - 1);
+                        self.value(&location, &source, field, variant, false);
+                    }
+                }
+            }
+            _ => {
+                if let Some((properties, members)) = self.properties(schema) {
+                    self.object(&location, name, message, &properties, &members);
+                }
+            }
+        }
+    }
+
+    fn request(&mut self, file: &str, message: &ProtoMessage, method: &'a Method) {
+        let properties = method
+            .params
+            .iter()
+            .map(|param| Property {
+                name: &param.name,
+                schema: &param.schema,
+                required: param.required,
+                member: None,
+            })
+            .collect::<Vec<_>>();
+        let location = format!("{}: {}", file, message.name);
+        self.object(&location, &method.name, message, &properties, &[]);
+    }
+
+    fn response(&mut self, file: &str, message: &ProtoMessage, method: &'a Method) {
+        let properties = method
+            .result
+            .iter()
+            .map(|result| Property {
+                name: "result",
+                schema: &result.schema,
+                required: result.required.unwrap_or(true),
+                member: None,
+            })
+            .collect::<Vec<_>>();
+        let location = format!("{}: {}", file, message.name);
+        self.object(&location, &method.name, message, &properties, &[]);
+    }
+
+    /// Compares the fields of `message` with the properties of the object it stands for
+    fn object(
+        &mut self,
+        location: &str,
+        source: &str,
+        message: &ProtoMessage,
+        properties: &[Property<'a>],
+        members: &[&str],
+    ) {
+        for field in &message.fields {
+            let location = format!("{}.{}", location, field.name);
+            let json_name = field.json_name();
+            if let Some(property) = properties.iter().find(|property| property.name == json_name) {
+                let source = format!("{}.{}", source, property.name);
+                self.value(&location, &source, field, property.schema, property.required);
+            } else if let Some(member) =
+                members.iter().find(|member| to_proto_name(member) == field.name)
+            {
+                self.push(
+                    location,
+                    format!("{}.allOf", source),
+                    ShapeMismatch::NestedAllOf,
+                    format!("the properties of {} are nested under `{}`", member, json_name),
+                );
+            } else if !field.optional {
+                // Optional fields are only written when set, like the error of responses
+                self.push(
+                    location,
+                    source.to_owned(),
+                    ShapeMismatch::ExtraField,
+                    format!("`{}` is not a property of the schema", json_name),
+                );
+            }
+        }
+
+        for property in properties {
+            let nested = property.member.is_some_and(|member| {
+                message.fields.iter().any(|field| field.name == to_proto_name(member))
+            });
+            let generated = message.fields.iter().any(|field| field.json_name() == property.name);
+            if !generated && !nested {
+                self.push(
+                    location.to_owned(),
+                    format!("{}.{}", source, property.name),
+                    ShapeMismatch::MissingField,
+                    format!("property `{}` has no field", property.name),
+                );
+            }
+        }
+    }
+
+    /// Compares the value of `field` with `schema`
+    fn value(
+        &mut self,
+        location: &str,
+        source: &str,
+        field: &ProtoField,
+        schema: &'a Schema,
+        required: bool,
+    ) {
+        for (mismatch, description) in self.value_mismatches(field, schema, required) {
+            self.push(location.to_owned(), source.to_owned(), mismatch, description);
+        }
+    }
+
+    fn value_mismatches(
+        &self,
+        field: &ProtoField,
+        schema: &'a Schema,
+        required: bool,
+    ) -> Vec<(ShapeMismatch, String)> {
+        let mut mismatches = vec![];
+        let mut push = |mismatch, description: String| mismatches.push((mismatch, description));
+
+        let schema = self.resolve(schema);
+        let schema = match (schema, field.repeated) {
+            (Schema::Primitive(Primitive::Array(array)), true) => {
+                if required {
+                    push(
+                        ShapeMismatch::DefaultOmitted,
+                        "an empty list is left out".to_owned(),
+                    );
+                }
+                self.resolve(&array.items)
+            }
+            (Schema::Primitive(Primitive::Array(_)), false) => {
+                push(
+                    ShapeMismatch::NotRepeated,
+                    format!("the array is generated as a single {}", field.field_type),
+                );
+                return mismatches;
+            }
+            (_, true) => {
+                push(
+                    ShapeMismatch::TypeMismatch,
+                    format!("{} is generated as a list", describe(schema)),
+                );
+                return mismatches;
+            }
+            (schema, false) => schema,
+        };
+        let can_omit_default = required && !field.optional && field.oneof_name.is_none();
+
+        match (&field.field_type, schema) {
+            (
+                ProtoFieldType::Int64 | ProtoFieldType::Uint64,
+                Schema::Primitive(Primitive::Integer(_)),
+            ) => {
+                push(
+                    ShapeMismatch::Int64AsString,
+                    format!("the integer is written as a string by {}", field.field_type),
+                );
+                if can_omit_default {
+                    push(ShapeMismatch::DefaultOmitted, "`0` is left out".to_owned());
+                }
+            }
+            (
+                ProtoFieldType::Int32 | ProtoFieldType::Uint32 | ProtoFieldType::Double,
+                Schema::Primitive(Primitive::Integer(_)),
+            ) => {
+                if can_omit_default {
+                    push(ShapeMismatch::DefaultOmitted, "`0` is left out".to_owned());
+                }
+            }
+            (ProtoFieldType::String, Schema::Primitive(Primitive::String(_))) => {}
+            (ProtoFieldType::Bool, Schema::Primitive(Primitive::Boolean(_))) => {
+                if can_omit_default {
+                    push(ShapeMismatch::DefaultOmitted, "`false` is left out".to_owned());
+                }
+            }
+            (ProtoFieldType::Message(name) | ProtoFieldType::Enum(name), _) => {
+                let name = name.rsplit('.').next().unwrap_or(name);
+                match (self.types.get(name), schema) {
+                    (
+                        Some(TypeDefinition::Enum(values)),
+                        Schema::Primitive(Primitive::String(string)),
+                    ) => {
+                        let spec_values = string.r#enum.clone().unwrap_or_default();
+                        if values.iter().zip(&spec_values).any(|(value, spec)| value != spec) {
+                            push(
+                                ShapeMismatch::EnumName,
+                                format!(
+                                    "values are written as {} instead of {}",
+                                    quoted(values.iter()),
+                                    quoted(&spec_values),
+                                ),
+                            );
+                        }
+                        if can_omit_default {
+                            if let Some(first) = spec_values.first() {
+                                push(
+                                    ShapeMismatch::DefaultOmitted,
+                                    format!("`{}`, the first value, is left out", first),
+                                );
+                            }
+                        }
+                    }
+                    (Some(TypeDefinition::Message(message)), Schema::Primitive(primitive))
+                        if !matches!(primitive, Primitive::Object(_)) =>
+                    {
+                        push(
+                            ShapeMismatch::WrappedScalar,
+                            format!(
+                                "{} is written as an object with {}",
+                                describe(schema),
+                                quoted(message.fields.iter().map(|field| field.json_name()))
+                            ),
+                        );
+                    }
+                    (Some(TypeDefinition::Message(message)), _) if message.name == "Object" => {
+                        push(
+                            ShapeMismatch::TypeMismatch,
+                            format!(
+                                "{} is written as a JSON string in `data` of the generic Object",
+                                describe(schema)
+                            ),
+                        );
+                    }
+                    (Some(TypeDefinition::Message(_)), _) => {}
+                    (Some(TypeDefinition::Enum(_)), _) => push(
+                        ShapeMismatch::TypeMismatch,
+                        format!("{} is generated as an enum", describe(schema)),
+                    ),
+                    // Undefined types are reported by the validator
+                    (None, _) => {}
+                }
+            }
+            (field_type, schema) => push(
+                ShapeMismatch::TypeMismatch,
+                format!("{} is written as {}", describe(schema), json_type(field_type)),
+            ),
+        }
+
+        mismatches
+    }
+}
+
+/// Properties of an object or `allOf` schema along with the `allOf` members referencing other
+/// schemas, or `None` for other schemas
+pub(crate) fn object_properties<'a>(
+    schemas: &'a IndexMap<String, Schema>,
+    schema: &'a Schema,
+) -> Option<(Vec<Property<'a>>, Vec<&'a str>)> {
+    let mut properties = vec![];
+    let mut members = vec![];
+    match resolve_schema(schemas, schema) {
+        Schema::Primitive(Primitive::Object(object)) => {
+            for (name, schema) in &object.properties {
+                properties.push(Property {
+                    name,
+                    schema,
+                    required: object.required.contains(name),
+                    member: None,
+                });
+            }
+        }
+        Schema::AllOf(all_of) => {
+            for member in &all_of.all_of {
+                let reference = match member {
+                    Schema::Ref(reference) => {
+                        members.push(reference.name());
+                        Some(reference.name())
+                    }
+                    _ => None,
+                };
+                let Some((member_properties, _)) = object_properties(schemas, member) else {
+                    continue;
+                };
+                properties.extend(member_properties.into_iter().map(|property| Property {
+                    member: reference.or(property.member),
+                    ..property
+                }));
+            }
+        }
+        _ => return None,
+    }
+    Some((properties, members))
+}
+
+/// Values as a list of code spans
+fn quoted(values: impl IntoIterator<Item = impl Display>) -> String {
+    values
+        .into_iter()
+        .map(|value| format!("`{}`", value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// What a resolved schema describes, for issue descriptions
+fn describe(schema: &Schema) -> String {
+    match schema {
+        Schema::Primitive(Primitive::String(string)) => match &string.pattern {
+            Some(pattern) if pattern.starts_with("^0x") => "a hex string".to_owned(),
+            _ => "a string".to_owned(),
+        },
+        Schema::Primitive(Primitive::Integer(_)) => "an integer".to_owned(),
+        Schema::Primitive(Primitive::Boolean(_)) => "a boolean".to_owned(),
+        Schema::Primitive(Primitive::Array(_)) => "an array".to_owned(),
+        Schema::Primitive(Primitive::Object(_)) | Schema::AllOf(_) => "an object".to_owned(),
+        Schema::OneOf(_) => "a union".to_owned(),
+        Schema::Ref(reference) => format!("undefined {}", reference.name()),
+    }
+}
+
+/// JSON a scalar proto type is written as
+fn json_type(field_type: &ProtoFieldType) -> String {
+    match field_type {
+        ProtoFieldType::String => "a string".to_owned(),
+        ProtoFieldType::Int32
+        | ProtoFieldType::Uint32
+        | ProtoFieldType::Double
+        | ProtoFieldType::Float => format!("a number by {}", field_type),
+        ProtoFieldType::Int64 | ProtoFieldType::Uint64 => {
+            format!("a decimal string by {}", field_type)
+        }
+        ProtoFieldType::Bool => "a boolean".to_owned(),
+        ProtoFieldType::Bytes => "a base64 string".to_owned(),
+        ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => format!("a {}", name),
+        ProtoFieldType::Any => "an Any object".to_owned(),
+    }
+}
+
+impl Display for ShapeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let title = match self {
+            Self::Int64AsString => "64-bit integers written as strings",
+            Self::EnumName => "Enum values written by their proto names",
+            Self::OneofWrapper => "Unions wrapped in their variant's key",
+            Self::DefaultOmitted => "Required default values left out",
+            Self::NotRepeated => "Arrays generated as single values",
+            Self::WrappedScalar => "Scalars written as objects",
+            Self::TypeMismatch => "Values written as another JSON type",
+            Self::NestedAllOf => "allOf members nested instead of merged",
+            Self::MissingField => "Properties without a field",
+            Self::ExtraField => "Fields without a property",
+        };
+        write!(f, "{}", title)
+    }
+}
+
+impl Display for ShapeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` (`{}`): {}", self.location, self.source, self.description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoGenerator;
+
+    fn create_test_specs() -> Specification {
+        serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [{
+                    "name": "starknet_blockNumber",
+                    "summary": "Latest block number",
+                    "params": [],
+                    "result": {
+                        "name": "result",
+                        "schema": { "$ref": "#/components/schemas/BLOCK_NUMBER" }
+                    }
+                }],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "BLOCK_NUMBER": { "type": "integer", "minimum": 0 },
+                        "FELT": { "type": "string", "pattern": "^0x[a-fA-F0-9]+$" },
+                        "STATUS": { "type": "string", "enum": ["ACCEPTED", "REJECTED"] },
+                        "BLOCK": {
+                            "type": "object",
+                            "properties": {
+                                "hash": { "$ref": "#/components/schemas/FELT" },
+                                "number": { "$ref": "#/components/schemas/BLOCK_NUMBER" },
+                                "status": { "$ref": "#/components/schemas/STATUS" },
+                                "transactions": {
+                                    "type": "array",
+                                    "items": { "$ref": "#/components/schemas/FELT" }
+                                }
+                            },
+                            "required": ["hash", "number", "status", "transactions"]
+                        },
+                        "BLOCK_ID": {
+                            "oneOf": [
+                                { "$ref": "#/components/schemas/FELT" },
+                                { "$ref": "#/components/schemas/BLOCK_NUMBER" },
+                                {
+                                    "type": "array",
+                                    "items": { "$ref": "#/components/schemas/FELT" }
+                                }
+                            ]
+                        }
+                    },
+                    "errors": {}
+                }
+            }"##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_differences_are_classified_per_field() {
+        let specs = create_test_specs();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+
+        let report = JsonShapeReport::new(&specs, &config, &result.protos);
+        let issues = report
+            .issues
+            .iter()
+            .map(|issue| (issue.location.as_str(), issue.mismatch))
+            .collect::<Vec<_>>();
+
+        use ShapeMismatch::*;
+        assert_eq!(
+            issues,
+            [
+                ("common.proto: Block.number", Int64AsString),
+                ("common.proto: Block.number", DefaultOmitted),
+                ("common.proto: Block.status", EnumName),
+                ("common.proto: Block.status", DefaultOmitted),
+                ("common.proto: Block.transactions", DefaultOmitted),
+                ("common.proto: BlockId", OneofWrapper),
+                ("common.proto: BlockId.variant_2", Int64AsString),
+                ("common.proto: BlockId.variant_3", NotRepeated),
+                ("main.proto: BlockNumberResponse.result", Int64AsString),
+                ("main.proto: BlockNumberResponse.result", DefaultOmitted),
+            ]
+        );
+        assert_eq!(
+            report.issues[2].description,
+            "values are written as `STATUS_ACCEPTED`, `STATUS_REJECTED` instead of `ACCEPTED`, \
+             `REJECTED`"
+        );
+        assert!(report.to_markdown().contains(
+            "\n## Arrays generated as single values (1)\n\n- `common.proto: BlockId.variant_3` \
+             (`BLOCK_ID.oneOf[2]`): the array is generated as a single string\n"
+        ));
+    }
+}
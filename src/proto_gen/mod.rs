@@ -1,12 +1,35 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 use crate::spec::*;
+use backend::{Backend, ProtoBackend};
+use shared_types::SharedTypes;
+use subset::Subset;
+use writer::to_proto_type_name;
 
+pub mod backend;
+pub mod layout;
+pub mod plugin;
 pub mod writer;
 pub mod types;
 pub mod service;
+pub mod parser;
+pub mod breaking;
+pub mod validate;
+pub mod well_known;
+pub mod descriptor;
+pub mod json_shape;
+pub mod json_mapping;
+pub mod examples;
+pub mod rust;
+pub mod rust_serde;
+pub mod rust_types;
+pub mod subset;
+pub mod shared_types;
+pub mod convert;
 
 pub use writer::ProtoWriter;
 pub use types::*;
@@ -25,23 +48,57 @@ pub struct ProtoConfig {
 #[derive(Debug, Clone)]
 pub struct ProtoGenerationResult {
     pub files: HashMap<String, String>,
+    /// Definitions behind `files`, in the same shape `parser::parse_proto_file` returns
+    pub protos: Vec<ProtoFile>,
     pub package_info: PackageInfo,
 }
 
+/// Definitions `ProtoGenerator` resolves from a spec, which backends render into files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoIr {
+    /// Version of the spec, e.g. `0.8.1`
+    pub spec_version: String,
+    pub package_info: PackageInfo,
+    pub type_resolution: TypeResolution,
+    /// Definitions by file, with the services of each package
+    pub files: Vec<ProtoFile>,
+    /// Component schema each message and enum is generated from, by qualified name
+    pub schemas: BTreeMap<String, String>,
+    /// JSON-RPC method each rpc calls, by full name, e.g.
+    /// `/starknet.v0_8_1.main.StarknetMainService/GetBlockWithTxHashes`
+    pub methods: BTreeMap<String, String>,
+}
+
+/// Definitions of a single proto file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoFile {
+    pub name: String,
+    pub package: String,
+    pub imports: Vec<String>,
+    /// File options in `name = value` form, e.g. `java_multiple_files = true`
+    pub options: Vec<String>,
+    pub enums: Vec<ProtoEnum>,
+    pub messages: Vec<ProtoMessage>,
+    pub services: Vec<ProtoService>,
+}
+
 /// Package information for generated protos
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub main_package: String,
     pub write_package: String,
     pub trace_package: String,
     pub ws_package: String,
     pub common_package: String,
+    pub extension_packages: Vec<String>,
 }
 
 /// Main proto generator
 pub struct ProtoGenerator {
     config: ProtoConfig,
     type_resolver: TypeResolver,
+    subset: Subset,
+    shared_types: Option<SharedTypes>,
 }
 
 impl ProtoGenerator {
@@ -49,47 +106,138 @@ impl ProtoGenerator {
         Self {
             type_resolver: TypeResolver::new(&config),
             config,
+            subset: Subset::default(),
+            shared_types: None,
         }
     }
 
+    /// Only generates the methods and services `subset` selects, and the definitions they use
+    pub fn with_subset(mut self, subset: Subset) -> Self {
+        self.subset = subset;
+        self
+    }
+
+    /// Imports the types of `shared_types` from their package instead of defining them
+    pub fn with_shared_types(mut self, shared_types: SharedTypes) -> Self {
+        self.shared_types = Some(shared_types);
+        self
+    }
+
     pub fn generate(&mut self, specs: &Specification) -> Result<ProtoGenerationResult> {
-        let mut files = HashMap::new();
+        let ir = self.resolve(specs)?;
+        let files = ProtoBackend.render(specs, &ir)?.into_iter().collect();
+
+        Ok(ProtoGenerationResult {
+            files,
+            protos: ir.files,
+            package_info: ir.package_info,
+        })
+    }
+
+    /// Resolves the definitions generated from `specs`, for a backend to render
+    pub fn resolve(&mut self, specs: &Specification) -> Result<ProtoIr> {
+        let mut protos = Vec::new();
+        let mut add_file = |name: String, writer: ProtoWriter| {
+            protos.push(writer.to_file(&name));
+        };
         
         // Resolve all types first
         let type_resolution = self.type_resolver.resolve_types(specs)?;
         
         // Generate common types file
         let common_proto = self.generate_common_proto(&type_resolution)?;
-        files.insert("common.proto".to_string(), common_proto);
+        add_file("common.proto".to_string(), common_proto);
         
         // Generate service files
-        let main_proto = self.generate_main_service_proto(specs, &type_resolution)?;
-        files.insert("main.proto".to_string(), main_proto);
-        
-        let write_proto = self.generate_write_service_proto(specs, &type_resolution)?;
-        files.insert("write.proto".to_string(), write_proto);
-        
-        let trace_proto = self.generate_trace_service_proto(specs, &type_resolution)?;
-        files.insert("trace.proto".to_string(), trace_proto);
-        
-        let ws_proto = self.generate_ws_service_proto(specs, &type_resolution)?;
-        files.insert("ws.proto".to_string(), ws_proto);
+        let main_proto = self.generate_service_proto(
+            &self.config.main_package(),
+            "StarknetMainService",
+            &self.filter_main_methods(&specs.methods),
+            &type_resolution.main_types,
+            &specs.components.schemas,
+            &[],
+        )?;
+        add_file("main.proto".to_string(), main_proto);
+        
+        let write_proto = self.generate_service_proto(
+            &self.config.write_package(),
+            "StarknetWriteService",
+            &self.filter_write_methods(&specs.methods),
+            &type_resolution.write_types,
+            &specs.components.schemas,
+            &[],
+        )?;
+        add_file("write.proto".to_string(), write_proto);
+        
+        let trace_proto = self.generate_service_proto(
+            &self.config.trace_package(),
+            "StarknetTraceService",
+            &self.filter_trace_methods(&specs.methods),
+            &type_resolution.trace_types,
+            &specs.components.schemas,
+            &[],
+        )?;
+        add_file("trace.proto".to_string(), trace_proto);
+        
+        let ws_proto = self.generate_service_proto(
+            &self.config.ws_package(),
+            "StarknetWsService",
+            &self.filter_ws_methods(&specs.methods),
+            &type_resolution.ws_types,
+            &specs.components.schemas,
+            &specs.methods.iter().collect::<Vec<_>>(),
+        )?;
+        add_file("ws.proto".to_string(), ws_proto);
+
+        // Generate one service file per extension namespace
+        let mut extension_packages = Vec::new();
+        for extension in &specs.extensions {
+            let package = self.config.extension_package(&extension.name);
+            let service_name = format!("Starknet{}Service", to_proto_type_name(&extension.name));
+            let methods = extension.methods.iter().collect::<Vec<_>>();
+
+            let extension_proto = self.generate_service_proto(
+                &package,
+                &service_name,
+                &methods,
+                &[],
+                &specs.components.schemas,
+                &[],
+            )?;
+            add_file(format!("{}.proto", extension.name), extension_proto);
+            extension_packages.push(package);
+        }
         
         let package_info = PackageInfo {
-            main_package: format!("{}.{}.main", self.config.package_prefix, self.config.version),
-            write_package: format!("{}.{}.write", self.config.package_prefix, self.config.version),
-            trace_package: format!("{}.{}.trace", self.config.package_prefix, self.config.version),
-            ws_package: format!("{}.{}.ws", self.config.package_prefix, self.config.version),
-            common_package: format!("{}.{}.common", self.config.package_prefix, self.config.version),
+            main_package: self.config.main_package(),
+            write_package: self.config.write_package(),
+            trace_package: self.config.trace_package(),
+            ws_package: self.config.ws_package(),
+            common_package: self.config.common_package(),
+            extension_packages,
         };
         
-        Ok(ProtoGenerationResult {
-            files,
+        let schemas = definition_schemas(specs, &protos);
+        let methods = rpc_methods(specs, &self.config, &protos);
+
+        let mut ir = ProtoIr {
+            spec_version: specs.info.version.clone(),
             package_info,
-        })
+            type_resolution,
+            files: protos,
+            schemas,
+            methods,
+        };
+        if !self.subset.is_empty() {
+            self.subset.apply(&mut ir)?;
+        }
+        if let Some(shared_types) = &self.shared_types {
+            shared_types.apply(&mut ir)?;
+        }
+        Ok(ir)
     }
     
-    fn generate_common_proto(&self, type_resolution: &TypeResolution) -> Result<String> {
+    fn generate_common_proto(&self, type_resolution: &TypeResolution) -> Result<ProtoWriter> {
         let mut writer = ProtoWriter::new(&self.config.common_package());
         
         // Add common imports for better language compatibility
@@ -108,49 +256,30 @@ impl ProtoGenerator {
             writer.add_enum(proto_enum);
         }
         
-        Ok(writer.to_string())
-    }
-    
-    fn generate_main_service_proto(&self, specs: &Specification, type_resolution: &TypeResolution) -> Result<String> {
-        let mut writer = ProtoWriter::new(&self.config.main_package());
-        writer.add_import("common.proto");
-        
-        let methods = self.filter_main_methods(&specs.methods);
-        let service_generator = ServiceGenerator::new("StarknetMainService", &self.config);
-        let service = service_generator.generate_from_methods(&methods)?;
-        
-        writer.add_service(&service);
-        
-        // Generate request/response messages for each method
-        for method in &methods {
-            let (request_msg, response_msg) = service_generator.generate_request_response_messages(method)?;
-            writer.add_message(&request_msg);
-            writer.add_message(&response_msg);
-        }
-        
-        // Add common error message
-        writer.add_message(&ServiceGenerator::generate_error_message());
-        
-        // Add service-specific types
-        for proto_type in &type_resolution.main_types {
-            writer.add_message(proto_type);
-        }
-        
-        Ok(writer.to_string())
+        Ok(writer)
     }
     
-    fn generate_write_service_proto(&self, specs: &Specification, type_resolution: &TypeResolution) -> Result<String> {
-        let mut writer = ProtoWriter::new(&self.config.write_package());
+    fn generate_service_proto(
+        &self,
+        package: &str,
+        service_name: &str,
+        methods: &[&Method],
+        service_types: &[ProtoMessage],
+        schemas: &IndexMap<String, Schema>,
+        notifications: &[&Method],
+    ) -> Result<ProtoWriter> {
+        let mut writer = ProtoWriter::new(package);
         writer.add_import("common.proto");
         
-        let methods = self.filter_write_methods(&specs.methods);
-        let service_generator = ServiceGenerator::new("StarknetWriteService", &self.config);
-        let service = service_generator.generate_from_methods(&methods)?;
+        let service_generator = ServiceGenerator::new(service_name, &self.config)
+            .with_schemas(schemas)
+            .with_notifications(notifications);
+        let service = service_generator.generate_from_methods(methods)?;
         
         writer.add_service(&service);
         
         // Generate request/response messages for each method
-        for method in &methods {
+        for method in methods {
             let (request_msg, response_msg) = service_generator.generate_request_response_messages(method)?;
             writer.add_message(&request_msg);
             writer.add_message(&response_msg);
@@ -160,67 +289,11 @@ impl ProtoGenerator {
         writer.add_message(&ServiceGenerator::generate_error_message());
         
         // Add service-specific types
-        for proto_type in &type_resolution.write_types {
+        for proto_type in service_types {
             writer.add_message(proto_type);
         }
         
-        Ok(writer.to_string())
-    }
-    
-    fn generate_trace_service_proto(&self, specs: &Specification, type_resolution: &TypeResolution) -> Result<String> {
-        let mut writer = ProtoWriter::new(&self.config.trace_package());
-        writer.add_import("common.proto");
-        
-        let methods = self.filter_trace_methods(&specs.methods);
-        let service_generator = ServiceGenerator::new("StarknetTraceService", &self.config);
-        let service = service_generator.generate_from_methods(&methods)?;
-        
-        writer.add_service(&service);
-        
-        // Generate request/response messages for each method
-        for method in &methods {
-            let (request_msg, response_msg) = service_generator.generate_request_response_messages(method)?;
-            writer.add_message(&request_msg);
-            writer.add_message(&response_msg);
-        }
-        
-        // Add common error message
-        writer.add_message(&ServiceGenerator::generate_error_message());
-        
-        // Add service-specific types
-        for proto_type in &type_resolution.trace_types {
-            writer.add_message(proto_type);
-        }
-        
-        Ok(writer.to_string())
-    }
-    
-    fn generate_ws_service_proto(&self, specs: &Specification, type_resolution: &TypeResolution) -> Result<String> {
-        let mut writer = ProtoWriter::new(&self.config.ws_package());
-        writer.add_import("common.proto");
-        
-        let methods = self.filter_ws_methods(&specs.methods);
-        let service_generator = ServiceGenerator::new("StarknetWsService", &self.config);
-        let service = service_generator.generate_from_methods(&methods)?;
-        
-        writer.add_service(&service);
-        
-        // Generate request/response messages for each method
-        for method in &methods {
-            let (request_msg, response_msg) = service_generator.generate_request_response_messages(method)?;
-            writer.add_message(&request_msg);
-            writer.add_message(&response_msg);
-        }
-        
-        // Add common error message
-        writer.add_message(&ServiceGenerator::generate_error_message());
-        
-        // Add service-specific types
-        for proto_type in &type_resolution.ws_types {
-            writer.add_message(proto_type);
-        }
-        
-        Ok(writer.to_string())
+        Ok(writer)
     }
     
     fn filter_main_methods<'a>(&self, methods: &'a [Method]) -> Vec<&'a Method> {
@@ -248,6 +321,56 @@ impl ProtoGenerator {
     }
 }
 
+/// Component schema of `specs` each message and enum of `files` is generated from, by qualified
+/// name
+fn definition_schemas(specs: &Specification, files: &[ProtoFile]) -> BTreeMap<String, String> {
+    let mut schema_names = HashMap::new();
+    for name in specs.components.schemas.keys() {
+        schema_names.entry(to_proto_type_name(name)).or_insert(name);
+    }
+    let mut schemas = BTreeMap::new();
+    for file in files {
+        let names = file
+            .messages
+            .iter()
+            .map(|message| &message.name)
+            .chain(file.enums.iter().map(|proto_enum| &proto_enum.name));
+        for name in names {
+            if let Some(schema) = schema_names.get(name) {
+                schemas.insert(format!("{}.{}", file.package, name), (*schema).clone());
+            }
+        }
+    }
+    schemas
+}
+
+/// JSON-RPC method of `specs` each rpc of `files` calls, by full name
+fn rpc_methods(
+    specs: &Specification,
+    config: &ProtoConfig,
+    files: &[ProtoFile],
+) -> BTreeMap<String, String> {
+    let rpc_names = ServiceGenerator::new("", config);
+    let methods = specs
+        .methods
+        .iter()
+        .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+        .map(|method| (rpc_names.method_name_to_rpc_name(&method.name), &method.name))
+        .collect::<HashMap<_, _>>();
+    let mut rpc_methods = BTreeMap::new();
+    for file in files {
+        for service in &file.services {
+            for rpc in &service.rpcs {
+                if let Some(method) = methods.get(&rpc.name) {
+                    let name = format!("/{}.{}/{}", file.package, service.name, rpc.name);
+                    rpc_methods.insert(name, (*method).clone());
+                }
+            }
+        }
+    }
+    rpc_methods
+}
+
 impl ProtoConfig {
     pub fn new(version: &str) -> Self {
         Self {
@@ -276,4 +399,62 @@ impl ProtoConfig {
     pub fn common_package(&self) -> String {
         format!("{}.{}.common", self.package_prefix, self.version)
     }
-} 
\ No newline at end of file
+    
+    pub fn extension_package(&self, name: &str) -> String {
+        format!("{}.{}.{}", self.package_prefix, self.version, name)
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_specs() -> Specification {
+        let mut specs: Specification = serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [],
+                "components": { "contentDescriptors": {}, "schemas": {}, "errors": {} }
+            }"##,
+        )
+        .unwrap();
+
+        let method: Method = serde_json::from_str(
+            r##"{
+                "name": "dev_generateBlock",
+                "summary": "Generates a new block",
+                "params": [],
+                "result": {
+                    "name": "result",
+                    "schema": { "$ref": "#/components/schemas/BLOCK_HASH" }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        specs.extensions.push(Extension {
+            name: "katana".to_string(),
+            methods: vec![method],
+        });
+        specs
+    }
+
+    #[test]
+    fn test_extension_service_generation() {
+        let mut generator = ProtoGenerator::new(ProtoConfig::new("v0_8_1"));
+        let result = generator.generate(&create_test_specs()).unwrap();
+
+        assert_eq!(result.package_info.extension_packages, vec!["starknet.v0_8_1.katana"]);
+
+        let katana_proto = result.files.get("katana.proto").unwrap();
+        assert!(katana_proto.contains("package starknet.v0_8_1.katana;"));
+        assert!(katana_proto.contains("import \"common.proto\";"));
+        assert!(katana_proto.contains("service StarknetKatanaService {"));
+        assert!(katana_proto.contains("rpc DevGenerateBlock(DevGenerateBlockRequest) returns (DevGenerateBlockResponse);"));
+
+        // Extension methods must not leak into the core services
+        let main_proto = result.files.get("main.proto").unwrap();
+        assert!(!main_proto.contains("DevGenerateBlock"));
+    }
+}
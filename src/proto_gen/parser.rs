@@ -0,0 +1,408 @@
+use anyhow::{anyhow, bail, Result};
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    uninterpreted_option::NamePart,
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, ServiceDescriptorProto,
+    UninterpretedOption,
+};
+
+use crate::proto_gen::{
+    types::{
+        ProtoEnum, ProtoEnumValue, ProtoField, ProtoFieldType, ProtoMessage, ProtoOneof, ProtoRpc,
+        ProtoService,
+    },
+    ProtoFile,
+};
+
+/// Parses a proto3 file into the same definitions the generator writes.
+///
+/// Comments and options other than `json_name` are dropped. Named field types are returned as
+/// `ProtoFieldType::Message` since whether they refer to an enum is only known once every file
+/// of the package is loaded. Only the syntax is checked, so references to undefined types are
+/// left for `validate` to report.
+pub fn parse_proto_file(name: &str, content: &str) -> Result<ProtoFile> {
+    let descriptor = protox::file::File::from_source(name, content)?
+        .file_descriptor_proto()
+        .clone();
+    match descriptor.syntax() {
+        "proto3" => {}
+        "" => bail!("{}: unsupported syntax \"proto2\"", name),
+        syntax => bail!("{}: unsupported syntax \"{}\"", name, syntax),
+    }
+
+    let file = FileConverter { file: name };
+    Ok(ProtoFile {
+        name: name.to_owned(),
+        package: descriptor.package().to_owned(),
+        imports: descriptor.dependency.clone(),
+        options: descriptor
+            .options
+            .iter()
+            .flat_map(|options| &options.uninterpreted_option)
+            .filter_map(render_option)
+            .collect(),
+        enums: descriptor.enum_type.iter().map(convert_enum).collect(),
+        messages: descriptor
+            .message_type
+            .iter()
+            .map(|message| file.message(message))
+            .collect::<Result<_>>()?,
+        services: descriptor.service.iter().map(convert_service).collect(),
+    })
+}
+
+/// Value of an option like `go_package = "..."`, as stored in `ProtoFile::options`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    String(String),
+    Integer(i64),
+    /// `true`, `false` or an enum value name
+    Identifier(String),
+}
+
+/// Parses an option in the `name = value` form `ProtoFile::options` stores them in.
+pub fn parse_option(option: &str) -> Result<(String, OptionValue)> {
+    let source = format!("syntax = \"proto3\";\noption {};\n", option);
+    let descriptor = protox::file::File::from_source("option", &source)
+        .map_err(|err| anyhow!("invalid option {}: {}", option, err))?
+        .file_descriptor_proto()
+        .clone();
+    let option = descriptor
+        .options
+        .and_then(|options| options.uninterpreted_option.into_iter().next())
+        .ok_or_else(|| anyhow!("invalid option {}", option))?;
+
+    let name = render_option_name(&option.name);
+    let value = if let Some(value) = option.string_value {
+        OptionValue::String(String::from_utf8(value)?)
+    } else if let Some(value) = option.positive_int_value {
+        OptionValue::Integer(i64::try_from(value)?)
+    } else if let Some(value) = option.negative_int_value {
+        OptionValue::Integer(value)
+    } else if let Some(value) = option.identifier_value {
+        OptionValue::Identifier(value)
+    } else {
+        bail!("unsupported value for option {}", name);
+    };
+    Ok((name, value))
+}
+
+/// Renders an option back to the `name = value` form, or `None` for aggregate values
+fn render_option(option: &UninterpretedOption) -> Option<String> {
+    let value = if let Some(value) = &option.string_value {
+        let value = String::from_utf8_lossy(value);
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else if let Some(value) = option.positive_int_value {
+        value.to_string()
+    } else if let Some(value) = option.negative_int_value {
+        value.to_string()
+    } else if let Some(value) = option.double_value {
+        value.to_string()
+    } else {
+        option.identifier_value.clone()?
+    };
+    Some(format!("{} = {}", render_option_name(&option.name), value))
+}
+
+/// Option name like `json_name` or `(custom.option).field`
+fn render_option_name(name: &[NamePart]) -> String {
+    name.iter()
+        .map(|part| match part.is_extension {
+            true => format!("({})", part.name_part),
+            false => part.name_part.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+struct FileConverter<'a> {
+    file: &'a str,
+}
+
+impl FileConverter<'_> {
+    fn message(&self, message: &DescriptorProto) -> Result<ProtoMessage> {
+        // Map fields come with a nested entry message
+        let map_entry = |nested: &DescriptorProto| {
+            nested
+                .options
+                .as_ref()
+                .is_some_and(|options| options.map_entry())
+        };
+        if message.nested_type.iter().any(map_entry) {
+            bail!("{}: unsupported field type `map`", self.file);
+        }
+
+        // Fields of proto3 `optional` come with a synthetic oneof, which isn't a real one
+        let real_oneof = |field: &FieldDescriptorProto| {
+            field
+                .oneof_index
+                .filter(|_| !field.proto3_optional())
+                .map(|index| index as usize)
+        };
+
+        let mut oneofs = message
+            .oneof_decl
+            .iter()
+            .map(|oneof| ProtoOneof {
+                name: oneof.name().to_owned(),
+                fields: vec![],
+                comment: None,
+            })
+            .collect::<Vec<_>>();
+        let mut fields = vec![];
+        for field in &message.field {
+            let mut converted = self.field(field)?;
+            match real_oneof(field) {
+                Some(index) => {
+                    converted.oneof_name = Some(oneofs[index].name.clone());
+                    oneofs[index].fields.push(converted);
+                }
+                None => fields.push(converted),
+            }
+        }
+        let synthetic = message
+            .field
+            .iter()
+            .filter(|field| field.proto3_optional())
+            .filter_map(|field| field.oneof_index)
+            .map(|index| index as usize)
+            .collect::<Vec<_>>();
+        let oneofs = oneofs
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !synthetic.contains(index))
+            .map(|(_, oneof)| oneof)
+            .collect();
+
+        Ok(ProtoMessage {
+            name: message.name().to_owned(),
+            fields,
+            nested_messages: message
+                .nested_type
+                .iter()
+                .map(|nested| self.message(nested))
+                .collect::<Result<_>>()?,
+            nested_enums: message.enum_type.iter().map(convert_enum).collect(),
+            oneofs,
+            comment: None,
+            options: vec![],
+        })
+    }
+
+    fn field(&self, field: &FieldDescriptorProto) -> Result<ProtoField> {
+        let field_type = match (field.r#type.map(|_| field.r#type()), field.type_name()) {
+            (None, "google.protobuf.Any" | ".google.protobuf.Any") => ProtoFieldType::Any,
+            (None | Some(Type::Message | Type::Enum), type_name) => {
+                ProtoFieldType::Message(type_name.trim_start_matches('.').to_owned())
+            }
+            (Some(Type::String), _) => ProtoFieldType::String,
+            (Some(Type::Int32), _) => ProtoFieldType::Int32,
+            (Some(Type::Int64), _) => ProtoFieldType::Int64,
+            (Some(Type::Uint32), _) => ProtoFieldType::Uint32,
+            (Some(Type::Uint64), _) => ProtoFieldType::Uint64,
+            (Some(Type::Bool), _) => ProtoFieldType::Bool,
+            (Some(Type::Bytes), _) => ProtoFieldType::Bytes,
+            (Some(Type::Double), _) => ProtoFieldType::Double,
+            (Some(Type::Float), _) => ProtoFieldType::Float,
+            (Some(other), _) => bail!(
+                "{}: unsupported field type `{}`",
+                self.file,
+                other.as_str_name().trim_start_matches("TYPE_").to_lowercase()
+            ),
+        };
+
+        Ok(ProtoField {
+            name: field.name().to_owned(),
+            field_type,
+            number: u32::try_from(field.number())?,
+            json_name: field.json_name.clone(),
+            comment: None,
+            optional: field.proto3_optional(),
+            repeated: field.label.is_some() && field.label() == Label::Repeated,
+            oneof_name: None,
+        })
+    }
+}
+
+fn convert_enum(proto_enum: &EnumDescriptorProto) -> ProtoEnum {
+    ProtoEnum {
+        name: proto_enum.name().to_owned(),
+        values: proto_enum
+            .value
+            .iter()
+            .map(|value| ProtoEnumValue {
+                name: value.name().to_owned(),
+                number: value.number(),
+                comment: None,
+            })
+            .collect(),
+        comment: None,
+    }
+}
+
+fn convert_service(service: &ServiceDescriptorProto) -> ProtoService {
+    ProtoService {
+        name: service.name().to_owned(),
+        rpcs: service
+            .method
+            .iter()
+            .map(|method| ProtoRpc {
+                name: method.name().to_owned(),
+                request_type: method.input_type().trim_start_matches('.').to_owned(),
+                response_type: method.output_type().trim_start_matches('.').to_owned(),
+                comment: None,
+                client_streaming: method.client_streaming(),
+                server_streaming: method.server_streaming(),
+            })
+            .collect(),
+        comment: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoWriter;
+
+    #[test]
+    fn test_parse_writer_output() {
+        let mut writer = ProtoWriter::new("starknet.v0_8_1.main");
+        writer.add_import("common.proto");
+        writer.add_enum(&ProtoEnum {
+            name: "Status".to_string(),
+            values: vec![
+                ProtoEnumValue {
+                    name: "STATUS_UNSPECIFIED".to_string(),
+                    number: 0,
+                    comment: Some("Default".to_string()),
+                },
+                ProtoEnumValue {
+                    name: "ACCEPTED".to_string(),
+                    number: 1,
+                    comment: None,
+                },
+            ],
+            comment: None,
+        });
+        let field = |name: &str, field_type, number| ProtoField {
+            name: name.to_string(),
+            field_type,
+            number,
+            json_name: Some(name.to_uppercase()),
+            comment: Some("A field".to_string()),
+            optional: false,
+            repeated: false,
+            oneof_name: None,
+        };
+        writer.add_message(&ProtoMessage {
+            name: "Block".to_string(),
+            fields: vec![
+                ProtoField {
+                    repeated: true,
+                    ..field("transactions", ProtoFieldType::Message("Txn".to_string()), 1)
+                },
+                ProtoField {
+                    optional: true,
+                    ..field("status", ProtoFieldType::Message("Status".to_string()), 2)
+                },
+            ],
+            nested_messages: vec![],
+            nested_enums: vec![],
+            oneofs: vec![ProtoOneof {
+                name: "id".to_string(),
+                fields: vec![
+                    field("hash", ProtoFieldType::String, 3),
+                    field("number", ProtoFieldType::Uint64, 4),
+                ],
+                comment: None,
+            }],
+            comment: Some("A block".to_string()),
+            options: vec![],
+        });
+        writer.add_service(&ProtoService {
+            name: "StarknetWsService".to_string(),
+            rpcs: vec![ProtoRpc {
+                name: "SubscribeNewHeads".to_string(),
+                request_type: "SubscribeNewHeadsRequest".to_string(),
+                response_type: "SubscribeNewHeadsResponse".to_string(),
+                comment: Some("Streams new heads".to_string()),
+                client_streaming: false,
+                server_streaming: true,
+            }],
+            comment: None,
+        });
+
+        let file = parse_proto_file("ws.proto", &writer.to_string()).unwrap();
+        assert_eq!(file.package, "starknet.v0_8_1.main");
+        assert_eq!(file.imports, vec!["common.proto"]);
+
+        assert_eq!(file.enums[0].values[1].name, "ACCEPTED");
+        assert_eq!(file.enums[0].values[1].number, 1);
+
+        let block = &file.messages[0];
+        assert_eq!(block.fields.len(), 2);
+        assert!(block.fields[0].repeated);
+        assert!(
+            matches!(&block.fields[0].field_type, ProtoFieldType::Message(name) if name == "Txn")
+        );
+        assert!(block.fields[1].optional);
+        assert_eq!(block.fields[1].json_name.as_deref(), Some("STATUS"));
+        assert_eq!(block.oneofs[0].fields[1].number, 4);
+        assert_eq!(block.oneofs[0].fields[1].oneof_name.as_deref(), Some("id"));
+        assert!(matches!(block.oneofs[0].fields[1].field_type, ProtoFieldType::Uint64));
+
+        let rpc = &file.services[0].rpcs[0];
+        assert_eq!(rpc.request_type, "SubscribeNewHeadsRequest");
+        assert!(rpc.server_streaming && !rpc.client_streaming);
+    }
+
+    #[test]
+    fn test_parse_common_constructs() {
+        let file = parse_proto_file(
+            "other.proto",
+            r#"
+            syntax = "proto3";
+            /* block
+               comment */
+            package other.v1;
+            import public "google/protobuf/any.proto";
+            option (custom) = { a: 1 };
+
+            message Outer {
+              reserved 2, 15 to 20;
+              reserved "old";
+              message Inner { int64 value = 1 [deprecated = true, (x.y).z = -1]; }
+              enum Kind { option allow_alias = true; KIND_A = 0; KIND_B = 1 [deprecated = true]; }
+              .other.v1.Outer.Inner inner = 1;
+              google.protobuf.Any any = 3;
+            }
+
+            service Other {
+              rpc Call(Outer) returns (Outer) { option deprecated = true; }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let outer = &file.messages[0];
+        assert_eq!(outer.nested_messages[0].fields[0].name, "value");
+        assert_eq!(outer.nested_enums[0].values.len(), 2);
+        assert!(matches!(
+            &outer.fields[0].field_type,
+            ProtoFieldType::Message(name) if name == "other.v1.Outer.Inner"
+        ));
+        assert!(matches!(outer.fields[1].field_type, ProtoFieldType::Any));
+        assert_eq!(file.services[0].rpcs[0].name, "Call");
+
+        let error = parse_proto_file(
+            "bad.proto",
+            "syntax = \"proto3\";\nmessage A {\n  map<string, string> m = 1;\n}",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "bad.proto: unsupported field type `map`");
+
+        let error = parse_proto_file("proto2.proto", "message A { optional string a = 1; }")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "proto2.proto: unsupported syntax \"proto2\"");
+    }
+}
@@ -0,0 +1,237 @@
+//! Backends running an external program, protoc-plugin style: the definitions are written to its
+//! standard input and the files to generate are read from its standard output, so other targets
+//! don't need changes to this crate.
+
+use std::{
+    io::Write,
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use prost::Message;
+use prost_types::compiler::{CodeGeneratorRequest, CodeGeneratorResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    proto_gen::{backend::Backend, descriptor::file_descriptor_set, ProtoIr},
+    spec::Specification,
+};
+
+/// How a plugin reads the definitions and writes the files it generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginFormat {
+    /// A `PluginRequest` as JSON, answered with a `PluginResponse` as JSON
+    Json,
+    /// A `google.protobuf.compiler.CodeGeneratorRequest`, answered with a
+    /// `CodeGeneratorResponse`, the way protoc runs its plugins. The request only holds the
+    /// descriptors of the files, not the rest of the IR.
+    Protobuf,
+}
+
+impl FromStr for PluginFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(Self::Json),
+            "protobuf" => Ok(Self::Protobuf),
+            _ => bail!("unknown plugin format: {} (expected json or protobuf)", s),
+        }
+    }
+}
+
+/// What a JSON plugin reads from its standard input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRequest {
+    /// Free-form option passed to the plugin, like the parameter of a protoc plugin
+    pub parameter: Option<String>,
+    pub ir: ProtoIr,
+}
+
+/// What a JSON plugin writes to its standard output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginResponse {
+    #[serde(default)]
+    pub files: Vec<PluginFile>,
+    /// Why the plugin failed, in which case its files are ignored
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginFile {
+    /// Path relative to the directory of the spec version
+    pub name: String,
+    pub content: String,
+}
+
+/// Runs `program` with the definitions on its standard input, and renders the files it writes
+/// to its standard output
+pub struct PluginBackend {
+    program: PathBuf,
+    format: PluginFormat,
+    parameter: Option<String>,
+}
+
+impl PluginBackend {
+    pub fn new(program: impl Into<PathBuf>, format: PluginFormat) -> Self {
+        Self {
+            program: program.into(),
+            format,
+            parameter: None,
+        }
+    }
+
+    pub fn with_parameter(mut self, parameter: Option<String>) -> Self {
+        self.parameter = parameter;
+        self
+    }
+
+    /// What the plugin reads from its standard input
+    pub fn request(&self, ir: &ProtoIr) -> Result<Vec<u8>> {
+        match self.format {
+            PluginFormat::Json => {
+                let request = PluginRequest {
+                    parameter: self.parameter.clone(),
+                    ir: ir.clone(),
+                };
+                Ok(serde_json::to_vec_pretty(&request)?)
+            }
+            PluginFormat::Protobuf => {
+                let set = file_descriptor_set(&ir.files, |_| 0)?;
+                let request = CodeGeneratorRequest {
+                    file_to_generate: ir.files.iter().map(|file| file.name.clone()).collect(),
+                    parameter: self.parameter.clone(),
+                    proto_file: set.file,
+                    compiler_version: None,
+                };
+                Ok(request.encode_to_vec())
+            }
+        }
+    }
+
+    /// Files of what the plugin wrote to its standard output
+    fn files(&self, output: &[u8]) -> Result<Vec<(String, String)>> {
+        let (files, error) = match self.format {
+            PluginFormat::Json => {
+                let response: PluginResponse =
+                    serde_json::from_slice(output).context("invalid plugin response")?;
+                let files = response.files.into_iter().map(|file| (file.name, file.content));
+                (files.collect::<Vec<_>>(), response.error)
+            }
+            PluginFormat::Protobuf => {
+                let response =
+                    CodeGeneratorResponse::decode(output).context("invalid plugin response")?;
+                let mut files = vec![];
+                for file in response.file {
+                    let name = file.name.ok_or_else(|| anyhow!("plugin wrote a file without name"))?;
+                    if file.insertion_point.is_some() {
+                        bail!("plugin wrote an insertion point into {}, which isn't supported", name);
+                    }
+                    files.push((name, file.content.unwrap_or_default()));
+                }
+                (files, response.error)
+            }
+        };
+        if let Some(error) = error {
+            bail!("plugin {} failed: {}", self.program.display(), error);
+        }
+        for (name, _) in &files {
+            check_path(name)?;
+        }
+        Ok(files)
+    }
+}
+
+impl Backend for PluginBackend {
+    fn render(&self, _specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>> {
+        let request = self.request(ir)?;
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("failed to run plugin {}", self.program.display()))?;
+
+        // Written from another thread so a plugin writing before reading all of its input can't
+        // block on a full pipe
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = thread::spawn(move || stdin.write_all(&request));
+        let output = child.wait_with_output()?;
+        // A plugin may exit without reading its input, which fails the write
+        let written = writer.join().expect("writing the plugin request panicked");
+        if !output.status.success() {
+            bail!("plugin {} exited with {}", self.program.display(), output.status);
+        }
+        written.with_context(|| format!("failed to write to plugin {}", self.program.display()))?;
+
+        self.files(&output.stdout)
+    }
+}
+
+/// Checks a plugin writes `name` inside the output directory
+fn check_path(name: &str) -> Result<()> {
+    let path = Path::new(name);
+    let inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if name.is_empty() || !inside {
+        bail!("plugin wrote {:?}, which isn't a relative path inside the output directory", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::{ProtoConfig, ProtoGenerator};
+    use prost_types::compiler::code_generator_response::File;
+
+    #[test]
+    fn test_responses() {
+        let json = PluginBackend::new("plugin", PluginFormat::Json);
+        let files = json
+            .files(br#"{"files":[{"name":"kotlin/Txn.kt","content":"class Txn"}]}"#)
+            .unwrap();
+        assert_eq!(files, [("kotlin/Txn.kt".to_owned(), "class Txn".to_owned())]);
+        let error = json.files(br#"{"error":"unsupported schema"}"#).unwrap_err();
+        assert_eq!(error.to_string(), "plugin plugin failed: unsupported schema");
+        assert!(json.files(br#"{"files":[{"name":"../Txn.kt","content":""}]}"#).is_err());
+        assert!(json.files(br#"{"files":[{"name":"/tmp/Txn.kt","content":""}]}"#).is_err());
+
+        let protobuf = PluginBackend::new("plugin", PluginFormat::Protobuf);
+        let response = CodeGeneratorResponse {
+            file: vec![File {
+                name: Some("txn.swift".to_owned()),
+                content: Some("struct Txn {}".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let files = protobuf.files(&response.encode_to_vec()).unwrap();
+        assert_eq!(files, [("txn.swift".to_owned(), "struct Txn {}".to_owned())]);
+    }
+
+    #[test]
+    fn test_protobuf_requests_hold_file_descriptors() {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let ir = ProtoGenerator::new(ProtoConfig::new("v0_8_1")).resolve(&specs).unwrap();
+        let backend =
+            PluginBackend::new("plugin", PluginFormat::Protobuf).with_parameter(Some("lang=kotlin".to_owned()));
+
+        let request = CodeGeneratorRequest::decode(backend.request(&ir).unwrap().as_slice()).unwrap();
+        assert_eq!(request.parameter.as_deref(), Some("lang=kotlin"));
+        assert_eq!(
+            request.file_to_generate,
+            ["common.proto", "main.proto", "write.proto", "trace.proto", "ws.proto"]
+        );
+        // Well-known imports come first, so every file follows its dependencies
+        let names = request.proto_file.iter().filter_map(|file| file.name.as_deref()).collect::<Vec<_>>();
+        assert!(names.starts_with(&["google/protobuf/timestamp.proto"]));
+        assert!(names.ends_with(&["common.proto", "main.proto", "write.proto", "trace.proto", "ws.proto"]));
+    }
+}
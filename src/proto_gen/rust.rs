@@ -0,0 +1,604 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::{
+    proto_gen::{
+        backend::Backend,
+        layout::proto_sources,
+        rust_serde::HEX_MODULE,
+        rust_types::{SerdeGenerator, SPEC_JSON_MODULE},
+        service::{subscription_notifications, ServiceGenerator},
+        ProtoConfig, ProtoFile, ProtoIr, ProtoRpc,
+    },
+    spec::{Method, Specification},
+};
+
+/// Name of the file holding the merged specification next to the bridge
+pub const BRIDGE_SPEC_FILE: &str = "spec.json";
+
+/// Directory of the Rust crate next to the proto files of a version
+pub const RUST_CRATE_DIR: &str = "rust";
+
+/// Directory of the proto files within the Rust crate
+pub const RUST_CRATE_PROTO_DIR: &str = "proto";
+
+/// Renders the [`generate_bridge`] module as `bridge.rs`, with the merged specification it reads
+/// in [`BRIDGE_SPEC_FILE`]
+pub struct BridgeBackend {
+    config: ProtoConfig,
+}
+
+impl BridgeBackend {
+    pub fn new(config: ProtoConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Backend for BridgeBackend {
+    fn render(&self, specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>> {
+        Ok(vec![
+            ("bridge.rs".to_owned(), generate_bridge(&ir.files, specs, &self.config)),
+            // The bridge embeds the merged specification to convert payloads at runtime
+            (BRIDGE_SPEC_FILE.to_owned(), serde_json::to_string_pretty(specs)?),
+        ])
+    }
+}
+
+/// Renders the [`generate_rust_crate`] package in [`RUST_CRATE_DIR`]
+pub struct RustCrateBackend {
+    config: ProtoConfig,
+}
+
+impl RustCrateBackend {
+    pub fn new(config: ProtoConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Backend for RustCrateBackend {
+    fn render(&self, specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>> {
+        let mut files = generate_rust_crate(&ir.files, specs, &self.config)?;
+        // The crate is self-contained, so it gets copies of the proto files
+        for (filename, content) in proto_sources(specs, ir)? {
+            files.push((format!("{}/{}", RUST_CRATE_PROTO_DIR, filename), content));
+        }
+        Ok(files
+            .into_iter()
+            .map(|(filename, content)| (format!("{}/{}", RUST_CRATE_DIR, filename), content))
+            .collect())
+    }
+}
+
+/// Rust source implementing every generated service for
+/// `starknet_grpc_codegen::bridge::JsonRpcBridge`, the services being compiled by
+/// `tonic-prost-build`, and creating the `starknet_grpc_codegen::server::JsonRpcServer` calling
+/// them.
+///
+/// The file is meant to be a module next to the modules of the generated packages, e.g.
+/// `starknet::v0_8_1::bridge`, and to find the merged specification in
+/// [`BRIDGE_SPEC_FILE`] next to it.
+pub fn generate_bridge(files: &[ProtoFile], specs: &Specification, config: &ProtoConfig) -> String {
+    let rpc_names = ServiceGenerator::new("", config);
+    let methods = specs
+        .methods
+        .iter()
+        .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+        .collect::<Vec<_>>();
+    let version_prefix = format!("{}.{}.", config.package_prefix, config.version);
+
+    let mut out = String::new();
+    out.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+    out.push_str("// Generated from Starknet JSON-RPC specification\n");
+    let _ = writeln!(out, "// Specification version: {}", specs.info.version);
+    out.push_str("//\n");
+    out.push_str("// Implements the gRPC services by forwarding each call to a JSON-RPC node, and\n");
+    out.push_str("// serves JSON-RPC on top of them.\n");
+    out.push('\n');
+    let streams = files
+        .iter()
+        .flat_map(|file| &file.services)
+        .any(|service| service.rpcs.iter().any(|rpc| rpc.server_streaming));
+    let bridge_items = match streams {
+        true => "{JsonRpcBridge, ResponseStream}",
+        false => "JsonRpcBridge",
+    };
+    out.push_str("use starknet_grpc_codegen::{\n");
+    let _ = writeln!(out, "    bridge::{},", bridge_items);
+    out.push_str("    server::JsonRpcServer,\n");
+    out.push_str("    ProtoConfig, Transcoder,\n");
+    out.push_str("};\n");
+    out.push_str("use tonic::{Request, Response, Status};\n");
+    out.push('\n');
+    out.push_str("type BoxError = Box<dyn std::error::Error + Send + Sync>;\n");
+    out.push('\n');
+    out.push_str("/// Merged specification the services were generated from\n");
+    let _ = writeln!(out, "pub const SPEC_JSON: &str = include_str!(\"{}\");", BRIDGE_SPEC_FILE);
+    out.push('\n');
+    out.push_str("/// Transcoder between the methods of the spec and the generated messages\n");
+    out.push_str("pub fn transcoder() -> Result<Transcoder, BoxError> {\n");
+    let _ = writeln!(
+        out,
+        "    Ok(Transcoder::from_spec_json(SPEC_JSON, &ProtoConfig::new(\"{}\"))?)",
+        config.version
+    );
+    out.push_str("}\n");
+    out.push('\n');
+    out.push_str("/// Bridge to the JSON-RPC node at the `http://` URL `endpoint`\n");
+    out.push_str("pub fn bridge(endpoint: &str) -> Result<JsonRpcBridge, BoxError> {\n");
+    out.push_str("    Ok(JsonRpcBridge::new(endpoint, transcoder()?)?)\n");
+    out.push_str("}\n");
+    out.push('\n');
+    out.push_str("/// JSON-RPC server calling the services on `backend`, e.g. a\n");
+    out.push_str("/// `tonic::transport::Channel`\n");
+    out.push_str("pub fn json_rpc_server<T>(backend: T) -> Result<JsonRpcServer<T>, BoxError> {\n");
+    out.push_str("    Ok(JsonRpcServer::new(backend, transcoder()?))\n");
+    out.push_str("}\n");
+
+    for file in files {
+        let module = match file.package.strip_prefix(&version_prefix) {
+            Some(relative) => format!("super::{}", relative.replace('.', "::")),
+            None => format!("crate::{}", file.package.replace('.', "::")),
+        };
+        for service in &file.services {
+            out.push('\n');
+            out.push_str("#[tonic::async_trait]\n");
+            let _ = writeln!(
+                out,
+                "impl {}::{}_server::{} for JsonRpcBridge {{",
+                module,
+                naive_snake_case(&service.name),
+                service.name
+            );
+            let rpcs = service.rpcs.iter().filter_map(|rpc| {
+                let method = methods
+                    .iter()
+                    .find(|method| rpc_names.method_name_to_rpc_name(&method.name) == rpc.name)?;
+                Some((method, rpc))
+            });
+            for (i, (method, rpc)) in rpcs.enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                let subscription = !subscription_notifications(method, &methods).is_empty();
+                write_rpc_impl(&mut out, &module, method, rpc, subscription);
+            }
+            out.push_str("}\n");
+        }
+    }
+
+    out
+}
+
+fn write_rpc_impl(
+    out: &mut String,
+    module: &str,
+    method: &Method,
+    rpc: &ProtoRpc,
+    subscription: bool,
+) {
+    let function = to_snake_case(&rpc.name);
+    let request = format!("{}::{}", module, to_upper_camel_case(&rpc.request_type));
+    let response = format!("{}::{}", module, to_upper_camel_case(&rpc.response_type));
+    let request = match rpc.client_streaming {
+        true => format!("Request<tonic::Streaming<{}>>", request),
+        false => format!("Request<{}>", request),
+    };
+
+    if rpc.server_streaming {
+        let stream = format!("{}Stream", rpc.name);
+        let _ = writeln!(out, "    type {} = ResponseStream<{}>;", stream, response);
+        out.push('\n');
+        let _ = writeln!(out, "    async fn {}(", function);
+        out.push_str("        &self,\n");
+        let _ = writeln!(out, "        request: {},", request);
+        let _ = writeln!(out, "    ) -> Result<Response<Self::{}>, Status> {{", stream);
+    } else {
+        let _ = writeln!(out, "    async fn {}(", function);
+        out.push_str("        &self,\n");
+        let _ = writeln!(out, "        request: {},", request);
+        let _ = writeln!(out, "    ) -> Result<Response<{}>, Status> {{", response);
+    }
+
+    // Subscriptions stream their notifications over a WebSocket
+    let call = match (rpc.server_streaming, subscription) {
+        (true, true) => "subscribe",
+        (true, false) => "call_streaming",
+        (false, _) => "call",
+    };
+    if rpc.client_streaming {
+        // A JSON-RPC call has a single set of params
+        out.push_str("        let _ = request;\n");
+        let _ = writeln!(
+            out,
+            "        Err(Status::unimplemented(\"{} takes a single request\"))",
+            method.name
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "        JsonRpcBridge::{}(self, \"{}\", request).await",
+            call, method.name
+        );
+    }
+    out.push_str("    }\n");
+}
+
+/// Files of a Cargo package compiling `files` with `protox` and `tonic-prost-build`, by path
+/// relative to its root, the proto files themselves being expected in
+/// [`RUST_CRATE_PROTO_DIR`].
+///
+/// Each package gets a module under its full name, e.g. `starknet::v0_8_1::main`. Packages with
+/// services are behind a feature named after their last segment, all enabled by default, and
+/// only compiled when it is. The `serde` feature, also enabled by default, adds `serde` impls
+/// writing and reading the messages in the JSON of the spec.
+pub fn generate_rust_crate(
+    files: &[ProtoFile],
+    specs: &Specification,
+    config: &ProtoConfig,
+) -> Result<Vec<(String, String)>> {
+    let features = files
+        .iter()
+        .map(|file| match file.services.is_empty() {
+            true => None,
+            false => file.package.rsplit('.').next(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut manifest = String::new();
+    manifest.push_str("# AUTO-GENERATED CARGO MANIFEST. DO NOT EDIT\n");
+    let _ = writeln!(
+        manifest,
+        "# Generated from Starknet JSON-RPC specification {}",
+        specs.info.version
+    );
+    manifest.push('\n');
+    manifest.push_str("[package]\n");
+    let _ = writeln!(
+        manifest,
+        "name = \"{}-grpc-{}\"",
+        config.package_prefix.replace(['.', '_'], "-"),
+        config.version.replace('_', "-")
+    );
+    let _ = writeln!(manifest, "version = \"{}\"", specs.info.version);
+    manifest.push_str("edition = \"2021\"\n");
+    let _ = writeln!(
+        manifest,
+        "description = \"gRPC services of the Starknet JSON-RPC specification {}\"",
+        specs.info.version
+    );
+    manifest.push('\n');
+    manifest.push_str("[features]\n");
+    let feature_list = features
+        .iter()
+        .flatten()
+        .chain([&SERDE_FEATURE])
+        .map(|feature| format!("\"{}\"", feature))
+        .collect::<Vec<_>>();
+    let _ = writeln!(manifest, "default = [{}]", feature_list.join(", "));
+    for feature in features.iter().flatten() {
+        let _ = writeln!(manifest, "{} = []", feature);
+    }
+    let _ = writeln!(manifest, "{} = [\"dep:serde\", \"dep:serde_json\"]", SERDE_FEATURE);
+    manifest.push('\n');
+    manifest.push_str("[dependencies]\n");
+    manifest.push_str("prost = \"0.14.1\"\n");
+    manifest.push_str("serde = { version = \"1.0.162\", optional = true }\n");
+    manifest.push_str("serde_json = { version = \"1.0.96\", optional = true }\n");
+    manifest.push_str("tonic = \"0.14.2\"\n");
+    manifest.push_str("tonic-prost = \"0.14.2\"\n");
+    manifest.push('\n');
+    manifest.push_str("[build-dependencies]\n");
+    manifest.push_str("protox = \"0.9.1\"\n");
+    manifest.push_str("tonic-prost-build = \"0.14.2\"\n");
+
+    let mut build = String::new();
+    build.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+    let _ = writeln!(
+        build,
+        "// Generated from Starknet JSON-RPC specification {}",
+        specs.info.version
+    );
+    build.push_str("//\n");
+    build.push_str("// Compiles the proto files of the enabled features without `protoc`.\n");
+    build.push('\n');
+    build.push_str("use std::env;\n");
+    build.push('\n');
+    build.push_str("/// Proto files compiled whatever the enabled features\n");
+    build.push_str("const FILES: &[&str] = &[");
+    let shared = files.iter().zip(&features).filter(|(_, feature)| feature.is_none());
+    for (i, (file, _)) in shared.enumerate() {
+        if i > 0 {
+            build.push_str(", ");
+        }
+        let _ = write!(build, "\"{}/{}\"", RUST_CRATE_PROTO_DIR, file.name);
+    }
+    build.push_str("];\n");
+    build.push('\n');
+    build.push_str("/// Proto file of each feature\n");
+    build.push_str("const FEATURES: &[(&str, &str)] = &[\n");
+    for (file, feature) in files.iter().zip(&features) {
+        if let Some(feature) = feature {
+            let _ = writeln!(
+                build,
+                "    (\"{}\", \"{}/{}\"),",
+                feature,
+                RUST_CRATE_PROTO_DIR,
+                file.name
+            );
+        }
+    }
+    build.push_str("];\n");
+    build.push('\n');
+    build.push_str("fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    let _ = writeln!(build, "    println!(\"cargo:rerun-if-changed={}\");", RUST_CRATE_PROTO_DIR);
+    build.push_str("    let mut files = FILES.to_vec();\n");
+    build.push_str("    for (feature, file) in FEATURES {\n");
+    build.push_str("        let name = format!(\"CARGO_FEATURE_{}\", feature.to_uppercase());\n");
+    build.push_str("        if env::var_os(name).is_some() {\n");
+    build.push_str("            files.push(file);\n");
+    build.push_str("        }\n");
+    build.push_str("    }\n");
+    let _ = writeln!(
+        build,
+        "    let fds = protox::compile(files, [\"{}\"])?;",
+        RUST_CRATE_PROTO_DIR
+    );
+    build.push_str("    tonic_prost_build::configure().compile_fds(fds)?;\n");
+    build.push_str("    Ok(())\n");
+    build.push_str("}\n");
+
+    let mut lib = String::new();
+    lib.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+    let _ = writeln!(
+        lib,
+        "// Generated from Starknet JSON-RPC specification {}",
+        specs.info.version
+    );
+    lib.push('\n');
+    let _ = writeln!(
+        lib,
+        "//! gRPC services of the Starknet JSON-RPC specification {}",
+        specs.info.version
+    );
+    lib.push('\n');
+    let _ = writeln!(lib, "#[cfg(feature = \"{}\")]", SERDE_FEATURE);
+    lib.push_str("mod spec_json;\n");
+    let _ = writeln!(lib, "#[cfg(feature = \"{}\")]", SERDE_FEATURE);
+    lib.push_str("mod hex;\n");
+    lib.push('\n');
+    let packages = files
+        .iter()
+        .zip(&features)
+        .map(|(file, feature)| Package {
+            segments: file.package.split('.').collect(),
+            feature: *feature,
+            serde: serde_file(file),
+        })
+        .collect::<Vec<_>>();
+    write_package_modules(&mut lib, &packages, 0);
+
+    let mut crate_files = vec![
+        ("Cargo.toml".to_owned(), manifest),
+        ("build.rs".to_owned(), build),
+        ("src/lib.rs".to_owned(), lib),
+        ("src/spec_json.rs".to_owned(), SPEC_JSON_MODULE.to_owned()),
+        ("src/hex.rs".to_owned(), HEX_MODULE.to_owned()),
+    ];
+    let serde = SerdeGenerator::new(specs, config, files)?;
+    for file in files {
+        crate_files.push((format!("src/{}", serde_file(file)), serde.generate(file)?));
+    }
+    Ok(crate_files)
+}
+
+/// Feature of the generated crate enabling the `serde` impls
+const SERDE_FEATURE: &str = "serde";
+
+/// Path of the `serde` impls of the messages of `file`, relative to `src`
+fn serde_file(file: &ProtoFile) -> String {
+    format!("serde/{}.rs", file.name.trim_end_matches(".proto"))
+}
+
+/// Package compiled into the generated crate
+#[derive(Clone)]
+struct Package<'a> {
+    /// Segments of the name of the package
+    segments: Vec<&'a str>,
+    /// Feature enabling the package, if it has services
+    feature: Option<&'a str>,
+    /// Path of the `serde` impls of its messages, relative to `src`
+    serde: String,
+}
+
+/// Nested modules including the code generated for `packages` below their first `depth` name
+/// segments, in the order the packages come
+fn write_package_modules(out: &mut String, packages: &[Package], depth: usize) {
+    let indent = "    ".repeat(depth);
+    let mut segments = vec![];
+    for package in packages {
+        if !segments.contains(&package.segments[depth]) {
+            segments.push(package.segments[depth]);
+        }
+    }
+    for segment in segments {
+        let children = packages
+            .iter()
+            .filter(|package| package.segments[depth] == segment)
+            .cloned()
+            .collect::<Vec<_>>();
+        // A package ends here if its name has no further segment
+        let leaf = children.iter().find(|package| package.segments.len() == depth + 1);
+        if let Some(feature) = leaf.and_then(|package| package.feature) {
+            let _ = writeln!(out, "{}#[cfg(feature = \"{}\")]", indent, feature);
+        }
+        let _ = writeln!(out, "{}pub mod {} {{", indent, segment);
+        if let Some(package) = leaf {
+            let name = package.segments.join(".");
+            let _ = writeln!(out, "{}    tonic::include_proto!(\"{}\");", indent, name);
+            let _ = writeln!(out, "{}    #[cfg(feature = \"{}\")]", indent, SERDE_FEATURE);
+            let _ = writeln!(out, "{}    include!(\"{}\");", indent, package.serde);
+        }
+        let nested = children
+            .into_iter()
+            .filter(|package| package.segments.len() > depth + 1)
+            .collect::<Vec<_>>();
+        write_package_modules(out, &nested, depth + 1);
+        let _ = writeln!(out, "{}}}", indent);
+    }
+}
+
+/// Path of the Rust type `prost-build` generates for the fully qualified proto type `qualified`
+/// from the module of `package`, going up with `super` to the package both names share, e.g.
+/// `super::common::BlockId`. An empty `package` gives the path from the root of the crate.
+pub fn rust_type_path(package: &str, qualified: &str) -> String {
+    let (target, name) = qualified.rsplit_once('.').unwrap_or(("", qualified));
+    let from = package.split('.').filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+    let to = target.split('.').filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+    let shared = from.iter().zip(&to).take_while(|(from, to)| from == to).count();
+    let mut path = vec!["super".to_owned(); from.len() - shared];
+    path.extend(to[shared..].iter().map(|segment| to_snake_case(segment)));
+    path.push(to_upper_camel_case(name));
+    path.join("::")
+}
+
+/// Module name `tonic-build` gives to a service, inserting an underscore before each uppercase
+/// letter
+fn naive_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(ch) = chars.next() {
+        snake.push(ch.to_ascii_lowercase());
+        if chars.peek().is_some_and(|next| next.is_uppercase()) {
+            snake.push('_');
+        }
+    }
+    snake
+}
+
+/// Words of an identifier the way `prost-build` splits them: at underscores, before an
+/// uppercase letter following a lowercase one or a digit, and before the last letter of an
+/// uppercase run followed by a lowercase one
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let starts_word = ch.is_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            });
+        if starts_word && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(ch);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Rust name `prost-build` gives to a field or rpc
+pub(crate) fn to_snake_case(name: &str) -> String {
+    let snake = words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match snake.as_str() {
+        "as" | "async" | "await" | "break" | "const" | "continue" | "crate" | "dyn" | "else"
+        | "enum" | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop"
+        | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct"
+        | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while" => {
+            format!("r#{}", snake)
+        }
+        "self" | "super" => format!("{}_", snake),
+        _ => snake,
+    }
+}
+
+/// Rust name `prost-build` gives to a message or enum
+pub(crate) fn to_upper_camel_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().map(|first| first.to_uppercase().collect::<String>());
+            first.unwrap_or_default() + &chars.as_str().to_lowercase()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoGenerator;
+
+    #[test]
+    fn test_rust_names_follow_prost_build() {
+        assert_eq!(naive_snake_case("StarknetMainService"), "starknet_main_service");
+        assert_eq!(to_snake_case("GetBlockWithTxHashes"), "get_block_with_tx_hashes");
+        assert_eq!(to_snake_case("GetL1MessagesStatus"), "get_l1_messages_status");
+        assert_eq!(to_snake_case("Type"), "r#type");
+        assert_eq!(to_upper_camel_case("GetTXNStatusRequest"), "GetTxnStatusRequest");
+        assert_eq!(to_upper_camel_case("CallRequest"), "CallRequest");
+    }
+
+    #[test]
+    fn test_bridge_implements_every_rpc() {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+        let bridge = generate_bridge(&result.protos, &specs, &config);
+
+        assert!(bridge.contains(
+            "impl super::main::starknet_main_service_server::StarknetMainService \
+             for JsonRpcBridge {"
+        ));
+        assert!(bridge.contains(
+            "    async fn get_block_with_tx_hashes(\n        &self,\n        \
+             request: Request<super::main::GetBlockWithTxHashesRequest>,\n    \
+             ) -> Result<Response<super::main::GetBlockWithTxHashesResponse>, Status> {\n        \
+             JsonRpcBridge::call(self, \"starknet_getBlockWithTxHashes\", request).await\n    }\n"
+        ));
+        let rpcs = result.protos.iter().flat_map(|file| &file.services).flat_map(|s| &s.rpcs);
+        assert_eq!(bridge.matches("    async fn ").count(), rpcs.count());
+    }
+
+    #[test]
+    fn test_rust_crate_has_a_feature_per_service() {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+        let files = generate_rust_crate(&result.protos, &specs, &config).unwrap();
+        let file = |path: &str| &files.iter().find(|(name, _)| name == path).unwrap().1;
+
+        let manifest = file("Cargo.toml");
+        assert!(manifest.contains("name = \"starknet-grpc-v0-8-1\"\n"));
+        assert!(manifest.contains(
+            "default = [\"main\", \"write\", \"trace\", \"ws\", \"serde\"]\n"
+        ));
+        assert!(manifest.contains("serde = [\"dep:serde\", \"dep:serde_json\"]\n"));
+        assert!(file("build.rs").contains("const FILES: &[&str] = &[\"proto/common.proto\"];\n"));
+        assert!(file("build.rs").contains("    (\"trace\", \"proto/trace.proto\"),\n"));
+        assert!(file("src/lib.rs").contains(
+            "pub mod starknet {\n    pub mod v0_8_1 {\n        pub mod common {\n            \
+             tonic::include_proto!(\"starknet.v0_8_1.common\");\n            \
+             #[cfg(feature = \"serde\")]\n            include!(\"serde/common.rs\");\n        }\n        \
+             #[cfg(feature = \"main\")]\n        pub mod main {\n"
+        ));
+    }
+}
@@ -0,0 +1,772 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    profile::{profile_type_name, FixedField, FixedValue, ProfileOptions},
+    proto_gen::{
+        backend::Backend,
+        rust::{rust_type_path, to_snake_case, to_upper_camel_case},
+        rust_types::{indent_lines, Kind, Layout, Property, SerdeGenerator},
+        types::{qualify, ProtoEnum, ProtoField, ProtoFieldType, ProtoMessage},
+        ProtoConfig, ProtoFile, ProtoIr,
+    },
+    spec::Specification,
+};
+
+/// Helpers writing integers as hex strings, to be the `hex` module next to the generated ones,
+/// also written at the root of the crates of `generate --rust-crate`
+pub const HEX_MODULE: &str = include_str!("rust_serde/hex.rs");
+
+/// Helpers reading unions, to be the `variants` module next to the generated ones
+pub const VARIANTS_MODULE: &str = include_str!("rust_serde/variants.rs");
+
+/// Path of the hex helpers from the module of a package
+const HEX: &str = "super::hex";
+
+/// Path of the union helpers from the module of a package
+const VARIANTS: &str = "super::variants";
+
+/// Depth of `allOf` members searched for the fields a profile names
+const MAX_MEMBER_DEPTH: usize = 8;
+
+/// Renders plain Rust types deriving `serde` impls that read and write the JSON of the spec, for
+/// services speaking only JSON-RPC. Each package becomes a module next to a `mod.rs` declaring
+/// them.
+///
+/// The options of the profile of the spec version apply to the types they name: fixed fields are
+/// left out of the type and only written and checked in JSON, `Arc` wrapped fields hold an
+/// `Arc`, and additional derives are added to the default ones. Options naming types or fields
+/// the spec version doesn't generate are ignored.
+pub struct RustSerdeBackend {
+    config: ProtoConfig,
+    options: ProfileOptions,
+}
+
+/// Field a profile fixes, in the message holding it
+struct Fixed<'o> {
+    /// Property of the field
+    name: String,
+    field: &'o FixedField,
+}
+
+impl RustSerdeBackend {
+    pub fn new(config: ProtoConfig, options: ProfileOptions) -> Self {
+        Self { config, options }
+    }
+}
+
+impl Backend for RustSerdeBackend {
+    fn render(&self, specs: &Specification, ir: &ProtoIr) -> Result<Vec<(String, String)>> {
+        let files = ir.files.as_slice();
+        let serde = SerdeGenerator::new(specs, &self.config, files)?;
+        let mut layouts = HashMap::new();
+        for file in files {
+            for message in &file.messages {
+                let qualified = format!("{}.{}", file.package, message.name);
+                layouts.insert(qualified, serde.layout(file, message)?);
+            }
+        }
+
+        let mut renderer = Renderer {
+            serde: &serde,
+            options: &self.options,
+            layouts: &layouts,
+            fixed: HashMap::new(),
+            arc_wrapped: HashMap::new(),
+        };
+        // Profiles name fields of flattened `allOf` members after the type flattening them, so
+        // they go to the member holding them
+        for file in files {
+            for message in &file.messages {
+                let qualified = format!("{}.{}", file.package, message.name);
+                let name = profile_type_name(&message.name);
+                for field in self.options.fixed_fields(&name) {
+                    if let Some(holder) = renderer.holder(&qualified, &field.name, 0) {
+                        let fixed = renderer.fixed.entry(holder).or_default();
+                        match fixed.iter_mut().find(|fixed| fixed.name == field.name) {
+                            // Members shared by broadcasted and accepted transactions take the
+                            // query version of the broadcasted ones
+                            Some(existing) => {
+                                if field.is_query_version {
+                                    existing.field = field;
+                                }
+                            }
+                            None => fixed.push(Fixed {
+                                name: field.name.clone(),
+                                field,
+                            }),
+                        }
+                    }
+                }
+                for field in self.options.arc_wrapped_fields(&name) {
+                    if let Some(holder) = renderer.holder(&qualified, field, 0) {
+                        renderer.arc_wrapped.entry(holder).or_default().insert(field.clone());
+                    }
+                }
+            }
+        }
+
+        let mut module = String::new();
+        write_header(&mut module, specs);
+        module.push_str("//\n");
+        module.push_str(
+            "// Rust types reading and writing the JSON of the spec, one module per package. They\n",
+        );
+        module.push_str("// need `serde` with the `derive` and `rc` features, and `serde_json`.\n");
+        module.push('\n');
+
+        let mut rendered = vec![];
+        for file in files {
+            let name = module_name(&file.package);
+            let _ = writeln!(module, "pub mod {};", name);
+            rendered.push((format!("{}.rs", name), renderer.file(specs, file)?));
+        }
+        module.push('\n');
+        module.push_str("mod hex;\n");
+        module.push_str("mod variants;\n");
+
+        rendered.insert(0, ("mod.rs".to_owned(), module));
+        rendered.push(("hex.rs".to_owned(), HEX_MODULE.to_owned()));
+        rendered.push(("variants.rs".to_owned(), VARIANTS_MODULE.to_owned()));
+        Ok(rendered)
+    }
+}
+
+struct Renderer<'a, 'm> {
+    serde: &'a SerdeGenerator<'a>,
+    options: &'a ProfileOptions,
+    layouts: &'a HashMap<String, Layout<'m>>,
+    /// Fixed fields of each message, by qualified name
+    fixed: HashMap<String, Vec<Fixed<'a>>>,
+    /// Properties of each message holding an `Arc`, by qualified name
+    arc_wrapped: HashMap<String, HashSet<String>>,
+}
+
+/// Field of a generated struct
+#[derive(Clone)]
+struct RustField {
+    ident: String,
+    ty: String,
+    /// Arguments of its `#[serde]` attribute
+    serde: Vec<String>,
+    comment: Option<String>,
+}
+
+impl<'a, 'm> Renderer<'a, 'm> {
+    /// Qualified name of the message holding the property `name` of the message `qualified`,
+    /// itself or one of the `allOf` members it flattens
+    fn holder(&self, qualified: &str, name: &str, depth: usize) -> Option<String> {
+        let Some(Layout::Object(properties, members)) = self.layouts.get(qualified) else {
+            return None;
+        };
+        if properties.iter().any(|property| property.name == name) {
+            return Some(qualified.to_owned());
+        }
+        if depth >= MAX_MEMBER_DEPTH {
+            return None;
+        }
+        let package = package_of(qualified);
+        members.iter().find_map(|(field, _)| match &field.field_type {
+            ProtoFieldType::Message(member) => {
+                self.holder(&qualify(package, member), name, depth + 1)
+            }
+            _ => None,
+        })
+    }
+
+    fn file(&self, specs: &Specification, file: &ProtoFile) -> Result<String> {
+        let mut out = String::new();
+        write_header(&mut out, specs);
+        out.push_str("//\n");
+        let _ = writeln!(
+            out,
+            "// Types of {} reading and writing the JSON of the spec.",
+            file.name
+        );
+        out.push('\n');
+        out.push_str("use serde::{Deserialize, Serialize};\n");
+        for proto_enum in &file.enums {
+            out.push('\n');
+            self.write_enum(&mut out, proto_enum);
+        }
+        for message in &file.messages {
+            out.push('\n');
+            self.write_message(&mut out, file, message)?;
+        }
+        Ok(out)
+    }
+
+    /// Derives of the type named `name`
+    fn derives(&self, defaults: &[&str], name: &str) -> String {
+        let mut derives = defaults.iter().map(|derive| (*derive).to_owned()).collect::<Vec<_>>();
+        for derive in self.options.additional_derives(&profile_type_name(name)) {
+            if !derives.contains(derive) {
+                derives.push(derive.clone());
+            }
+        }
+        format!("#[derive({})]\n", derives.join(", "))
+    }
+
+    fn write_enum(&self, out: &mut String, proto_enum: &ProtoEnum) {
+        // Enums that don't come from a string schema keep the names of their proto values
+        let values = match self.serde.enum_values(&proto_enum.name) {
+            Some(values) => values.to_vec(),
+            None => proto_enum.values.iter().map(|value| value.name.clone()).collect(),
+        };
+        write_doc(out, proto_enum.comment.as_deref(), "");
+        out.push_str(&self.derives(
+            &["Debug", "Clone", "Copy", "PartialEq", "Eq", "Hash", "Serialize", "Deserialize"],
+            &proto_enum.name,
+        ));
+        let _ = writeln!(out, "pub enum {} {{", to_upper_camel_case(&proto_enum.name));
+        for value in values {
+            let _ = writeln!(out, "    #[serde(rename = {:?})]", value);
+            let _ = writeln!(out, "    {},", variant_name(&value));
+        }
+        out.push_str("}\n");
+    }
+
+    fn write_message(&self, out: &mut String, file: &ProtoFile, message: &ProtoMessage) -> Result<()> {
+        let qualified = format!("{}.{}", file.package, message.name);
+        let name = to_upper_camel_case(&message.name);
+        let derives = &["Debug", "Clone", "PartialEq", "Serialize", "Deserialize"];
+        write_doc(out, message.comment.as_deref(), "");
+        match &self.layouts[&qualified] {
+            Layout::Text(field) => {
+                out.push_str(&self.derives(derives, &message.name));
+                out.push_str("#[serde(transparent)]\n");
+                let _ = writeln!(
+                    out,
+                    "pub struct {} {{\n    pub {}: serde_json::Value,\n}}",
+                    name,
+                    to_snake_case(&field.name)
+                );
+            }
+            Layout::Wrapper(field, kind) => {
+                let property = Property {
+                    field,
+                    name: field.json_name(),
+                    kind: kind.clone(),
+                    required: true,
+                };
+                let mut rust_field = self.field(&qualified, &property);
+                // The wrapper is written as its value, whatever the property is called
+                rust_field.ident = to_snake_case(&field.name);
+                rust_field.serde.retain(|argument| !argument.starts_with("rename"));
+                out.push_str(&self.derives(derives, &message.name));
+                out.push_str("#[serde(transparent)]\n");
+                let _ = writeln!(out, "pub struct {} {{", name);
+                write_field(out, &rust_field, "pub ");
+                out.push_str("}\n");
+            }
+            Layout::Union(oneof, variants) => {
+                let mut names = oneof
+                    .fields
+                    .iter()
+                    .map(|field| match &field.field_type {
+                        ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                            to_upper_camel_case(name.rsplit('.').next().unwrap_or(name))
+                        }
+                        _ => to_upper_camel_case(&field.name),
+                    })
+                    .collect::<Vec<_>>();
+                let distinct = names.iter().collect::<HashSet<_>>().len() == names.len();
+                if !distinct {
+                    names = oneof.fields.iter().map(|field| to_upper_camel_case(&field.name)).collect();
+                }
+                // Deserialize is implemented below, reading the variant the value fits best
+                out.push_str(&self.derives(&["Debug", "Clone", "PartialEq", "Serialize"], &message.name));
+                out.push_str("#[serde(untagged)]\n");
+                let _ = writeln!(out, "pub enum {} {{", name);
+                let mut reads = String::new();
+                for ((field, (kind, discriminators)), variant) in oneof.fields.iter().zip(variants).zip(names) {
+                    let property = Property {
+                        field,
+                        name: field.json_name(),
+                        kind: kind.clone(),
+                        required: true,
+                    };
+                    let rust_field = self.field(&qualified, &property);
+                    write_doc(out, field.comment.as_deref(), "    ");
+                    let (with, read) = match kind {
+                        Kind::Hex(_) => (
+                            format!("#[serde(with = \"{}\")] ", HEX),
+                            format!("{}::deserialize(value.clone())", HEX),
+                        ),
+                        _ => (String::new(), "serde_json::from_value(value.clone())".to_owned()),
+                    };
+                    let _ = writeln!(out, "    {}({}{}),", variant, with, rust_field.ty);
+
+                    let known = match self.properties(package_of(&qualified), field, 0) {
+                        Some(properties) => format!("{}::known(&value, &{:?})", VARIANTS, properties),
+                        None => "0".to_owned(),
+                    };
+                    let attempt = format!(
+                        "if let Ok(v) = {} {{\n    best.offer({}, Self::{}(v));\n}}\n",
+                        read, known, variant
+                    );
+                    if discriminators.is_empty() {
+                        reads.push_str(&indent_lines(&attempt, 8));
+                    } else {
+                        let discriminators = discriminators
+                            .iter()
+                            .map(|(name, values)| format!("({:?}, &{:?})", name, values))
+                            .collect::<Vec<_>>();
+                        let _ = writeln!(
+                            reads,
+                            "        if {}::discriminated(&value, &[{}]) {{",
+                            VARIANTS,
+                            discriminators.join(", ")
+                        );
+                        reads.push_str(&indent_lines(&attempt, 12));
+                        reads.push_str("        }\n");
+                    }
+                }
+                out.push_str("}\n");
+                out.push('\n');
+                let _ = writeln!(out, "impl<'de> Deserialize<'de> for {} {{", name);
+                out.push_str(
+                    "    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {\n",
+                );
+                out.push_str("        let value = serde_json::Value::deserialize(deserializer)?;\n");
+                let _ = writeln!(out, "        let mut best = {}::Best::new();", VARIANTS);
+                out.push_str(&reads);
+                let _ = writeln!(out, "        best.finish({:?})", name);
+                out.push_str("    }\n");
+                out.push_str("}\n");
+            }
+            Layout::Object(properties, members) => {
+                let fixed = self.fixed.get(&qualified).map(Vec::as_slice).unwrap_or_default();
+                let is_fixed = |property: &Property| fixed.iter().any(|fixed| fixed.name == property.name);
+                let mut fields = properties
+                    .iter()
+                    .filter(|property| !is_fixed(property))
+                    .map(|property| self.field(&qualified, property))
+                    .collect::<Vec<_>>();
+                fields.extend(members.iter().map(|(field, _)| self.member(&qualified, field)));
+                let query = fixed.iter().any(|fixed| fixed.field.is_query_version);
+                if query {
+                    fields.push(RustField {
+                        ident: "is_query".to_owned(),
+                        ty: "bool".to_owned(),
+                        serde: vec![],
+                        comment: Some(
+                            "Whether the transaction is only simulated or estimated, its version \
+                             offset by 2^128"
+                                .to_owned(),
+                        ),
+                    });
+                }
+
+                out.push_str(&self.derives(derives, &message.name));
+                if !fixed.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "#[serde(into = \"{}Json\", try_from = \"{}Json\")]",
+                        name, name
+                    );
+                }
+                let _ = writeln!(out, "pub struct {} {{", name);
+                for field in &fields {
+                    write_field(out, field, "pub ");
+                }
+                out.push_str("}\n");
+                if !fixed.is_empty() {
+                    self.write_fixed(out, &qualified, &name, properties, members, fixed)?;
+                }
+            }
+            Layout::Response {
+                result,
+                error,
+                notifications,
+            } => {
+                // Each of the result, the error and the notifications is left out of the others
+                let mut properties = result.iter().chain(error).cloned().collect::<Vec<_>>();
+                if let Some((oneof, kinds)) = notifications {
+                    properties.extend(oneof.fields.iter().zip(kinds).map(|(field, kind)| Property {
+                        field,
+                        name: field.json_name(),
+                        kind: kind.clone(),
+                        required: false,
+                    }));
+                }
+                out.push_str(&self.derives(derives, &message.name));
+                let _ = writeln!(out, "pub struct {} {{", name);
+                for property in properties {
+                    let property = Property { required: false, ..property };
+                    write_field(out, &self.field(&qualified, &property), "pub ");
+                }
+                out.push_str("}\n");
+            }
+        }
+        Ok(())
+    }
+
+    /// Private struct with the fixed fields of the message `name`, the form its JSON is read and
+    /// written in, and the conversions checking and dropping them
+    fn write_fixed(
+        &self,
+        out: &mut String,
+        qualified: &str,
+        name: &str,
+        properties: &[Property],
+        members: &[(&ProtoField, Vec<String>)],
+        fixed: &[Fixed],
+    ) -> Result<()> {
+        let package = package_of(qualified);
+        let mut fields = vec![];
+        let mut writes = vec![];
+        let mut checks = String::new();
+        let mut reads = vec![];
+        for property in properties {
+            let Some(fixed) = fixed.iter().find(|fixed| fixed.name == property.name) else {
+                let field = self.field(qualified, property);
+                writes.push(format!("{}: value.{}", field.ident, field.ident));
+                reads.push(format!("{}: value.{}", field.ident, field.ident));
+                fields.push(field);
+                continue;
+            };
+            let mut field = self.field(qualified, &Property { required: true, ..property.clone() });
+            let ident = field.ident.clone();
+            let value = fixed.field.parse_value()?;
+            if fixed.field.is_query_version {
+                let FixedValue::Number(version) = value else {
+                    bail!("query version `{}` of {} isn't a number", fixed.name, name);
+                };
+                let (version, query) = (format!("{:#x}", version), format!("0x1{:032x}", version));
+                field.ty = "String".to_owned();
+                field.serde.retain(|argument| !argument.starts_with("with"));
+                writes.push(format!(
+                    "{}: if value.is_query {{ {:?} }} else {{ {:?} }}.to_owned()",
+                    ident, query, version
+                ));
+                let _ = writeln!(
+                    checks,
+                    "        let is_query = match value.{}.as_str() {{\n            \
+                     {:?} => false,\n            {:?} => true,\n            \
+                     other => return Err(format!(\"`{}` must be {{:?}} or {{:?}}, got {{:?}}\", \
+                     {:?}, {:?}, other)),\n        }};",
+                    ident, version, query, fixed.name, version, query
+                );
+                reads.push("is_query".to_owned());
+            } else {
+                let (expression, display) = self.fixed_value(package, property, &value, name)?;
+                let write = match field.ty.as_str() {
+                    "String" => format!("{}.to_owned()", expression),
+                    _ => expression.clone(),
+                };
+                writes.push(format!("{}: {}", ident, write));
+                let _ = writeln!(
+                    checks,
+                    "        if value.{} != {} {{\n            \
+                     return Err({:?}.to_owned());\n        }}",
+                    ident,
+                    expression,
+                    format!("`{}` must be {}", fixed.name, display)
+                );
+            }
+            fields.push(field);
+        }
+        for (field, _) in members {
+            let field = self.member(qualified, field);
+            writes.push(format!("{}: value.{}", field.ident, field.ident));
+            reads.push(format!("{}: value.{}", field.ident, field.ident));
+            fields.push(field);
+        }
+
+        out.push('\n');
+        let _ = writeln!(out, "/// JSON of `{}`, with the fields the spec fixes", name);
+        out.push_str("#[derive(Serialize, Deserialize)]\n");
+        let _ = writeln!(out, "struct {}Json {{", name);
+        for field in &fields {
+            write_field(out, &RustField { comment: None, ..field.clone() }, "");
+        }
+        out.push_str("}\n");
+        out.push('\n');
+        let _ = writeln!(out, "impl From<{}> for {}Json {{", name, name);
+        let _ = writeln!(out, "    fn from(value: {}) -> Self {{", name);
+        let _ = writeln!(out, "{}", construct(&writes));
+        out.push_str("    }\n");
+        out.push_str("}\n");
+        out.push('\n');
+        let _ = writeln!(out, "impl TryFrom<{}Json> for {} {{", name, name);
+        out.push_str("    type Error = String;\n");
+        out.push('\n');
+        let _ = writeln!(
+            out,
+            "    fn try_from(value: {}Json) -> Result<Self, Self::Error> {{",
+            name
+        );
+        out.push_str(&checks);
+        let _ = writeln!(out, "        Ok({})", construct(&reads).trim_start());
+        out.push_str("    }\n");
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    /// Rust expression of the fixed `value` of `property` of the message `name`, along with how
+    /// it is written in JSON
+    fn fixed_value(
+        &self,
+        package: &str,
+        property: &Property,
+        value: &FixedValue,
+        name: &str,
+    ) -> Result<(String, String)> {
+        let fixed = match (&property.kind, value) {
+            (Kind::StringIn(_), FixedValue::String(value)) => {
+                (format!("{:?}", value), format!("{:?}", value))
+            }
+            (Kind::Json(rust_type), FixedValue::String(value)) if rust_type == "String" => {
+                (format!("{:?}", value), format!("{:?}", value))
+            }
+            (Kind::Hex(_), FixedValue::Number(value)) => {
+                (value.to_string(), format!("\"{:#x}\"", value))
+            }
+            (Kind::Json(rust_type), FixedValue::Number(value)) if rust_type != "String" => {
+                (value.to_string(), value.to_string())
+            }
+            (Kind::Enum(values), FixedValue::String(_) | FixedValue::Variant(_)) => {
+                let found = values.iter().find(|spec_value| match value {
+                    FixedValue::String(value) => *spec_value == value,
+                    _ => FixedValue::Variant(variant_name(spec_value)) == *value,
+                });
+                let Some(spec_value) = found else {
+                    bail!("{:?} isn't a value of `{}` of {}", value, property.name, name);
+                };
+                let path = self.value_type(package, property.field, &property.kind);
+                (format!("{}::{}", path, variant_name(spec_value)), format!("{:?}", spec_value))
+            }
+            _ => bail!(
+                "`{}` of {} can't hold the fixed value {:?}",
+                property.name,
+                name,
+                value
+            ),
+        };
+        Ok(fixed)
+    }
+
+    /// Rust type of the values of `field`, which have `kind`
+    fn value_type(&self, package: &str, field: &ProtoField, kind: &Kind) -> String {
+        match kind {
+            Kind::Json(rust_type) | Kind::Hex(rust_type) => rust_type.clone(),
+            Kind::StringIn(_) => "String".to_owned(),
+            Kind::Text => "serde_json::Value".to_owned(),
+            Kind::Enum(_) => match &field.field_type {
+                ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                    rust_type_path(package, &qualify(package, name))
+                }
+                _ => "i32".to_owned(),
+            },
+        }
+    }
+
+    /// Field of the message `qualified` holding `property`
+    fn field(&self, qualified: &str, property: &Property) -> RustField {
+        let package = package_of(qualified);
+        let field = property.field;
+        let mut ty = self.value_type(package, field, &property.kind);
+        let arc_wrapped = self
+            .arc_wrapped
+            .get(qualified)
+            .is_some_and(|fields| fields.contains(&property.name));
+        if arc_wrapped {
+            ty = format!("std::sync::Arc<{}>", ty);
+        } else if !field.repeated && self.boxed(qualified, field) {
+            ty = format!("Box<{}>", ty);
+        }
+
+        let ident = field_name(&property.name);
+        let mut serde = vec![];
+        if ident.trim_start_matches("r#") != property.name {
+            serde.push(format!("rename = {:?}", property.name));
+        }
+        if let Kind::Hex(_) = property.kind {
+            serde.push(format!("with = \"{}\"", HEX));
+        }
+        if field.repeated {
+            ty = format!("Vec<{}>", ty);
+            if !property.required {
+                serde.push("default".to_owned());
+                serde.push("skip_serializing_if = \"Vec::is_empty\"".to_owned());
+            }
+        } else if !property.required {
+            ty = format!("Option<{}>", ty);
+            serde.push("default".to_owned());
+            serde.push("skip_serializing_if = \"Option::is_none\"".to_owned());
+        }
+        RustField {
+            ident,
+            ty,
+            serde,
+            comment: field.comment.clone(),
+        }
+    }
+
+    /// Field of the message `qualified` flattening the `allOf` member `field`
+    fn member(&self, qualified: &str, field: &ProtoField) -> RustField {
+        let package = package_of(qualified);
+        let mut ty = match &field.field_type {
+            ProtoFieldType::Message(name) => rust_type_path(package, &qualify(package, name)),
+            _ => "serde_json::Value".to_owned(),
+        };
+        if self.boxed(qualified, field) {
+            ty = format!("Box<{}>", ty);
+        }
+        RustField {
+            ident: to_snake_case(&field.name),
+            ty,
+            serde: vec!["flatten".to_owned()],
+            comment: field.comment.clone(),
+        }
+    }
+
+    /// Properties the JSON of the message `field` holds can have, if it is an object or a union
+    /// of objects
+    fn properties(&self, package: &str, field: &ProtoField, depth: usize) -> Option<Vec<String>> {
+        let ProtoFieldType::Message(name) = &field.field_type else {
+            return None;
+        };
+        if depth >= MAX_MEMBER_DEPTH || field.repeated {
+            return None;
+        }
+        let qualified = qualify(package, name);
+        let mut names = match self.layouts.get(&qualified)? {
+            Layout::Object(properties, members) => properties
+                .iter()
+                .map(|property| property.name.clone())
+                .chain(members.iter().flat_map(|(_, names)| names.iter().cloned()))
+                .collect::<Vec<_>>(),
+            Layout::Union(oneof, _) => {
+                let mut names = vec![];
+                for variant in &oneof.fields {
+                    names.extend(self.properties(package_of(&qualified), variant, depth + 1)?);
+                }
+                names
+            }
+            _ => return None,
+        };
+        names.sort();
+        names.dedup();
+        Some(names)
+    }
+
+    /// Whether a singular `field` of the message `qualified` needs a `Box` to end the recursion
+    /// of its type
+    fn boxed(&self, qualified: &str, field: &ProtoField) -> bool {
+        match &field.field_type {
+            ProtoFieldType::Message(name) => {
+                self.serde.boxed(qualified, &qualify(package_of(qualified), name))
+            }
+            _ => false,
+        }
+    }
+}
+
+fn write_header(out: &mut String, specs: &Specification) {
+    out.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+    let _ = writeln!(
+        out,
+        "// Generated from Starknet JSON-RPC specification {}",
+        specs.info.version
+    );
+}
+
+fn write_doc(out: &mut String, comment: Option<&str>, indent: &str) {
+    for line in comment.into_iter().flat_map(str::lines) {
+        let _ = writeln!(out, "{}/// {}", indent, line.trim_end());
+    }
+}
+
+fn write_field(out: &mut String, field: &RustField, visibility: &str) {
+    write_doc(out, field.comment.as_deref(), "    ");
+    if !field.serde.is_empty() {
+        let _ = writeln!(out, "    #[serde({})]", field.serde.join(", "));
+    }
+    let _ = writeln!(out, "    {}{}: {},", visibility, field.ident, field.ty);
+}
+
+/// `Self { .. }` with `fields` in `name: value` form, in a function body
+fn construct(fields: &[String]) -> String {
+    let mut out = String::from("        Self {\n");
+    for field in fields {
+        let _ = writeln!(out, "            {},", field);
+    }
+    out.push_str("        }");
+    out
+}
+
+/// Package of the qualified name of a message
+fn package_of(qualified: &str) -> &str {
+    qualified.rsplit_once('.').map_or("", |(package, _)| package)
+}
+
+/// Module of a package, named after its last segment
+fn module_name(package: &str) -> String {
+    to_snake_case(package.rsplit('.').next().unwrap_or(package))
+}
+
+/// Rust name of the field holding the property `name`
+fn field_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    let ident = to_snake_case(&name);
+    match ident.starts_with(|ch: char| ch.is_ascii_digit()) {
+        true => format!("_{}", ident),
+        false => ident,
+    }
+}
+
+/// Rust name of the variant of an enum written as `value`
+fn variant_name(value: &str) -> String {
+    let value = value
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    let name = to_upper_camel_case(&value);
+    match name.starts_with(|ch: char| ch.is_ascii_digit()) || name.is_empty() {
+        true => format!("V{}", name),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoGenerator;
+
+    #[test]
+    fn test_profile_fixes_fields_of_the_members_holding_them() {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let config = ProtoConfig::new("v0_8_1");
+        let ir = ProtoGenerator::new(config.clone()).resolve(&specs).unwrap();
+        let options = ProfileOptions::parse(include_str!("../profiles/0.8.1.json")).unwrap();
+        let files = RustSerdeBackend::new(config, options).render(&specs, &ir).unwrap();
+        let (_, common) = files.iter().find(|(name, _)| name == "common.rs").unwrap();
+
+        // DeclareTransactionV0 fixes `type` and `version`, which its content member holds
+        assert!(common.contains(
+            "#[serde(into = \"DeclareTxnV0ContentJson\", try_from = \"DeclareTxnV0ContentJson\")]\n\
+             pub struct DeclareTxnV0Content {\n"
+        ));
+        assert!(common.contains(
+            "        Self {\n\
+             \x20           r#type: \"DECLARE\".to_owned(),\n"
+        ));
+        assert!(common.contains(
+            "    pub contract_class: std::sync::Arc<SierraContractClass>,\n"
+        ));
+        assert!(common.contains(
+            "            \"0x3\" => false,\n\
+             \x20           \"0x100000000000000000000000000000003\" => true,\n"
+        ));
+    }
+}
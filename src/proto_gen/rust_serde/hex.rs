@@ -0,0 +1,90 @@
+// AUTO-GENERATED RUST FILE. DO NOT EDIT
+//
+// `serde` helpers writing integers as the hex strings of the spec, through `#[serde(with)]`
+// naming this module on integers and on options and vectors of them, or through `serialize` and
+// `deserialize` called with `serde_json` values.
+
+#![allow(dead_code)]
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<T: Hex, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    value.serialize_hex(serializer)
+}
+
+pub fn deserialize<'de, T: Hex, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    T::deserialize_hex(deserializer)
+}
+
+/// Value written as a hex string
+pub trait Hex: Sized {
+    fn serialize_hex<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Value written by its `Hex` impl
+struct AsHex<T>(T);
+
+impl<T: Hex> Serialize for AsHex<&T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_hex(serializer)
+    }
+}
+
+impl<'de, T: Hex> Deserialize<'de> for AsHex<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_hex(deserializer).map(AsHex)
+    }
+}
+
+impl Hex for u64 {
+    fn serialize_hex<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:#x}", self))
+    }
+
+    fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let digits = hex
+            .strip_prefix("0x")
+            .ok_or_else(|| de::Error::custom(format!("expected a hex string, got {:?}", hex)))?;
+        u64::from_str_radix(digits, 16).map_err(de::Error::custom)
+    }
+}
+
+impl Hex for i64 {
+    fn serialize_hex<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let unsigned = u64::try_from(*self)
+            .map_err(|_| ser::Error::custom(format!("{} can't be written as hex", self)))?;
+        unsigned.serialize_hex(serializer)
+    }
+
+    fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unsigned = u64::deserialize_hex(deserializer)?;
+        i64::try_from(unsigned).map_err(de::Error::custom)
+    }
+}
+
+impl<T: Hex> Hex for Option<T> {
+    fn serialize_hex<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(value) => serializer.serialize_some(&AsHex(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<AsHex<T>>::deserialize(deserializer)?.map(|value| value.0))
+    }
+}
+
+impl<T: Hex> Hex for Vec<T> {
+    fn serialize_hex<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(AsHex))
+    }
+
+    fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values = Vec::<AsHex<T>>::deserialize(deserializer)?;
+        Ok(values.into_iter().map(|value| value.0).collect())
+    }
+}
@@ -0,0 +1,51 @@
+// AUTO-GENERATED RUST FILE. DO NOT EDIT
+//
+// Helpers telling apart the variants of the unions of the spec. A union is read as the variant
+// knowing the most properties of the value among the ones its discriminating properties allow,
+// the first one on ties, since unions flattened into an object also see the properties of the
+// object.
+
+#![allow(dead_code)]
+
+use serde::de;
+use serde_json::Value;
+
+/// Whether `value` has one of the allowed values for each discriminating property of a variant
+pub fn discriminated(value: &Value, discriminators: &[(&str, &[&str])]) -> bool {
+    discriminators.iter().all(|(property, values)| {
+        let found = value.get(property).and_then(Value::as_str);
+        found.is_some_and(|found| values.contains(&found))
+    })
+}
+
+/// Number of properties of `value` among `properties`
+pub fn known(value: &Value, properties: &[&str]) -> usize {
+    match value {
+        Value::Object(object) => object.keys().filter(|key| properties.contains(&key.as_str())).count(),
+        _ => 0,
+    }
+}
+
+/// Variant of a union read so far knowing the most properties of the value
+pub struct Best<T>(Option<(usize, T)>);
+
+impl<T> Best<T> {
+    pub fn new() -> Self {
+        Self(None)
+    }
+
+    /// Keeps `variant`, which knows `known` properties of the value, if it knows more than the
+    /// variant kept so far
+    pub fn offer(&mut self, known: usize, variant: T) {
+        if self.0.as_ref().is_none_or(|(best, _)| known > *best) {
+            self.0 = Some((known, variant));
+        }
+    }
+
+    pub fn finish<E: de::Error>(self, union: &str) -> Result<T, E> {
+        match self.0 {
+            Some((_, variant)) => Ok(variant),
+            None => Err(E::custom(format!("the value matches no variant of {}", union))),
+        }
+    }
+}
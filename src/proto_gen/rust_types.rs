@@ -0,0 +1,1109 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    proto_gen::{
+        json_mapping::{JsonMapping, MAX_DEPTH},
+        json_shape::object_properties,
+        rust::{rust_type_path, to_snake_case, to_upper_camel_case},
+        service::{subscription_notifications, ServiceGenerator},
+        types::{qualify, ProtoField, ProtoFieldType, ProtoMessage, ProtoOneof},
+        writer::to_proto_type_name,
+        ProtoConfig, ProtoFile,
+    },
+    refs::resolve_schema,
+    spec::{Method, Primitive, Schema, Specification},
+};
+
+/// Helpers the generated impls call, to be the `spec_json` module at the root of the crate
+pub const SPEC_JSON_MODULE: &str = include_str!("rust_types/spec_json.rs");
+
+/// Path of the helpers in the generated impls
+const HELPERS: &str = "crate::spec_json";
+
+/// What a message stands for in the JSON of the spec
+#[derive(Debug, Clone, Copy)]
+enum Source<'a> {
+    Schema(&'a Schema),
+    Request(&'a Method),
+    Response(&'a Method),
+    /// The error of a response, its `data` holding JSON text
+    RpcError,
+}
+
+/// How a single value is written in the JSON of the spec
+#[derive(Debug, Clone)]
+pub(crate) enum Kind {
+    /// Written by its own `serde` impl, with the Rust type of the value
+    Json(String),
+    /// Integer written as a hex string
+    Hex(String),
+    /// Enum value written as the value of the spec at its index
+    Enum(Vec<String>),
+    /// String restricted to the `enum` of its schema
+    StringIn(Vec<String>),
+    /// Free-form JSON kept as text
+    Text,
+}
+
+/// Property of an object held by a field
+#[derive(Clone)]
+pub(crate) struct Property<'m> {
+    pub(crate) field: &'m ProtoField,
+    pub(crate) name: String,
+    pub(crate) kind: Kind,
+    pub(crate) required: bool,
+}
+
+/// Shape of the JSON of a message
+pub(crate) enum Layout<'m> {
+    /// Free-form JSON kept as text in its single field
+    Text(&'m ProtoField),
+    /// The value of a single field
+    Wrapper(&'m ProtoField, Kind),
+    /// One of the variants of a oneof, each with its kind and the values its discriminating
+    /// properties can have
+    Union(&'m ProtoOneof, Vec<(Kind, Discriminators)>),
+    /// Object of the properties of its fields, followed by the fields of `allOf` members with the
+    /// properties they take
+    Object(Vec<Property<'m>>, Vec<(&'m ProtoField, Vec<String>)>),
+    /// The result of a method, or its error, or one of the notifications of a subscription
+    Response {
+        result: Option<Property<'m>>,
+        error: Option<Property<'m>>,
+        notifications: Option<(&'m ProtoOneof, Vec<Kind>)>,
+    },
+}
+
+/// Required properties of a union variant restricted to an `enum`, e.g. the `type` of
+/// transactions, with the values they can have
+pub(crate) type Discriminators = Vec<(String, Vec<String>)>;
+
+/// Generates `serde` impls writing and reading the messages generated from a spec in the JSON
+/// of the spec: hex strings, spec enum values, unions flattened to the value of their variant
+/// and `allOf` members to their properties
+pub struct SerdeGenerator<'a> {
+    specs: &'a Specification,
+    mapping: JsonMapping,
+    /// Spec values of the enums generated from string schemas, by enum name
+    enum_values: HashMap<String, Vec<String>>,
+    /// Qualified names of the enums
+    enums: HashSet<String>,
+    /// What each message stands for, by qualified name
+    sources: HashMap<String, Source<'a>>,
+    /// Message types of the singular fields of each message, by qualified name
+    nesting: HashMap<String, Vec<String>>,
+}
+
+impl<'a> SerdeGenerator<'a> {
+    pub fn new(
+        specs: &'a Specification,
+        config: &ProtoConfig,
+        files: &'a [ProtoFile],
+    ) -> Result<Self> {
+        let mut enum_values = HashMap::new();
+        for (name, schema) in &specs.components.schemas {
+            if let Schema::Primitive(Primitive::String(string)) = schema {
+                if let Some(values) = &string.r#enum {
+                    enum_values.insert(to_proto_type_name(name), values.clone());
+                }
+            }
+        }
+
+        let mut enums = HashSet::new();
+        let mut nesting = HashMap::new();
+        for file in files {
+            for proto_enum in &file.enums {
+                enums.insert(format!("{}.{}", file.package, proto_enum.name));
+            }
+        }
+        for file in files {
+            for message in &file.messages {
+                let nested = message
+                    .all_fields()
+                    .iter()
+                    .filter(|field| !field.repeated)
+                    .filter_map(|field| match &field.field_type {
+                        ProtoFieldType::Message(name) => Some(qualify(&file.package, name)),
+                        _ => None,
+                    })
+                    .filter(|name| !enums.contains(name))
+                    .collect();
+                nesting.insert(format!("{}.{}", file.package, message.name), nested);
+            }
+        }
+
+        let mut generator = Self {
+            specs,
+            mapping: JsonMapping::new(specs, files)?,
+            enum_values,
+            enums,
+            sources: HashMap::new(),
+            nesting,
+        };
+
+        let mut sources = HashMap::new();
+        let common_package = config.common_package();
+        if let Some(file) = files.iter().find(|file| file.package == common_package) {
+            for (name, schema) in &specs.components.schemas {
+                let message_name = to_proto_type_name(name);
+                if file.messages.iter().any(|message| message.name == message_name) {
+                    let qualified = format!("{}.{}", file.package, message_name);
+                    sources.entry(qualified).or_insert(Source::Schema(schema));
+                }
+            }
+        }
+        let rpc_names = ServiceGenerator::new("", config);
+        let methods = specs
+            .methods
+            .iter()
+            .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+            .collect::<Vec<_>>();
+        for file in files {
+            for rpc in file.services.iter().flat_map(|service| &service.rpcs) {
+                let Some(method) = methods
+                    .iter()
+                    .find(|method| rpc_names.method_name_to_rpc_name(&method.name) == rpc.name)
+                else {
+                    continue;
+                };
+                let request = format!("{}.{}", file.package, rpc.request_type);
+                sources.insert(request, Source::Request(method));
+                let response = format!("{}.{}", file.package, rpc.response_type);
+                sources.insert(response, Source::Response(method));
+            }
+        }
+
+        // Wrappers without a schema of their own, like the variants of BLOCK_ID, stand for the
+        // schema of the first field holding them
+        loop {
+            let mut found = vec![];
+            for file in files {
+                for message in &file.messages {
+                    let qualified = format!("{}.{}", file.package, message.name);
+                    let Some(&source) = sources.get(&qualified) else {
+                        continue;
+                    };
+                    for (field, schema) in generator.field_schemas(message, source) {
+                        let ProtoFieldType::Message(name) = &field.field_type else {
+                            continue;
+                        };
+                        let name = qualify(&file.package, name);
+                        if sources.contains_key(&name) || generator.enums.contains(&name) {
+                            continue;
+                        }
+                        let source = match schema {
+                            Some(schema) => Source::Schema(generator.item_schema(field, schema)),
+                            None if field.name == "error" => Source::RpcError,
+                            None => continue,
+                        };
+                        found.push((name, source));
+                    }
+                }
+            }
+            if found.is_empty() {
+                break;
+            }
+            for (name, source) in found {
+                sources.entry(name).or_insert(source);
+            }
+        }
+        generator.sources = sources;
+
+        Ok(generator)
+    }
+
+    /// Rust source of the impls of the messages of `file`, to include in the module of its
+    /// package
+    pub fn generate(&self, file: &ProtoFile) -> Result<String> {
+        let mut out = String::new();
+        out.push_str("// AUTO-GENERATED RUST FILE. DO NOT EDIT\n");
+        let _ = writeln!(
+            out,
+            "// Generated from Starknet JSON-RPC specification {}",
+            self.specs.info.version
+        );
+        out.push_str("//\n");
+        let _ = writeln!(
+            out,
+            "// Writes and reads the messages of {} in the JSON of the spec.",
+            file.name
+        );
+        for message in &file.messages {
+            out.push('\n');
+            self.write_impls(&mut out, file, message)?;
+        }
+        Ok(out)
+    }
+
+    /// Spec values of the enum named `name`, if it was generated from a string schema
+    pub(crate) fn enum_values(&self, name: &str) -> Option<&[String]> {
+        self.enum_values.get(name).map(Vec::as_slice)
+    }
+
+    fn resolve(&self, schema: &'a Schema) -> &'a Schema {
+        resolve_schema(&self.specs.components.schemas, schema)
+    }
+
+    /// Schema of the items of a repeated field, or the schema of the field
+    fn item_schema(&self, field: &ProtoField, schema: &'a Schema) -> &'a Schema {
+        match (field.repeated, self.resolve(schema)) {
+            (true, Schema::Primitive(Primitive::Array(array))) => &array.items,
+            _ => schema,
+        }
+    }
+
+    /// Fields of `message` along with the schema of their value, if it has one
+    fn field_schemas<'m>(
+        &self,
+        message: &'m ProtoMessage,
+        source: Source<'a>,
+    ) -> Vec<(&'m ProtoField, Option<&'a Schema>)> {
+        match source {
+            Source::Schema(schema) => {
+                let resolved = self.resolve(schema);
+                if let (Schema::OneOf(one_of), Some(oneof)) =
+                    (resolved, message.declared_oneofs().next())
+                {
+                    return oneof.fields.iter().zip(one_of.one_of.iter().map(Some)).collect();
+                }
+                let Some((properties, _)) = object_properties(&self.specs.components.schemas, schema)
+                else {
+                    return message.fields.iter().map(|field| (field, Some(schema))).collect();
+                };
+                message
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let property =
+                            properties.iter().find(|property| property.name == field.json_name());
+                        (field, property.map(|property| property.schema))
+                    })
+                    .collect()
+            }
+            Source::Request(method) => message
+                .fields
+                .iter()
+                .map(|field| {
+                    let param = method.params.iter().find(|param| param.name == field.json_name());
+                    (field, param.map(|param| &param.schema))
+                })
+                .collect(),
+            Source::Response(method) => {
+                let methods = self.methods();
+                let notifications = subscription_notifications(method, &methods);
+                message
+                    .all_fields()
+                    .into_iter()
+                    .filter_map(|field| {
+                        let field = message
+                            .fields
+                            .iter()
+                            .chain(message.oneofs.iter().flat_map(|oneof| &oneof.fields))
+                            .find(|candidate| candidate.name == field.name)?;
+                        let schema = match field.name.as_str() {
+                            "result" => method.result.as_ref().map(|result| &result.schema),
+                            _ => notifications
+                                .iter()
+                                .find(|(name, _)| name == &field.name)
+                                .and_then(|(_, notification)| notification_result(notification)),
+                        };
+                        Some((field, schema))
+                    })
+                    .collect()
+            }
+            Source::RpcError => message.fields.iter().map(|field| (field, None)).collect(),
+        }
+    }
+
+    fn methods(&self) -> Vec<&'a Method> {
+        self.specs
+            .methods
+            .iter()
+            .chain(self.specs.extensions.iter().flat_map(|extension| &extension.methods))
+            .collect()
+    }
+
+    /// Shape of the JSON of `message` of `file`
+    pub(crate) fn layout<'m>(
+        &self,
+        file: &ProtoFile,
+        message: &'m ProtoMessage,
+    ) -> Result<Layout<'m>> {
+        let qualified = format!("{}.{}", file.package, message.name);
+        let source = self.sources.get(&qualified).copied();
+        if message.name == "Object" && message.fields.len() == 1 {
+            return Ok(Layout::Text(&message.fields[0]));
+        }
+
+        let property = |field: &'m ProtoField, schema: Option<&'a Schema>, required: bool| {
+            Ok::<_, anyhow::Error>(Property {
+                field,
+                name: field.json_name(),
+                kind: self.kind(&file.package, field, schema)?,
+                required,
+            })
+        };
+
+        match source {
+            Some(Source::Schema(schema)) => {
+                if let (Schema::OneOf(one_of), Some(oneof)) =
+                    (self.resolve(schema), message.declared_oneofs().next())
+                {
+                    let variants = oneof
+                        .fields
+                        .iter()
+                        .zip(&one_of.one_of)
+                        .map(|(field, variant)| {
+                            let kind = self.kind(&file.package, field, Some(variant))?;
+                            Ok((kind, self.discriminators(variant, 0)))
+                        })
+                        .collect::<Result<_>>()?;
+                    return Ok(Layout::Union(oneof, variants));
+                }
+                let Some((properties, members)) =
+                    object_properties(&self.specs.components.schemas, schema)
+                else {
+                    let field = match message.fields.as_slice() {
+                        [field] => field,
+                        // Unions without variants have no value to hold
+                        [] => return Ok(Layout::Object(vec![], vec![])),
+                        _ => bail!("{} doesn't wrap a single value", message.name),
+                    };
+                    return Ok(Layout::Wrapper(field, self.kind(&file.package, field, Some(schema))?));
+                };
+
+                let mut object_properties = vec![];
+                let mut object_members = vec![];
+                for field in message.fields.iter().filter(|field| field.oneof_name.is_none()) {
+                    let json_name = field.json_name();
+                    let spec_property = properties
+                        .iter()
+                        .find(|property| property.member.is_none() && property.name == json_name);
+                    if let Some(spec_property) = spec_property {
+                        let required = spec_property.required;
+                        object_properties.push(property(field, Some(spec_property.schema), required)?);
+                    } else if let Some(member) = members
+                        .iter()
+                        .find(|member| crate::proto_gen::writer::to_proto_name(member) == field.name)
+                    {
+                        // The member takes the properties its schema can have
+                        let mut names = HashSet::new();
+                        if let Some(member_schema) = self.mapping.schema(member) {
+                            self.mapping.property_names(member_schema, &mut names, 0);
+                        }
+                        let mut names = names.into_iter().map(str::to_owned).collect::<Vec<_>>();
+                        names.sort();
+                        object_members.push((field, names));
+                    }
+                    // Other fields aren't part of the JSON of the spec
+                }
+                Ok(Layout::Object(object_properties, object_members))
+            }
+            Some(Source::Request(method)) => {
+                let properties = self
+                    .field_schemas(message, Source::Request(method))
+                    .into_iter()
+                    .filter_map(|(field, schema)| {
+                        let param = method.params.iter().find(|param| param.name == field.json_name())?;
+                        Some(property(field, schema, param.required))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Layout::Object(properties, vec![]))
+            }
+            Some(Source::Response(method)) => {
+                let schemas = self.field_schemas(message, Source::Response(method));
+                let schema = |field: &ProtoField| {
+                    schemas
+                        .iter()
+                        .find(|(candidate, _)| candidate.name == field.name)
+                        .and_then(|(_, schema)| *schema)
+                };
+                let required = method.result.as_ref().is_none_or(|result| result.required.unwrap_or(true));
+                let result = message.fields.iter().find(|field| field.name == "result");
+                let error = message.fields.iter().find(|field| field.name == "error");
+                let notifications = match message.declared_oneofs().next() {
+                    Some(oneof) => {
+                        let kinds = oneof
+                            .fields
+                            .iter()
+                            .map(|field| self.kind(&file.package, field, schema(field)))
+                            .collect::<Result<_>>()?;
+                        Some((oneof, kinds))
+                    }
+                    None => None,
+                };
+                Ok(Layout::Response {
+                    result: result.map(|field| property(field, schema(field), required)).transpose()?,
+                    error: error.map(|field| property(field, None, false)).transpose()?,
+                    notifications,
+                })
+            }
+            Some(Source::RpcError) => {
+                let properties = message
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        Ok(Property {
+                            field,
+                            name: field.json_name(),
+                            kind: match field.name.as_str() {
+                                "data" => Kind::Text,
+                                _ => self.kind(&file.package, field, None)?,
+                            },
+                            required: !field.optional,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Layout::Object(properties, vec![]))
+            }
+            None => {
+                // Messages no schema reaches are written as an object of their fields
+                let properties = message
+                    .fields
+                    .iter()
+                    .filter(|field| field.oneof_name.is_none())
+                    .map(|field| property(field, None, !field.optional))
+                    .collect::<Result<_>>()?;
+                Ok(Layout::Object(properties, vec![]))
+            }
+        }
+    }
+
+    /// Kind of the values of `field`, in the message of `package`, valid against `schema`
+    fn kind(&self, package: &str, field: &ProtoField, schema: Option<&'a Schema>) -> Result<Kind> {
+        let schema = schema.map(|schema| self.resolve(self.item_schema(field, schema)));
+        let string_enum = match schema {
+            Some(Schema::Primitive(Primitive::String(string))) => Some(string.r#enum.as_ref()),
+            _ => None,
+        };
+        let kind = match &field.field_type {
+            ProtoFieldType::String => match string_enum {
+                Some(Some(values)) => Kind::StringIn(values.clone()),
+                _ => Kind::Json("String".to_owned()),
+            },
+            ProtoFieldType::Int64 | ProtoFieldType::Uint64 => {
+                let rust_type = match field.field_type {
+                    ProtoFieldType::Int64 => "i64",
+                    _ => "u64",
+                };
+                match string_enum {
+                    Some(_) => Kind::Hex(rust_type.to_owned()),
+                    None => Kind::Json(rust_type.to_owned()),
+                }
+            }
+            ProtoFieldType::Int32 => Kind::Json("i32".to_owned()),
+            ProtoFieldType::Uint32 => Kind::Json("u32".to_owned()),
+            ProtoFieldType::Bool => Kind::Json("bool".to_owned()),
+            ProtoFieldType::Double => Kind::Json("f64".to_owned()),
+            ProtoFieldType::Float => Kind::Json("f32".to_owned()),
+            ProtoFieldType::Bytes => Kind::Json("Vec<u8>".to_owned()),
+            ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                let qualified = qualify(package, name);
+                if self.enums.contains(&qualified) {
+                    let enum_name = qualified.rsplit('.').next().unwrap_or(name);
+                    let values = match self.enum_values.get(enum_name) {
+                        Some(values) => values.clone(),
+                        None => bail!("{} has no values in the spec", enum_name),
+                    };
+                    Kind::Enum(values)
+                } else {
+                    Kind::Json(rust_type_path(package, &qualified))
+                }
+            }
+            ProtoFieldType::Any => bail!("{} has no Rust type", field.field_type),
+        };
+        Ok(kind)
+    }
+
+    /// Required properties of the variant `schema` restricted to an `enum`, through `allOf`
+    /// members and the variants of nested unions
+    fn discriminators(&self, schema: &'a Schema, depth: usize) -> Discriminators {
+        if depth >= MAX_DEPTH {
+            return vec![];
+        }
+        if let Schema::OneOf(one_of) = self.resolve(schema) {
+            // A property discriminates a nested union if it does in each of its variants
+            let mut variants =
+                one_of.one_of.iter().map(|variant| self.discriminators(variant, depth + 1));
+            let Some(mut common) = variants.next() else {
+                return vec![];
+            };
+            for variant in variants {
+                common.retain_mut(|(name, values)| {
+                    let Some((_, other)) = variant.iter().find(|(other, _)| other == name) else {
+                        return false;
+                    };
+                    for value in other {
+                        if !values.contains(value) {
+                            values.push(value.clone());
+                        }
+                    }
+                    true
+                });
+            }
+            return common;
+        }
+
+        let Some((properties, _)) = object_properties(&self.specs.components.schemas, schema)
+        else {
+            return vec![];
+        };
+        properties
+            .iter()
+            .filter(|property| property.required)
+            .filter_map(|property| {
+                let values = self.mapping.spec_enum_values(property.schema)?;
+                Some((property.name.to_owned(), values.to_vec()))
+            })
+            .collect()
+    }
+
+    /// Whether prost-build boxes a singular field of `message` holding `field_type`, which it
+    /// does when the type contains the message
+    pub(crate) fn boxed(&self, message: &str, field_type: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![field_type];
+        while let Some(name) = pending.pop() {
+            if name == message {
+                return true;
+            }
+            if seen.insert(name) {
+                pending.extend(self.nesting.get(name).into_iter().flatten().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Rust expression wrapping `value` of a singular field of `field_type` the way prost-build
+    /// stores it in `message`
+    fn stored(&self, package: &str, message: &str, field: &ProtoField, value: &str) -> String {
+        match &field.field_type {
+            ProtoFieldType::Message(name)
+                if self.boxed(&format!("{}.{}", package, message), &qualify(package, name)) =>
+            {
+                format!("Box::new({})", value)
+            }
+            _ => value.to_owned(),
+        }
+    }
+
+    fn is_message(&self, package: &str, field: &ProtoField) -> bool {
+        match &field.field_type {
+            ProtoFieldType::Message(name) => !self.enums.contains(&qualify(package, name)),
+            _ => false,
+        }
+    }
+
+    fn write_impls(&self, out: &mut String, file: &ProtoFile, message: &ProtoMessage) -> Result<()> {
+        let layout = self.layout(file, message)?;
+        let type_name = to_upper_camel_case(&message.name);
+
+        let _ = writeln!(out, "impl serde::Serialize for {} {{", type_name);
+        out.push_str(
+            "    fn serialize<S: serde::Serializer>(&self, serializer: S) -> \
+             ::core::result::Result<S::Ok, S::Error> {\n",
+        );
+        let _ = writeln!(out, "        {}::serialize(serializer, || {{", HELPERS);
+        self.write_serialize(out, file, message, &layout);
+        out.push_str("        })\n");
+        out.push_str("    }\n");
+        out.push_str("}\n");
+        out.push('\n');
+        let _ = writeln!(out, "impl<'de> serde::Deserialize<'de> for {} {{", type_name);
+        out.push_str(
+            "    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> \
+             ::core::result::Result<Self, D::Error> {\n",
+        );
+        let _ = writeln!(out, "        {}::deserialize(deserializer, |value| {{", HELPERS);
+        self.write_deserialize(out, file, message, &layout);
+        out.push_str("        })\n");
+        out.push_str("    }\n");
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    fn write_serialize(
+        &self,
+        out: &mut String,
+        file: &ProtoFile,
+        message: &ProtoMessage,
+        layout: &Layout,
+    ) {
+        let indent = "            ";
+        match layout {
+            Layout::Text(field) => {
+                let _ = writeln!(
+                    out,
+                    "{}{}::json_text(&self.{})",
+                    indent,
+                    HELPERS,
+                    to_snake_case(&field.name)
+                );
+            }
+            Layout::Wrapper(field, kind) => {
+                let value = self.field_value(file, field, kind, "value");
+                let _ = writeln!(out, "{}{}", indent, value);
+            }
+            Layout::Union(oneof, variants) => {
+                let _ = writeln!(out, "{}match &self.{} {{", indent, to_snake_case(&oneof.name));
+                for (field, (kind, _)) in oneof.fields.iter().zip(variants) {
+                    let _ = writeln!(
+                        out,
+                        "{}    Some({}(v)) => {},",
+                        indent,
+                        oneof_variant(message, oneof, field),
+                        to_json(kind, "v")
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}    None => Err({}::Error::new(\"no variant of {} is set\")),",
+                    indent, HELPERS, message.name
+                );
+                let _ = writeln!(out, "{}}}", indent);
+            }
+            Layout::Object(properties, members) => {
+                if properties.is_empty() && members.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "{}Ok(serde_json::Value::Object(serde_json::Map::new()))",
+                        indent
+                    );
+                    return;
+                }
+                let _ = writeln!(out, "{}let mut object = serde_json::Map::new();", indent);
+                for property in properties {
+                    self.write_insert(out, file, property, indent);
+                }
+                for (field, _) in members {
+                    let _ = writeln!(
+                        out,
+                        "{}if let Some(v) = &self.{} {{\n{}    {}::flatten(&mut object, \
+                         {}::to_value(v)?)?;\n{}}}",
+                        indent,
+                        to_snake_case(&field.name),
+                        indent,
+                        HELPERS,
+                        HELPERS,
+                        indent
+                    );
+                }
+                let _ = writeln!(out, "{}Ok(serde_json::Value::Object(object))", indent);
+            }
+            Layout::Response {
+                result,
+                error,
+                notifications,
+            } => {
+                let _ = writeln!(out, "{}let mut object = serde_json::Map::new();", indent);
+                let mut branches = vec![];
+                if let Some(error) = error {
+                    let mut branch = String::new();
+                    self.write_insert(&mut branch, file, &Property { required: true, ..error.clone() }, indent);
+                    branches.push((format!("self.{}.is_some()", to_snake_case(&error.field.name)), branch));
+                }
+                if let Some((oneof, kinds)) = notifications {
+                    let mut branch = String::new();
+                    let _ = writeln!(branch, "{}    match notification {{", indent);
+                    for (field, kind) in oneof.fields.iter().zip(kinds) {
+                        let _ = writeln!(
+                            branch,
+                            "{}        {}(v) => {{\n{}            object.insert({:?}.to_owned(), {}?);\n{}        }}",
+                            indent,
+                            oneof_variant(message, oneof, field),
+                            indent,
+                            field.json_name(),
+                            to_json(kind, "v"),
+                            indent
+                        );
+                    }
+                    let _ = writeln!(branch, "{}    }}", indent);
+                    branches.push((
+                        format!("let Some(notification) = &self.{}", to_snake_case(&oneof.name)),
+                        branch,
+                    ));
+                }
+                let mut last = String::new();
+                if let Some(result) = result {
+                    self.write_insert(&mut last, file, result, indent);
+                }
+                if branches.is_empty() {
+                    out.push_str(&last);
+                } else {
+                    for (i, (condition, branch)) in branches.iter().enumerate() {
+                        let keyword = if i == 0 { "if" } else { "} else if" };
+                        let _ = writeln!(out, "{}{} {} {{", indent, keyword, condition);
+                        out.push_str(&indent_lines(branch, 4));
+                    }
+                    if !last.is_empty() {
+                        let _ = writeln!(out, "{}}} else {{", indent);
+                        out.push_str(&indent_lines(&last, 4));
+                    }
+                    let _ = writeln!(out, "{}}}", indent);
+                }
+                let _ = writeln!(out, "{}Ok(serde_json::Value::Object(object))", indent);
+            }
+        }
+    }
+
+    /// Statement inserting `property` in `object`, leaving it out if it isn't required and has
+    /// no value
+    fn write_insert(&self, out: &mut String, file: &ProtoFile, property: &Property, indent: &str) {
+        let field = property.field;
+        let name = to_snake_case(&field.name);
+        let insert = |value: &str| format!("object.insert({:?}.to_owned(), {}?);", property.name, value);
+        if field.repeated {
+            let value = self.field_value(file, field, &property.kind, &property.name);
+            match property.required {
+                true => {
+                    let _ = writeln!(out, "{}{}", indent, insert(&value));
+                }
+                false => {
+                    let _ = writeln!(
+                        out,
+                        "{}if !self.{}.is_empty() {{\n{}    {}\n{}}}",
+                        indent,
+                        name,
+                        indent,
+                        insert(&value),
+                        indent
+                    );
+                }
+            }
+        } else if field.optional || self.is_message(&file.package, field) && !property.required {
+            let _ = writeln!(
+                out,
+                "{}if let Some(v) = &self.{} {{\n{}    {}\n{}}}",
+                indent,
+                name,
+                indent,
+                insert(&to_json(&property.kind, "v")),
+                indent
+            );
+        } else {
+            let value = self.field_value(file, field, &property.kind, &property.name);
+            let _ = writeln!(out, "{}{}", indent, insert(&value));
+        }
+    }
+
+    /// Expression converting the value of a required `field` to JSON
+    fn field_value(
+        &self,
+        file: &ProtoFile,
+        field: &ProtoField,
+        kind: &Kind,
+        property: &str,
+    ) -> String {
+        let name = to_snake_case(&field.name);
+        if field.repeated {
+            return format!("{}::array(&self.{}, |v| {})", HELPERS, name, to_json(kind, "v"));
+        }
+        if self.is_message(&file.package, field) {
+            let value = format!("{}::present(&self.{}, {:?})?", HELPERS, name, property);
+            return to_json(kind, &value);
+        }
+        if field.optional {
+            return format!(
+                "match &self.{} {{ Some(v) => {}, None => Ok(serde_json::Value::Null) }}",
+                name,
+                to_json(kind, "v")
+            );
+        }
+        to_json(kind, &format!("&self.{}", name))
+    }
+
+    fn write_deserialize(
+        &self,
+        out: &mut String,
+        file: &ProtoFile,
+        message: &ProtoMessage,
+        layout: &Layout,
+    ) {
+        let indent = "            ";
+        let package = &file.package;
+        match layout {
+            Layout::Text(field) => {
+                let _ = writeln!(
+                    out,
+                    "{}Ok(Self {{ {}: {}::to_text(value)? }})",
+                    indent,
+                    to_snake_case(&field.name),
+                    HELPERS
+                );
+            }
+            Layout::Wrapper(field, kind) => {
+                let value = if field.repeated {
+                    format!("{}::from_array(value, {})?", HELPERS, from_json_fn(kind))
+                } else if self.is_message(package, field) {
+                    let value = format!("{}?", from_json(kind, "value"));
+                    format!("Some({})", self.stored(package, &message.name, field, &value))
+                } else if field.optional {
+                    format!("Some({}?)", from_json(kind, "value"))
+                } else {
+                    format!("{}?", from_json(kind, "value"))
+                };
+                let _ = writeln!(
+                    out,
+                    "{}Ok(Self {{ {}: {} }})",
+                    indent,
+                    to_snake_case(&field.name),
+                    value
+                );
+            }
+            Layout::Union(oneof, variants) => {
+                for (field, (kind, discriminators)) in oneof.fields.iter().zip(variants) {
+                    let mut attempt = String::new();
+                    let _ = writeln!(
+                        attempt,
+                        "if let Ok(v) = {} {{\n    return Ok(Self {{ {}: Some({}({})) }});\n}}",
+                        from_json(kind, "value.clone()"),
+                        to_snake_case(&oneof.name),
+                        oneof_variant(message, oneof, field),
+                        self.stored(package, &message.name, field, "v")
+                    );
+                    if discriminators.is_empty() {
+                        out.push_str(&indent_lines(&attempt, 12));
+                        continue;
+                    }
+                    let discriminators = discriminators
+                        .iter()
+                        .map(|(name, values)| format!("({:?}, &{:?})", name, values))
+                        .collect::<Vec<_>>();
+                    let _ = writeln!(
+                        out,
+                        "{}if {}::discriminated(&value, &[{}]) {{",
+                        indent,
+                        HELPERS,
+                        discriminators.join(", ")
+                    );
+                    out.push_str(&indent_lines(&attempt, 16));
+                    let _ = writeln!(out, "{}}}", indent);
+                }
+                let _ = writeln!(
+                    out,
+                    "{}Err({}::Error::new(\"the value matches no variant of {}\"))",
+                    indent, HELPERS, message.name
+                );
+            }
+            Layout::Object(properties, members) => {
+                let mutable = match properties.is_empty() && members.is_empty() {
+                    true => "",
+                    false => "mut ",
+                };
+                let _ = writeln!(
+                    out,
+                    "{}let {}object = {}::object(value)?;",
+                    indent, mutable, HELPERS
+                );
+                let mut names = vec![];
+                for property in properties {
+                    self.write_take(out, file, message, property, indent);
+                    names.push(to_snake_case(&property.field.name));
+                }
+                for (field, properties) in members {
+                    let Some(path) = self.message_path(package, field) else {
+                        continue;
+                    };
+                    let value = format!(
+                        "{}::from_value::<{}>({}::take(&mut object, &{:?}))?",
+                        HELPERS, path, HELPERS, properties
+                    );
+                    let name = to_snake_case(&field.name);
+                    let _ = writeln!(
+                        out,
+                        "{}let {} = Some({});",
+                        indent,
+                        name,
+                        self.stored(package, &message.name, field, &value)
+                    );
+                    names.push(name);
+                }
+                let _ = writeln!(out, "{}{}::finish(object, {:?})?;", indent, HELPERS, message.name);
+                self.write_construct(out, message, names, indent);
+            }
+            Layout::Response {
+                result,
+                error,
+                notifications,
+            } => {
+                let _ = writeln!(out, "{}let mut object = {}::object(value)?;", indent, HELPERS);
+                let mut names = vec![];
+                for property in result.iter().chain(error) {
+                    // The result is left out of responses carrying an error or a notification
+                    let property = Property { required: false, ..property.clone() };
+                    self.write_take(out, file, message, &property, indent);
+                    names.push(to_snake_case(&property.field.name));
+                }
+                if let Some((oneof, kinds)) = notifications {
+                    let name = to_snake_case(&oneof.name);
+                    let _ = write!(out, "{}let {} = ", indent, name);
+                    for (field, kind) in oneof.fields.iter().zip(kinds) {
+                        let value = format!("{}?", from_json(kind, "value"));
+                        let _ = writeln!(
+                            out,
+                            "if let Some(value) = object.remove({:?}) {{\n{}    Some({}({}))\n{}}} else ",
+                            field.json_name(),
+                            indent,
+                            oneof_variant(message, oneof, field),
+                            self.stored(package, &message.name, field, &value),
+                            indent
+                        );
+                    }
+                    let _ = writeln!(out, "{{\n{}    None\n{}}};", indent, indent);
+                    names.push(name);
+                }
+                let _ = writeln!(out, "{}{}::finish(object, {:?})?;", indent, HELPERS, message.name);
+                self.write_construct(out, message, names, indent);
+            }
+        }
+    }
+
+    /// Statement taking `property` out of `object` into a variable named after its field
+    fn write_take(
+        &self,
+        out: &mut String,
+        file: &ProtoFile,
+        message: &ProtoMessage,
+        property: &Property,
+        indent: &str,
+    ) {
+        let field = property.field;
+        let package = &file.package;
+        let take = |helper: &str, from_json: &str| {
+            format!("{}::{}(&mut object, {:?}, {})?", HELPERS, helper, property.name, from_json)
+        };
+        let value = if field.repeated {
+            let from_json = format!("|value| {}::from_array(value, {})", HELPERS, from_json_fn(&property.kind));
+            match property.required {
+                true => take("required", &from_json),
+                false => format!("{}.unwrap_or_default()", take("optional", &from_json)),
+            }
+        } else if self.is_message(package, field) {
+            let boxed = self.stored(package, &message.name, field, "v") != "v";
+            match (property.required, boxed) {
+                (true, _) => {
+                    let value = take("required", &from_json_fn(&property.kind));
+                    format!("Some({})", self.stored(package, &message.name, field, &value))
+                }
+                (false, true) => format!("{}.map(Box::new)", take("optional", &from_json_fn(&property.kind))),
+                (false, false) => take("optional", &from_json_fn(&property.kind)),
+            }
+        } else if field.optional {
+            take("optional", &from_json_fn(&property.kind))
+        } else if property.required {
+            take("required", &from_json_fn(&property.kind))
+        } else {
+            format!("{}.unwrap_or_default()", take("optional", &from_json_fn(&property.kind)))
+        };
+        let _ = writeln!(out, "{}let {} = {};", indent, to_snake_case(&field.name), value);
+    }
+
+    /// `Ok(Self { .. })` with the fields set from the variables in `names`, the fields not in the
+    /// JSON of the spec keeping their default
+    fn write_construct(&self, out: &mut String, message: &ProtoMessage, names: Vec<String>, indent: &str) {
+        let field_count = message.fields.iter().filter(|field| field.oneof_name.is_none()).count()
+            + message.declared_oneofs().count();
+        let mut fields = names;
+        if fields.len() < field_count {
+            fields.push("..Default::default()".to_owned());
+        }
+        let _ = writeln!(out, "{}Ok(Self {{ {} }})", indent, fields.join(", "));
+    }
+
+    fn message_path(&self, package: &str, field: &ProtoField) -> Option<String> {
+        match &field.field_type {
+            ProtoFieldType::Message(name) => Some(rust_type_path(package, &qualify(package, name))),
+            _ => None,
+        }
+    }
+}
+
+/// Result of a notification, the value its `result` param holds
+fn notification_result(notification: &Method) -> Option<&Schema> {
+    notification
+        .params
+        .iter()
+        .find(|param| param.name == "result")
+        .map(|param| &param.schema)
+}
+
+/// Rust path of the variant of `field` in the enum prost-build generates for `oneof`
+fn oneof_variant(message: &ProtoMessage, oneof: &ProtoOneof, field: &ProtoField) -> String {
+    format!(
+        "{}::{}::{}",
+        to_snake_case(&message.name),
+        to_upper_camel_case(&oneof.name),
+        to_upper_camel_case(&field.name)
+    )
+}
+
+/// Expression converting the reference `value` to JSON
+fn to_json(kind: &Kind, value: &str) -> String {
+    match kind {
+        Kind::Json(_) | Kind::StringIn(_) => format!("{}::to_value({})", HELPERS, value),
+        Kind::Hex(_) => format!("{}::hex(*{})", HELPERS, value),
+        Kind::Enum(values) => format!("{}::enum_value(&{:?}, *{})", HELPERS, values, value),
+        Kind::Text => format!("{}::json_text({})", HELPERS, value),
+    }
+}
+
+/// Expression converting the JSON `value` to a Rust value
+fn from_json(kind: &Kind, value: &str) -> String {
+    match kind {
+        Kind::Json(rust_type) => format!("{}::from_value::<{}>({})", HELPERS, rust_type, value),
+        Kind::Hex(rust_type) => format!("{}::from_hex::<{}>({})", HELPERS, rust_type, value),
+        Kind::Enum(values) => format!("{}::enum_number(&{:?}, {})", HELPERS, values, value),
+        Kind::StringIn(values) => format!("{}::string_in(&{:?}, {})", HELPERS, values, value),
+        Kind::Text => format!("{}::to_text({})", HELPERS, value),
+    }
+}
+
+/// Function converting JSON values to Rust values
+fn from_json_fn(kind: &Kind) -> String {
+    match kind {
+        Kind::Json(rust_type) => format!("{}::from_value::<{}>", HELPERS, rust_type),
+        Kind::Hex(rust_type) => format!("{}::from_hex::<{}>", HELPERS, rust_type),
+        Kind::Text => format!("{}::to_text", HELPERS),
+        Kind::Enum(_) | Kind::StringIn(_) => format!("|value| {}", from_json(kind, "value")),
+    }
+}
+
+pub(crate) fn indent_lines(lines: &str, width: usize) -> String {
+    let indent = " ".repeat(width);
+    lines
+        .lines()
+        .map(|line| format!("{}{}\n", indent, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::ProtoGenerator;
+
+    #[test]
+    fn test_unions_are_read_by_their_discriminators() {
+        let main = include_str!("../specs/0.8.1/starknet_api_openrpc.json");
+        let specs: Specification = serde_json::from_str(main).unwrap();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+        let generator = SerdeGenerator::new(&specs, &config, &result.protos).unwrap();
+        let common = result.protos.iter().find(|file| file.name == "common.proto").unwrap();
+        let code = generator.generate(common).unwrap();
+
+        assert!(code.contains(
+            "            if crate::spec_json::discriminated(&value, &[(\"type\", &[\"INVOKE\"])]) {\n\
+             \x20               if let Ok(v) = crate::spec_json::from_value::<InvokeTxn>(value.clone()) {\n\
+             \x20                   return Ok(Self { value: Some(txn::Value::Variant1(v)) });\n"
+        ));
+        assert!(code.contains(
+            "Some(block_id::Value::Variant3(v)) => \
+             crate::spec_json::enum_value(&[\"latest\", \"pending\"], *v),\n"
+        ));
+    }
+}
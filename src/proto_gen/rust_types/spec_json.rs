@@ -0,0 +1,192 @@
+// AUTO-GENERATED RUST FILE. DO NOT EDIT
+//
+// Helpers of the `serde` impls writing the generated messages in the JSON of the spec.
+
+use std::fmt;
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::hex::Hex;
+
+/// Why a message and the JSON of the spec don't convert
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl Error {
+    pub(crate) fn new(message: impl fmt::Display) -> Self {
+        Self(message.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::new(err)
+    }
+}
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Serializes the JSON `to_json` returns
+pub(crate) fn serialize<S: Serializer>(
+    serializer: S,
+    to_json: impl FnOnce() -> Result<Value>,
+) -> Result<S::Ok, S::Error> {
+    to_json().map_err(ser::Error::custom)?.serialize(serializer)
+}
+
+/// Deserializes a JSON value and converts it with `from_json`
+pub(crate) fn deserialize<'de, D: Deserializer<'de>, T>(
+    deserializer: D,
+    from_json: impl FnOnce(Value) -> Result<T>,
+) -> Result<T, D::Error> {
+    from_json(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+pub(crate) fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+pub(crate) fn from_value<T: de::DeserializeOwned>(value: Value) -> Result<T> {
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Integer the spec writes as a hex string
+pub(crate) fn hex<T: Hex>(number: T) -> Result<Value> {
+    Ok(crate::hex::serialize(&number, serde_json::value::Serializer)?)
+}
+
+pub(crate) fn from_hex<T: Hex>(value: Value) -> Result<T> {
+    Ok(crate::hex::deserialize(value)?)
+}
+
+/// Spec value of the enum value `number`, `values` being in the order of the generated enum
+pub(crate) fn enum_value(values: &[&str], number: i32) -> Result<Value> {
+    usize::try_from(number)
+        .ok()
+        .and_then(|index| values.get(index))
+        .map(|value| Value::String((*value).to_owned()))
+        .ok_or_else(|| Error::new(format!("{} is not a value of the enum", number)))
+}
+
+pub(crate) fn enum_number(values: &[&str], value: Value) -> Result<i32> {
+    let index = value
+        .as_str()
+        .and_then(|value| values.iter().position(|allowed| *allowed == value))
+        .ok_or_else(|| Error::new(format!("{} is not one of {:?}", value, values)))?;
+    i32::try_from(index).map_err(Error::new)
+}
+
+/// String restricted to the `enum` of its schema
+pub(crate) fn string_in(values: &[&str], value: Value) -> Result<String> {
+    match value {
+        Value::String(string) if values.contains(&string.as_str()) => Ok(string),
+        _ => Err(Error::new(format!("{} is not one of {:?}", value, values))),
+    }
+}
+
+/// Free-form JSON kept as text
+pub(crate) fn json_text(text: &str) -> Result<Value> {
+    match text {
+        "" => Ok(Value::Null),
+        _ => Ok(serde_json::from_str(text)?),
+    }
+}
+
+pub(crate) fn to_text(value: Value) -> Result<String> {
+    Ok(value.to_string())
+}
+
+pub(crate) fn array<T>(items: &[T], to_json: impl Fn(&T) -> Result<Value>) -> Result<Value> {
+    items.iter().map(to_json).collect::<Result<_>>().map(Value::Array)
+}
+
+pub(crate) fn from_array<T>(value: Value, from_json: impl Fn(Value) -> Result<T>) -> Result<Vec<T>> {
+    match value {
+        Value::Array(items) => items.into_iter().map(from_json).collect(),
+        _ => Err(Error::new(format!("expected an array, got {}", value))),
+    }
+}
+
+/// Message field the spec requires
+pub(crate) fn present<'a, T>(value: &'a Option<T>, property: &str) -> Result<&'a T> {
+    value
+        .as_ref()
+        .ok_or_else(|| Error::new(format!("missing required property `{}`", property)))
+}
+
+pub(crate) fn object(value: Value) -> Result<Map<String, Value>> {
+    match value {
+        Value::Object(object) => Ok(object),
+        _ => Err(Error::new(format!("expected an object, got {}", value))),
+    }
+}
+
+pub(crate) fn required<T>(
+    object: &mut Map<String, Value>,
+    property: &str,
+    from_json: impl FnOnce(Value) -> Result<T>,
+) -> Result<T> {
+    let value = object
+        .remove(property)
+        .ok_or_else(|| Error::new(format!("missing required property `{}`", property)))?;
+    from_json(value).map_err(|err| Error::new(format!("{}: {}", property, err)))
+}
+
+pub(crate) fn optional<T>(
+    object: &mut Map<String, Value>,
+    property: &str,
+    from_json: impl FnOnce(Value) -> Result<T>,
+) -> Result<Option<T>> {
+    let Some(value) = object.remove(property) else {
+        return Ok(None);
+    };
+    let value = from_json(value).map_err(|err| Error::new(format!("{}: {}", property, err)))?;
+    Ok(Some(value))
+}
+
+/// Takes the properties an `allOf` member can have out of `object`
+pub(crate) fn take(object: &mut Map<String, Value>, properties: &[&str]) -> Value {
+    let member = properties
+        .iter()
+        .filter_map(|property| Some(((*property).to_owned(), object.remove(*property)?)))
+        .collect();
+    Value::Object(member)
+}
+
+/// Adds the properties of an `allOf` member to `object`
+pub(crate) fn flatten(object: &mut Map<String, Value>, member: Value) -> Result<()> {
+    match member {
+        Value::Object(member) => {
+            object.extend(member);
+            Ok(())
+        }
+        _ => Err(Error::new(format!("expected an object, got {}", member))),
+    }
+}
+
+/// Fails on properties no field took
+pub(crate) fn finish(object: Map<String, Value>, message: &str) -> Result<()> {
+    match object.keys().next() {
+        Some(property) => Err(Error::new(format!(
+            "property `{}` is not a property of {}",
+            property, message
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Whether `value` has one of the allowed values for each discriminating property of a union
+/// variant
+pub(crate) fn discriminated(value: &Value, discriminators: &[(&str, &[&str])]) -> bool {
+    discriminators.iter().all(|(property, values)| {
+        let found = value.get(property).and_then(Value::as_str);
+        found.is_some_and(|found| values.contains(&found))
+    })
+}
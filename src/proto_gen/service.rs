@@ -1,13 +1,18 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
+use indexmap::IndexMap;
 
 use crate::spec::*;
-use crate::proto_gen::{ProtoConfig, types::*, writer::*};
+use crate::proto_gen::{ProtoConfig, ProtoFile, types::*, writer::*};
 
 /// Service generator for creating gRPC services from JSON-RPC methods
 pub struct ServiceGenerator<'a> {
     service_name: &'a str,
-    #[allow(dead_code)]
     config: &'a ProtoConfig,
+    aliases: HashMap<String, String>,
+    schemas: IndexMap<String, Schema>,
+    notifications: Vec<Method>,
 }
 
 impl<'a> ServiceGenerator<'a> {
@@ -15,9 +20,26 @@ impl<'a> ServiceGenerator<'a> {
         Self {
             service_name,
             config,
+            aliases: HashMap::new(),
+            schemas: IndexMap::new(),
+            notifications: vec![],
         }
     }
 
+    /// Resolves references to `schemas`, following the `$ref`-only ones to the schema they stand
+    /// for
+    pub fn with_schemas(mut self, schemas: &IndexMap<String, Schema>) -> Self {
+        self.aliases = schema_aliases(schemas);
+        self.schemas = schemas.clone();
+        self
+    }
+
+    /// Adds the notifications of the subscriptions among `methods` to their response messages
+    pub fn with_notifications(mut self, methods: &[&Method]) -> Self {
+        self.notifications = methods.iter().map(|method| (*method).clone()).collect();
+        self
+    }
+
     pub fn generate_from_methods(&self, methods: &[&Method]) -> Result<ProtoService> {
         let mut rpcs = Vec::new();
 
@@ -51,7 +73,7 @@ impl<'a> ServiceGenerator<'a> {
         })
     }
 
-    fn method_name_to_rpc_name(&self, method_name: &str) -> String {
+    pub(crate) fn method_name_to_rpc_name(&self, method_name: &str) -> String {
         // Convert starknet_getBlock to GetBlock
         let name_without_prefix = if let Some(stripped) = method_name.strip_prefix("starknet_") {
             stripped // Remove "starknet_"
@@ -82,7 +104,12 @@ impl<'a> ServiceGenerator<'a> {
                     None => String::new(),
                     Some(first) => {
                         let mut result = first.to_uppercase().collect::<String>();
-                        result.push_str(&chars.as_str().to_lowercase());
+                        // Keep camelCase segments such as `dev_generateBlock` readable
+                        if chars.as_str().contains(char::is_lowercase) {
+                            result.push_str(chars.as_str());
+                        } else {
+                            result.push_str(&chars.as_str().to_lowercase());
+                        }
                         result
                     }
                 }
@@ -115,11 +142,11 @@ impl<'a> ServiceGenerator<'a> {
 
     fn generate_request_message(&self, rpc_name: &str, method: &Method) -> Result<ProtoMessage> {
         let mut fields = Vec::new();
-        let mut field_number = 1u32;
 
-        for param in &method.params {
+        for (field_number, param) in (1u32..).zip(&method.params) {
             let field_name = to_proto_name(&param.name);
             let field_type = self.schema_to_proto_field_type(&param.schema)?;
+            let repeated = is_repeated_schema(&param.schema, &self.schemas, &field_type);
 
             fields.push(ProtoField {
                 name: field_name,
@@ -127,12 +154,10 @@ impl<'a> ServiceGenerator<'a> {
                 number: field_number,
                 json_name: Some(param.name.clone()),
                 comment: param.description.clone(),
-                optional: !param.required,
-                repeated: false,
+                optional: !param.required && !repeated,
+                repeated,
                 oneof_name: None,
             });
-
-            field_number += 1;
         }
 
         Ok(ProtoMessage {
@@ -151,6 +176,7 @@ impl<'a> ServiceGenerator<'a> {
 
         if let Some(result) = &method.result {
             let field_type = self.schema_to_proto_field_type(&result.schema)?;
+            let repeated = is_repeated_schema(&result.schema, &self.schemas, &field_type);
             
             fields.push(ProtoField {
                 name: "result".to_string(),
@@ -159,7 +185,7 @@ impl<'a> ServiceGenerator<'a> {
                 json_name: Some("result".to_string()),
                 comment: result.description.clone(),
                 optional: false,
-                repeated: false,
+                repeated,
                 oneof_name: None,
             });
         }
@@ -176,19 +202,53 @@ impl<'a> ServiceGenerator<'a> {
             oneof_name: None,
         });
 
+        // Subscriptions stream their notifications after the subscription id
+        let notifications = self.notifications.iter().collect::<Vec<_>>();
+        let mut oneofs = vec![];
+        let mut notification_fields = vec![];
+        for ((name, notification), number) in
+            subscription_notifications(method, &notifications).into_iter().zip(3u32..)
+        {
+            let Some(result) = notification.params.iter().find(|param| param.name == "result")
+            else {
+                continue;
+            };
+            let field_type = self.schema_to_proto_field_type(&result.schema)?;
+            if is_repeated_schema(&result.schema, &self.schemas, &field_type) {
+                anyhow::bail!("the result of {} can't be a oneof member", notification.name);
+            }
+            notification_fields.push(ProtoField {
+                json_name: Some(name.clone()),
+                name,
+                field_type,
+                number,
+                comment: notification.description.clone(),
+                optional: false,
+                repeated: false,
+                oneof_name: Some("notification".to_string()),
+            });
+        }
+        if !notification_fields.is_empty() {
+            oneofs.push(ProtoOneof {
+                name: "notification".to_string(),
+                fields: notification_fields,
+                comment: Some("Notifications of the subscription".to_string()),
+            });
+        }
+
         Ok(ProtoMessage {
             name: format!("{}Response", rpc_name),
             fields,
             nested_messages: vec![],
             nested_enums: vec![],
-            oneofs: vec![],
+            oneofs,
             comment: Some(format!("Response message for {}", method.name)),
             options: vec![],
         })
     }
 
     fn schema_to_proto_field_type(&self, schema: &Schema) -> Result<ProtoFieldType> {
-        schema_to_proto_field_type_impl(schema)
+        schema_to_proto_field_type_impl(schema, &self.config.common_package(), &self.aliases)
     }
 
     #[allow(dead_code)]
@@ -237,7 +297,11 @@ impl<'a> ServiceGenerator<'a> {
 }
 
 // Helper function to resolve the recursion issue
-fn schema_to_proto_field_type_impl(schema: &Schema) -> Result<ProtoFieldType> {
+fn schema_to_proto_field_type_impl(
+    schema: &Schema,
+    common_package: &str,
+    aliases: &HashMap<String, String>,
+) -> Result<ProtoFieldType> {
     match schema {
         Schema::Primitive(primitive) => match primitive {
             Primitive::String(str_prim) => {
@@ -252,61 +316,80 @@ fn schema_to_proto_field_type_impl(schema: &Schema) -> Result<ProtoFieldType> {
             Primitive::Boolean(_) => Ok(ProtoFieldType::Bool),
             Primitive::Array(array) => {
                 // Return the inner type - the repeated flag will be set separately
-                schema_to_proto_field_type_impl(&array.items)
+                schema_to_proto_field_type_impl(&array.items, common_package, aliases)
             }
-            Primitive::Object(_) => Ok(ProtoFieldType::Message("starknet.v0_8_1.common.Object".to_string())),
+            Primitive::Object(_) => Ok(ProtoFieldType::Message(format!("{}.Object", common_package))),
         },
         Schema::Ref(reference) => {
+            // Aliases don't get a message of their own, they stand for the schema they refer to
             let ref_name = reference.name();
-            
+            let target = aliases.get(ref_name).map(String::as_str).unwrap_or(ref_name);
+
             // Handle common type aliases that should map to primitive types
-            match ref_name {
-                "FELT" | "TXN_HASH" | "BLOCK_HASH" | "ADDRESS" | "CLASS_HASH" | "STORAGE_KEY" | "HASH_256" => {
-                    Ok(ProtoFieldType::String)
-                }
-                "BLOCK_NUMBER" | "NUM_AS_HEX" | "L1_TXN_HASH" => {
-                    Ok(ProtoFieldType::Uint64)
-                }
-                "u64" => {
-                    Ok(ProtoFieldType::Uint64)
-                }
-                "u128" => {
-                    Ok(ProtoFieldType::String) // Use string for large integers
-                }
-                "ETH_ADDRESS" => {
-                    Ok(ProtoFieldType::String)
-                }
-                // Handle generic object types
-                "Object" => {
-                    // Try to determine what kind of object this should be based on context
-                    // For now, we'll use a generic Object type, but this should be improved
-                    Ok(ProtoFieldType::Message("starknet.v0_8_1.common.Object".to_string()))
-                }
-                // Handle cross-service type references
-                "NestedCall" | "NESTED_CALL" => {
-                    Ok(ProtoFieldType::Message("starknet.v0_8_1.common.FunctionInvocation".to_string())) // NestedCall is an alias for FunctionInvocation
-                }
-                "BroadcastedInvokeTxn" | "BROADCASTED_INVOKE_TXN" | "BROADCASTED_INVOKE_TXN_V3" => {
-                    Ok(ProtoFieldType::Message("starknet.v0_8_1.common.InvokeTxnV3Content".to_string())) // Map to the actual content type
-                }
-                "BroadcastedDeclareTxn" | "BROADCASTED_DECLARE_TXN" | "BROADCASTED_DECLARE_TXN_V3" => {
-                    Ok(ProtoFieldType::Message("starknet.v0_8_1.common.DeclareTxnV3Content".to_string())) // Map to the actual content type
-                }
-                "BroadcastedDeployAccountTxn" | "BROADCASTED_DEPLOY_ACCOUNT_TXN" | "BROADCASTED_DEPLOY_ACCOUNT_TXN_V3" => {
-                    Ok(ProtoFieldType::Message("starknet.v0_8_1.common.DeployAccountTxnV3Content".to_string())) // Map to the actual content type
-                }
-                // For other references, treat as message types with qualified names
-                _ => {
-                    let proto_type_name = to_proto_type_name(ref_name);
-                    Ok(ProtoFieldType::Message(format!("starknet.v0_8_1.common.{}", proto_type_name)))
-                }
+            if let Some(scalar) = scalar_alias_type(ref_name).or_else(|| scalar_alias_type(target))
+            {
+                return Ok(scalar);
             }
+
+            // Treat other references as message types with qualified names
+            Ok(ProtoFieldType::Message(format!(
+                "{}.{}",
+                common_package,
+                to_proto_type_name(target)
+            )))
         }
-        Schema::OneOf(_) => Ok(ProtoFieldType::Message("starknet.v0_8_1.common.Object".to_string())),
-        Schema::AllOf(_) => Ok(ProtoFieldType::Message("starknet.v0_8_1.common.AllOf".to_string())),
+        Schema::OneOf(_) => Ok(ProtoFieldType::Message(format!("{}.Object", common_package))),
+        Schema::AllOf(_) => Ok(ProtoFieldType::Message(format!("{}.AllOf", common_package))),
     }
 }
 
+/// File, service and rpc generated for the JSON-RPC method `method_name` in `files`
+pub fn find_rpc<'f>(
+    files: &'f [ProtoFile],
+    config: &ProtoConfig,
+    method_name: &str,
+) -> Option<(&'f ProtoFile, &'f ProtoService, &'f ProtoRpc)> {
+    let rpc_name = ServiceGenerator::new("", config).method_name_to_rpc_name(method_name);
+    files.iter().find_map(|file| {
+        file.services.iter().find_map(|service| {
+            let rpc = service.rpcs.iter().find(|rpc| rpc.name == rpc_name)?;
+            Some((file, service, rpc))
+        })
+    })
+}
+
+/// Notifications the subscription `method` receives, along with the name of their field in its
+/// response message: the `<namespace>_subscription<Name>` method of a
+/// `<namespace>_subscribe<Name>` one, and those no subscription is named after, such as reorgs
+pub fn subscription_notifications<'m>(
+    method: &Method,
+    methods: &[&'m Method],
+) -> Vec<(String, &'m Method)> {
+    let Some((namespace, name)) = method.name.split_once("_subscribe") else {
+        return vec![];
+    };
+    let subscribed = |notification: &str| {
+        methods
+            .iter()
+            .any(|method| method.name == format!("{}_subscribe{}", namespace, notification))
+    };
+    let own = format!("{}_subscription{}", namespace, name);
+    // Only methods with a notification of their own are subscriptions
+    if !methods.iter().any(|notification| notification.name == own) {
+        return vec![];
+    }
+    methods
+        .iter()
+        .filter_map(|notification| {
+            let suffix = notification
+                .name
+                .strip_prefix(namespace)?
+                .strip_prefix("_subscription")?;
+            (suffix == name || !subscribed(suffix)).then(|| (to_proto_name(suffix), *notification))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,13 +416,17 @@ mod tests {
                         description: Some("Result value".to_string()),
                         r#enum: None,
                         pattern: None,
+                        additional_fields: Default::default(),
                     })),
                     summary: None,
+                    additional_fields: Default::default(),
                 })
             } else {
                 None
             },
             errors: None,
+            examples: vec![],
+            additional_fields: Default::default(),
         }
     }
 
@@ -355,7 +442,9 @@ mod tests {
                 description: Some("String parameter".to_string()),
                 r#enum: None,
                 pattern: None,
+                additional_fields: Default::default(),
             })),
+            additional_fields: Default::default(),
         }
     }
 
@@ -369,6 +458,7 @@ mod tests {
         assert_eq!(generator.method_name_to_rpc_name("starknet_addInvokeTransaction"), "AddInvokeTransaction");
         assert_eq!(generator.method_name_to_rpc_name("trace_transaction"), "TraceTransaction");
         assert_eq!(generator.method_name_to_rpc_name("custom_method_name"), "CustomMethodName");
+        assert_eq!(generator.method_name_to_rpc_name("dev_generateBlock"), "DevGenerateBlock");
     }
 
     #[test]
@@ -381,6 +471,7 @@ mod tests {
         assert_eq!(generator.snake_to_pascal_case("add_invoke_transaction"), "AddInvokeTransaction");
         assert_eq!(generator.snake_to_pascal_case("simple"), "Simple");
         assert_eq!(generator.snake_to_pascal_case("trace_block_transactions"), "TraceBlockTransactions");
+        assert_eq!(generator.snake_to_pascal_case("GET_BLOCK"), "GetBlock");
     }
 
     #[test]
@@ -501,7 +592,7 @@ mod tests {
         let config = create_test_config();
         let generator = ServiceGenerator::new("StarknetMainService", &config);
         
-        let methods = vec![
+        let methods = [
             create_test_method("starknet_getBlock", vec![create_test_param("block_id", true)], true),
             create_test_method("starknet_getTransaction", vec![create_test_param("txn_hash", true)], true),
             create_test_method("starknet_chainId", vec![], true),
@@ -554,6 +645,7 @@ mod tests {
             description: None,
             r#enum: None,
             pattern: None,
+            additional_fields: Default::default(),
         }));
         let field_type = generator.schema_to_proto_field_type(&string_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::String));
@@ -565,6 +657,7 @@ mod tests {
             description: None,
             r#enum: Some(vec!["value1".to_string(), "value2".to_string()]),
             pattern: None,
+            additional_fields: Default::default(),
         }));
         let field_type = generator.schema_to_proto_field_type(&enum_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::Enum(_)));
@@ -579,7 +672,9 @@ mod tests {
                 description: None,
                 r#enum: None,
                 pattern: None,
+                additional_fields: Default::default(),
             }))),
+            additional_fields: Default::default(),
         }));
         let field_type = generator.schema_to_proto_field_type(&array_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::String)); // Inner type, repeated will be set separately
@@ -594,7 +689,7 @@ mod tests {
         });
         let field_type = generator.schema_to_proto_field_type(&ref_schema).unwrap();
         if let ProtoFieldType::Message(type_name) = field_type {
-            assert_eq!(type_name, "Block");
+            assert_eq!(type_name, "starknet.v0_1_0.common.Block");
         } else {
             panic!("Expected Message type");
         }
@@ -623,4 +718,32 @@ mod tests {
         assert_eq!(response.fields[0].name, "result");
         assert_eq!(response.fields[1].name, "error");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_subscriptions_stream_their_notifications() {
+        let config = create_test_config();
+        let notification = |name: &str| {
+            create_test_method(name, vec![create_test_param("result", true)], false)
+        };
+        let methods = [
+            create_test_method("starknet_subscribeNewHeads", vec![], true),
+            create_test_method("starknet_subscribeEvents", vec![], true),
+            notification("starknet_subscriptionNewHeads"),
+            notification("starknet_subscriptionEvents"),
+            notification("starknet_subscriptionReorg"),
+            create_test_method("starknet_unsubscribe", vec![], true),
+        ];
+        let methods = methods.iter().collect::<Vec<_>>();
+        let generator =
+            ServiceGenerator::new("StarknetWsService", &config).with_notifications(&methods);
+
+        let (_, response) = generator.generate_request_response_messages(methods[0]).unwrap();
+        let oneof = &response.oneofs[0];
+        assert_eq!(oneof.name, "notification");
+        let fields = oneof.fields.iter().map(|field| (field.name.as_str(), field.number));
+        assert_eq!(fields.collect::<Vec<_>>(), vec![("new_heads", 3), ("reorg", 4)]);
+
+        let (_, response) = generator.generate_request_response_messages(methods[5]).unwrap();
+        assert!(response.oneofs.is_empty());
+    }
+}
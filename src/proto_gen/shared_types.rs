@@ -0,0 +1,304 @@
+//! Package of the types several spec versions define identically, e.g. `FELT`-based hashes or
+//! `BlockId`, so code handling several versions uses a single type for them. Each version
+//! imports it instead of redefining the types in its own common package.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+
+use crate::proto_gen::{
+    qualify, subset::message_references, ProtoEnum, ProtoFieldType, ProtoFile, ProtoIr,
+    ProtoMessage, ProtoWriter,
+};
+
+/// Package of the shared types
+pub const SHARED_TYPES_PACKAGE: &str = "starknet.types";
+/// File defining the shared types, which the files using them import
+pub const SHARED_TYPES_FILE: &str = "types.proto";
+/// Directory of the shared types in a workspace of several versions
+pub const SHARED_TYPES_DIR: &str = "types";
+
+/// Messages and enums identical in the common packages of several versions
+#[derive(Debug, Clone)]
+pub struct SharedTypes {
+    pub file: ProtoFile,
+}
+
+impl SharedTypes {
+    /// Messages and enums of the common package of the last of `irs` that every other one defines
+    /// the same way, comments aside, and that only refer to other shared types. Their comments
+    /// are the ones of the last version.
+    pub fn extract(irs: &[ProtoIr]) -> Self {
+        let mut writer = ProtoWriter::new(SHARED_TYPES_PACKAGE);
+        let Some((last, others)) = irs.split_last() else {
+            return Self {
+                file: writer.to_file(SHARED_TYPES_FILE),
+            };
+        };
+        let package = &last.package_info.common_package;
+        let common = common_file(last);
+        let other_files = others.iter().map(common_file).collect::<Vec<_>>();
+
+        let messages = common
+            .into_iter()
+            .flat_map(|file| &file.messages)
+            .filter(|message| {
+                let expected = shape(package, message);
+                other_files.iter().all(|other| {
+                    other.is_some_and(|other| {
+                        let package = &other.package;
+                        other.messages.iter().any(|candidate| shape(package, candidate) == expected)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let enums = common
+            .into_iter()
+            .flat_map(|file| &file.enums)
+            .filter(|proto_enum| {
+                let expected = enum_shape(proto_enum);
+                other_files.iter().all(|other| {
+                    other.is_some_and(|other| {
+                        other.enums.iter().any(|candidate| enum_shape(candidate) == expected)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Messages referring to a type that stays in the versioned packages can't move
+        let mut names = messages
+            .iter()
+            .map(|message| qualify(package, &message.name))
+            .chain(enums.iter().map(|proto_enum| qualify(package, &proto_enum.name)))
+            .collect::<HashSet<_>>();
+        loop {
+            let unshareable = messages
+                .iter()
+                .filter(|message| names.contains(&qualify(package, &message.name)))
+                .find(|message| {
+                    message_references(package, message).iter().any(|reference| {
+                        !names.contains(reference) && !reference.starts_with("google.protobuf.")
+                    })
+                });
+            match unshareable {
+                Some(message) => names.remove(&qualify(package, &message.name)),
+                None => break,
+            };
+        }
+
+        for import in common.into_iter().flat_map(|file| &file.imports) {
+            if import.starts_with("google/protobuf/") {
+                writer.add_import(import);
+            }
+        }
+        for message in messages {
+            if names.contains(&qualify(package, &message.name)) {
+                writer.add_message(&relative(package, message));
+            }
+        }
+        for proto_enum in enums {
+            if names.contains(&qualify(package, &proto_enum.name)) {
+                writer.add_enum(proto_enum);
+            }
+        }
+        Self {
+            file: writer.to_file(SHARED_TYPES_FILE),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.messages.is_empty() && self.file.enums.is_empty()
+    }
+
+    /// Removes the shared types from the common package of `ir`, pointing the fields and rpcs
+    /// using them to the shared package, which their files import
+    pub fn apply(&self, ir: &mut ProtoIr) -> Result<()> {
+        let package = ir.package_info.common_package.clone();
+        let Some(common) = ir.files.iter_mut().find(|file| file.package == package) else {
+            return Ok(());
+        };
+
+        let mut moved = HashMap::new();
+        for shared in &self.file.messages {
+            let Some(index) = common.messages.iter().position(|m| m.name == shared.name) else {
+                continue;
+            };
+            if shape(&package, &common.messages[index]) != shape(SHARED_TYPES_PACKAGE, shared) {
+                bail!("{}.{} differs from the shared type", package, shared.name);
+            }
+            common.messages.remove(index);
+            moved.insert(
+                qualify(&package, &shared.name),
+                qualify(SHARED_TYPES_PACKAGE, &shared.name),
+            );
+        }
+        for shared in &self.file.enums {
+            let Some(index) = common.enums.iter().position(|e| e.name == shared.name) else {
+                continue;
+            };
+            if enum_shape(&common.enums[index]) != enum_shape(shared) {
+                bail!("{}.{} differs from the shared type", package, shared.name);
+            }
+            common.enums.remove(index);
+            moved.insert(
+                qualify(&package, &shared.name),
+                qualify(SHARED_TYPES_PACKAGE, &shared.name),
+            );
+        }
+
+        for file in &mut ir.files {
+            let mut uses_shared = false;
+            let mut point_to_shared = |name: &mut String| {
+                if let Some(shared) = moved.get(&qualify(&file.package, name)) {
+                    *name = shared.clone();
+                    uses_shared = true;
+                }
+            };
+            for message in &mut file.messages {
+                let fields = message
+                    .fields
+                    .iter_mut()
+                    .chain(message.oneofs.iter_mut().flat_map(|oneof| &mut oneof.fields));
+                for field in fields {
+                    if let ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) =
+                        &mut field.field_type
+                    {
+                        point_to_shared(name);
+                    }
+                }
+            }
+            for rpc in file.services.iter_mut().flat_map(|service| &mut service.rpcs) {
+                point_to_shared(&mut rpc.request_type);
+                point_to_shared(&mut rpc.response_type);
+            }
+            if uses_shared && !file.imports.iter().any(|import| import == SHARED_TYPES_FILE) {
+                file.imports.push(SHARED_TYPES_FILE.to_owned());
+            }
+        }
+
+        ir.schemas = std::mem::take(&mut ir.schemas)
+            .into_iter()
+            .map(|(name, schema)| (moved.get(&name).cloned().unwrap_or(name), schema))
+            .collect();
+        let resolution = &mut ir.type_resolution;
+        resolution
+            .common_types
+            .retain(|message| !moved.contains_key(&qualify(&package, &message.name)));
+        resolution
+            .common_enums
+            .retain(|proto_enum| !moved.contains_key(&qualify(&package, &proto_enum.name)));
+
+        Ok(())
+    }
+}
+
+fn common_file(ir: &ProtoIr) -> Option<&ProtoFile> {
+    let package = &ir.package_info.common_package;
+    ir.files.iter().find(|file| &file.package == package)
+}
+
+/// `message`, defined in `package`, with the types of `package` it refers to named relatively
+fn relative(package: &str, message: &ProtoMessage) -> ProtoMessage {
+    let prefix = format!("{}.", package);
+    let mut message = message.clone();
+    let fields = message
+        .fields
+        .iter_mut()
+        .chain(message.oneofs.iter_mut().flat_map(|oneof| &mut oneof.fields));
+    for field in fields {
+        if let ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) = &mut field.field_type {
+            if let Some(relative) = name.strip_prefix(&prefix) {
+                *name = relative.to_owned();
+            }
+        }
+    }
+    message
+}
+
+/// What has to match for a message to be shared: `relative` without comments
+fn shape(package: &str, message: &ProtoMessage) -> ProtoMessage {
+    let mut message = relative(package, message);
+    message.comment = None;
+    for field in &mut message.fields {
+        field.comment = None;
+    }
+    for oneof in &mut message.oneofs {
+        oneof.comment = None;
+        for field in &mut oneof.fields {
+            field.comment = None;
+        }
+    }
+    message
+}
+
+fn enum_shape(proto_enum: &ProtoEnum) -> ProtoEnum {
+    let mut proto_enum = proto_enum.clone();
+    proto_enum.comment = None;
+    for value in &mut proto_enum.values {
+        value.comment = None;
+    }
+    proto_enum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostics::Diagnostics,
+        proto_gen::{validate::validate, ProtoConfig, ProtoGenerator},
+        source::{bundled_profiles, SpecVersion},
+        spec::ParseMode,
+    };
+
+    fn resolve(version: SpecVersion) -> ProtoIr {
+        let profile = bundled_profiles()
+            .into_iter()
+            .find(|profile| profile.version == version)
+            .unwrap();
+        let specs = profile
+            .raw_specs
+            .parse_full(&[], ParseMode::Lenient, &mut Diagnostics::new())
+            .unwrap();
+        let config = ProtoConfig::new(&version.to_version_string());
+        ProtoGenerator::new(config).resolve(&specs).unwrap()
+    }
+
+    #[test]
+    fn test_shared_types() {
+        let mut irs = [resolve(SpecVersion::V0_7_1), resolve(SpecVersion::V0_8_1)];
+        let shared = SharedTypes::extract(&irs);
+        assert!(!shared.is_empty());
+        let names = shared
+            .file
+            .messages
+            .iter()
+            .map(|message| message.name.as_str())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"BlockId"));
+        // Receipts gained fields in 0.8
+        assert!(!names.contains(&"InvokeTransactionReceipt"));
+
+        for ir in &mut irs {
+            shared.apply(ir).unwrap();
+            let mut files = ir.files.clone();
+            files.push(shared.file.clone());
+            let mut problems = Diagnostics::new();
+            validate(&files, &mut problems);
+            assert!(!problems.has_errors(), "{:?}", problems);
+
+            let common = common_file(ir).unwrap();
+            assert!(!common.messages.iter().any(|message| message.name == "BlockId"));
+            let main = ir.files.iter().find(|file| file.name == "main.proto").unwrap();
+            assert!(main.imports.iter().any(|import| import == SHARED_TYPES_FILE));
+        }
+        assert!(irs[1].schemas.contains_key("starknet.types.BlockId"));
+
+        // Versions defining a shared type differently can't use the package
+        let mut changed = resolve(SpecVersion::V0_8_1);
+        let common = changed.files.iter_mut().find(|file| file.name == "common.proto").unwrap();
+        let block_id = common.messages.iter_mut().find(|m| m.name == "BlockId").unwrap();
+        block_id.fields[0].number += 100;
+        let error = shared.apply(&mut changed).unwrap_err();
+        assert_eq!(error.to_string(), "starknet.v0_8_1.common.BlockId differs from the shared type");
+    }
+}
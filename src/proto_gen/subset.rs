@@ -0,0 +1,344 @@
+//! Generation of a subset of the spec: the rpcs of some methods and services, along with the
+//! definitions they reach, so the files and the clients derived from them only hold what a
+//! service uses.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+use regex::Regex;
+
+use crate::diagnostics::UsageError;
+use crate::proto_gen::{qualify, ProtoFieldType, ProtoIr, ProtoMessage};
+
+/// Pattern naming JSON-RPC methods: a regex between slashes, e.g. `/^starknet_get(Block|Events)/`,
+/// or else a glob where `*` matches any characters and `?` a single one, e.g. `starknet_get*`
+#[derive(Debug, Clone)]
+pub struct MethodPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl FromStr for MethodPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let regex = match s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+            Some(regex) => Regex::new(regex)?,
+            None => {
+                let mut regex = String::from("^");
+                for ch in s.chars() {
+                    match ch {
+                        '*' => regex.push_str(".*"),
+                        '?' => regex.push('.'),
+                        _ => regex.push_str(&regex::escape(&ch.to_string())),
+                    }
+                }
+                regex.push('$');
+                Regex::new(&regex)?
+            }
+        };
+        Ok(Self {
+            source: s.to_owned(),
+            regex,
+        })
+    }
+}
+
+impl fmt::Display for MethodPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl MethodPattern {
+    pub fn matches(&self, method: &str) -> bool {
+        self.regex.is_match(method)
+    }
+}
+
+/// Methods and services to generate. Everything is generated when none are selected.
+#[derive(Debug, Clone, Default)]
+pub struct Subset {
+    /// Patterns of the methods to keep, all of them when empty
+    pub include_methods: Vec<MethodPattern>,
+    pub exclude_methods: Vec<MethodPattern>,
+    /// Services to keep by the name of their file, e.g. `main` for `main.proto` or the name of
+    /// an extension, all of them when empty
+    pub include_services: Vec<String>,
+}
+
+impl Subset {
+    pub fn is_empty(&self) -> bool {
+        self.include_methods.is_empty()
+            && self.exclude_methods.is_empty()
+            && self.include_services.is_empty()
+    }
+
+    fn selects(&self, method: &str) -> bool {
+        let included = self.include_methods.is_empty()
+            || self.include_methods.iter().any(|pattern| pattern.matches(method));
+        included && !self.exclude_methods.iter().any(|pattern| pattern.matches(method))
+    }
+
+    /// Drops the rpcs of `ir` that aren't selected, then the services, messages, enums and files
+    /// none of the remaining rpcs reach
+    pub fn apply(&self, ir: &mut ProtoIr) -> Result<()> {
+        let services = ir
+            .files
+            .iter()
+            .filter(|file| !file.services.is_empty())
+            .map(|file| file.name.trim_end_matches(".proto"))
+            .collect::<Vec<_>>();
+        for service in &self.include_services {
+            if !services.contains(&service.as_str()) {
+                bail!(UsageError(format!(
+                    "unknown service {}, expected one of {}",
+                    service,
+                    services.join(", ")
+                )));
+            }
+        }
+        // A pattern selecting nothing is most likely a typo
+        for pattern in &self.include_methods {
+            if !ir.methods.values().any(|method| pattern.matches(method)) {
+                bail!(UsageError(format!("no method matches {}", pattern)));
+            }
+        }
+
+        let methods = &ir.methods;
+        let mut kept_rpcs = HashSet::new();
+        let mut roots = vec![];
+        for file in &mut ir.files {
+            let name = file.name.trim_end_matches(".proto");
+            let kept_service =
+                self.include_services.is_empty() || self.include_services.iter().any(|s| s == name);
+            for service in &mut file.services {
+                let prefix = format!("/{}.{}/", file.package, service.name);
+                service.rpcs.retain(|rpc| {
+                    let full_name = format!("{}{}", prefix, rpc.name);
+                    let selected = match methods.get(&full_name) {
+                        Some(method) => self.selects(method),
+                        None => self.include_methods.is_empty(),
+                    };
+                    if kept_service && selected {
+                        kept_rpcs.insert(full_name);
+                        true
+                    } else {
+                        false
+                    }
+                });
+                for rpc in &service.rpcs {
+                    roots.push(qualify(&file.package, &rpc.request_type));
+                    roots.push(qualify(&file.package, &rpc.response_type));
+                }
+            }
+            file.services.retain(|service| !service.rpcs.is_empty());
+        }
+        ir.methods.retain(|rpc, _| kept_rpcs.contains(rpc));
+
+        // Types each message refers to, by qualified name
+        let mut references = HashMap::new();
+        for file in &ir.files {
+            for message in &file.messages {
+                let qualified = qualify(&file.package, &message.name);
+                references.insert(qualified, message_references(&file.package, message));
+            }
+        }
+        let mut reachable = HashSet::new();
+        while let Some(name) = roots.pop() {
+            if reachable.insert(name.clone()) {
+                roots.extend(references.get(&name).into_iter().flatten().cloned());
+            }
+        }
+
+        for file in &mut ir.files {
+            let package = file.package.clone();
+            file.messages
+                .retain(|message| reachable.contains(&qualify(&package, &message.name)));
+            file.enums
+                .retain(|proto_enum| reachable.contains(&qualify(&package, &proto_enum.name)));
+        }
+        let generated = ir.files.iter().map(|file| file.name.clone()).collect::<Vec<_>>();
+        ir.files.retain(|file| {
+            !file.messages.is_empty() || !file.enums.is_empty() || !file.services.is_empty()
+        });
+
+        // Imports of generated files are only kept while something is used from them
+        let defined_in = ir
+            .files
+            .iter()
+            .flat_map(|file| {
+                let messages = file.messages.iter().map(|message| &message.name);
+                let names = messages.chain(file.enums.iter().map(|proto_enum| &proto_enum.name));
+                names.map(|name| (qualify(&file.package, name), file.name.clone()))
+            })
+            .collect::<HashMap<_, _>>();
+        for file in &mut ir.files {
+            let mut used = file
+                .messages
+                .iter()
+                .flat_map(|message| message_references(&file.package, message))
+                .collect::<Vec<_>>();
+            for rpc in file.services.iter().flat_map(|service| &service.rpcs) {
+                used.push(qualify(&file.package, &rpc.request_type));
+                used.push(qualify(&file.package, &rpc.response_type));
+            }
+            let used_files = used
+                .iter()
+                .filter_map(|name| defined_in.get(name))
+                .collect::<HashSet<_>>();
+            file.imports
+                .retain(|import| !generated.contains(import) || used_files.contains(import));
+        }
+
+        ir.schemas.retain(|name, _| reachable.contains(name));
+        let packages = ir.files.iter().map(|file| file.package.clone()).collect::<HashSet<_>>();
+        ir.package_info
+            .extension_packages
+            .retain(|package| packages.contains(package));
+        let info = &ir.package_info;
+        let resolution = &mut ir.type_resolution;
+        let lists = [
+            (&info.common_package, &mut resolution.common_types),
+            (&info.main_package, &mut resolution.main_types),
+            (&info.write_package, &mut resolution.write_types),
+            (&info.trace_package, &mut resolution.trace_types),
+            (&info.ws_package, &mut resolution.ws_types),
+        ];
+        for (package, messages) in lists {
+            messages.retain(|message| reachable.contains(&qualify(package, &message.name)));
+        }
+        resolution.common_enums.retain(|proto_enum| {
+            reachable.contains(&qualify(&info.common_package, &proto_enum.name))
+        });
+
+        Ok(())
+    }
+}
+
+/// Qualified names of the messages and enums the fields of `message`, in `package`, refer to
+pub(crate) fn message_references(package: &str, message: &ProtoMessage) -> Vec<String> {
+    let fields = message
+        .fields
+        .iter()
+        .chain(message.oneofs.iter().flat_map(|oneof| &oneof.fields));
+    fields
+        .filter_map(|field| match &field.field_type {
+            ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                Some(qualify(package, name))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostics::Diagnostics,
+        proto_gen::{validate::validate, ProtoConfig, ProtoGenerator},
+        source::{bundled_profiles, SpecVersion},
+        spec::ParseMode,
+    };
+
+    fn resolve() -> ProtoIr {
+        let profile = bundled_profiles()
+            .into_iter()
+            .find(|profile| profile.version == SpecVersion::V0_8_1)
+            .unwrap();
+        let specs = profile
+            .raw_specs
+            .parse_full(&[], ParseMode::Lenient, &mut Diagnostics::new())
+            .unwrap();
+        ProtoGenerator::new(ProtoConfig::new("v0_8_1")).resolve(&specs).unwrap()
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<MethodPattern> {
+        patterns.iter().map(|pattern| pattern.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_method_patterns() {
+        let glob: MethodPattern = "starknet_get*".parse().unwrap();
+        assert!(glob.matches("starknet_getEvents"));
+        assert!(!glob.matches("starknet_call"));
+        let single: MethodPattern = "starknet_cal?".parse().unwrap();
+        assert!(single.matches("starknet_call"));
+        assert!(!single.matches("starknet_calls"));
+        let regex: MethodPattern = "/^starknet_get(Events|Nonce)$/".parse().unwrap();
+        assert!(regex.matches("starknet_getNonce"));
+        assert!(!regex.matches("starknet_getBlockWithReceipts"));
+        assert!("/(/".parse::<MethodPattern>().is_err());
+    }
+
+    #[test]
+    fn test_subsets_keep_what_the_methods_reach() {
+        let full = resolve();
+        let mut ir = full.clone();
+        let subset = Subset {
+            include_methods: patterns(&[
+                "starknet_getBlockWithReceipts",
+                "starknet_getEvents",
+                "starknet_call",
+            ]),
+            ..Default::default()
+        };
+        subset.apply(&mut ir).unwrap();
+
+        let mut problems = Diagnostics::new();
+        validate(&ir.files, &mut problems);
+        assert!(!problems.has_errors(), "{:?}", problems);
+
+        assert_eq!(
+            ir.methods.values().collect::<Vec<_>>(),
+            ["starknet_call", "starknet_getBlockWithReceipts", "starknet_getEvents"]
+        );
+        let files = ir.files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(files, ["common.proto", "main.proto"]);
+        let count = |ir: &ProtoIr| ir.files.iter().map(|file| file.messages.len()).sum::<usize>();
+        assert!(count(&ir) < count(&full) / 2);
+        assert!(ir.schemas.contains_key("starknet.v0_8_1.common.EventsChunk"));
+        assert!(!ir.schemas.contains_key("starknet.v0_8_1.common.ContractClass"));
+    }
+
+    #[test]
+    fn test_subsets_of_services() {
+        let mut ir = resolve();
+        let subset = Subset {
+            exclude_methods: patterns(&["/^starknet_subscribe(Events|NewHeads)$/"]),
+            include_services: vec!["ws".to_owned()],
+            ..Default::default()
+        };
+        subset.apply(&mut ir).unwrap();
+        let mut problems = Diagnostics::new();
+        validate(&ir.files, &mut problems);
+        assert!(!problems.has_errors(), "{:?}", problems);
+        let files = ir.files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(files, ["common.proto", "ws.proto"]);
+        assert_eq!(
+            ir.methods.values().collect::<Vec<_>>(),
+            [
+                "starknet_subscribePendingTransactions",
+                "starknet_subscribeTransactionStatus",
+                "starknet_unsubscribe"
+            ]
+        );
+
+        let unknown = Subset {
+            include_services: vec!["admin".to_owned()],
+            ..Default::default()
+        };
+        assert!(unknown.apply(&mut resolve()).unwrap_err().is::<UsageError>());
+        let typo = Subset {
+            include_methods: patterns(&["starknet_getEvent"]),
+            ..Default::default()
+        };
+        let error = typo.apply(&mut resolve()).unwrap_err();
+        assert_eq!(error.to_string(), "no method matches starknet_getEvent");
+    }
+}
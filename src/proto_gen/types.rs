@@ -2,12 +2,15 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 use crate::proto_gen::{writer::*, ProtoConfig};
+use crate::refs;
 use crate::spec::*;
 
 /// Protobuf message definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtoMessage {
     pub name: String,
     pub fields: Vec<ProtoField>,
@@ -22,7 +25,7 @@ pub struct ProtoMessage {
 }
 
 /// Protobuf field definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtoField {
     pub name: String,
     pub field_type: ProtoFieldType,
@@ -35,7 +38,8 @@ pub struct ProtoField {
 }
 
 /// Protobuf field types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[allow(dead_code)]
 pub enum ProtoFieldType {
     String,
@@ -52,8 +56,16 @@ pub enum ProtoFieldType {
     Any,
 }
 
+/// Fully qualified name of a type named `name` in a field of a message of `package`
+pub(crate) fn qualify(package: &str, name: &str) -> String {
+    match name.contains('.') {
+        true => name.to_owned(),
+        false => format!("{}.{}", package, name),
+    }
+}
+
 /// Protobuf oneof definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtoOneof {
     pub name: String,
     pub fields: Vec<ProtoField>,
@@ -62,7 +74,7 @@ pub struct ProtoOneof {
 }
 
 /// Protobuf enum definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtoEnum {
     pub name: String,
     pub values: Vec<ProtoEnumValue>,
@@ -70,7 +82,7 @@ pub struct ProtoEnum {
 }
 
 /// Protobuf enum value
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtoEnumValue {
     pub name: String,
     pub number: i32,
@@ -78,7 +90,7 @@ pub struct ProtoEnumValue {
 }
 
 /// Protobuf service definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtoService {
     pub name: String,
     pub rpcs: Vec<ProtoRpc>,
@@ -86,7 +98,7 @@ pub struct ProtoService {
 }
 
 /// Protobuf RPC definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtoRpc {
     pub name: String,
     pub request_type: String,
@@ -97,7 +109,7 @@ pub struct ProtoRpc {
 }
 
 /// Type resolution result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeResolution {
     pub common_types: Vec<ProtoMessage>,
     pub common_enums: Vec<ProtoEnum>,
@@ -105,17 +117,29 @@ pub struct TypeResolution {
     pub write_types: Vec<ProtoMessage>,
     pub trace_types: Vec<ProtoMessage>,
     pub ws_types: Vec<ProtoMessage>,
+    /// Schemas that are only a `$ref`, mapped to the schema their chain of references ends at
+    #[serde(serialize_with = "sorted")]
+    pub aliases: HashMap<String, String>,
     #[allow(dead_code)]
+    #[serde(serialize_with = "sorted")]
     pub type_map: HashMap<String, String>, // JSON schema name -> Proto type name
 }
 
+/// Writes `map` sorted by key, so serialized definitions are reproducible
+fn sorted<S: serde::Serializer>(map: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<std::collections::BTreeMap<_, _>>())
+}
+
 /// Type resolver for converting JSON schemas to protobuf types
 pub struct TypeResolver {
     #[allow(dead_code)]
     config: ProtoConfig,
-    resolved_types: HashMap<String, ProtoMessage>,
-    resolved_enums: HashMap<String, ProtoEnum>,
+    // Kept in the order of the spec so generated files are reproducible
+    resolved_types: IndexMap<String, ProtoMessage>,
+    resolved_enums: IndexMap<String, ProtoEnum>,
     type_dependencies: HashMap<String, HashSet<String>>,
+    aliases: HashMap<String, String>,
+    schemas: IndexMap<String, Schema>,
 }
 
 // Type aliases to reduce complexity
@@ -128,9 +152,11 @@ impl TypeResolver {
     pub fn new(config: &ProtoConfig) -> Self {
         Self {
             config: config.clone(),
-            resolved_types: HashMap::new(),
-            resolved_enums: HashMap::new(),
+            resolved_types: IndexMap::new(),
+            resolved_enums: IndexMap::new(),
             type_dependencies: HashMap::new(),
+            aliases: HashMap::new(),
+            schemas: IndexMap::new(),
         }
     }
 
@@ -139,6 +165,8 @@ impl TypeResolver {
         for (name, _schema) in &specs.components.schemas {
             self.register_type_name(name);
         }
+        self.aliases = schema_aliases(&specs.components.schemas);
+        self.schemas = specs.components.schemas.clone();
 
         // Second pass: resolve all types
         for (name, schema) in &specs.components.schemas {
@@ -158,6 +186,7 @@ impl TypeResolver {
             write_types: service_types.get("write").cloned().unwrap_or_default(),
             trace_types: service_types.get("trace").cloned().unwrap_or_default(),
             ws_types: service_types.get("ws").cloned().unwrap_or_default(),
+            aliases: self.aliases.clone(),
             type_map: self.build_type_map(),
         })
     }
@@ -171,35 +200,9 @@ impl TypeResolver {
 
     fn resolve_schema_type(&mut self, name: &str, schema: &Schema) -> Result<()> {
         // Skip creating wrapper messages for type aliases that map to primitives
-        if matches!(
-            name,
-            "FELT"
-                | "TXN_HASH"
-                | "BLOCK_HASH"
-                | "ADDRESS"
-                | "CLASS_HASH"
-                | "STORAGE_KEY"
-                | "HASH_256"
-                | "BLOCK_NUMBER"
-                | "NUM_AS_HEX"
-                | "L1_TXN_HASH"
-                | "u64"
-                | "u128"
-                | "ETH_ADDRESS"
-                | "Object"
-                | "NestedCall"
-                | "NESTED_CALL"
-                | "BroadcastedInvokeTxn"
-                | "BROADCASTED_INVOKE_TXN"
-                | "BROADCASTED_INVOKE_TXN_V3"
-                | "BroadcastedDeclareTxn"
-                | "BROADCASTED_DECLARE_TXN"
-                | "BROADCASTED_DECLARE_TXN_V3"
-                | "BroadcastedDeployAccountTxn"
-                | "BROADCASTED_DEPLOY_ACCOUNT_TXN"
-                | "BROADCASTED_DEPLOY_ACCOUNT_TXN_V3"
-                | "NODE_HASH_TO_NODE_MAPPING"
-        ) {
+        if scalar_alias_type(name).is_some()
+            || matches!(name, "Object" | "NODE_HASH_TO_NODE_MAPPING")
+        {
             return Ok(());
         }
 
@@ -264,12 +267,12 @@ impl TypeResolver {
 
     fn convert_object_to_message(&self, name: &str, obj: &ObjectPrimitive) -> Result<ProtoMessage> {
         let mut fields = Vec::new();
-        let mut field_number = 1u32;
 
-        for (field_name, field_schema) in &obj.properties {
+        for (field_number, (field_name, field_schema)) in (1u32..).zip(&obj.properties) {
             let proto_field_name = to_proto_name(field_name);
             let field_type = self.schema_to_proto_field_type(field_schema)?;
             let is_required = obj.required.contains(field_name);
+            let repeated = is_repeated_schema(field_schema, &self.schemas, &field_type);
 
             fields.push(ProtoField {
                 name: proto_field_name,
@@ -277,12 +280,10 @@ impl TypeResolver {
                 number: field_number,
                 json_name: Some(field_name.clone()),
                 comment: field_schema.description().cloned(),
-                optional: !is_required,
-                repeated: false,
+                optional: !is_required && !repeated,
+                repeated,
                 oneof_name: None,
             });
-
-            field_number += 1;
         }
 
         Ok(ProtoMessage {
@@ -298,10 +299,9 @@ impl TypeResolver {
 
     fn convert_oneof_to_message(&self, name: &str, oneof: &OneOf) -> Result<ProtoMessage> {
         let mut oneof_fields = Vec::new();
-        let mut field_number = 1u32;
 
-        for (i, variant_schema) in oneof.one_of.iter().enumerate() {
-            let variant_name = format!("variant_{}", i + 1);
+        for (field_number, variant_schema) in (1u32..).zip(&oneof.one_of) {
+            let variant_name = format!("variant_{}", field_number);
             
             // Handle specific known patterns for block IDs and similar types
             let field_type = match variant_schema {
@@ -342,8 +342,6 @@ impl TypeResolver {
                 repeated: false,
                 oneof_name: Some("value".to_string()),
             });
-
-            field_number += 1;
         }
 
         let proto_oneof = ProtoOneof {
@@ -375,6 +373,7 @@ impl TypeResolver {
                         let proto_field_name = to_proto_name(field_name);
                         let field_type = self.schema_to_proto_field_type(field_schema)?;
                         let is_required = obj.required.contains(field_name);
+                        let repeated = is_repeated_schema(field_schema, &self.schemas, &field_type);
 
                         all_fields.push(ProtoField {
                             name: proto_field_name,
@@ -382,8 +381,8 @@ impl TypeResolver {
                             number: field_number,
                             json_name: Some(field_name.clone()),
                             comment: field_schema.description().cloned(),
-                            optional: !is_required,
-                            repeated: false,
+                            optional: !is_required && !repeated,
+                            repeated,
                             oneof_name: None,
                         });
 
@@ -393,7 +392,7 @@ impl TypeResolver {
                 Schema::Ref(reference) => {
                     // Handle reference in allOf by creating a field of that type
                     let ref_name = reference.name();
-                    let field_type = ProtoFieldType::Message(to_proto_type_name(ref_name));
+                    let field_type = self.schema_to_proto_field_type(schema)?;
 
                     all_fields.push(ProtoField {
                         name: to_proto_name(ref_name),
@@ -465,6 +464,7 @@ impl TypeResolver {
 
     fn convert_primitive_to_wrapper(&self, name: &str, schema: &Schema) -> Result<ProtoMessage> {
         let field_type = self.schema_to_proto_field_type(schema)?;
+        let repeated = is_repeated_schema(schema, &self.schemas, &field_type);
 
         Ok(ProtoMessage {
             name: name.to_string(),
@@ -475,7 +475,7 @@ impl TypeResolver {
                 json_name: None,
                 comment: schema.description().cloned(),
                 optional: false,
-                repeated: false,
+                repeated,
                 oneof_name: None,
             }],
             nested_messages: vec![],
@@ -487,7 +487,7 @@ impl TypeResolver {
     }
 
     fn schema_to_proto_field_type(&self, schema: &Schema) -> Result<ProtoFieldType> {
-        schema_to_proto_field_type_impl(schema)
+        schema_to_proto_field_type_impl(schema, &self.aliases)
     }
 
     fn organize_types_by_service(&self, _specs: &Specification) -> Result<OrganizedTypes> {
@@ -644,7 +644,10 @@ impl TypeResolver {
             comment: Some("A single node hash to node mapping".to_string()),
             options: vec![],
         };
-        self.resolved_types.insert("NodeHashToNodeMappingItem".to_string(), node_mapping_item);
+        // Specs without storage proofs don't define the MerkleNode the items refer to
+        if self.type_dependencies.contains_key("NODE_HASH_TO_NODE_MAPPING") {
+            self.resolved_types.insert("NodeHashToNodeMappingItem".to_string(), node_mapping_item);
+        }
 
         // Create a generic Object message type for remaining Object references
         let generic_object = ProtoMessage {
@@ -671,8 +674,64 @@ impl TypeResolver {
     }
 }
 
+/// Schemas that are only a `$ref` to another schema, mapped to the schema their chain of
+/// references ends at
+pub fn schema_aliases(schemas: &IndexMap<String, Schema>) -> HashMap<String, String> {
+    let mut aliases = HashMap::new();
+    for name in schemas.keys() {
+        let mut target = name.as_str();
+        let mut seen = HashSet::new();
+        while let Some(Schema::Ref(reference)) = schemas.get(target) {
+            if !seen.insert(target) {
+                break;
+            }
+            target = reference.name();
+        }
+        if target != name {
+            aliases.insert(name.clone(), target.to_string());
+        }
+    }
+    aliases
+}
+
+/// Scalar type of the schemas that are generated as protobuf scalars instead of messages
+pub(crate) fn scalar_alias_type(name: &str) -> Option<ProtoFieldType> {
+    match name {
+        "FELT" | "TXN_HASH" | "BLOCK_HASH" | "ADDRESS" | "CLASS_HASH" | "STORAGE_KEY"
+        | "HASH_256" | "ETH_ADDRESS" => Some(ProtoFieldType::String),
+        "BLOCK_NUMBER" | "NUM_AS_HEX" | "L1_TXN_HASH" | "u64" => Some(ProtoFieldType::Uint64),
+        // Use string for large integers
+        "u128" => Some(ProtoFieldType::String),
+        _ => None,
+    }
+}
+
+/// Whether fields of `schema` generated as `field_type` hold a list: inline arrays, and named
+/// arrays generated as their item type instead of as a message wrapping the list
+pub(crate) fn is_repeated_schema(
+    schema: &Schema,
+    schemas: &IndexMap<String, Schema>,
+    field_type: &ProtoFieldType,
+) -> bool {
+    match refs::resolve_named_schema(schemas, schema) {
+        (None, Schema::Primitive(Primitive::Array(_))) => true,
+        (Some(name), Schema::Primitive(Primitive::Array(_))) => {
+            let wrapper = to_proto_type_name(name);
+            !matches!(
+                field_type,
+                ProtoFieldType::Message(message)
+                    if message.rsplit('.').next() == Some(wrapper.as_str())
+            )
+        }
+        _ => false,
+    }
+}
+
 // Helper function to resolve the recursion issue
-fn schema_to_proto_field_type_impl(schema: &Schema) -> Result<ProtoFieldType> {
+fn schema_to_proto_field_type_impl(
+    schema: &Schema,
+    aliases: &HashMap<String, String>,
+) -> Result<ProtoFieldType> {
     match schema {
         Schema::Primitive(primitive) => match primitive {
             Primitive::String(_) => Ok(ProtoFieldType::String),
@@ -680,58 +739,35 @@ fn schema_to_proto_field_type_impl(schema: &Schema) -> Result<ProtoFieldType> {
             Primitive::Boolean(_) => Ok(ProtoFieldType::Bool),
             Primitive::Array(array) => {
                 // For arrays, we need to mark the field as repeated
-                schema_to_proto_field_type_impl(&array.items)
+                schema_to_proto_field_type_impl(&array.items, aliases)
             }
             Primitive::Object(_) => Ok(ProtoFieldType::Message("Object".to_string())),
         },
         Schema::Ref(reference) => {
+            // Aliases don't get a message of their own, they stand for the schema they refer to
             let ref_name = reference.name();
+            let target = aliases.get(ref_name).map(String::as_str).unwrap_or(ref_name);
 
             // Handle common type aliases that should map to primitive types
-            match ref_name {
-                "FELT" | "TXN_HASH" | "BLOCK_HASH" | "ADDRESS" | "CLASS_HASH" | "STORAGE_KEY"
-                | "HASH_256" => Ok(ProtoFieldType::String),
-                "BLOCK_NUMBER" | "NUM_AS_HEX" | "L1_TXN_HASH" => Ok(ProtoFieldType::Uint64),
-                "u64" => Ok(ProtoFieldType::Uint64),
-                "u128" => {
-                    Ok(ProtoFieldType::String) // Use string for large integers
-                }
-                "ETH_ADDRESS" => Ok(ProtoFieldType::String),
+            if let Some(scalar) = scalar_alias_type(ref_name).or_else(|| scalar_alias_type(target))
+            {
+                return Ok(scalar);
+            }
+
+            match target {
                 // Handle generic object types
                 "Object" => {
                     // Try to determine what kind of object this should be based on context
                     // For now, we'll use a generic Object type, but this should be improved
                     Ok(ProtoFieldType::Message("Object".to_string()))
                 }
-                // Handle cross-service type references
-                "NestedCall" | "NESTED_CALL" => {
-                    Ok(ProtoFieldType::Message("FunctionInvocation".to_string()))
-                    // NestedCall is an alias for FunctionInvocation
-                }
-                "BroadcastedInvokeTxn" | "BROADCASTED_INVOKE_TXN" | "BROADCASTED_INVOKE_TXN_V3" => {
-                    Ok(ProtoFieldType::Message("InvokeTxnV3Content".to_string()))
-                    // Map to the actual content type
-                }
-                "BroadcastedDeclareTxn"
-                | "BROADCASTED_DECLARE_TXN"
-                | "BROADCASTED_DECLARE_TXN_V3" => {
-                    Ok(ProtoFieldType::Message("DeclareTxnV3Content".to_string()))
-                    // Map to the actual content type
-                }
-                "BroadcastedDeployAccountTxn"
-                | "BROADCASTED_DEPLOY_ACCOUNT_TXN"
-                | "BROADCASTED_DEPLOY_ACCOUNT_TXN_V3" => {
-                    Ok(ProtoFieldType::Message(
-                        "DeployAccountTxnV3Content".to_string(),
-                    )) // Map to the actual content type
-                }
                 "NODE_HASH_TO_NODE_MAPPING" => {
                     // This should be an array of NodeHashToNodeMappingItem
                     Ok(ProtoFieldType::Message("NodeHashToNodeMappingItem".to_string()))
                 }
                 // For other references, treat as message types
                 _ => {
-                    let proto_type_name = to_proto_type_name(ref_name);
+                    let proto_type_name = to_proto_type_name(target);
                     Ok(ProtoFieldType::Message(proto_type_name))
                 }
             }
@@ -746,115 +782,91 @@ fn schema_to_proto_field_type_impl(schema: &Schema) -> Result<ProtoFieldType> {
 }
 
 // Display implementations for proto types
-impl fmt::Display for ProtoMessage {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(comment) = &self.comment {
-            writeln!(f, "{}", format_comment(comment, 0))?;
+impl ProtoField {
+    /// Name used for the field in proto3 JSON, `json_name` or else the name in lowerCamelCase
+    pub fn json_name(&self) -> String {
+        if let Some(json_name) = &self.json_name {
+            return json_name.clone();
         }
 
-        writeln!(f, "message {} {{", self.name)?;
-
-        // Write oneofs
-        for oneof in &self.oneofs {
-            // Skip empty oneofs
-            if oneof.fields.is_empty() {
-                continue;
-            }
-            writeln!(f, "  oneof {} {{", oneof.name)?;
-            for field in &oneof.fields {
-                if let Some(comment) = &field.comment {
-                    writeln!(f, "{}", format_comment(comment, 4))?;
-                }
-                write!(
-                    f,
-                    "    {} {} = {}",
-                    field.field_type, field.name, field.number
-                )?;
-                if let Some(json_name) = &field.json_name {
-                    write!(f, " [json_name = \"{}\"]", json_name)?;
-                }
-                writeln!(f, ";")?;
+        let mut name = String::new();
+        let mut upper = false;
+        for ch in self.name.chars() {
+            if ch == '_' {
+                upper = true;
+            } else if upper {
+                name.extend(ch.to_uppercase());
+                upper = false;
+            } else {
+                name.push(ch);
             }
-            writeln!(f, "  }}")?;
         }
+        name
+    }
+}
 
-        // Write regular fields
-        for field in &self.fields {
-            if field.oneof_name.is_some() {
-                continue; // Skip oneof fields, already written above
-            }
-
-            if let Some(comment) = &field.comment {
-                writeln!(f, "{}", format_comment(comment, 2))?;
-            }
-
-            write!(f, "  ")?;
-            if field.repeated {
-                write!(f, "repeated ")?;
-            } else if field.optional {
-                write!(f, "optional ")?;
-            }
-
-            write!(f, "{} {} = {}", field.field_type, field.name, field.number)?;
+impl ProtoMessage {
+    /// Regular and oneof fields, each once and with `oneof_name` set for oneof members
+    pub fn all_fields(&self) -> Vec<ProtoField> {
+        let mut fields = self
+            .fields
+            .iter()
+            .filter(|field| field.oneof_name.is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        for oneof in &self.oneofs {
+            fields.extend(oneof.fields.iter().map(|field| ProtoField {
+                oneof_name: Some(oneof.name.clone()),
+                ..field.clone()
+            }));
+        }
+        fields
+    }
 
-            if let Some(json_name) = &field.json_name {
-                write!(f, " [json_name = \"{}\"]", json_name)?;
-            }
+    /// Oneofs that are written out, i.e. the non-empty ones
+    pub fn declared_oneofs(&self) -> impl Iterator<Item = &ProtoOneof> {
+        self.oneofs.iter().filter(|oneof| !oneof.fields.is_empty())
+    }
 
-            writeln!(f, ";")?;
-        }
+    /// Fields in the order they are written, oneof members first, along with the index of their
+    /// oneof among `declared_oneofs`
+    pub fn declared_fields(&self) -> Vec<(&ProtoField, Option<usize>)> {
+        let mut fields = self
+            .declared_oneofs()
+            .enumerate()
+            .flat_map(|(index, oneof)| oneof.fields.iter().map(move |field| (field, Some(index))))
+            .collect::<Vec<_>>();
+        fields.extend(
+            self.fields
+                .iter()
+                .filter(|field| field.oneof_name.is_none())
+                .map(|field| (field, None)),
+        );
+        fields
+    }
+}
 
-        writeln!(f, "}}")?;
-        Ok(())
+impl fmt::Display for ProtoMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut renderer = Renderer::default();
+        renderer.message(vec![4, 0], 0, self);
+        write!(f, "{}", renderer.text)
     }
 }
 
 impl fmt::Display for ProtoEnum {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(comment) = &self.comment {
-            writeln!(f, "{}", format_comment(comment, 0))?;
-        }
-
-        writeln!(f, "enum {} {{", self.name)?;
-
-        for value in &self.values {
-            if let Some(comment) = &value.comment {
-                writeln!(f, "{}", format_comment(comment, 2))?;
-            }
-            writeln!(f, "  {} = {};", value.name, value.number)?;
-        }
-
-        writeln!(f, "}}")?;
-        Ok(())
+        let mut renderer = Renderer::default();
+        renderer.proto_enum(vec![5, 0], 0, self);
+        write!(f, "{}", renderer.text)
     }
 }
 
 impl fmt::Display for ProtoService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(comment) = &self.comment {
-            writeln!(f, "{}", format_comment(comment, 0))?;
-        }
-
-        writeln!(f, "service {} {{", self.name)?;
-
-        for rpc in &self.rpcs {
-            if let Some(comment) = &rpc.comment {
-                writeln!(f, "{}", format_comment(comment, 2))?;
-            }
-
-            write!(f, "  rpc {}(", rpc.name)?;
-            if rpc.client_streaming {
-                write!(f, "stream ")?;
-            }
-            write!(f, "{}) returns (", rpc.request_type)?;
-            if rpc.server_streaming {
-                write!(f, "stream ")?;
-            }
-            writeln!(f, "{});", rpc.response_type)?;
-        }
-
-        writeln!(f, "}}")?;
-        Ok(())
+        let mut renderer = Renderer::default();
+        renderer.service(vec![6, 0], 0, self);
+        write!(f, "{}", renderer.text)
     }
 }
 
@@ -1008,6 +1020,7 @@ mod tests {
                         description: Some("ID field".to_string()),
                         minimum: Some(0),
                         not: None,
+                        additional_fields: Default::default(),
                     })),
                 );
                 props.insert(
@@ -1018,6 +1031,7 @@ mod tests {
                         description: Some("Name field".to_string()),
                         r#enum: None,
                         pattern: None,
+                        additional_fields: Default::default(),
                     })),
                 );
                 props
@@ -1025,6 +1039,7 @@ mod tests {
             required: vec!["id".to_string()],
             additional_properties: None,
             not: None,
+            additional_fields: Default::default(),
         };
 
         let message = resolver
@@ -1087,14 +1102,17 @@ mod tests {
                     description: Some("String variant".to_string()),
                     r#enum: None,
                     pattern: None,
+                    additional_fields: Default::default(),
                 })),
                 Schema::Primitive(Primitive::Integer(IntegerPrimitive {
                     title: None,
                     description: Some("Integer variant".to_string()),
                     minimum: None,
                     not: None,
+                    additional_fields: Default::default(),
                 })),
             ],
+            additional_fields: Default::default(),
         };
 
         let message = resolver
@@ -1119,6 +1137,7 @@ mod tests {
             description: None,
             r#enum: None,
             pattern: None,
+            additional_fields: Default::default(),
         }));
         let field_type = resolver.schema_to_proto_field_type(&string_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::String));
@@ -1129,6 +1148,7 @@ mod tests {
             description: None,
             minimum: None,
             not: None,
+            additional_fields: Default::default(),
         }));
         let field_type = resolver.schema_to_proto_field_type(&int_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::Int64));
@@ -1137,11 +1157,12 @@ mod tests {
         let bool_schema = Schema::Primitive(Primitive::Boolean(BooleanPrimitive {
             title: None,
             description: None,
+            additional_fields: Default::default(),
         }));
         let field_type = resolver.schema_to_proto_field_type(&bool_schema).unwrap();
         assert!(matches!(field_type, ProtoFieldType::Bool));
 
-        // Test reference
+        // Test reference to a scalar alias
         let ref_schema = Schema::Ref(Reference {
             title: None,
             comment: None,
@@ -1150,10 +1171,78 @@ mod tests {
             additional_fields: std::collections::HashMap::new(),
         });
         let field_type = resolver.schema_to_proto_field_type(&ref_schema).unwrap();
+        assert!(matches!(field_type, ProtoFieldType::String));
+
+        // Test reference to a message type
+        let ref_schema = Schema::Ref(Reference {
+            title: None,
+            comment: None,
+            description: None,
+            ref_field: "#/components/schemas/BLOCK_HEADER".to_string(),
+            additional_fields: std::collections::HashMap::new(),
+        });
+        let field_type = resolver.schema_to_proto_field_type(&ref_schema).unwrap();
         if let ProtoFieldType::Message(type_name) = field_type {
-            assert_eq!(type_name, "Felt");
+            assert_eq!(type_name, "BlockHeader");
         } else {
             panic!("Expected Message type");
         }
     }
+
+    #[test]
+    fn test_references_to_aliases_name_their_target() {
+        let reference = |name: &str| {
+            Schema::Ref(Reference {
+                title: None,
+                comment: None,
+                description: None,
+                ref_field: format!("#/components/schemas/{}", name),
+                additional_fields: std::collections::HashMap::new(),
+            })
+        };
+        let schemas = IndexMap::from([
+            ("DEPLOY_TXN_RECEIPT".to_string(), reference("COMMON_RECEIPT_PROPERTIES")),
+            ("BROADCASTED_INVOKE_TXN".to_string(), reference("BROADCASTED_INVOKE_TXN_V3")),
+            ("BROADCASTED_INVOKE_TXN_V3".to_string(), reference("INVOKE_TXN_V3_CONTENT")),
+            ("BLOCK_HASH".to_string(), reference("FELT")),
+            ("LOOP".to_string(), reference("LOOP")),
+        ]);
+
+        let aliases = schema_aliases(&schemas);
+        assert_eq!(aliases["DEPLOY_TXN_RECEIPT"], "COMMON_RECEIPT_PROPERTIES");
+        assert_eq!(aliases["BROADCASTED_INVOKE_TXN"], "INVOKE_TXN_V3_CONTENT");
+        assert!(!aliases.contains_key("LOOP"));
+
+        let field_type = |name| schema_to_proto_field_type_impl(&reference(name), &aliases);
+        assert!(matches!(
+            field_type("BROADCASTED_INVOKE_TXN").unwrap(),
+            ProtoFieldType::Message(name) if name == "InvokeTxnV3Content"
+        ));
+        assert!(matches!(field_type("BLOCK_HASH").unwrap(), ProtoFieldType::String));
+    }
+
+    #[test]
+    fn test_arrays_are_repeated_unless_wrapped() {
+        let schemas: IndexMap<String, Schema> = serde_json::from_str(
+            r##"{
+                "SIGNATURE": { "type": "array", "items": { "$ref": "#/components/schemas/FELT" } },
+                "PROOF": { "$ref": "#/components/schemas/SIGNATURE" },
+                "NODES": { "type": "array", "items": { "$ref": "#/components/schemas/NODE" } }
+            }"##,
+        )
+        .unwrap();
+        let schema = |json: &str| serde_json::from_str::<Schema>(json).unwrap();
+        let reference =
+            |name: &str| schema(&format!(r##"{{ "$ref": "#/components/schemas/{}" }}"##, name));
+        let message = |name: &str| ProtoFieldType::Message(name.to_string());
+
+        let inline = schema(r#"{ "type": "array", "items": { "type": "string" } }"#);
+        assert!(is_repeated_schema(&inline, &schemas, &ProtoFieldType::String));
+        // Named arrays generated as a message wrapping the list
+        assert!(!is_repeated_schema(&reference("SIGNATURE"), &schemas, &message("Signature")));
+        assert!(!is_repeated_schema(&reference("PROOF"), &schemas, &message("common.Signature")));
+        // Named arrays generated as their item type
+        assert!(is_repeated_schema(&reference("NODES"), &schemas, &message("NodesItem")));
+        assert!(!is_repeated_schema(&reference("FELT"), &schemas, &ProtoFieldType::String));
+    }
 }
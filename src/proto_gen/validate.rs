@@ -0,0 +1,507 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    proto_gen::{
+        types::{ProtoEnum, ProtoFieldType, ProtoMessage},
+        well_known::{well_known_file, well_known_files},
+        writer::escape_proto_keyword,
+        ProtoFile,
+    },
+};
+
+/// Scalar type names, which a message or enum of the same name would shadow in field types
+const SCALAR_TYPES: &[&str] = &[
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+const MAX_FIELD_NUMBER: u32 = 536_870_911;
+const RESERVED_FIELD_NUMBERS: std::ops::RangeInclusive<u32> = 19_000..=19_999;
+
+/// What a fully qualified name refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SymbolKind {
+    Message,
+    Enum,
+    EnumValue,
+    Field,
+    Oneof,
+    Service,
+    Rpc,
+}
+
+#[derive(Debug, Clone)]
+struct Symbol {
+    kind: SymbolKind,
+    file: String,
+}
+
+/// Fully qualified names defined by a set of files and the well-known files
+pub(crate) struct SymbolTable {
+    symbols: HashMap<String, Symbol>,
+}
+
+struct Validator<'a> {
+    diagnostics: &'a mut Diagnostics,
+    table: SymbolTable,
+}
+
+/// Checks generated files the way `protoc` would before they are written: every type reference
+/// resolves to a message or enum of an imported file, names and numbers are unique within their
+/// scope, names are valid identifiers that aren't reserved words and imports exist.
+pub fn validate(files: &[ProtoFile], diagnostics: &mut Diagnostics) {
+    let mut validator = Validator::new(files, diagnostics);
+    for file in files {
+        validator.check_file(file, files);
+    }
+}
+
+impl SymbolTable {
+    /// Symbols of `files`, ignoring any clashes between them.
+    pub(crate) fn new(files: &[ProtoFile]) -> Self {
+        Validator::new(files, &mut Diagnostics::new()).table
+    }
+
+    /// Resolves the type `reference` from `scope` like `protoc` does, innermost scope first,
+    /// returning its fully qualified name, kind and the file defining it.
+    pub(crate) fn resolve(
+        &self,
+        scope: &str,
+        reference: &str,
+    ) -> Option<(String, SymbolKind, &str)> {
+        let candidates = match reference.strip_prefix('.') {
+            Some(reference) => vec![reference.to_owned()],
+            None => {
+                let mut candidates = vec![];
+                let mut scope = scope;
+                loop {
+                    candidates.push(qualify(scope, reference));
+                    if scope.is_empty() {
+                        break;
+                    }
+                    scope = scope.rsplit_once('.').map(|(parent, _)| parent).unwrap_or("");
+                }
+                candidates
+            }
+        };
+        candidates.into_iter().find_map(|candidate| {
+            let symbol = self
+                .symbols
+                .get(&candidate)
+                .filter(|symbol| matches!(symbol.kind, SymbolKind::Message | SymbolKind::Enum))?;
+            Some((candidate, symbol.kind, symbol.file.as_str()))
+        })
+    }
+}
+
+impl<'a> Validator<'a> {
+    fn new(files: &[ProtoFile], diagnostics: &'a mut Diagnostics) -> Self {
+        let mut validator = Self {
+            diagnostics,
+            table: SymbolTable {
+                symbols: HashMap::new(),
+            },
+        };
+        for file in well_known_files().iter().chain(files) {
+            validator.define_file(file);
+        }
+        validator
+    }
+
+    fn error(&mut self, file: &str, code: &str, message: String) {
+        self.diagnostics
+            .push(Diagnostic::error(message).code(code).in_document(file));
+    }
+
+    /// Registers `name` in `scope`, reporting clashes with anything already defined there.
+    fn define(&mut self, file: &str, scope: &str, name: &str, kind: SymbolKind) {
+        let full_name = qualify(scope, name);
+
+        if !is_identifier(name) {
+            self.error(
+                file,
+                "invalid-name",
+                format!("{} {} is not a valid identifier", describe(kind), full_name),
+            );
+        } else if is_reserved(name, kind) {
+            self.error(
+                file,
+                "reserved-word",
+                format!("{} {} is a reserved word", describe(kind), full_name),
+            );
+        }
+
+        match self.table.symbols.get(&full_name) {
+            Some(existing) => {
+                let message = format!(
+                    "{} {} clashes with {} defined in {}",
+                    describe(kind),
+                    full_name,
+                    describe(existing.kind),
+                    existing.file
+                );
+                self.error(file, "duplicate-name", message);
+            }
+            None => {
+                self.table.symbols.insert(
+                    full_name,
+                    Symbol {
+                        kind,
+                        file: file.to_owned(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn define_file(&mut self, file: &ProtoFile) {
+        for proto_enum in &file.enums {
+            self.define_enum(&file.name, &file.package, proto_enum);
+        }
+        for message in &file.messages {
+            self.define_message(&file.name, &file.package, message);
+        }
+        for service in &file.services {
+            self.define(&file.name, &file.package, &service.name, SymbolKind::Service);
+            let scope = qualify(&file.package, &service.name);
+            for rpc in &service.rpcs {
+                self.define(&file.name, &scope, &rpc.name, SymbolKind::Rpc);
+            }
+        }
+    }
+
+    fn define_enum(&mut self, file: &str, scope: &str, proto_enum: &ProtoEnum) {
+        self.define(file, scope, &proto_enum.name, SymbolKind::Enum);
+
+        // Enum values are siblings of their enum, following C++ scoping rules
+        let full_name = qualify(scope, &proto_enum.name);
+        for value in &proto_enum.values {
+            self.define(file, scope, &value.name, SymbolKind::EnumValue);
+        }
+
+        if proto_enum.values.first().is_none_or(|value| value.number != 0) {
+            self.error(
+                file,
+                "invalid-number",
+                format!("first value of enum {} must be numbered 0", full_name),
+            );
+        }
+        let mut numbers = HashSet::new();
+        for value in &proto_enum.values {
+            if !numbers.insert(value.number) {
+                self.error(
+                    file,
+                    "duplicate-number",
+                    format!(
+                        "enum value {}.{} reuses number {}",
+                        full_name, value.name, value.number
+                    ),
+                );
+            }
+        }
+    }
+
+    fn define_message(&mut self, file: &str, scope: &str, message: &ProtoMessage) {
+        self.define(file, scope, &message.name, SymbolKind::Message);
+        let full_name = qualify(scope, &message.name);
+
+        for proto_enum in &message.nested_enums {
+            self.define_enum(file, &full_name, proto_enum);
+        }
+        for nested in &message.nested_messages {
+            self.define_message(file, &full_name, nested);
+        }
+
+        let mut numbers = HashSet::new();
+        for field in message.all_fields() {
+            self.define(file, &full_name, &field.name, SymbolKind::Field);
+
+            if field.number == 0
+                || field.number > MAX_FIELD_NUMBER
+                || RESERVED_FIELD_NUMBERS.contains(&field.number)
+            {
+                self.error(
+                    file,
+                    "invalid-number",
+                    format!(
+                        "field {}.{} has invalid number {}",
+                        full_name, field.name, field.number
+                    ),
+                );
+            } else if !numbers.insert(field.number) {
+                self.error(
+                    file,
+                    "duplicate-number",
+                    format!("field {}.{} reuses number {}", full_name, field.name, field.number),
+                );
+            }
+        }
+
+        // The writer leaves out empty oneofs
+        for oneof in message.oneofs.iter().filter(|oneof| !oneof.fields.is_empty()) {
+            self.define(file, &full_name, &oneof.name, SymbolKind::Oneof);
+        }
+    }
+
+    fn check_file(&mut self, file: &ProtoFile, files: &[ProtoFile]) {
+        for import in &file.imports {
+            let exists = files.iter().any(|other| &other.name == import)
+                || well_known_file(import).is_some();
+            if !exists {
+                self.error(
+                    &file.name,
+                    "missing-import",
+                    format!("imported file {} does not exist", import),
+                );
+            }
+        }
+
+        for message in &file.messages {
+            self.check_message(file, &qualify(&file.package, &message.name), message);
+        }
+
+        for service in &file.services {
+            for rpc in &service.rpcs {
+                for (what, type_name) in [
+                    ("request", &rpc.request_type),
+                    ("response", &rpc.response_type),
+                ] {
+                    let user = format!("{} type of rpc {}.{}", what, service.name, rpc.name);
+                    self.check_reference(file, &file.package, type_name, &user, true);
+                }
+            }
+        }
+    }
+
+    fn check_message(&mut self, file: &ProtoFile, full_name: &str, message: &ProtoMessage) {
+        for field in message.all_fields() {
+            let type_name = match &field.field_type {
+                ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => name.as_str(),
+                ProtoFieldType::Any => "google.protobuf.Any",
+                _ => continue,
+            };
+            let user = format!("type of field {}.{}", full_name, field.name);
+            self.check_reference(file, full_name, type_name, &user, false);
+        }
+        for nested in &message.nested_messages {
+            self.check_message(file, &qualify(full_name, &nested.name), nested);
+        }
+    }
+
+    fn check_reference(
+        &mut self,
+        file: &ProtoFile,
+        scope: &str,
+        reference: &str,
+        user: &str,
+        message_only: bool,
+    ) {
+        let Some((full_name, kind, defined_in)) = self.table.resolve(scope, reference) else {
+            self.error(
+                &file.name,
+                "unresolved-type",
+                format!("{} refers to undefined type {}", user, reference),
+            );
+            return;
+        };
+        let defined_in = defined_in.to_owned();
+
+        if defined_in != file.name && !file.imports.contains(&defined_in) {
+            self.error(
+                &file.name,
+                "missing-import",
+                format!(
+                    "{} refers to {} from {}, which is not imported",
+                    user, full_name, defined_in
+                ),
+            );
+        }
+        if message_only && kind != SymbolKind::Message {
+            self.error(
+                &file.name,
+                "wrong-type-kind",
+                format!("{} refers to enum {} instead of a message", user, full_name),
+            );
+        }
+    }
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// Whether `name` can't be used for a `kind` without being mistaken for a keyword or scalar type.
+/// Fields, values and rpcs may use keywords, as in `string message = 1`.
+fn is_reserved(name: &str, kind: SymbolKind) -> bool {
+    match kind {
+        SymbolKind::Message | SymbolKind::Enum | SymbolKind::Service => {
+            escape_proto_keyword(name) != name || SCALAR_TYPES.contains(&name)
+        }
+        _ => false,
+    }
+}
+
+fn describe(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::Message => "message",
+        SymbolKind::Enum => "enum",
+        SymbolKind::EnumValue => "enum value",
+        SymbolKind::Field => "field",
+        SymbolKind::Oneof => "oneof",
+        SymbolKind::Service => "service",
+        SymbolKind::Rpc => "rpc",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::parser::parse_proto_file;
+
+    fn problems(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        let files = files
+            .iter()
+            .map(|(name, content)| parse_proto_file(name, content).unwrap())
+            .collect::<Vec<_>>();
+        let mut diagnostics = Diagnostics::new();
+        validate(&files, &mut diagnostics);
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code.clone().unwrap(), diagnostic.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_files_pass() {
+        let problems = problems(&[
+            (
+                "common.proto",
+                r#"
+                syntax = "proto3";
+                package starknet.v0_8_1.common;
+                import "google/protobuf/any.proto";
+                enum Status { STATUS_UNSPECIFIED = 0; ACCEPTED = 1; }
+                message Felt { string value = 1; string message = 2; }
+                message Block {
+                  message Header { Felt hash = 1; }
+                  Header header = 1;
+                  Status status = 2;
+                  oneof id { Felt hash = 3; uint64 number = 4; }
+                  google.protobuf.Any extra = 5;
+                }
+                "#,
+            ),
+            (
+                "main.proto",
+                r#"
+                syntax = "proto3";
+                package starknet.v0_8_1.main;
+                import "common.proto";
+                message GetBlockRequest { starknet.v0_8_1.common.Block.Header header = 1; }
+                message GetBlockResponse { .starknet.v0_8_1.common.Block result = 1; }
+                service Main { rpc GetBlock(GetBlockRequest) returns (GetBlockResponse); }
+                "#,
+            ),
+        ]);
+        assert_eq!(problems, vec![]);
+    }
+
+    #[test]
+    fn test_invalid_files_are_reported() {
+        let problems = problems(&[
+            (
+                "common.proto",
+                r#"
+                syntax = "proto3";
+                package starknet.v0_8_1.common;
+                enum Status { ACCEPTED = 1; REJECTED = 1; }
+                enum Other { STATUS_UNSPECIFIED = 0; ACCEPTED = 1; }
+                message Block {
+                  AllOf parts = 1;
+                  string hash = 1;
+                  oneof hash { string a = 2; }
+                  string syntax = 3;
+                }
+                message bytes {}
+                "#,
+            ),
+            (
+                "main.proto",
+                r#"
+                syntax = "proto3";
+                package starknet.v0_8_1.main;
+                import "missing.proto";
+                message Request { Block block = 1; starknet.v0_8_1.common.Block other = 2; }
+                service Main { rpc Get(Request) returns (starknet.v0_8_1.common.Status); }
+                "#,
+            ),
+        ]);
+
+        let expected = [
+            (
+                "invalid-number",
+                "first value of enum starknet.v0_8_1.common.Status must be numbered 0",
+            ),
+            (
+                "duplicate-number",
+                "enum value starknet.v0_8_1.common.Status.REJECTED reuses number 1",
+            ),
+            (
+                "duplicate-name",
+                "enum value starknet.v0_8_1.common.ACCEPTED clashes with enum value defined in \
+                 common.proto",
+            ),
+            ("duplicate-number", "field starknet.v0_8_1.common.Block.hash reuses number 1"),
+            (
+                "duplicate-name",
+                "oneof starknet.v0_8_1.common.Block.hash clashes with field defined in \
+                 common.proto",
+            ),
+            ("reserved-word", "message starknet.v0_8_1.common.bytes is a reserved word"),
+            (
+                "unresolved-type",
+                "type of field starknet.v0_8_1.common.Block.parts refers to undefined type AllOf",
+            ),
+            ("missing-import", "imported file missing.proto does not exist"),
+            (
+                "unresolved-type",
+                "type of field starknet.v0_8_1.main.Request.block refers to undefined type Block",
+            ),
+            (
+                "missing-import",
+                "type of field starknet.v0_8_1.main.Request.other refers to \
+                 starknet.v0_8_1.common.Block from common.proto, which is not imported",
+            ),
+            (
+                "missing-import",
+                "response type of rpc Main.Get refers to starknet.v0_8_1.common.Status from \
+                 common.proto, which is not imported",
+            ),
+            (
+                "wrong-type-kind",
+                "response type of rpc Main.Get refers to enum starknet.v0_8_1.common.Status \
+                 instead of a message",
+            ),
+        ];
+        assert_eq!(
+            problems,
+            expected
+                .iter()
+                .map(|(code, message)| (code.to_string(), message.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
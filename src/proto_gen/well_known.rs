@@ -0,0 +1,110 @@
+use crate::proto_gen::{parser::parse_proto_file, ProtoFile};
+
+/// Well-known files generated files may import, without their comments
+const WELL_KNOWN_FILES: &[(&str, &str)] = &[
+    (
+        "google/protobuf/any.proto",
+        r#"
+        syntax = "proto3";
+        package google.protobuf;
+        option go_package = "google.golang.org/protobuf/types/known/anypb";
+        option java_package = "com.google.protobuf";
+        option java_outer_classname = "AnyProto";
+        option java_multiple_files = true;
+        option objc_class_prefix = "GPB";
+        option csharp_namespace = "Google.Protobuf.WellKnownTypes";
+        message Any {
+          string type_url = 1;
+          bytes value = 2;
+        }
+        "#,
+    ),
+    (
+        "google/protobuf/duration.proto",
+        r#"
+        syntax = "proto3";
+        package google.protobuf;
+        option cc_enable_arenas = true;
+        option go_package = "google.golang.org/protobuf/types/known/durationpb";
+        option java_package = "com.google.protobuf";
+        option java_outer_classname = "DurationProto";
+        option java_multiple_files = true;
+        option objc_class_prefix = "GPB";
+        option csharp_namespace = "Google.Protobuf.WellKnownTypes";
+        message Duration {
+          int64 seconds = 1;
+          int32 nanos = 2;
+        }
+        "#,
+    ),
+    (
+        "google/protobuf/empty.proto",
+        r#"
+        syntax = "proto3";
+        package google.protobuf;
+        option go_package = "google.golang.org/protobuf/types/known/emptypb";
+        option java_package = "com.google.protobuf";
+        option java_outer_classname = "EmptyProto";
+        option java_multiple_files = true;
+        option objc_class_prefix = "GPB";
+        option csharp_namespace = "Google.Protobuf.WellKnownTypes";
+        option cc_enable_arenas = true;
+        message Empty {}
+        "#,
+    ),
+    (
+        "google/protobuf/timestamp.proto",
+        r#"
+        syntax = "proto3";
+        package google.protobuf;
+        option cc_enable_arenas = true;
+        option go_package = "google.golang.org/protobuf/types/known/timestamppb";
+        option java_package = "com.google.protobuf";
+        option java_outer_classname = "TimestampProto";
+        option java_multiple_files = true;
+        option objc_class_prefix = "GPB";
+        option csharp_namespace = "Google.Protobuf.WellKnownTypes";
+        message Timestamp {
+          int64 seconds = 1;
+          int32 nanos = 2;
+        }
+        "#,
+    ),
+    (
+        "google/protobuf/wrappers.proto",
+        r#"
+        syntax = "proto3";
+        package google.protobuf;
+        option cc_enable_arenas = true;
+        option go_package = "google.golang.org/protobuf/types/known/wrapperspb";
+        option java_package = "com.google.protobuf";
+        option java_outer_classname = "WrappersProto";
+        option java_multiple_files = true;
+        option objc_class_prefix = "GPB";
+        option csharp_namespace = "Google.Protobuf.WellKnownTypes";
+        message DoubleValue { double value = 1; }
+        message FloatValue { float value = 1; }
+        message Int64Value { int64 value = 1; }
+        message UInt64Value { uint64 value = 1; }
+        message Int32Value { int32 value = 1; }
+        message UInt32Value { uint32 value = 1; }
+        message BoolValue { bool value = 1; }
+        message StringValue { string value = 1; }
+        message BytesValue { bytes value = 1; }
+        "#,
+    ),
+];
+
+/// Definitions of a well-known file like `google/protobuf/timestamp.proto`, if `name` is one
+pub fn well_known_file(name: &str) -> Option<ProtoFile> {
+    let (name, content) = WELL_KNOWN_FILES.iter().find(|(file, _)| *file == name)?;
+    Some(parse_proto_file(name, content).expect("Well-known files are valid"))
+}
+
+/// Definitions of all well-known files
+pub fn well_known_files() -> Vec<ProtoFile> {
+    WELL_KNOWN_FILES
+        .iter()
+        .filter_map(|(name, _)| well_known_file(name))
+        .collect()
+}
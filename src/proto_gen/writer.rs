@@ -1,6 +1,9 @@
 use std::fmt;
 
-use crate::proto_gen::types::{ProtoMessage, ProtoEnum, ProtoService, ProtoField};
+use crate::proto_gen::{
+    types::{ProtoMessage, ProtoEnum, ProtoService, ProtoField},
+    ProtoFile,
+};
 
 /// Writer for generating formatted protobuf files
 pub struct ProtoWriter {
@@ -30,7 +33,7 @@ impl ProtoWriter {
                 format!("go_package = \"github.com/cartridge-gg/starknet-grpc-codegen/go/{}\"", package.replace('.', "/")),
                 
                 // C# options (useful for .NET ecosystem)
-                format!("csharp_namespace = \"Starknet.{}\";", package.split('.').map(|s| {
+                format!("csharp_namespace = \"Starknet.{}\"", package.split('.').map(|s| {
                     let mut chars = s.chars();
                     match chars.next() {
                         None => String::new(),
@@ -39,7 +42,7 @@ impl ProtoWriter {
                 }).collect::<Vec<_>>().join(".")),
                 
                 // PHP options
-                format!("php_namespace = \"Starknet\\\\{}\";", package.split('.').map(|s| {
+                format!("php_namespace = \"Starknet\\\\{}\"", package.split('.').map(|s| {
                     let mut chars = s.chars();
                     match chars.next() {
                         None => String::new(),
@@ -72,56 +75,272 @@ impl ProtoWriter {
     pub fn add_option(&mut self, option: &str) {
         self.options.push(option.to_string());
     }
+
+    /// Definitions written so far, as the file named `name`
+    pub fn to_file(&self, name: &str) -> ProtoFile {
+        ProtoFile {
+            name: name.to_string(),
+            package: self.package.clone(),
+            imports: self.imports.clone(),
+            options: self.options.clone(),
+            enums: self.enums.clone(),
+            messages: self.messages.clone(),
+            services: self.services.clone(),
+        }
+    }
 }
 
-impl fmt::Display for ProtoWriter {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Proto file header
-        writeln!(f, "syntax = \"proto3\";")?;
-        writeln!(f)?;
-        
-        // Package declaration
-        writeln!(f, "package {};", self.package)?;
-        writeln!(f)?;
-        
-        // Imports
-        for import in &self.imports {
-            writeln!(f, "import \"{}\";", import)?;
+impl From<&ProtoFile> for ProtoWriter {
+    fn from(file: &ProtoFile) -> Self {
+        Self {
+            package: file.package.clone(),
+            imports: file.imports.clone(),
+            messages: file.messages.clone(),
+            enums: file.enums.clone(),
+            services: file.services.clone(),
+            options: file.options.clone(),
+        }
+    }
+}
+
+/// Element of a rendered file, addressed like a `google.protobuf.SourceCodeInfo.Location`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Field numbers and indexes leading to the element from its `FileDescriptorProto`
+    pub path: Vec<i32>,
+    /// Zero-based start line and column, end line (left out for single-line elements) and end
+    /// column
+    pub span: Vec<i32>,
+    pub leading_comments: Option<String>,
+}
+
+/// Proto source along with where each definition ended up in it
+#[derive(Debug, Clone)]
+pub struct RenderedFile {
+    pub text: String,
+    pub locations: Vec<SourceLocation>,
+}
+
+impl ProtoWriter {
+    pub fn render(&self) -> RenderedFile {
+        let mut renderer = Renderer::default();
+
+        renderer.element(vec![12], None, 0, "syntax = \"proto3\";");
+        renderer.line(0, "");
+
+        renderer.element(vec![2], None, 0, &format!("package {};", self.package));
+        renderer.line(0, "");
+
+        for (index, import) in self.imports.iter().enumerate() {
+            renderer.element(vec![3, index as i32], None, 0, &format!("import \"{}\";", import));
         }
         if !self.imports.is_empty() {
-            writeln!(f)?;
+            renderer.line(0, "");
         }
-        
-        // Options
+
         for option in &self.options {
-            writeln!(f, "option {};", option)?;
+            renderer.element(vec![8], None, 0, &format!("option {};", option));
         }
         if !self.options.is_empty() {
-            writeln!(f)?;
+            renderer.line(0, "");
         }
-        
-        // Enums
-        for proto_enum in &self.enums {
-            write!(f, "{}", proto_enum)?;
-            writeln!(f)?;
+
+        for (index, proto_enum) in self.enums.iter().enumerate() {
+            renderer.proto_enum(vec![5, index as i32], 0, proto_enum);
+            renderer.line(0, "");
         }
-        
-        // Messages
-        for message in &self.messages {
-            write!(f, "{}", message)?;
-            writeln!(f)?;
+
+        for (index, message) in self.messages.iter().enumerate() {
+            renderer.message(vec![4, index as i32], 0, message);
+            renderer.line(0, "");
         }
-        
-        // Services
-        for service in &self.services {
-            write!(f, "{}", service)?;
-            writeln!(f)?;
+
+        for (index, service) in self.services.iter().enumerate() {
+            renderer.service(vec![6, index as i32], 0, service);
+            renderer.line(0, "");
+        }
+
+        RenderedFile {
+            text: renderer.text,
+            locations: renderer.locations,
         }
-        
-        Ok(())
     }
 }
 
+impl fmt::Display for ProtoWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render().text)
+    }
+}
+
+/// Writes proto source line by line, keeping track of the location of each element
+#[derive(Default)]
+pub(crate) struct Renderer {
+    pub(crate) text: String,
+    line: i32,
+    locations: Vec<SourceLocation>,
+}
+
+impl Renderer {
+    fn line(&mut self, indent: usize, content: &str) {
+        if !content.is_empty() {
+            self.text.push_str(&" ".repeat(indent));
+            self.text.push_str(content);
+        }
+        self.text.push('\n');
+        self.line += 1;
+    }
+
+    /// Writes the comment of the next element, returning it in `SourceCodeInfo` form.
+    fn comment(&mut self, comment: Option<&String>, indent: usize) -> Option<String> {
+        let comment = comment?;
+        let formatted = format_comment(comment, indent);
+        self.text.push_str(&formatted);
+        self.text.push('\n');
+        self.line += formatted.split('\n').count() as i32;
+
+        let leading = comment
+            .lines()
+            .map(|line| format!(" {}\n", line.trim()))
+            .collect::<String>();
+        (!leading.is_empty()).then_some(leading)
+    }
+
+    fn element(&mut self, path: Vec<i32>, leading: Option<String>, indent: usize, content: &str) {
+        let start = indent as i32;
+        self.locations.push(SourceLocation {
+            path,
+            span: vec![self.line, start, start + content.chars().count() as i32],
+            leading_comments: leading,
+        });
+        self.line(indent, content);
+    }
+
+    /// Opens a block, returning the index of its location for `close`.
+    fn open(
+        &mut self,
+        path: Vec<i32>,
+        leading: Option<String>,
+        indent: usize,
+        header: &str,
+    ) -> usize {
+        self.locations.push(SourceLocation {
+            path,
+            span: vec![self.line, indent as i32],
+            leading_comments: leading,
+        });
+        self.line(indent, &format!("{} {{", header));
+        self.locations.len() - 1
+    }
+
+    fn close(&mut self, location: usize, indent: usize) {
+        let end = [self.line, indent as i32 + 1];
+        self.locations[location].span.extend(end);
+        self.line(indent, "}");
+    }
+
+    pub(crate) fn message(&mut self, path: Vec<i32>, indent: usize, message: &ProtoMessage) {
+        let leading = self.comment(message.comment.as_ref(), indent);
+        let header = format!("message {}", message.name);
+        let location = self.open(path.clone(), leading, indent, &header);
+        let child = |kind: i32, index: usize| [path.as_slice(), &[kind, index as i32]].concat();
+
+        for (index, proto_enum) in message.nested_enums.iter().enumerate() {
+            self.proto_enum(child(4, index), indent + 2, proto_enum);
+        }
+        for (index, nested) in message.nested_messages.iter().enumerate() {
+            self.message(child(3, index), indent + 2, nested);
+        }
+
+        // Oneof members are declared first, so their fields come first in the descriptor too
+        let fields = message.declared_fields();
+        let mut fields = fields.iter().enumerate().peekable();
+        for (oneof_index, oneof) in message.declared_oneofs().enumerate() {
+            let oneof_location = self.open(
+                child(8, oneof_index),
+                None,
+                indent + 2,
+                &format!("oneof {}", oneof.name),
+            );
+            while let Some((index, (field, _))) =
+                fields.next_if(|(_, (_, oneof))| *oneof == Some(oneof_index))
+            {
+                let leading = self.comment(field.comment.as_ref(), indent + 4);
+                let declaration = field_declaration(field, false);
+                self.element(child(2, index), leading, indent + 4, &declaration);
+            }
+            self.close(oneof_location, indent + 2);
+        }
+        for (index, (field, _)) in fields {
+            let leading = self.comment(field.comment.as_ref(), indent + 2);
+            self.element(child(2, index), leading, indent + 2, &field_declaration(field, true));
+        }
+
+        self.close(location, indent);
+    }
+
+    pub(crate) fn proto_enum(&mut self, path: Vec<i32>, indent: usize, proto_enum: &ProtoEnum) {
+        let leading = self.comment(proto_enum.comment.as_ref(), indent);
+        let header = format!("enum {}", proto_enum.name);
+        let location = self.open(path.clone(), leading, indent, &header);
+
+        for (index, value) in proto_enum.values.iter().enumerate() {
+            let leading = self.comment(value.comment.as_ref(), indent + 2);
+            self.element(
+                [path.as_slice(), &[2, index as i32]].concat(),
+                leading,
+                indent + 2,
+                &format!("{} = {};", value.name, value.number),
+            );
+        }
+
+        self.close(location, indent);
+    }
+
+    pub(crate) fn service(&mut self, path: Vec<i32>, indent: usize, service: &ProtoService) {
+        let leading = self.comment(service.comment.as_ref(), indent);
+        let header = format!("service {}", service.name);
+        let location = self.open(path.clone(), leading, indent, &header);
+
+        for (index, rpc) in service.rpcs.iter().enumerate() {
+            let leading = self.comment(rpc.comment.as_ref(), indent + 2);
+            let stream = |streaming: bool| if streaming { "stream " } else { "" };
+            self.element(
+                [path.as_slice(), &[2, index as i32]].concat(),
+                leading,
+                indent + 2,
+                &format!(
+                    "rpc {}({}{}) returns ({}{});",
+                    rpc.name,
+                    stream(rpc.client_streaming),
+                    rpc.request_type,
+                    stream(rpc.server_streaming),
+                    rpc.response_type
+                ),
+            );
+        }
+
+        self.close(location, indent);
+    }
+}
+
+/// Field line without indentation; oneof members have no label
+fn field_declaration(field: &ProtoField, label: bool) -> String {
+    let label = match (label, field.repeated, field.optional) {
+        (false, _, _) => "",
+        (true, true, _) => "repeated ",
+        (true, false, true) => "optional ",
+        (true, false, false) => "",
+    };
+    let mut declaration =
+        format!("{}{} {} = {}", label, field.field_type, field.name, field.number);
+    if let Some(json_name) = &field.json_name {
+        declaration.push_str(&format!(" [json_name = \"{}\"]", json_name));
+    }
+    declaration.push(';');
+    declaration
+}
+
 /// Helper functions for proto formatting
 pub fn format_comment(text: &str, indent: usize) -> String {
     let prefix = " ".repeat(indent);
@@ -180,7 +399,6 @@ pub fn to_proto_type_name(json_name: &str) -> String {
         .collect()
 }
 
-#[allow(dead_code)]
 pub fn escape_proto_keyword(name: &str) -> String {
     match name {
         "import" | "package" | "message" | "enum" | "service" | "rpc" | 
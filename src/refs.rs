@@ -0,0 +1,750 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    spec::*,
+};
+
+/// Maximum number of `$ref` hops followed before assuming a cycle
+const MAX_REF_DEPTH: usize = 32;
+
+/// Parsed `$ref` value: an optional document URI and a JSON pointer into that document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefTarget {
+    pub document: Option<String>,
+    pub pointer: Vec<String>,
+}
+
+/// Location a `$ref` resolved to
+#[derive(Debug, Clone)]
+pub struct ResolvedRef<'a> {
+    pub document: &'a str,
+    pub pointer: Vec<String>,
+    pub value: &'a Value,
+}
+
+/// Set of raw OpenRPC documents that `$ref`s are resolved against.
+///
+/// Documents are merged in the order they are added. They are keyed by file name; the directory
+/// part of a reference URI is ignored, so `./api/starknet_api_openrpc.json` and
+/// `starknet_api_openrpc.json` name the same document.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentSet {
+    documents: IndexMap<String, Value>,
+}
+
+impl RefTarget {
+    pub fn parse(reference: &str) -> Result<Self> {
+        let (document, fragment) = match reference.split_once('#') {
+            Some((document, fragment)) => (document, fragment),
+            None => (reference, ""),
+        };
+
+        let document = if document.is_empty() {
+            None
+        } else {
+            Some(document_name(document).to_owned())
+        };
+
+        let pointer = if fragment.is_empty() {
+            vec![]
+        } else {
+            let fragment = fragment
+                .strip_prefix('/')
+                .ok_or_else(|| anyhow::anyhow!("invalid JSON pointer in $ref: {}", reference))?;
+            fragment
+                .split('/')
+                .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                .collect()
+        };
+
+        Ok(Self { document, pointer })
+    }
+}
+
+impl ResolvedRef<'_> {
+    /// Returns the entry name if the reference landed on `#/components/{kind}/{name}`.
+    pub fn component(&self, kind: &str) -> Option<&str> {
+        component_name(&self.pointer, kind)
+    }
+}
+
+impl DocumentSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, content: &str) -> Result<()> {
+        let value = serde_json::from_str(content)?;
+        self.documents.insert(document_name(name).to_owned(), value);
+        Ok(())
+    }
+
+    pub fn document_names(&self) -> impl Iterator<Item = &str> {
+        self.documents.keys().map(|name| name.as_str())
+    }
+
+    /// Resolves `reference` relative to the document named `base`.
+    pub fn resolve(&self, base: &str, reference: &str) -> Result<ResolvedRef<'_>> {
+        let target = RefTarget::parse(reference)?;
+        let document = target.document.as_deref().unwrap_or(base);
+
+        let (document, mut value) = self
+            .documents
+            .get_key_value(document)
+            .ok_or_else(|| anyhow::anyhow!("unknown document in $ref: {}", reference))?;
+
+        for segment in target.pointer.iter() {
+            value = match value {
+                Value::Object(map) => map.get(segment),
+                Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow::anyhow!("dangling $ref: {}", reference))?;
+        }
+
+        Ok(ResolvedRef {
+            document,
+            pointer: target.pointer,
+            value,
+        })
+    }
+
+    /// Follows pure alias schemas (`{"$ref": ...}`) until reaching the actual definition.
+    ///
+    /// Returns the document and schema name the definition lives under.
+    pub fn schema_identity(&self, document: &str, name: &str) -> Result<(String, String)> {
+        let mut current = (document.to_owned(), name.to_owned());
+
+        for _ in 0..MAX_REF_DEPTH {
+            let resolved = self.resolve(
+                &current.0,
+                &format!("#/components/schemas/{}", escape_pointer_segment(&current.1)),
+            )?;
+
+            let next = match resolved.value {
+                Value::Object(map) if map.len() == 1 => match map.get("$ref") {
+                    Some(Value::String(reference)) => {
+                        let target = self.resolve(resolved.document, reference)?;
+                        target
+                            .component("schemas")
+                            .map(|name| (target.document.to_owned(), name.to_owned()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match next {
+                Some(next) if next != current => current = next,
+                _ => return Ok(current),
+            }
+        }
+
+        anyhow::bail!("$ref cycle while resolving schema {}", name)
+    }
+
+    /// Lists the schemas of every document that no method reaches, directly or through other
+    /// definitions, as document and schema name.
+    pub fn unused_schemas(&self) -> Vec<(String, String)> {
+        let mut used = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = self
+            .documents
+            .iter()
+            .filter_map(|(document, value)| Some((document.as_str(), value.get("methods")?)))
+            .collect::<Vec<_>>();
+
+        while let Some((document, value)) = pending.pop() {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(reference)) = map.get("$ref") {
+                        // Dangling references are reported by `canonicalize`
+                        if let Ok(resolved) = self.resolve(document, reference) {
+                            if let [components, kind, name, ..] = resolved.pointer.as_slice() {
+                                if components == "components" && kind == "schemas" {
+                                    used.insert((resolved.document, name.to_owned()));
+                                }
+                            }
+                            if visited.insert((resolved.document, resolved.pointer)) {
+                                pending.push((resolved.document, resolved.value));
+                            }
+                        }
+                    }
+                    pending.extend(map.values().map(|value| (document, value)));
+                }
+                Value::Array(items) => pending.extend(items.iter().map(|item| (document, item))),
+                _ => {}
+            }
+        }
+
+        let mut unused = vec![];
+        for (document, value) in self.documents.iter() {
+            let Some(Value::Object(schemas)) = value.pointer("/components/schemas") else {
+                continue;
+            };
+            for name in schemas.keys() {
+                if !used.contains(&(document.as_str(), name.to_owned())) {
+                    unused.push((document.to_owned(), name.to_owned()));
+                }
+            }
+        }
+
+        unused
+    }
+
+    /// Rewrites every `$ref` of `specs` (parsed from the document named `document`) so that it
+    /// can be looked up by name in the merged specification.
+    ///
+    /// References to `#/components/schemas/*` and `#/components/errors/*` of any document become
+    /// local references; references to any other location are replaced by the schema they point
+    /// to. A schema reference whose name designates a different definition once all documents are
+    /// merged is redirected to the name of the definition it aliases. Dangling references,
+    /// references of the wrong kind and unresolvable name collisions are reported to
+    /// `diagnostics` and left untouched.
+    pub fn canonicalize(
+        &self,
+        document: &str,
+        specs: &mut Specification,
+        diagnostics: &mut Diagnostics,
+    ) {
+        for (i, method) in specs.methods.iter_mut().enumerate() {
+            for (j, param) in method.params.iter_mut().enumerate() {
+                let path = format!("#/methods/{}/params/{}/schema", i, j);
+                self.canonicalize_schema(document, &path, &mut param.schema, 0, diagnostics);
+            }
+            if let Some(result) = &mut method.result {
+                let path = format!("#/methods/{}/result/schema", i);
+                self.canonicalize_schema(document, &path, &mut result.schema, 0, diagnostics);
+            }
+            if let Some(errors) = &mut method.errors {
+                for (j, error) in errors.iter_mut().enumerate() {
+                    let path = format!("#/methods/{}/errors/{}", i, j);
+                    self.canonicalize_error_ref(document, &path, error, diagnostics);
+                }
+            }
+        }
+
+        for (name, schema) in specs.components.schemas.iter_mut() {
+            let path = format!("#/components/schemas/{}", escape_pointer_segment(name));
+            self.canonicalize_schema(document, &path, schema, 0, diagnostics);
+        }
+
+        for (name, error) in specs.components.errors.iter_mut() {
+            let path = format!("#/components/errors/{}", escape_pointer_segment(name));
+            match error {
+                ErrorType::Error(error) => {
+                    if let Some(data) = &mut error.data {
+                        let path = format!("{}/data", path);
+                        self.canonicalize_schema(document, &path, data, 0, diagnostics);
+                    }
+                }
+                ErrorType::Reference(reference) => {
+                    self.canonicalize_error_ref(document, &path, reference, diagnostics);
+                }
+            }
+        }
+    }
+
+    /// Name under which the merged specification exposes the schema `target` points to.
+    fn merged_name(&self, target: &ResolvedRef<'_>) -> Result<String> {
+        let name = target.component("schemas").unwrap_or_default();
+        let intended = self.schema_identity(target.document, name)?;
+
+        let merged = self.merged_identity(name)?;
+        if merged == intended
+            || same_definition(self.schema_value(&merged)?, self.schema_value(&intended)?)
+        {
+            return Ok(name.to_owned());
+        }
+
+        // The name is taken by another document; fall back to the aliased definition's own name
+        if self.merged_identity(&intended.1)? == intended {
+            return Ok(intended.1);
+        }
+
+        anyhow::bail!(
+            "$ref to {} resolves to {}#/components/schemas/{} but the merged specification \
+             defines it in {}",
+            name,
+            intended.0,
+            intended.1,
+            merged.0,
+        )
+    }
+
+    fn schema_value(&self, identity: &(String, String)) -> Result<&Value> {
+        Ok(self
+            .resolve(
+                &identity.0,
+                &format!("#/components/schemas/{}", escape_pointer_segment(&identity.1)),
+            )?
+            .value)
+    }
+
+    /// Definition that `name` designates once all documents are merged.
+    fn merged_identity(&self, name: &str) -> Result<(String, String)> {
+        let mut first_alias = None;
+
+        for (document, value) in self.documents.iter() {
+            let Some(Value::Object(schemas)) = value.pointer("/components/schemas") else {
+                continue;
+            };
+
+            match schemas.get(name) {
+                Some(Value::Object(schema)) if !schema.contains_key("$ref") => {
+                    return Ok((document.to_owned(), name.to_owned()));
+                }
+                Some(_) if first_alias.is_none() => first_alias = Some(document.as_str()),
+                _ => {}
+            }
+        }
+
+        match first_alias {
+            Some(document) => self.schema_identity(document, name),
+            None => anyhow::bail!("schema {} is not defined by any document", name),
+        }
+    }
+
+    fn canonicalize_schema(
+        &self,
+        document: &str,
+        path: &str,
+        schema: &mut Schema,
+        depth: usize,
+        diagnostics: &mut Diagnostics,
+    ) {
+        if depth > MAX_REF_DEPTH {
+            diagnostics.push(Diagnostic::error("$ref cycle").code("ref-cycle").at(document, path));
+            return;
+        }
+
+        match schema {
+            Schema::Ref(reference) => {
+                let resolved = match self.resolve(document, &reference.ref_field) {
+                    Ok(resolved) => resolved,
+                    Err(err) => {
+                        let diagnostic = Diagnostic::error(err.to_string()).code("dangling-ref");
+                        diagnostics.push(diagnostic.at(document, path));
+                        return;
+                    }
+                };
+
+                if resolved.component("schemas").is_some() {
+                    match self.merged_name(&resolved) {
+                        Ok(name) => {
+                            reference.ref_field =
+                                format!("#/components/schemas/{}", escape_pointer_segment(&name));
+                        }
+                        Err(err) => {
+                            let diagnostic =
+                                Diagnostic::error(err.to_string()).code("ref-collision");
+                            diagnostics.push(diagnostic.at(document, path));
+                        }
+                    }
+                } else if resolved.component("errors").is_some() {
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "$ref {} points to an error, expected a schema",
+                            reference.ref_field
+                        ))
+                        .code("wrong-ref-kind")
+                        .at(document, path),
+                    );
+                } else {
+                    // Pointer into the middle of a schema: inline the definition it designates
+                    match serde_json::from_value::<Schema>(resolved.value.clone()) {
+                        Ok(mut inlined) => {
+                            let inlined_document = resolved.document.to_owned();
+                            let inlined_path = format!(
+                                "#/{}",
+                                resolved
+                                    .pointer
+                                    .iter()
+                                    .map(|segment| escape_pointer_segment(segment))
+                                    .collect::<Vec<_>>()
+                                    .join("/")
+                            );
+                            self.canonicalize_schema(
+                                &inlined_document,
+                                &inlined_path,
+                                &mut inlined,
+                                depth + 1,
+                                diagnostics,
+                            );
+                            *schema = inlined;
+                        }
+                        Err(err) => diagnostics.push(
+                            Diagnostic::error(format!(
+                                "$ref {} is not a schema: {}",
+                                reference.ref_field, err
+                            ))
+                            .code("wrong-ref-kind")
+                            .at(document, path),
+                        ),
+                    }
+                }
+            }
+            Schema::OneOf(oneof) => {
+                for (i, variant) in oneof.one_of.iter_mut().enumerate() {
+                    let path = format!("{}/oneOf/{}", path, i);
+                    self.canonicalize_schema(document, &path, variant, depth, diagnostics);
+                }
+            }
+            Schema::AllOf(allof) => {
+                for (i, part) in allof.all_of.iter_mut().enumerate() {
+                    let path = format!("{}/allOf/{}", path, i);
+                    self.canonicalize_schema(document, &path, part, depth, diagnostics);
+                }
+            }
+            Schema::Primitive(Primitive::Array(array)) => {
+                let path = format!("{}/items", path);
+                self.canonicalize_schema(document, &path, &mut array.items, depth, diagnostics);
+            }
+            Schema::Primitive(Primitive::Object(object)) => {
+                for (name, property) in object.properties.iter_mut() {
+                    let path = format!("{}/properties/{}", path, escape_pointer_segment(name));
+                    self.canonicalize_schema(document, &path, property, depth, diagnostics);
+                }
+            }
+            Schema::Primitive(_) => {}
+        }
+    }
+
+    fn canonicalize_error_ref(
+        &self,
+        document: &str,
+        path: &str,
+        reference: &mut Reference,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let diagnostic = match self.resolve(document, &reference.ref_field) {
+            Ok(resolved) => match resolved.component("errors") {
+                Some(name) => {
+                    reference.ref_field =
+                        format!("#/components/errors/{}", escape_pointer_segment(name));
+                    return;
+                }
+                None => Diagnostic::error(format!(
+                    "$ref {} does not point to an error",
+                    reference.ref_field
+                ))
+                .code("wrong-ref-kind"),
+            },
+            // Error missing from the `components.errors` of the document it is looked up in
+            Err(err) => Diagnostic::error(err.to_string()).code("missing-error"),
+        };
+
+        diagnostics.push(diagnostic.at(document, path));
+    }
+}
+
+/// Follows references to the schema they name, leaving dangling ones as they are
+pub fn resolve_schema<'a>(schemas: &'a IndexMap<String, Schema>, schema: &'a Schema) -> &'a Schema {
+    resolve_named_schema(schemas, schema).1
+}
+
+/// Schema `schema` stands for once references are followed, along with the name of the last
+/// schema referenced, or `None` if `schema` isn't a reference
+pub fn resolve_named_schema<'a>(
+    schemas: &'a IndexMap<String, Schema>,
+    mut schema: &'a Schema,
+) -> (Option<&'a str>, &'a Schema) {
+    let mut name = None;
+    // Bounded in case of reference cycles
+    for _ in 0..MAX_REF_DEPTH {
+        let Schema::Ref(reference) = schema else {
+            break;
+        };
+        match schemas.get(reference.name()) {
+            Some(target) => {
+                name = Some(reference.name());
+                schema = target;
+            }
+            None => break,
+        }
+    }
+    (name, schema)
+}
+
+fn component_name<'a>(pointer: &'a [String], kind: &str) -> Option<&'a str> {
+    match pointer {
+        [components, entry_kind, name] if components == "components" && entry_kind == kind => {
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/// Whether two schema definitions only differ in their documentation keywords
+pub fn same_definition(a: &Value, b: &Value) -> bool {
+    strip_documentation(a, false) == strip_documentation(b, false)
+}
+
+fn strip_documentation(value: &Value, properties: bool) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                // Keys of `properties` are field names, not keywords
+                .filter(|(key, _)| {
+                    properties || !matches!(key.as_str(), "title" | "description" | "summary")
+                })
+                .map(|(key, value)| {
+                    let value = strip_documentation(value, !properties && key == "properties");
+                    (key.to_owned(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| strip_documentation(item, false)).collect())
+        }
+        value => value.to_owned(),
+    }
+}
+
+/// File name part of a document URI
+pub fn document_name(uri: &str) -> &str {
+    match uri.rfind('/') {
+        Some(ind_slash) => &uri[ind_slash + 1..],
+        None => uri,
+    }
+}
+
+pub fn escape_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.1.0", "title": "Main", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "starknet_getFoo",
+                "summary": "Returns a foo",
+                "params": [],
+                "result": {
+                    "name": "result",
+                    "schema": { "$ref": "#/components/schemas/FOO/properties/bar" }
+                },
+                "errors": [{ "$ref": "#/components/errors/FOO_NOT_FOUND" }]
+            }
+        ],
+        "components": {
+            "contentDescriptors": {},
+            "schemas": {
+                "FELT": { "type": "string", "pattern": "^0x[a-fA-F0-9]+$" },
+                "FOO": {
+                    "type": "object",
+                    "properties": {
+                        "bar": { "type": "array", "items": { "$ref": "#/components/schemas/FELT" } }
+                    },
+                    "required": []
+                }
+            },
+            "errors": {
+                "FOO_NOT_FOUND": { "code": 1, "message": "Foo not found" }
+            }
+        }
+    }"##;
+
+    const WRITE: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.1.0", "title": "Write", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "starknet_addFoo",
+                "summary": "Adds a foo",
+                "params": [
+                    { "name": "foo", "required": true, "schema": { "$ref": "#/components/schemas/FOO" } }
+                ],
+                "errors": [{ "$ref": "./api/starknet_api_openrpc.json#/components/errors/FOO_NOT_FOUND" }]
+            }
+        ],
+        "components": {
+            "contentDescriptors": {},
+            "schemas": {
+                "FOO": { "$ref": "./api/starknet_api_openrpc.json#/components/schemas/FOO" }
+            },
+            "errors": {}
+        }
+    }"##;
+
+    fn create_test_documents() -> DocumentSet {
+        let mut documents = DocumentSet::new();
+        documents.add("starknet_api_openrpc.json", MAIN).unwrap();
+        documents.add("starknet_write_api.json", WRITE).unwrap();
+        documents
+    }
+
+    #[test]
+    fn test_ref_target_parsing() {
+        let target = RefTarget::parse("./api/starknet_api_openrpc.json#/components/schemas/FELT").unwrap();
+        assert_eq!(target.document.as_deref(), Some("starknet_api_openrpc.json"));
+        assert_eq!(component_name(&target.pointer, "schemas"), Some("FELT"));
+        assert_eq!(component_name(&target.pointer, "errors"), None);
+
+        let target = RefTarget::parse("#/components/schemas/A~1B/properties/c~0d").unwrap();
+        assert_eq!(target.document, None);
+        assert_eq!(target.pointer, vec!["components", "schemas", "A/B", "properties", "c~d"]);
+
+        assert!(RefTarget::parse("#components/schemas/FELT").is_err());
+    }
+
+    #[test]
+    fn test_cross_document_resolution() {
+        let documents = create_test_documents();
+
+        let resolved = documents
+            .resolve("starknet_write_api.json", "./api/starknet_api_openrpc.json#/components/schemas/FELT")
+            .unwrap();
+        assert_eq!(resolved.document, "starknet_api_openrpc.json");
+        assert_eq!(resolved.value["type"], "string");
+
+        assert_eq!(
+            documents.schema_identity("starknet_write_api.json", "FOO").unwrap(),
+            ("starknet_api_openrpc.json".to_string(), "FOO".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dangling_ref_is_an_error() {
+        let documents = create_test_documents();
+
+        let err = documents
+            .resolve("starknet_api_openrpc.json", "#/components/schemas/MISSING")
+            .unwrap_err();
+        assert!(err.to_string().contains("dangling $ref"));
+
+        let mut specs: Specification = serde_json::from_str(&MAIN.replace(
+            "#/components/errors/FOO_NOT_FOUND",
+            "#/components/errors/BAR_NOT_FOUND",
+        ))
+        .unwrap();
+        let mut diagnostics = Diagnostics::new();
+        documents.canonicalize("starknet_api_openrpc.json", &mut specs, &mut diagnostics);
+        let diagnostic = diagnostics.iter().next().unwrap();
+        assert_eq!(diagnostic.path.as_deref(), Some("#/methods/0/errors/0"));
+        assert!(diagnostic.message.contains("dangling $ref"));
+    }
+
+    #[test]
+    fn test_canonicalize_rewrites_and_inlines() {
+        let documents = create_test_documents();
+
+        let mut specs: Specification = serde_json::from_str(MAIN).unwrap();
+        let mut diagnostics = Diagnostics::new();
+        documents.canonicalize("starknet_api_openrpc.json", &mut specs, &mut diagnostics);
+
+        // The nested pointer is replaced by the array schema it designates
+        match &specs.methods[0].result.as_ref().unwrap().schema {
+            Schema::Primitive(Primitive::Array(array)) => match array.items.as_ref() {
+                Schema::Ref(reference) => assert_eq!(reference.ref_field, "#/components/schemas/FELT"),
+                _ => panic!("Expected reference items"),
+            },
+            _ => panic!("Expected inlined array schema"),
+        }
+
+        let mut write_specs: Specification = serde_json::from_str(WRITE).unwrap();
+        documents.canonicalize("starknet_write_api.json", &mut write_specs, &mut diagnostics);
+        assert_eq!(diagnostics.iter().count(), 0);
+        let errors = write_specs.methods[0].errors.as_ref().unwrap();
+        assert_eq!(errors[0].ref_field, "#/components/errors/FOO_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_conflicting_definitions_are_detected() {
+        let mut documents = DocumentSet::new();
+        documents.add("starknet_api_openrpc.json", MAIN).unwrap();
+        // Write document defines its own, different FOO under the same name
+        documents
+            .add(
+                "starknet_write_api.json",
+                &WRITE.replace(
+                    r##"{ "$ref": "./api/starknet_api_openrpc.json#/components/schemas/FOO" }"##,
+                    r##"{ "type": "object", "properties": {}, "required": [] }"##,
+                ),
+            )
+            .unwrap();
+
+        let mut write_specs: Specification =
+            serde_json::from_str(&serde_json::to_string(&documents.documents["starknet_write_api.json"]).unwrap())
+                .unwrap();
+        let mut diagnostics = Diagnostics::new();
+        documents.canonicalize("starknet_write_api.json", &mut write_specs, &mut diagnostics);
+        let paths = diagnostics.iter().map(|d| d.path.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["#/methods/0/params/0/schema"]);
+    }
+
+    #[test]
+    fn test_shadowed_alias_is_redirected() {
+        let mut documents = DocumentSet::new();
+        documents.add("starknet_api_openrpc.json", MAIN).unwrap();
+        // Write document aliases FELT under the name FOO, which main uses for something else
+        let write = WRITE.replace(
+            "./api/starknet_api_openrpc.json#/components/schemas/FOO",
+            "./api/starknet_api_openrpc.json#/components/schemas/FELT",
+        );
+        documents.add("starknet_write_api.json", &write).unwrap();
+
+        let mut write_specs: Specification = serde_json::from_str(&write).unwrap();
+        let mut diagnostics = Diagnostics::new();
+        documents.canonicalize("starknet_write_api.json", &mut write_specs, &mut diagnostics);
+        assert_eq!(diagnostics.iter().count(), 0);
+
+        match &write_specs.methods[0].params[0].schema {
+            Schema::Ref(reference) => assert_eq!(reference.ref_field, "#/components/schemas/FELT"),
+            _ => panic!("Expected reference"),
+        }
+    }
+
+    #[test]
+    fn test_same_definition_ignores_documentation() {
+        let a = serde_json::json!({
+            "title": "Number as hex",
+            "type": "object",
+            "properties": { "title": { "type": "string", "description": "Name" } }
+        });
+        let b = serde_json::json!({
+            "type": "object",
+            "properties": { "title": { "type": "string" } }
+        });
+        let c = serde_json::json!({ "type": "object", "properties": {} });
+
+        assert!(same_definition(&a, &b));
+        assert!(!same_definition(&a, &c));
+    }
+
+    #[test]
+    fn test_unused_schemas() {
+        let mut documents = create_test_documents();
+        assert_eq!(documents.unused_schemas(), vec![]);
+
+        documents
+            .add(
+                "starknet_trace_api_openrpc.json",
+                r##"{
+                    "methods": [],
+                    "components": {
+                        "schemas": {
+                            "UNUSED": { "$ref": "./starknet_api_openrpc.json#/components/schemas/FELT" }
+                        }
+                    }
+                }"##,
+            )
+            .unwrap();
+        assert_eq!(
+            documents.unused_schemas(),
+            vec![("starknet_trace_api_openrpc.json".to_string(), "UNUSED".to_string())]
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use std::{io, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::{
+    body::Incoming, header, server::conn::http1, service::service_fn, Method as HttpMethod,
+    Request as HttpRequest, Response as HttpResponse, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use prost::bytes::{Buf, BufMut};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tonic::{
+    client::{Grpc, GrpcService},
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    codegen::{http::uri::PathAndQuery, Body, Bytes, StdError},
+    Request, Status,
+};
+
+use crate::transcoder::{RpcError, Transcoder};
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method doesn't exist or has no rpc
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The params don't match those of the method
+pub const INVALID_PARAMS: i64 = -32602;
+/// The gRPC backend failed or answered with an invalid message
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Size of the largest request body read unless `with_max_body_size` sets another
+pub const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Callback `serve` reports the connections it fails to accept to
+type AcceptErrorHandler = Arc<dyn Fn(&io::Error) + Send + Sync>;
+
+/// Serves JSON-RPC over HTTP by calling the generated gRPC services of a backend.
+///
+/// Params are checked against the schemas of the method, sent to the rpc generated for it, and
+/// its response is written back in the JSON of the spec. Errors set on the response are returned
+/// with their spec code, while failures of the call itself get the codes JSON-RPC reserves.
+#[derive(Clone)]
+pub struct JsonRpcServer<T> {
+    backend: Grpc<T>,
+    transcoder: Arc<Transcoder>,
+    max_body_size: usize,
+    on_accept_error: Option<AcceptErrorHandler>,
+}
+
+impl<T> JsonRpcServer<T> {
+    /// Server for the methods `transcoder` converts, calling their rpcs on `backend`, e.g. a
+    /// `tonic::transport::Channel`
+    pub fn new(backend: T, transcoder: Transcoder) -> Self {
+        Self {
+            backend: Grpc::new(backend),
+            transcoder: Arc::new(transcoder),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            on_accept_error: None,
+        }
+    }
+
+    /// Answers requests whose body is larger than `max_body_size` bytes with
+    /// `413 Payload Too Large` instead of reading them
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Calls `on_accept_error` with the errors of the connections `serve` fails to accept, which
+    /// are otherwise dropped
+    pub fn with_accept_error_handler(
+        mut self,
+        on_accept_error: impl Fn(&io::Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_accept_error = Some(Arc::new(on_accept_error));
+        self
+    }
+
+    pub fn transcoder(&self) -> &Transcoder {
+        &self.transcoder
+    }
+}
+
+impl<T> JsonRpcServer<T>
+where
+    T: GrpcService<tonic::body::Body> + Clone + Send + Sync + 'static,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    T::Error: Into<StdError>,
+    T::Future: Send,
+{
+    /// Accepts connections on `listener`, answering the JSON-RPC requests POSTed on any path
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            // Failing to accept, e.g. when out of file descriptors, only loses that connection
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    if let Some(on_accept_error) = &self.on_accept_error {
+                        on_accept_error(&err);
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let server = self.clone();
+            let service = service_fn(move |request| {
+                let server = server.clone();
+                async move { server.handle_http(request).await }
+            });
+            tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        }
+    }
+
+    /// Response to the JSON-RPC request or batch `body`, `None` if it only holds notifications
+    pub async fn handle(&self, body: &[u8]) -> Option<Value> {
+        let request = match serde_json::from_slice::<Value>(body) {
+            Ok(request) => request,
+            Err(err) => return Some(error_response(Value::Null, PARSE_ERROR, err.to_string())),
+        };
+        match request {
+            Value::Array(requests) if requests.is_empty() => Some(error_response(
+                Value::Null,
+                INVALID_REQUEST,
+                "empty batch",
+            )),
+            Value::Array(requests) => {
+                let mut responses = vec![];
+                for request in requests {
+                    responses.extend(self.handle_request(request).await);
+                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            request => self.handle_request(request).await,
+        }
+    }
+
+    /// Response to a single JSON-RPC request, `None` for notifications
+    pub async fn handle_request(&self, request: Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let (Some(method), Some("2.0")) = (method, request["jsonrpc"].as_str()) else {
+            let id = id.unwrap_or_default();
+            return Some(error_response(id, INVALID_REQUEST, "invalid request object"));
+        };
+        let params = request.get("params").cloned().unwrap_or_default();
+        let outcome = self.call(method, &params).await;
+        let id = id?;
+        Some(match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        })
+    }
+
+    /// Result of calling `method` with `params` on the backend
+    pub async fn call(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        let transcoder = self.transcoder();
+        let (Some(rpc), Some(path)) = (transcoder.rpc(method), transcoder.rpc_path(method)) else {
+            return Err(rpc_error(METHOD_NOT_FOUND, format!("{} is not served", method)));
+        };
+        if rpc.client_streaming || transcoder.notifications(method).next().is_some() {
+            let message = format!("{} can't be called over HTTP", method);
+            return Err(rpc_error(METHOD_NOT_FOUND, message));
+        }
+        // Missing required params and unknown named ones are refused before reaching the backend
+        let params = transcoder
+            .params_by_name(method, params)
+            .map_err(|err| rpc_error(INVALID_PARAMS, format!("{:#}", err)))?;
+        let bytes = transcoder
+            .encode_params(method, &Value::Object(params))
+            .map_err(|err| rpc_error(INVALID_PARAMS, format!("{:#}", err)))?;
+
+        let path = PathAndQuery::try_from(path)
+            .map_err(|err| rpc_error(INTERNAL_ERROR, err.to_string()))?;
+        let mut backend = self.backend.clone();
+        backend.ready().await.map_err(|err| {
+            rpc_error(INTERNAL_ERROR, format!("the backend is unavailable: {}", err.into()))
+        })?;
+        let response = match rpc.server_streaming {
+            true => {
+                let mut stream = backend
+                    .server_streaming(Request::new(bytes), path, RawCodec)
+                    .await
+                    .map_err(status_error)?
+                    .into_inner();
+                stream
+                    .message()
+                    .await
+                    .map_err(status_error)?
+                    .ok_or_else(|| rpc_error(INTERNAL_ERROR, "the backend sent no response"))?
+            }
+            false => backend
+                .unary(Request::new(bytes), path, RawCodec)
+                .await
+                .map_err(status_error)?
+                .into_inner(),
+        };
+
+        let result = transcoder.decode_result(method, &response);
+        result.map_err(|err| match err.downcast::<RpcError>() {
+            Ok(mut error) => {
+                // Errors the method declares keep the message of the spec if the backend has none
+                if let Some(spec_error) = transcoder.spec_error(method, error.code) {
+                    if error.message.is_empty() {
+                        error.message.clone_from(&spec_error.message);
+                    }
+                }
+                error
+            }
+            Err(err) => rpc_error(INTERNAL_ERROR, format!("invalid response: {:#}", err)),
+        })
+    }
+
+    async fn handle_http(
+        &self,
+        request: HttpRequest<Incoming>,
+    ) -> Result<HttpResponse<Full<Bytes>>, StdError> {
+        if request.method() != HttpMethod::POST {
+            return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        let body = match Limited::new(request.into_body(), self.max_body_size).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(err) if err.is::<LengthLimitError>() => {
+                return Ok(empty_response(StatusCode::PAYLOAD_TOO_LARGE));
+            }
+            Err(err) => return Err(err),
+        };
+        let Some(response) = self.handle(&body).await else {
+            return Ok(empty_response(StatusCode::NO_CONTENT));
+        };
+        let mut response = HttpResponse::new(Full::new(Bytes::from(response.to_string())));
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+        Ok(response)
+    }
+}
+
+fn empty_response(status: StatusCode) -> HttpResponse<Full<Bytes>> {
+    let mut response = HttpResponse::new(Full::default());
+    *response.status_mut() = status;
+    response
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> RpcError {
+    RpcError {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn status_error(status: Status) -> RpcError {
+    let message = format!("the backend failed with {:?}: {}", status.code(), status.message());
+    rpc_error(INTERNAL_ERROR, message)
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": rpc_error(code, message) })
+}
+
+/// Passes the wire bytes of messages through, the transcoder handling their encoding
+#[derive(Debug, Clone, Copy, Default)]
+struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Vec<u8>;
+    type Decode = Vec<u8>;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RawCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        RawCodec
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Vec<u8>>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining()).to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::Mutex,
+        task::{Context, Poll},
+        vec,
+    };
+
+    use futures_util::stream;
+    use http_body_util::StreamBody;
+    use hyper::body::Frame;
+    use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+    use prost::Message;
+    use tonic::codegen::{http, BoxFuture, Service};
+
+    use super::*;
+    use crate::{proto_gen::ProtoConfig, spec::Specification};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetStorageAtRequest {
+        #[prost(string, tag = "1")]
+        contract_address: String,
+        #[prost(string, tag = "2")]
+        key: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetStorageAtResponse {
+        #[prost(string, tag = "1")]
+        result: String,
+        #[prost(message, optional, tag = "2")]
+        error: Option<Error>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Error {
+        #[prost(int32, tag = "1")]
+        code: i32,
+        #[prost(string, tag = "2")]
+        message: String,
+        #[prost(string, optional, tag = "3")]
+        data: Option<String>,
+    }
+
+    type MockBody = StreamBody<stream::Iter<vec::IntoIter<Result<Frame<Bytes>, Infallible>>>>;
+
+    /// gRPC backend answering every rpc with the response `reply` gives for its request, along
+    /// with the paths it was called on
+    #[derive(Clone)]
+    struct MockBackend {
+        reply: fn(GetStorageAtRequest) -> GetStorageAtResponse,
+        paths: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Service<http::Request<tonic::body::Body>> for MockBackend {
+        type Response = http::Response<MockBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<tonic::body::Body>) -> Self::Future {
+            let backend = self.clone();
+            Box::pin(async move {
+                backend.paths.lock().unwrap().push(request.uri().path().to_owned());
+                // Messages are framed by a compression flag and their length
+                let body = request.into_body().collect().await.unwrap().to_bytes();
+                let request = GetStorageAtRequest::decode(&body[5..]).unwrap();
+                let message = (backend.reply)(request).encode_to_vec();
+                let mut frame = vec![0];
+                frame.extend((message.len() as u32).to_be_bytes());
+                frame.extend(message);
+
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+                let frames = vec![
+                    Ok(Frame::data(Bytes::from(frame))),
+                    Ok(Frame::trailers(trailers)),
+                ];
+                let response = http::Response::builder()
+                    .header(header::CONTENT_TYPE, "application/grpc")
+                    .body(StreamBody::new(stream::iter(frames)))
+                    .unwrap();
+                Ok(response)
+            })
+        }
+    }
+
+    fn create_test_server(
+        reply: fn(GetStorageAtRequest) -> GetStorageAtResponse,
+    ) -> (JsonRpcServer<MockBackend>, Arc<Mutex<Vec<String>>>) {
+        let specs: Specification = serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [
+                    {
+                        "name": "starknet_getStorageAt",
+                        "summary": "Storage value",
+                        "params": [
+                            {
+                                "name": "contract_address",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            { "name": "key", "required": true, "schema": { "type": "string" } }
+                        ],
+                        "result": { "name": "result", "schema": { "type": "string" } },
+                        "errors": [{ "$ref": "#/components/errors/CONTRACT_NOT_FOUND" }]
+                    }
+                ],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {},
+                    "errors": {
+                        "CONTRACT_NOT_FOUND": { "code": 20, "message": "Contract not found" }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+        let transcoder = Transcoder::for_spec(&specs, &ProtoConfig::new("v0_8_1")).unwrap();
+        let paths = Arc::new(Mutex::new(vec![]));
+        let backend = MockBackend {
+            reply,
+            paths: paths.clone(),
+        };
+        (JsonRpcServer::new(backend, transcoder), paths)
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_served_by_the_backend() {
+        let (server, paths) = create_test_server(|request| GetStorageAtResponse {
+            result: format!("{}{}", request.contract_address, &request.key[2..]),
+            error: None,
+        });
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_getStorageAt",
+            "params": ["0x1", "0x2"],
+        });
+        let response = server.handle(request.to_string().as_bytes()).await.unwrap();
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 1, "result": "0x12" }));
+        assert_eq!(
+            *paths.lock().unwrap(),
+            vec!["/starknet.v0_8_1.main.StarknetMainService/GetStorageAt"]
+        );
+
+        // Notifications get no response
+        let mut notification = request.clone();
+        notification.as_object_mut().unwrap().remove("id");
+        let batch = json!([request, notification]);
+        let response = server.handle(batch.to_string().as_bytes()).await.unwrap();
+        assert_eq!(response, json!([{ "jsonrpc": "2.0", "id": 1, "result": "0x12" }]));
+        assert_eq!(server.handle(notification.to_string().as_bytes()).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_errors_have_spec_codes() {
+        let (server, paths) = create_test_server(|_| GetStorageAtResponse {
+            result: String::new(),
+            error: Some(Error {
+                code: 20,
+                message: String::new(),
+                data: None,
+            }),
+        });
+        let method = "starknet_getStorageAt";
+
+        // The message of the spec is used if the backend sets none
+        let params = json!({ "contract_address": "0x1", "key": "0x2" });
+        let error = server.call(method, &params).await.unwrap_err();
+        assert_eq!((error.code, error.message.as_str()), (20, "Contract not found"));
+
+        let params = json!({ "contract_address": "0x1", "key": 2 });
+        assert_eq!(server.call(method, &params).await.unwrap_err().code, INVALID_PARAMS);
+        let error = server.call("starknet_unknown", &json!([])).await.unwrap_err();
+        assert_eq!(error.code, METHOD_NOT_FOUND);
+        assert_eq!(paths.lock().unwrap().len(), 1);
+
+        let response = server.handle(b"{").await.unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        let response = server.handle(br#"{ "id": 1, "method": 1 }"#).await.unwrap();
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+        assert_eq!(response["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_params_are_checked_against_the_method() {
+        let (server, paths) = create_test_server(|_| unreachable!("params are invalid"));
+        let method = "starknet_getStorageAt";
+
+        for (params, message) in [
+            (
+                json!({ "contract_address": "0x1" }),
+                "missing required param `key` of starknet_getStorageAt",
+            ),
+            (json!(["0x1"]), "missing required param `key` of starknet_getStorageAt"),
+            (
+                json!({ "contract_address": "0x1", "key": "0x2", "block_id": "latest" }),
+                "`block_id` is not a param of starknet_getStorageAt",
+            ),
+        ] {
+            let error = server.call(method, &params).await.unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (INVALID_PARAMS, message));
+        }
+        assert!(paths.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_large_bodies_are_refused() {
+        let (server, paths) = create_test_server(|request| GetStorageAtResponse {
+            result: request.key,
+            error: None,
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(server.with_max_body_size(256).serve(listener));
+
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        let post = |key: String| {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "starknet_getStorageAt",
+                "params": ["0x1", key],
+            });
+            let body = Full::new(Bytes::from(request.to_string()));
+            HttpRequest::post(format!("http://{}", address)).body(body).unwrap()
+        };
+        let response = client.request(post("0x2".to_owned())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = client.request(post(format!("0x{}", "0".repeat(256)))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(paths.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,904 @@
+//! Spec documents generation starts from: the versions bundled with the crate and their
+//! profiles, or documents read at runtime, along with extension documents.
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt,
+    ops::{Bound, RangeBounds},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics, UsageError},
+    refs::{self, escape_pointer_segment, same_definition, DocumentSet},
+    spec::{Components, Extension, ParseMode, Schema, Specification},
+};
+
+/// Spec documents of a version along with the options tailoring what is generated from them
+#[derive(Debug, Clone)]
+pub struct GenerationProfile {
+    pub version: SpecVersion,
+    /// Options of `src/profiles/<version>.json`, see `profile::ProfileOptions`
+    pub raw_options: Cow<'static, str>,
+    pub raw_specs: RawSpecs,
+}
+
+/// Bundled spec versions, ordered from the oldest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpecVersion {
+    V0_1_0,
+    V0_2_1,
+    V0_3_0,
+    V0_4_0,
+    V0_5_1,
+    V0_6_0,
+    V0_7_1,
+    V0_8_1,
+}
+
+/// Versions a run generates from: one, a range like `0.6.0..=0.8.1` or `0.6.0..` with the
+/// syntax of Rust ranges, or `all` of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecVersions {
+    One(SpecVersion),
+    Range(Bound<SpecVersion>, Bound<SpecVersion>),
+    All,
+}
+
+/// Core documents of a spec version
+#[derive(Debug, Clone)]
+pub struct RawSpecs {
+    pub main: Cow<'static, str>,
+    pub write: Cow<'static, str>,
+    pub trace: Cow<'static, str>,
+    pub ws: Option<Cow<'static, str>>,
+}
+
+/// Extension OpenRPC document supplied at runtime (e.g. `--extension katana_dev.json`)
+#[derive(Debug, Clone)]
+pub struct RawExtension {
+    pub name: String,
+    pub document: String,
+    pub content: String,
+}
+
+// Document names `$ref`s use to point across the core specification files
+pub const MAIN_DOCUMENT: &str = "starknet_api_openrpc.json";
+pub const WRITE_DOCUMENT: &str = "starknet_write_api.json";
+pub const TRACE_DOCUMENT: &str = "starknet_trace_api_openrpc.json";
+pub const WS_DOCUMENT: &str = "starknet_ws_api.json";
+
+/// Known mistakes of the upstream documents, corrected before they are parsed so that every
+/// bundled version generates. The vendored documents stay byte-identical to upstream: a fixup
+/// only applies where a document still has the mistake, and is reported as a `spec-fixup`
+/// warning when it does.
+const SPEC_FIXUPS: &[SpecFixup] = &[
+    SpecFixup {
+        document: MAIN_DOCUMENT,
+        pointer: "/components/schemas/CONTRACT_CLASS/oneOf/1",
+        correction: Correction::Ref {
+            from: "#/components/schemas/LEGACY_CONTRACT_CLASS",
+            to: "#/components/schemas/DEPRECATED_CONTRACT_CLASS",
+        },
+        reason: "LEGACY_CONTRACT_CLASS isn't defined, the class of Cairo 0 contracts is \
+                 DEPRECATED_CONTRACT_CLASS",
+    },
+    SpecFixup {
+        document: MAIN_DOCUMENT,
+        pointer: "/methods/24/result/schema",
+        correction: Correction::Ref {
+            from: "#/components/errors/STORAGE_PROOF",
+            to: "#/components/schemas/STORAGE_PROOF",
+        },
+        reason: "the result of starknet_getStorageProof is the STORAGE_PROOF schema, not an error",
+    },
+    SpecFixup {
+        document: TRACE_DOCUMENT,
+        pointer: "/methods/1/result/schema/items",
+        correction: Correction::Unwrap { key: "schema" },
+        reason: "the items of the result of starknet_simulateTransaction are wrapped in a \
+                 `schema` key",
+    },
+];
+
+/// Correction of a mistake at `pointer` in a core document
+struct SpecFixup {
+    document: &'static str,
+    pointer: &'static str,
+    correction: Correction,
+    reason: &'static str,
+}
+
+enum Correction {
+    /// Points the `$ref` of the value to `to` instead of `from`, a location of the same
+    /// document that doesn't exist
+    Ref {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Replaces the value, an object with `key` as its only key, with the value of `key`
+    Unwrap { key: &'static str },
+}
+
+impl SpecFixup {
+    /// Corrects the mistake in `document`, returning whether it has it
+    fn apply(&self, document: &mut Value) -> bool {
+        match self.correction {
+            Correction::Ref { from, to } => {
+                if document.pointer(&from[1..]).is_some() {
+                    return false;
+                }
+                let value = document.pointer_mut(self.pointer);
+                match value.and_then(|value| value.get_mut("$ref")) {
+                    Some(reference) if reference == from => {
+                        *reference = Value::from(to);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Correction::Unwrap { key } => {
+                let Some(value) = document.pointer_mut(self.pointer) else {
+                    return false;
+                };
+                match value {
+                    Value::Object(wrapper) if wrapper.len() == 1 => match wrapper.remove(key) {
+                        Some(wrapped) => {
+                            *value = wrapped;
+                            true
+                        }
+                        None => false,
+                    },
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "0.1.0" | "v0.1.0" => Self::V0_1_0,
+            "0.2.1" | "v0.2.1" => Self::V0_2_1,
+            "0.3.0" | "v0.3.0" => Self::V0_3_0,
+            "0.4.0" | "v0.4.0" => Self::V0_4_0,
+            "0.5.1" | "v0.5.1" => Self::V0_5_1,
+            "0.6.0" | "v0.6.0" => Self::V0_6_0,
+            "0.7.1" | "v0.7.1" => Self::V0_7_1,
+            "0.8.1" | "v0.8.1" => Self::V0_8_1,
+            _ => anyhow::bail!("unknown spec version: {}", s),
+        })
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("every version has a value");
+        f.write_str(value.get_name())
+    }
+}
+
+impl ValueEnum for SpecVersion {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::V0_1_0,
+            Self::V0_2_1,
+            Self::V0_3_0,
+            Self::V0_4_0,
+            Self::V0_5_1,
+            Self::V0_6_0,
+            Self::V0_7_1,
+            Self::V0_8_1,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        use clap::builder::PossibleValue;
+
+        match self {
+            Self::V0_1_0 => Some(PossibleValue::new("0.1.0").alias("v0.1.0")),
+            Self::V0_2_1 => Some(PossibleValue::new("0.2.1").alias("v0.2.1")),
+            Self::V0_3_0 => Some(PossibleValue::new("0.3.0").alias("v0.3.0")),
+            Self::V0_4_0 => Some(PossibleValue::new("0.4.0").alias("v0.4.0")),
+            Self::V0_5_1 => Some(PossibleValue::new("0.5.1").alias("v0.5.1")),
+            Self::V0_6_0 => Some(PossibleValue::new("0.6.0").alias("v0.6.0")),
+            Self::V0_7_1 => Some(PossibleValue::new("0.7.1").alias("v0.7.1")),
+            Self::V0_8_1 => Some(PossibleValue::new("0.8.1").alias("v0.8.1")),
+        }
+    }
+}
+
+impl SpecVersion {
+    /// Version as packages and output directories name it, e.g. `v0_8_1`
+    pub fn to_version_string(self) -> String {
+        match self {
+            SpecVersion::V0_1_0 => "v0_1_0".to_string(),
+            SpecVersion::V0_2_1 => "v0_2_1".to_string(),
+            SpecVersion::V0_3_0 => "v0_3_0".to_string(),
+            SpecVersion::V0_4_0 => "v0_4_0".to_string(),
+            SpecVersion::V0_5_1 => "v0_5_1".to_string(),
+            SpecVersion::V0_6_0 => "v0_6_0".to_string(),
+            SpecVersion::V0_7_1 => "v0_7_1".to_string(),
+            SpecVersion::V0_8_1 => "v0_8_1".to_string(),
+        }
+    }
+}
+
+impl FromStr for SpecVersions {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "all" {
+            return Ok(Self::All);
+        }
+        let Some((start, end)) = s.split_once("..") else {
+            return Ok(Self::One(parse_version(s)?));
+        };
+        let start = match start {
+            "" => Bound::Unbounded,
+            start => Bound::Included(parse_version(start)?),
+        };
+        let end = match end.strip_prefix('=') {
+            Some(end) => Bound::Included(parse_version(end)?),
+            None if end.is_empty() => Bound::Unbounded,
+            None => Bound::Excluded(parse_version(end)?),
+        };
+        let versions = Self::Range(start, end);
+        if versions.versions().is_empty() {
+            anyhow::bail!("no spec version is in {}", s);
+        }
+        Ok(versions)
+    }
+}
+
+/// Parses a version of `SpecVersions`, listing the known ones if it isn't one of them
+fn parse_version(s: &str) -> Result<SpecVersion> {
+    s.parse().map_err(|_| {
+        let known = SpecVersion::value_variants()
+            .iter()
+            .filter_map(|version| version.to_possible_value())
+            .map(|value| value.get_name().to_owned())
+            .collect::<Vec<_>>();
+        anyhow::anyhow!(
+            "unknown spec version: {} (expected one of {}, a range of them or all)",
+            s,
+            known.join(", ")
+        )
+    })
+}
+
+impl SpecVersions {
+    /// Selected versions, oldest first
+    pub fn versions(&self) -> Vec<SpecVersion> {
+        let versions = SpecVersion::value_variants().iter().copied();
+        match self {
+            Self::One(version) => vec![*version],
+            Self::Range(start, end) => {
+                versions.filter(|version| (*start, *end).contains(version)).collect()
+            }
+            Self::All => versions.collect(),
+        }
+    }
+
+    /// Whether more than a single version can be selected, even if a range selects only one
+    pub fn is_multiple(&self) -> bool {
+        !matches!(self, Self::One(_))
+    }
+}
+
+impl RawSpecs {
+    /// Core documents of the profile with the names `$ref`s use for them, in merge order, with
+    /// the mistakes of `SPEC_FIXUPS` corrected.
+    pub fn documents(&self) -> Vec<(&'static str, Cow<'_, str>)> {
+        self.corrected_documents()
+            .into_iter()
+            .map(|(document, content, _)| (document, content))
+            .collect()
+    }
+
+    /// Core documents along with the fixups applied to each of them
+    fn corrected_documents(&self) -> Vec<(&'static str, Cow<'_, str>, Vec<&'static SpecFixup>)> {
+        let mut documents = vec![
+            (MAIN_DOCUMENT, self.main.as_ref()),
+            (WRITE_DOCUMENT, self.write.as_ref()),
+            (TRACE_DOCUMENT, self.trace.as_ref()),
+        ];
+        if let Some(ws) = &self.ws {
+            documents.push((WS_DOCUMENT, ws));
+        }
+
+        documents
+            .into_iter()
+            .map(|(document, content)| {
+                let fixups = SPEC_FIXUPS.iter().filter(|fixup| fixup.document == document);
+                // Invalid JSON is reported when parsing the document
+                let Ok(mut value) = serde_json::from_str::<Value>(content) else {
+                    return (document, Cow::Borrowed(content), vec![]);
+                };
+                let applied = fixups.filter(|fixup| fixup.apply(&mut value)).collect::<Vec<_>>();
+                match applied.is_empty() {
+                    true => (document, Cow::Borrowed(content), applied),
+                    false => (document, Cow::Owned(value.to_string()), applied),
+                }
+            })
+            .collect()
+    }
+
+    /// Parses and merges all documents of the profile along with `extensions`.
+    ///
+    /// Problems are reported to `diagnostics` across all documents; `None` is returned if any of
+    /// them is an error. In lenient mode, keywords the spec types don't model are reported as
+    /// warnings and a missing `required` means nothing is required; in strict mode both are
+    /// errors.
+    pub fn parse_full(
+        &self,
+        extensions: &[RawExtension],
+        mode: ParseMode,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<Specification> {
+        let errors_before = diagnostics.error_count();
+
+        let mut sources = vec![];
+        for (document, content, fixups) in self.corrected_documents() {
+            for fixup in fixups {
+                let message = format!("corrected upstream mistake: {}", fixup.reason);
+                let diagnostic = Diagnostic::warning(message).code("spec-fixup");
+                diagnostics.push(diagnostic.at(document, &format!("#{}", fixup.pointer)));
+            }
+            sources.push((document, content));
+        }
+
+        let mut documents = DocumentSet::new();
+        for (document, content) in sources
+            .iter()
+            .map(|(document, content)| (*document, content.as_ref()))
+            .chain(extensions.iter().map(|e| (e.document.as_str(), e.content.as_str())))
+        {
+            if documents.document_names().any(|name| name == document) {
+                let diagnostic =
+                    Diagnostic::error("duplicate extension document").code("duplicate-extension");
+                diagnostics.push(diagnostic.at(document, "#"));
+            } else {
+                // Invalid JSON is reported when parsing the document below
+                let _ = documents.add(document, content);
+            }
+        }
+
+        let mut parse = |document: &str, content: &str| {
+            let mut specs = Specification::from_document(document, content, diagnostics)?;
+
+            // Strict mode rejects what parsing only accepts by defaulting it
+            if mode == ParseMode::Strict {
+                let raw = serde_json::from_str(content).unwrap_or_default();
+                for keyword in specs.defaulted_keywords(document, &raw) {
+                    let message = format!("missing keyword `{}`", keyword.keyword);
+                    diagnostics.push(
+                        Diagnostic::error(message)
+                            .code("missing-keyword")
+                            .at(&keyword.document, &keyword.path),
+                    );
+                }
+            }
+
+            documents.canonicalize(document, &mut specs, diagnostics);
+
+            for keyword in specs.unknown_keywords(document) {
+                let message = format!("unknown keyword `{}`", keyword.keyword);
+                let diagnostic = match mode {
+                    ParseMode::Strict => Diagnostic::error(message),
+                    ParseMode::Lenient => Diagnostic::warning(message),
+                };
+                diagnostics.push(
+                    diagnostic
+                        .code("unknown-keyword")
+                        .at(&keyword.document, &keyword.path),
+                );
+            }
+
+            Some(specs)
+        };
+
+        let core_specs = sources
+            .iter()
+            .map(|(document, content)| parse(document, content).map(|specs| (*document, specs)))
+            .collect::<Vec<_>>();
+        let extension_specs = extensions
+            .iter()
+            .map(|extension| {
+                parse(&extension.document, &extension.content).map(|specs| (extension, specs))
+            })
+            .collect::<Vec<_>>();
+
+        let core_specs = core_specs.into_iter().flatten().collect::<Vec<_>>();
+
+        let mut method_names = HashSet::new();
+        for (document, specs) in core_specs.iter() {
+            for (i, method) in specs.methods.iter().enumerate() {
+                if !method_names.insert(method.name.as_str()) {
+                    diagnostics.push(
+                        Diagnostic::error(format!("duplicate method: {}", method.name))
+                            .code("duplicate-method")
+                            .at(document, &format!("#/methods/{}", i)),
+                    );
+                }
+            }
+        }
+
+        let mut core_specs = core_specs.into_iter();
+        let (_, mut specs) = core_specs.next()?;
+
+        for (document, mut additional_specs) in core_specs {
+            specs.methods.append(&mut additional_specs.methods);
+            merge_components(
+                document,
+                &mut specs.components,
+                &additional_specs.components,
+                diagnostics,
+            );
+        }
+
+        for (extension, mut extension_specs) in extension_specs.into_iter().flatten() {
+            if specs.extensions.iter().any(|e| e.name == extension.name) {
+                diagnostics.push(
+                    Diagnostic::error(format!("duplicate extension name: {}", extension.name))
+                        .code("duplicate-extension")
+                        .at(&extension.document, "#"),
+                );
+                continue;
+            }
+
+            for (i, method) in extension_specs.methods.iter().enumerate() {
+                if specs.methods.iter().any(|m| m.name == method.name)
+                    || specs
+                        .extensions
+                        .iter()
+                        .any(|e| e.methods.iter().any(|m| m.name == method.name))
+                {
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "extension {} redefines method: {}",
+                            extension.name, method.name
+                        ))
+                        .code("duplicate-method")
+                        .at(&extension.document, &format!("#/methods/{}", i)),
+                    );
+                }
+            }
+
+            merge_components(
+                &extension.document,
+                &mut specs.components,
+                &extension_specs.components,
+                diagnostics,
+            );
+            specs.extensions.push(Extension {
+                name: extension.name.clone(),
+                methods: std::mem::take(&mut extension_specs.methods),
+            });
+        }
+
+        if diagnostics.error_count() > errors_before {
+            return None;
+        }
+
+        Some(specs)
+    }
+}
+
+/// Merges the components of `document` into `components`, keeping the first definition of each
+/// entry.
+fn merge_components(
+    document: &str,
+    components: &mut Components,
+    additional: &Components,
+    diagnostics: &mut Diagnostics,
+) {
+    for (key, value) in additional.schemas.iter() {
+        match components.schemas.entry(key.to_owned()) {
+            indexmap::map::Entry::Occupied(entry) => match &value {
+                Schema::Ref(_) => {}
+                _ => {
+                    let existing = serde_json::to_value(entry.get());
+                    let conflicting = match (serde_json::to_value(value), existing) {
+                        (Ok(value), Ok(existing)) => !same_definition(&value, &existing),
+                        _ => true,
+                    };
+                    if conflicting {
+                        let path = format!("#/components/schemas/{}", escape_pointer_segment(key));
+                        diagnostics.push(
+                            Diagnostic::error(format!(
+                                "duplicate entries must be ref or identical: {}",
+                                key
+                            ))
+                            .code("conflicting-schema")
+                            .at(document, &path),
+                        );
+                    }
+                }
+            },
+            indexmap::map::Entry::Vacant(entry) => {
+                entry.insert(value.to_owned());
+            }
+        }
+    }
+
+    for (key, value) in additional.errors.iter() {
+        if let indexmap::map::Entry::Vacant(entry) = components.errors.entry(key.to_owned()) {
+            entry.insert(value.to_owned());
+        }
+    }
+}
+
+impl RawExtension {
+    /// Loads an extension document from a `[NAME=]PATH` argument.
+    ///
+    /// Without an explicit name, the file stem up to its first underscore is used, so
+    /// `katana_dev.json` becomes the `katana` extension.
+    pub fn load(arg: &str) -> Result<Self> {
+        let (name, path) = match arg.split_once('=') {
+            Some((name, path)) => (name.to_owned(), path),
+            None => {
+                let stem = Path::new(arg)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .ok_or_else(|| UsageError(format!("invalid extension path: {}", arg)))?;
+                let name = stem.split('_').next().unwrap_or(stem);
+                (name.to_lowercase(), arg)
+            }
+        };
+
+        let valid_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            anyhow::bail!(UsageError(format!("invalid extension name: {}", name)));
+        }
+        if matches!(name.as_str(), "main" | "write" | "trace" | "ws" | "common") {
+            anyhow::bail!(UsageError(format!("extension name is reserved: {}", name)));
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read extension {}", path))?;
+
+        let document = refs::document_name(path).to_owned();
+
+        Ok(Self {
+            name,
+            document,
+            content,
+        })
+    }
+}
+
+/// Profiles of the spec versions bundled with the crate, oldest first
+pub fn bundled_profiles() -> [GenerationProfile; 8] {
+    [
+        GenerationProfile {
+            version: SpecVersion::V0_1_0,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.1.0.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.1.0/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.1.0/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.1.0/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_2_1,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.2.1.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.2.1/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.2.1/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.2.1/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_3_0,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.3.0.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.3.0/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.3.0/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.3.0/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_4_0,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.4.0.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.4.0/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.4.0/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.4.0/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_5_1,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.5.1.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.5.1/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.5.1/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.5.1/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_6_0,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.6.0.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.6.0/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.6.0/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.6.0/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_7_1,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.7.1.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.7.1/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.7.1/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.7.1/starknet_trace_api_openrpc.json")),
+                ws: None,
+            },
+        },
+        GenerationProfile {
+            version: SpecVersion::V0_8_1,
+            raw_options: Cow::Borrowed(include_str!("./profiles/0.8.1.json")),
+            raw_specs: RawSpecs {
+                main: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_api_openrpc.json")),
+                write: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_write_api.json")),
+                trace: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_trace_api_openrpc.json")),
+                ws: Some(Cow::Borrowed(include_str!("./specs/0.8.1/starknet_ws_api.json"))),
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+
+    const KATANA_EXTENSION: &str = r##"{
+        "openrpc": "1.0.0-rc1",
+        "info": { "version": "0.8.1", "title": "Katana Dev API", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "dev_predeployedAccounts",
+                "summary": "Returns the predeployed accounts",
+                "params": [],
+                "result": {
+                    "name": "result",
+                    "schema": {
+                        "type": "array",
+                        "items": { "$ref": "#/components/schemas/DEV_ACCOUNT" }
+                    }
+                }
+            }
+        ],
+        "components": {
+            "contentDescriptors": {},
+            "schemas": {
+                "FELT": { "$ref": "./starknet_api_openrpc.json#/components/schemas/FELT" },
+                "DEV_ACCOUNT": {
+                    "type": "object",
+                    "properties": {
+                        "address": { "$ref": "#/components/schemas/FELT" }
+                    },
+                    "required": ["address"]
+                }
+            },
+            "errors": {}
+        }
+    }"##;
+
+    /// Document the parser rejected before lenient mode: a param and an object schema without
+    /// `required`, and a union also typed as an object
+    const LOOSE_MAIN: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.9.0", "title": "Loose", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "starknet_getFoo",
+                "summary": "Returns a foo",
+                "params": [{ "name": "foo_id", "schema": { "type": "string" } }],
+                "result": { "name": "result", "schema": { "$ref": "#/components/schemas/FOO" } }
+            }
+        ],
+        "components": {
+            "contentDescriptors": {},
+            "schemas": {
+                "FOO": { "type": "object", "properties": { "bar": { "type": "string" } } },
+                "FOO_OR_BAR": {
+                    "oneOf": [{ "$ref": "#/components/schemas/FOO" }],
+                    "type": "object",
+                    "properties": {}
+                }
+            },
+            "errors": {}
+        }
+    }"##;
+
+    const EMPTY_DOCUMENT: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.9.0", "title": "Empty", "license": {} },
+        "servers": [],
+        "methods": [],
+        "components": { "contentDescriptors": {}, "schemas": {}, "errors": {} }
+    }"##;
+
+    fn raw_specs_0_8_1() -> RawSpecs {
+        RawSpecs {
+            main: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_api_openrpc.json")),
+            write: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_write_api.json")),
+            trace: Cow::Borrowed(include_str!("./specs/0.8.1/starknet_trace_api_openrpc.json")),
+            ws: Some(Cow::Borrowed(include_str!("./specs/0.8.1/starknet_ws_api.json"))),
+        }
+    }
+
+    #[test]
+    fn test_spec_versions() {
+        let versions = |s: &str| s.parse::<SpecVersions>().unwrap().versions();
+        assert_eq!(versions("0.8.1"), [SpecVersion::V0_8_1]);
+        assert_eq!(
+            versions("0.6.0..=0.8.1"),
+            [SpecVersion::V0_6_0, SpecVersion::V0_7_1, SpecVersion::V0_8_1]
+        );
+        assert_eq!(versions("v0.6.0..0.8.1"), [SpecVersion::V0_6_0, SpecVersion::V0_7_1]);
+        assert_eq!(versions("0.7.1.."), [SpecVersion::V0_7_1, SpecVersion::V0_8_1]);
+        assert_eq!(versions("..0.2.1"), [SpecVersion::V0_1_0]);
+        assert_eq!(versions("all").len(), 8);
+        assert!("0.8.1..0.6.0".parse::<SpecVersions>().is_err());
+        assert!("0.8.2..".parse::<SpecVersions>().is_err());
+    }
+
+    #[test]
+    fn test_parse_full_with_extension() {
+        let extension = RawExtension {
+            name: "katana".to_string(),
+            document: "katana_dev.json".to_string(),
+            content: KATANA_EXTENSION.to_string(),
+        };
+
+        let mut diagnostics = Diagnostics::new();
+        let specs = raw_specs_0_8_1()
+            .parse_full(&[extension], ParseMode::Strict, &mut diagnostics)
+            .unwrap();
+        // Only the mistake of the upstream 0.8.1 documents is reported
+        let codes = diagnostics.iter().map(|d| d.code.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["spec-fixup"]);
+
+        assert_eq!(specs.extensions.len(), 1);
+        assert_eq!(specs.extensions[0].name, "katana");
+        assert_eq!(specs.extensions[0].methods[0].name, "dev_predeployedAccounts");
+        assert!(!specs.methods.iter().any(|m| m.name == "dev_predeployedAccounts"));
+
+        // Extension schemas are merged while core definitions win over aliases
+        assert!(specs.components.schemas.contains_key("DEV_ACCOUNT"));
+        assert!(!matches!(specs.components.schemas["FELT"], Schema::Ref(_)));
+    }
+
+    #[test]
+    fn test_extension_names_are_usage_errors() {
+        for arg in ["Katana=katana_dev.json", "main=katana_dev.json", "_dev.json"] {
+            let err = RawExtension::load(arg).unwrap_err();
+            assert!(err.is::<UsageError>(), "{}: {:#}", arg, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_full_rejects_redefined_methods() {
+        let extension = RawExtension {
+            name: "katana".to_string(),
+            document: "katana_dev.json".to_string(),
+            content: KATANA_EXTENSION.replace("dev_predeployedAccounts", "starknet_chainId"),
+        };
+
+        let mut diagnostics = Diagnostics::new();
+        assert!(raw_specs_0_8_1()
+            .parse_full(&[extension], ParseMode::Lenient, &mut diagnostics)
+            .is_none());
+
+        let diagnostic = diagnostics.iter().find(|d| d.severity == Severity::Error).unwrap();
+        assert_eq!(diagnostic.document.as_deref(), Some("katana_dev.json"));
+        assert_eq!(diagnostic.path.as_deref(), Some("#/methods/0"));
+    }
+
+    #[test]
+    fn test_spec_fixups() {
+        // The vendored document keeps the upstream mistake
+        let raw_specs = raw_specs_0_8_1();
+        assert!(raw_specs.main.contains(r##""$ref": "#/components/errors/STORAGE_PROOF""##));
+
+        let mut diagnostics = Diagnostics::new();
+        let specs = raw_specs.parse_full(&[], ParseMode::Lenient, &mut diagnostics).unwrap();
+        let diagnostic = diagnostics.iter().next().unwrap();
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.code.as_deref(), Some("spec-fixup"));
+        assert_eq!(diagnostic.document.as_deref(), Some(MAIN_DOCUMENT));
+        assert_eq!(diagnostic.path.as_deref(), Some("#/methods/24/result/schema"));
+
+        let method = specs.methods.iter().find(|m| m.name == "starknet_getStorageProof").unwrap();
+        match &method.result.as_ref().unwrap().schema {
+            Schema::Ref(reference) => {
+                assert_eq!(reference.ref_field, "#/components/schemas/STORAGE_PROOF")
+            }
+            _ => panic!("Expected reference"),
+        }
+
+        // Documents without the mistake are left as they are
+        let mut fixed = raw_specs.clone();
+        fixed.main = Cow::Owned(raw_specs.main.replace(
+            r##""#/components/errors/STORAGE_PROOF""##,
+            r##""#/components/schemas/STORAGE_PROOF""##,
+        ));
+        assert!(matches!(fixed.documents()[0].1, Cow::Borrowed(_)));
+        let mut diagnostics = Diagnostics::new();
+        let parsed = fixed.parse_full(&[], ParseMode::Lenient, &mut diagnostics);
+        assert!(parsed.is_some(), "{:?}", diagnostics);
+        assert_eq!(diagnostics.iter().count(), 0);
+
+        // 0.3.0 wraps the items of a result in a stray `schema` key
+        let profile = &bundled_profiles()[2];
+        let mut diagnostics = Diagnostics::new();
+        assert!(profile.raw_specs.parse_full(&[], ParseMode::Lenient, &mut diagnostics).is_some());
+        let paths = diagnostics
+            .iter()
+            .filter(|d| d.code.as_deref() == Some("spec-fixup"))
+            .map(|d| format!("{}{}", d.document.as_deref().unwrap(), d.path.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "starknet_api_openrpc.json#/components/schemas/CONTRACT_CLASS/oneOf/1",
+                "starknet_trace_api_openrpc.json#/methods/1/result/schema/items",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_defaulted_and_unknown_keywords() {
+        let raw_specs = RawSpecs {
+            main: Cow::Borrowed(LOOSE_MAIN),
+            write: Cow::Borrowed(EMPTY_DOCUMENT),
+            trace: Cow::Borrowed(EMPTY_DOCUMENT),
+            ws: None,
+        };
+
+        let mut diagnostics = Diagnostics::new();
+        assert!(raw_specs.parse_full(&[], ParseMode::Strict, &mut diagnostics).is_none());
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| format!("{} {}", d.path.as_deref().unwrap(), d.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "#/methods/0/params/0 missing keyword `required`",
+                "#/components/schemas/FOO missing keyword `required`",
+                "#/components/schemas/FOO_OR_BAR unknown keyword `properties`",
+                "#/components/schemas/FOO_OR_BAR unknown keyword `type`",
+            ]
+        );
+
+        // Lenient mode defaults `required` and only warns about the keywords it doesn't model
+        let mut diagnostics = Diagnostics::new();
+        assert!(raw_specs.parse_full(&[], ParseMode::Lenient, &mut diagnostics).is_some());
+        assert!(!diagnostics.has_errors());
+    }
+}
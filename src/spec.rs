@@ -1,26 +1,68 @@
+use std::collections::HashMap;
+
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    refs::escape_pointer_segment,
+};
+
+/// How keywords the spec types don't model are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Unknown keywords abort parsing
+    Strict,
+    /// Unknown keywords are kept in `additional_fields` and reported as warnings
+    Lenient,
+}
+
+/// Keyword found at `path` in `document` that none of the spec types model, or that the
+/// document leaves out although the spec types require it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKeyword {
+    pub document: String,
+    pub path: String,
+    pub keyword: String,
+}
+
+// Every type keeps the keywords it doesn't model in `additional_fields`, so that new spec
+// revisions can still be parsed. `Specification::unknown_keywords` reports them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Specification {
     pub openrpc: String,
     pub info: Info,
     pub servers: Vec<String>,
     pub methods: Vec<Method>,
     pub components: Components,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+    /// Node-specific method namespaces merged on top of the core documents. They are only
+    /// written along with merged specs, under an `x-` key OpenRPC tools ignore.
+    #[serde(rename = "x-extensions", default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<Extension>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Info {
     pub version: String,
     pub title: String,
     pub license: Empty,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+}
+
+/// Methods contributed by an extension document (e.g. Katana's `dev_*` namespace)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Extension {
+    pub name: String,
+    pub methods: Vec<Method>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Method {
     pub name: String,
     pub summary: String,
@@ -33,34 +75,75 @@ pub struct Method {
     pub result: Option<MethodResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub errors: Option<Vec<Reference>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<ExamplePairing>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+}
+
+/// OpenRPC example of a method call, its params by name and the result they produce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamplePairing {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub params: Vec<Example>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Example>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Example {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub value: serde_json::Value,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Components {
     pub content_descriptors: Empty,
     pub schemas: IndexMap<String, Schema>,
     pub errors: IndexMap<String, ErrorType>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct Empty {}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Empty {
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Param {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
+    #[serde(default)]
     pub required: bool,
     pub schema: Schema,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct MethodResult {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -70,6 +153,8 @@ pub struct MethodResult {
     pub schema: Schema,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -93,23 +178,24 @@ pub struct Reference {
     pub description: Option<String>,
     #[serde(rename = "$ref")]
     pub ref_field: String,
-    // Allow additional fields that we don't need to parse
     #[serde(flatten)]
-    pub additional_fields: std::collections::HashMap<String, serde_json::Value>,
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct OneOf {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub one_of: Vec<Schema>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct AllOf {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -118,6 +204,8 @@ pub struct AllOf {
     pub all_of: Vec<Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub additional_properties: Option<bool>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -131,26 +219,30 @@ pub enum Primitive {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct ArrayPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub items: Box<Schema>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct BooleanPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct IntegerPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -161,10 +253,12 @@ pub struct IntegerPrimitive {
     // Field not handled for now
     #[serde(skip_serializing_if = "Option::is_none")]
     pub not: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct ObjectPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -173,16 +267,19 @@ pub struct ObjectPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
     pub properties: IndexMap<String, Schema>,
+    #[serde(default)]
     pub required: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub additional_properties: Option<bool>,
     // Field not handled for now
     #[serde(skip_serializing_if = "Option::is_none")]
     pub not: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct StringPrimitive {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -195,6 +292,8 @@ pub struct StringPrimitive {
     pub r#enum: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -205,12 +304,14 @@ pub enum ErrorType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Error {
     pub code: u32,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Schema>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
 impl Schema {
@@ -286,3 +387,384 @@ impl Reference {
         }
     }
 }
+
+impl Specification {
+    /// Parses the document named `document`, reporting problems to `diagnostics`.
+    ///
+    /// When the document doesn't match the spec types, every offending entry is reported at the
+    /// deepest JSON path that fails to deserialize, rather than only the first one.
+    pub fn from_document(
+        document: &str,
+        content: &str,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<Self> {
+        let value: serde_json::Value = match serde_json::from_str(content) {
+            Ok(value) => value,
+            Err(err) => {
+                let diagnostic = Diagnostic::error(format!("invalid JSON: {}", err)).code("invalid-json");
+                diagnostics.push(diagnostic.at(document, "#"));
+                return None;
+            }
+        };
+
+        let err = match serde_json::from_value(value.clone()) {
+            Ok(specs) => return Some(specs),
+            Err(err) => err,
+        };
+
+        let mut located = vec![];
+        for (i, method) in value["methods"].as_array().into_iter().flatten().enumerate() {
+            locate_error::<Method>(method, &format!("#/methods/{}", i), &mut located);
+        }
+        for (kind, entries) in value["components"].as_object().into_iter().flatten() {
+            for (name, entry) in entries.as_object().into_iter().flatten() {
+                let path = format!("#/components/{}/{}", kind, escape_pointer_segment(name));
+                match kind.as_str() {
+                    "schemas" => locate_error::<Schema>(entry, &path, &mut located),
+                    "errors" => locate_error::<ErrorType>(entry, &path, &mut located),
+                    _ => {}
+                }
+            }
+        }
+
+        if located.is_empty() {
+            located.push(("#".to_owned(), err.to_string()));
+        }
+        for (path, message) in located {
+            diagnostics.push(Diagnostic::error(message).code("invalid-spec").at(document, &path));
+        }
+
+        None
+    }
+
+    /// Lists keywords kept in `additional_fields` anywhere in the document, with their JSON path.
+    pub fn unknown_keywords(&self, document: &str) -> Vec<UnknownKeyword> {
+        let mut collector = KeywordCollector::new(document, None);
+        collector.specification(self);
+        collector.keywords
+    }
+
+    /// Lists the keywords `raw`, the JSON the document was parsed from, leaves out although the
+    /// spec types require them: `required` of params and of object schemas, which parsing
+    /// defaults to nothing being required.
+    pub fn defaulted_keywords(
+        &self,
+        document: &str,
+        raw: &serde_json::Value,
+    ) -> Vec<UnknownKeyword> {
+        let mut collector = KeywordCollector::new(document, Some(raw));
+        collector.specification(self);
+        collector.defaulted
+    }
+}
+
+/// Pushes the deepest paths under `path` where `value` fails to deserialize, with the error.
+fn locate_error<T: serde::de::DeserializeOwned>(
+    value: &serde_json::Value,
+    path: &str,
+    located: &mut Vec<(String, String)>,
+) {
+    let err = match serde_json::from_value::<T>(value.clone()) {
+        Ok(_) => return,
+        Err(err) => err,
+    };
+
+    let count = located.len();
+    for (key, child) in value.as_object().into_iter().flatten() {
+        let child_path = format!("{}/{}", path, escape_pointer_segment(key));
+        match (key.as_str(), child) {
+            ("schema" | "items" | "data", _) => locate_error::<Schema>(child, &child_path, located),
+            ("oneOf" | "allOf", serde_json::Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    locate_error::<Schema>(item, &format!("{}/{}", child_path, i), located);
+                }
+            }
+            ("properties", serde_json::Value::Object(properties)) => {
+                for (name, property) in properties.iter() {
+                    let property_path = format!("{}/{}", child_path, escape_pointer_segment(name));
+                    locate_error::<Schema>(property, &property_path, located);
+                }
+            }
+            ("params", serde_json::Value::Array(params)) => {
+                for (i, param) in params.iter().enumerate() {
+                    locate_error::<Param>(param, &format!("{}/{}", child_path, i), located);
+                }
+            }
+            ("result", _) => locate_error::<MethodResult>(child, &child_path, located),
+            ("errors", serde_json::Value::Array(errors)) => {
+                for (i, error) in errors.iter().enumerate() {
+                    locate_error::<Reference>(error, &format!("{}/{}", child_path, i), located);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // None of the children is to blame, so the problem is with the value itself
+    if located.len() == count {
+        located.push((path.to_owned(), err.to_string()));
+    }
+}
+
+struct KeywordCollector<'a> {
+    document: &'a str,
+    /// JSON the document was parsed from, to find the keywords it leaves out
+    raw: Option<&'a serde_json::Value>,
+    keywords: Vec<UnknownKeyword>,
+    defaulted: Vec<UnknownKeyword>,
+}
+
+impl<'a> KeywordCollector<'a> {
+    fn new(document: &'a str, raw: Option<&'a serde_json::Value>) -> Self {
+        Self {
+            document,
+            raw,
+            keywords: vec![],
+            defaulted: vec![],
+        }
+    }
+
+    fn specification(&mut self, specs: &Specification) {
+        self.collect("#", &specs.additional_fields);
+        self.collect("#/info", &specs.info.additional_fields);
+        self.collect("#/info/license", &specs.info.license.additional_fields);
+
+        for (i, method) in specs.methods.iter().enumerate() {
+            let path = format!("#/methods/{}", i);
+            self.collect(&path, &method.additional_fields);
+
+            for (j, param) in method.params.iter().enumerate() {
+                let path = format!("{}/params/{}", path, j);
+                self.collect(&path, &param.additional_fields);
+                self.require(&path, "required");
+                self.schema(&format!("{}/schema", path), &param.schema);
+            }
+            if let Some(result) = &method.result {
+                let path = format!("{}/result", path);
+                self.collect(&path, &result.additional_fields);
+                self.schema(&format!("{}/schema", path), &result.schema);
+            }
+            for (j, error) in method.errors.iter().flatten().enumerate() {
+                self.collect(&format!("{}/errors/{}", path, j), &error.additional_fields);
+            }
+            for (j, example) in method.examples.iter().enumerate() {
+                let path = format!("{}/examples/{}", path, j);
+                self.collect(&path, &example.additional_fields);
+                for (k, param) in example.params.iter().enumerate() {
+                    self.collect(&format!("{}/params/{}", path, k), &param.additional_fields);
+                }
+                if let Some(result) = &example.result {
+                    self.collect(&format!("{}/result", path), &result.additional_fields);
+                }
+            }
+        }
+
+        self.collect("#/components", &specs.components.additional_fields);
+        self.collect(
+            "#/components/contentDescriptors",
+            &specs.components.content_descriptors.additional_fields,
+        );
+        for (name, schema) in specs.components.schemas.iter() {
+            self.schema(
+                &format!("#/components/schemas/{}", escape_pointer_segment(name)),
+                schema,
+            );
+        }
+        for (name, error) in specs.components.errors.iter() {
+            let path = format!("#/components/errors/{}", escape_pointer_segment(name));
+            match error {
+                ErrorType::Error(error) => {
+                    self.collect(&path, &error.additional_fields);
+                    if let Some(data) = &error.data {
+                        self.schema(&format!("{}/data", path), data);
+                    }
+                }
+                ErrorType::Reference(reference) => {
+                    self.collect(&path, &reference.additional_fields);
+                }
+            }
+        }
+    }
+
+    fn collect(&mut self, path: &str, additional_fields: &HashMap<String, serde_json::Value>) {
+        let mut keywords = additional_fields.keys().collect::<Vec<_>>();
+        keywords.sort();
+
+        for keyword in keywords {
+            self.keywords.push(UnknownKeyword {
+                document: self.document.to_owned(),
+                path: path.to_owned(),
+                keyword: keyword.to_owned(),
+            });
+        }
+    }
+
+    /// Reports `keyword` if the raw JSON at `path` leaves it out
+    fn require(&mut self, path: &str, keyword: &str) {
+        let Some(value) = self.raw.and_then(|raw| raw.pointer(&path[1..])) else {
+            return;
+        };
+        if value.get(keyword).is_none() {
+            self.defaulted.push(UnknownKeyword {
+                document: self.document.to_owned(),
+                path: path.to_owned(),
+                keyword: keyword.to_owned(),
+            });
+        }
+    }
+
+    fn schema(&mut self, path: &str, schema: &Schema) {
+        match schema {
+            Schema::Ref(reference) => self.collect(path, &reference.additional_fields),
+            Schema::OneOf(oneof) => {
+                self.collect(path, &oneof.additional_fields);
+                for (i, variant) in oneof.one_of.iter().enumerate() {
+                    self.schema(&format!("{}/oneOf/{}", path, i), variant);
+                }
+            }
+            Schema::AllOf(allof) => {
+                self.collect(path, &allof.additional_fields);
+                for (i, part) in allof.all_of.iter().enumerate() {
+                    self.schema(&format!("{}/allOf/{}", path, i), part);
+                }
+            }
+            Schema::Primitive(Primitive::Array(array)) => {
+                self.collect(path, &array.additional_fields);
+                self.schema(&format!("{}/items", path), &array.items);
+            }
+            Schema::Primitive(Primitive::Boolean(boolean)) => {
+                self.collect(path, &boolean.additional_fields)
+            }
+            Schema::Primitive(Primitive::Integer(integer)) => {
+                self.collect(path, &integer.additional_fields)
+            }
+            Schema::Primitive(Primitive::Object(object)) => {
+                self.collect(path, &object.additional_fields);
+                self.require(path, "required");
+                for (name, property) in object.properties.iter() {
+                    self.schema(
+                        &format!("{}/properties/{}", path, escape_pointer_segment(name)),
+                        property,
+                    );
+                }
+            }
+            Schema::Primitive(Primitive::String(string)) => {
+                self.collect(path, &string.additional_fields)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_keywords_are_kept_and_reported() {
+        let specs: Specification = serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.9.0", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [
+                    {
+                        "name": "starknet_getFoo",
+                        "summary": "Returns a foo",
+                        "deprecated": true,
+                        "params": [],
+                        "result": {
+                            "name": "result",
+                            "schema": { "type": "integer", "minimum": 0, "maximum": 10 }
+                        }
+                    }
+                ],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "FOO": {
+                            "type": "object",
+                            "x-internal": true,
+                            "properties": {
+                                "bar": { "type": "string", "format": "hex", "examples": ["0x1"] }
+                            }
+                        }
+                    },
+                    "errors": {}
+                }
+            }"##,
+        )
+        .unwrap();
+
+        // `required` defaults to no required properties
+        match &specs.components.schemas["FOO"] {
+            Schema::Primitive(Primitive::Object(object)) => assert!(object.required.is_empty()),
+            _ => panic!("Expected object schema"),
+        }
+
+        let keywords = specs
+            .unknown_keywords("test.json")
+            .into_iter()
+            .map(|keyword| format!("{}:{}", keyword.path, keyword.keyword))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            keywords,
+            vec![
+                "#/methods/0:deprecated",
+                "#/methods/0/result/schema:maximum",
+                "#/components/schemas/FOO:x-internal",
+                "#/components/schemas/FOO/properties/bar:examples",
+                "#/components/schemas/FOO/properties/bar:format",
+            ]
+        );
+
+        // Unknown keywords survive a roundtrip
+        let json = serde_json::to_value(&specs).unwrap();
+        assert_eq!(json["methods"][0]["deprecated"], true);
+    }
+
+    #[test]
+    fn test_parse_errors_are_located() {
+        let mut diagnostics = Diagnostics::new();
+        let specs = Specification::from_document(
+            "starknet_api_openrpc.json",
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.9.0", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [
+                    {
+                        "name": "starknet_getFoo",
+                        "params": [],
+                        "result": {
+                            "name": "result",
+                            "schema": { "type": "array", "items": { "schema": { "type": "string" } } }
+                        }
+                    }
+                ],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "TXN": { "oneOf": [{ "type": "string" }, { "type": "object", "properties": 1 }] }
+                    },
+                    "errors": {}
+                }
+            }"##,
+            &mut diagnostics,
+        );
+
+        assert!(specs.is_none());
+        let paths = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.path.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["#/methods/0/result/schema/items", "#/components/schemas/TXN/oneOf/1"]
+        );
+
+        let mut diagnostics = Diagnostics::new();
+        assert!(Specification::from_document("broken.json", "{", &mut diagnostics).is_none());
+        assert!(diagnostics.iter().next().unwrap().message.starts_with("invalid JSON"));
+    }
+}
@@ -0,0 +1,747 @@
+use std::fmt::{self, Display};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::spec::*;
+
+/// Change between two versions of the merged specification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpecChange {
+    MethodAdded {
+        method: String,
+    },
+    MethodRemoved {
+        method: String,
+    },
+    ParamAdded {
+        method: String,
+        param: String,
+        required: bool,
+    },
+    ParamRemoved {
+        method: String,
+        param: String,
+    },
+    ParamRetyped {
+        method: String,
+        param: String,
+        from: String,
+        to: String,
+    },
+    ParamRequiredChanged {
+        method: String,
+        param: String,
+        required: bool,
+    },
+    ResultRetyped {
+        method: String,
+        from: String,
+        to: String,
+    },
+    MethodErrorAdded {
+        method: String,
+        error: String,
+    },
+    MethodErrorRemoved {
+        method: String,
+        error: String,
+    },
+    SchemaAdded {
+        schema: String,
+    },
+    SchemaRemoved {
+        schema: String,
+    },
+    SchemaRetyped {
+        schema: String,
+        from: String,
+        to: String,
+    },
+    PropertyAdded {
+        schema: String,
+        property: String,
+        required: bool,
+    },
+    PropertyRemoved {
+        schema: String,
+        property: String,
+    },
+    PropertyRetyped {
+        schema: String,
+        property: String,
+        from: String,
+        to: String,
+    },
+    PropertyRequiredChanged {
+        schema: String,
+        property: String,
+        required: bool,
+    },
+    /// Enum values of a schema, or of one of its properties when `property` is set
+    EnumValuesChanged {
+        schema: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        property: Option<String>,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    ErrorAdded {
+        error: String,
+        code: u32,
+    },
+    ErrorRemoved {
+        error: String,
+        code: u32,
+    },
+    ErrorCodeChanged {
+        error: String,
+        from: u32,
+        to: u32,
+    },
+}
+
+/// Part of the specification a change belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecSection {
+    Methods,
+    Schemas,
+    Errors,
+}
+
+/// Differences between two merged specifications, suitable for release notes
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecDiff {
+    pub from: String,
+    pub to: String,
+    pub changes: Vec<SpecChange>,
+}
+
+impl SpecDiff {
+    pub fn new(from: &Specification, to: &Specification) -> Self {
+        let mut changes = vec![];
+
+        diff_methods(&from.methods, &to.methods, &mut changes);
+        diff_schemas(&from.components.schemas, &to.components.schemas, &mut changes);
+        diff_errors(&from.components.errors, &to.components.errors, &mut changes);
+
+        Self {
+            from: from.info.version.clone(),
+            to: to.info.version.clone(),
+            changes,
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# Specification changes from {} to {}\n", self.from, self.to);
+
+        if self.changes.is_empty() {
+            markdown.push_str("\nNo changes.\n");
+        }
+
+        for (section, title) in [
+            (SpecSection::Methods, "Methods"),
+            (SpecSection::Schemas, "Schemas"),
+            (SpecSection::Errors, "Errors"),
+        ] {
+            let changes = self
+                .changes
+                .iter()
+                .filter(|change| change.section() == section)
+                .collect::<Vec<_>>();
+            if changes.is_empty() {
+                continue;
+            }
+
+            markdown.push_str(&format!("\n## {}\n\n", title));
+            for change in changes {
+                markdown.push_str(&format!("- {}\n", change));
+            }
+        }
+
+        markdown
+    }
+}
+
+impl SpecChange {
+    pub fn section(&self) -> SpecSection {
+        match self {
+            Self::MethodAdded { .. }
+            | Self::MethodRemoved { .. }
+            | Self::ParamAdded { .. }
+            | Self::ParamRemoved { .. }
+            | Self::ParamRetyped { .. }
+            | Self::ParamRequiredChanged { .. }
+            | Self::ResultRetyped { .. }
+            | Self::MethodErrorAdded { .. }
+            | Self::MethodErrorRemoved { .. } => SpecSection::Methods,
+            Self::SchemaAdded { .. }
+            | Self::SchemaRemoved { .. }
+            | Self::SchemaRetyped { .. }
+            | Self::PropertyAdded { .. }
+            | Self::PropertyRemoved { .. }
+            | Self::PropertyRetyped { .. }
+            | Self::PropertyRequiredChanged { .. }
+            | Self::EnumValuesChanged { .. } => SpecSection::Schemas,
+            Self::ErrorAdded { .. } | Self::ErrorRemoved { .. } | Self::ErrorCodeChanged { .. } => {
+                SpecSection::Errors
+            }
+        }
+    }
+}
+
+impl Display for SpecChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MethodAdded { method } => write!(f, "Added `{}`", method),
+            Self::MethodRemoved { method } => write!(f, "Removed `{}`", method),
+            Self::ParamAdded {
+                method,
+                param,
+                required,
+            } => write!(
+                f,
+                "`{}`: added {} param `{}`",
+                method,
+                required_str(*required),
+                param
+            ),
+            Self::ParamRemoved { method, param } => {
+                write!(f, "`{}`: removed param `{}`", method, param)
+            }
+            Self::ParamRetyped {
+                method,
+                param,
+                from,
+                to,
+            } => write!(
+                f,
+                "`{}`: param `{}` changed from `{}` to `{}`",
+                method, param, from, to
+            ),
+            Self::ParamRequiredChanged {
+                method,
+                param,
+                required,
+            } => write!(
+                f,
+                "`{}`: param `{}` is now {}",
+                method,
+                param,
+                required_str(*required)
+            ),
+            Self::ResultRetyped { method, from, to } => {
+                write!(f, "`{}`: result changed from `{}` to `{}`", method, from, to)
+            }
+            Self::MethodErrorAdded { method, error } => {
+                write!(f, "`{}`: may now return `{}`", method, error)
+            }
+            Self::MethodErrorRemoved { method, error } => {
+                write!(f, "`{}`: no longer returns `{}`", method, error)
+            }
+            Self::SchemaAdded { schema } => write!(f, "Added `{}`", schema),
+            Self::SchemaRemoved { schema } => write!(f, "Removed `{}`", schema),
+            Self::SchemaRetyped { schema, from, to } => {
+                write!(f, "`{}`: changed from `{}` to `{}`", schema, from, to)
+            }
+            Self::PropertyAdded {
+                schema,
+                property,
+                required,
+            } => write!(
+                f,
+                "`{}`: added {} property `{}`",
+                schema,
+                required_str(*required),
+                property
+            ),
+            Self::PropertyRemoved { schema, property } => {
+                write!(f, "`{}`: removed property `{}`", schema, property)
+            }
+            Self::PropertyRetyped {
+                schema,
+                property,
+                from,
+                to,
+            } => write!(
+                f,
+                "`{}`: property `{}` changed from `{}` to `{}`",
+                schema, property, from, to
+            ),
+            Self::PropertyRequiredChanged {
+                schema,
+                property,
+                required,
+            } => write!(
+                f,
+                "`{}`: property `{}` is now {}",
+                schema,
+                property,
+                required_str(*required)
+            ),
+            Self::EnumValuesChanged {
+                schema,
+                property,
+                added,
+                removed,
+            } => {
+                match property {
+                    Some(property) => write!(f, "`{}`: enum values of `{}`", schema, property)?,
+                    None => write!(f, "`{}`: enum values", schema)?,
+                }
+                if !added.is_empty() {
+                    write!(f, " added {}", code_list(added))?;
+                }
+                if !added.is_empty() && !removed.is_empty() {
+                    write!(f, ",")?;
+                }
+                if !removed.is_empty() {
+                    write!(f, " removed {}", code_list(removed))?;
+                }
+                Ok(())
+            }
+            Self::ErrorAdded { error, code } => write!(f, "Added `{}` (code {})", error, code),
+            Self::ErrorRemoved { error, code } => write!(f, "Removed `{}` (code {})", error, code),
+            Self::ErrorCodeChanged { error, from, to } => {
+                write!(f, "`{}`: code changed from {} to {}", error, from, to)
+            }
+        }
+    }
+}
+
+fn diff_methods(from: &[Method], to: &[Method], changes: &mut Vec<SpecChange>) {
+    for method in to.iter() {
+        let Some(previous) = from.iter().find(|m| m.name == method.name) else {
+            changes.push(SpecChange::MethodAdded {
+                method: method.name.clone(),
+            });
+            continue;
+        };
+
+        for param in method.params.iter() {
+            let Some(previous_param) = previous.params.iter().find(|p| p.name == param.name) else {
+                changes.push(SpecChange::ParamAdded {
+                    method: method.name.clone(),
+                    param: param.name.clone(),
+                    required: param.required,
+                });
+                continue;
+            };
+
+            let from_type = type_name(&previous_param.schema);
+            let to_type = type_name(&param.schema);
+            if from_type != to_type {
+                changes.push(SpecChange::ParamRetyped {
+                    method: method.name.clone(),
+                    param: param.name.clone(),
+                    from: from_type,
+                    to: to_type,
+                });
+            }
+            if previous_param.required != param.required {
+                changes.push(SpecChange::ParamRequiredChanged {
+                    method: method.name.clone(),
+                    param: param.name.clone(),
+                    required: param.required,
+                });
+            }
+        }
+        for param in previous.params.iter() {
+            if !method.params.iter().any(|p| p.name == param.name) {
+                changes.push(SpecChange::ParamRemoved {
+                    method: method.name.clone(),
+                    param: param.name.clone(),
+                });
+            }
+        }
+
+        let from_result = previous.result.as_ref().map(|result| type_name(&result.schema));
+        let to_result = method.result.as_ref().map(|result| type_name(&result.schema));
+        if from_result != to_result {
+            changes.push(SpecChange::ResultRetyped {
+                method: method.name.clone(),
+                from: from_result.unwrap_or_else(|| "none".to_owned()),
+                to: to_result.unwrap_or_else(|| "none".to_owned()),
+            });
+        }
+
+        let from_errors = error_names(previous);
+        let to_errors = error_names(method);
+        for error in to_errors.iter().filter(|error| !from_errors.contains(error)) {
+            changes.push(SpecChange::MethodErrorAdded {
+                method: method.name.clone(),
+                error: error.to_string(),
+            });
+        }
+        for error in from_errors.iter().filter(|error| !to_errors.contains(error)) {
+            changes.push(SpecChange::MethodErrorRemoved {
+                method: method.name.clone(),
+                error: error.to_string(),
+            });
+        }
+    }
+
+    for method in from.iter() {
+        if !to.iter().any(|m| m.name == method.name) {
+            changes.push(SpecChange::MethodRemoved {
+                method: method.name.clone(),
+            });
+        }
+    }
+}
+
+fn diff_schemas(
+    from: &IndexMap<String, Schema>,
+    to: &IndexMap<String, Schema>,
+    changes: &mut Vec<SpecChange>,
+) {
+    for (name, schema) in to.iter() {
+        let Some(previous) = from.get(name) else {
+            changes.push(SpecChange::SchemaAdded {
+                schema: name.clone(),
+            });
+            continue;
+        };
+
+        let (from_type, to_type) = (type_name(previous), type_name(schema));
+        if from_type != to_type {
+            changes.push(SpecChange::SchemaRetyped {
+                schema: name.clone(),
+                from: from_type,
+                to: to_type,
+            });
+        }
+
+        diff_enum_values(name, None, previous, schema, changes);
+        diff_properties(name, "", previous, schema, changes);
+    }
+
+    for name in from.keys() {
+        if !to.contains_key(name) {
+            changes.push(SpecChange::SchemaRemoved {
+                schema: name.clone(),
+            });
+        }
+    }
+}
+
+/// Compares the properties of two object schemas, descending into inline objects with `prefix`.
+fn diff_properties(
+    schema: &str,
+    prefix: &str,
+    from: &Schema,
+    to: &Schema,
+    changes: &mut Vec<SpecChange>,
+) {
+    // Swapping inline properties for a reference is already reported as a type change
+    if matches!(from, Schema::Ref(_)) || matches!(to, Schema::Ref(_)) {
+        return;
+    }
+
+    let (from_properties, from_required) = properties(from);
+    let (to_properties, to_required) = properties(to);
+
+    for (property, to_schema) in to_properties.iter() {
+        let path = format!("{}{}", prefix, property);
+        let required = to_required.contains(property);
+
+        let Some(from_schema) = from_properties.get(property) else {
+            changes.push(SpecChange::PropertyAdded {
+                schema: schema.to_owned(),
+                property: path,
+                required,
+            });
+            continue;
+        };
+
+        let (from_type, to_type) = (type_name(from_schema), type_name(to_schema));
+        if from_type != to_type {
+            changes.push(SpecChange::PropertyRetyped {
+                schema: schema.to_owned(),
+                property: path.clone(),
+                from: from_type,
+                to: to_type,
+            });
+        }
+        if from_required.contains(property) != required {
+            changes.push(SpecChange::PropertyRequiredChanged {
+                schema: schema.to_owned(),
+                property: path.clone(),
+                required,
+            });
+        }
+
+        diff_enum_values(schema, Some(&path), from_schema, to_schema, changes);
+        diff_properties(schema, &format!("{}.", path), from_schema, to_schema, changes);
+    }
+
+    for property in from_properties.keys() {
+        if !to_properties.contains_key(property) {
+            changes.push(SpecChange::PropertyRemoved {
+                schema: schema.to_owned(),
+                property: format!("{}{}", prefix, property),
+            });
+        }
+    }
+}
+
+fn diff_enum_values(
+    schema: &str,
+    property: Option<&str>,
+    from: &Schema,
+    to: &Schema,
+    changes: &mut Vec<SpecChange>,
+) {
+    let (Some(from_values), Some(to_values)) = (enum_values(from), enum_values(to)) else {
+        return;
+    };
+
+    let added = to_values
+        .iter()
+        .filter(|value| !from_values.contains(value))
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed = from_values
+        .iter()
+        .filter(|value| !to_values.contains(value))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(SpecChange::EnumValuesChanged {
+            schema: schema.to_owned(),
+            property: property.map(|property| property.to_owned()),
+            added,
+            removed,
+        });
+    }
+}
+
+fn diff_errors(
+    from: &IndexMap<String, ErrorType>,
+    to: &IndexMap<String, ErrorType>,
+    changes: &mut Vec<SpecChange>,
+) {
+    let code = |error: &ErrorType| match error {
+        ErrorType::Error(error) => Some(error.code),
+        ErrorType::Reference(_) => None,
+    };
+
+    for (name, error) in to.iter() {
+        let Some(to_code) = code(error) else {
+            continue;
+        };
+        match from.get(name).and_then(code) {
+            Some(from_code) if from_code != to_code => {
+                changes.push(SpecChange::ErrorCodeChanged {
+                    error: name.clone(),
+                    from: from_code,
+                    to: to_code,
+                });
+            }
+            Some(_) => {}
+            None => changes.push(SpecChange::ErrorAdded {
+                error: name.clone(),
+                code: to_code,
+            }),
+        }
+    }
+
+    for (name, error) in from.iter() {
+        if let (Some(code), None) = (code(error), to.get(name).and_then(code)) {
+            changes.push(SpecChange::ErrorRemoved {
+                error: name.clone(),
+                code,
+            });
+        }
+    }
+}
+
+/// Short description of the shape of a schema, e.g. `array<FELT>`
+fn type_name(schema: &Schema) -> String {
+    match schema {
+        Schema::Ref(reference) => reference.name().to_owned(),
+        Schema::OneOf(oneof) => format!(
+            "oneOf<{}>",
+            oneof.one_of.iter().map(type_name).collect::<Vec<_>>().join(" | ")
+        ),
+        Schema::AllOf(allof) => format!(
+            "allOf<{}>",
+            allof.all_of.iter().map(type_name).collect::<Vec<_>>().join(" & ")
+        ),
+        Schema::Primitive(Primitive::Array(array)) => format!("array<{}>", type_name(&array.items)),
+        Schema::Primitive(Primitive::Boolean(_)) => "boolean".to_owned(),
+        Schema::Primitive(Primitive::Integer(_)) => "integer".to_owned(),
+        Schema::Primitive(Primitive::Object(_)) => "object".to_owned(),
+        Schema::Primitive(Primitive::String(_)) => "string".to_owned(),
+    }
+}
+
+/// Inline properties of an object schema, including the inline parts of an `allOf`.
+fn properties(schema: &Schema) -> (IndexMap<&String, &Schema>, Vec<&String>) {
+    let mut properties = IndexMap::new();
+    let mut required = vec![];
+
+    match schema {
+        Schema::Primitive(Primitive::Object(object)) => {
+            properties.extend(object.properties.iter());
+            required.extend(object.required.iter());
+        }
+        Schema::AllOf(allof) => {
+            for part in allof.all_of.iter() {
+                let (part_properties, part_required) = self::properties(part);
+                properties.extend(part_properties);
+                required.extend(part_required);
+            }
+        }
+        _ => {}
+    }
+
+    (properties, required)
+}
+
+fn enum_values(schema: &Schema) -> Option<&Vec<String>> {
+    match schema {
+        Schema::Primitive(Primitive::String(string)) => string.r#enum.as_ref(),
+        _ => None,
+    }
+}
+
+fn error_names(method: &Method) -> Vec<&str> {
+    method
+        .errors
+        .iter()
+        .flatten()
+        .map(|error| error.name())
+        .collect()
+}
+
+fn required_str(required: bool) -> &'static str {
+    if required {
+        "required"
+    } else {
+        "optional"
+    }
+}
+
+fn code_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| format!("`{}`", value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_spec(version: &str, methods: &str, schemas: &str, errors: &str) -> Specification {
+        serde_json::from_str(&format!(
+            r##"{{
+                "openrpc": "1.0.0",
+                "info": {{ "version": "{}", "title": "Test", "license": {{}} }},
+                "servers": [],
+                "methods": {},
+                "components": {{ "contentDescriptors": {{}}, "schemas": {}, "errors": {} }}
+            }}"##,
+            version, methods, schemas, errors
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spec_diff() {
+        let from = create_spec(
+            "0.7.1",
+            r##"[
+                {
+                    "name": "starknet_getFoo",
+                    "summary": "Returns a foo",
+                    "params": [
+                        { "name": "id", "required": true, "schema": { "$ref": "#/components/schemas/FELT" } },
+                        { "name": "tag", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "result": { "name": "result", "schema": { "$ref": "#/components/schemas/FOO" } },
+                    "errors": [{ "$ref": "#/components/errors/FOO_NOT_FOUND" }]
+                },
+                { "name": "starknet_old", "summary": "Gone", "params": [] }
+            ]"##,
+            r##"{
+                "FELT": { "type": "string" },
+                "STATUS": { "type": "string", "enum": ["ACCEPTED", "PENDING"] },
+                "FOO": {
+                    "type": "object",
+                    "properties": {
+                        "a": { "$ref": "#/components/schemas/FELT" },
+                        "b": { "type": "integer" }
+                    },
+                    "required": ["a"]
+                }
+            }"##,
+            r##"{ "FOO_NOT_FOUND": { "code": 20, "message": "Foo not found" } }"##,
+        );
+        let to = create_spec(
+            "0.8.1",
+            r##"[
+                {
+                    "name": "starknet_getFoo",
+                    "summary": "Returns a foo",
+                    "params": [
+                        { "name": "id", "required": false, "schema": { "type": "integer" } },
+                        { "name": "block", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "result": { "name": "result", "schema": { "$ref": "#/components/schemas/FOO" } },
+                    "errors": [{ "$ref": "#/components/errors/BLOCK_NOT_FOUND" }]
+                }
+            ]"##,
+            r##"{
+                "FELT": { "type": "string" },
+                "STATUS": { "type": "string", "enum": ["ACCEPTED", "REJECTED"] },
+                "FOO": {
+                    "type": "object",
+                    "properties": {
+                        "a": { "$ref": "#/components/schemas/FELT" },
+                        "c": { "type": "boolean" }
+                    },
+                    "required": []
+                }
+            }"##,
+            r##"{
+                "FOO_NOT_FOUND": { "code": 21, "message": "Foo not found" },
+                "BLOCK_NOT_FOUND": { "code": 24, "message": "Block not found" }
+            }"##,
+        );
+
+        let diff = SpecDiff::new(&from, &to);
+        let lines = diff.changes.iter().map(|change| change.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                "`starknet_getFoo`: param `id` changed from `FELT` to `integer`",
+                "`starknet_getFoo`: param `id` is now optional",
+                "`starknet_getFoo`: added required param `block`",
+                "`starknet_getFoo`: removed param `tag`",
+                "`starknet_getFoo`: may now return `BLOCK_NOT_FOUND`",
+                "`starknet_getFoo`: no longer returns `FOO_NOT_FOUND`",
+                "Removed `starknet_old`",
+                "`STATUS`: enum values added `REJECTED`, removed `PENDING`",
+                "`FOO`: property `a` is now optional",
+                "`FOO`: added optional property `c`",
+                "`FOO`: removed property `b`",
+                "`FOO_NOT_FOUND`: code changed from 20 to 21",
+                "Added `BLOCK_NOT_FOUND` (code 24)",
+            ]
+        );
+
+        let markdown = diff.to_markdown();
+        assert!(markdown.starts_with("# Specification changes from 0.7.1 to 0.8.1\n"));
+        assert!(markdown.contains("\n## Errors\n\n- `FOO_NOT_FOUND`: code changed from 20 to 21\n"));
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["changes"][0]["kind"], "param_retyped");
+        assert_eq!(json["changes"][7]["removed"][0], "PENDING");
+    }
+}
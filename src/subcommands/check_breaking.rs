@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use prost_types::FileDescriptorProto;
+
+use starknet_grpc_codegen::{
+    diagnostics::{Diagnostic, Diagnostics},
+    proto_gen::{
+        breaking::{Breakage, ProtoDiff},
+        descriptor::{compile_proto_dir, file_descriptors},
+        ProtoConfig, ProtoGenerator,
+    },
+    spec::ParseMode,
+    source::{GenerationProfile, RawExtension, SpecVersion},
+};
+
+use crate::subcommands::diff_spec::DiffFormat;
+
+#[derive(Debug, Parser)]
+pub struct CheckBreaking {
+    #[clap(long, env, help = "Version of the specification to generate")]
+    spec: SpecVersion,
+    #[clap(
+        long,
+        value_name = "DIR",
+        required_unless_present = "against_spec",
+        conflicts_with = "against_spec",
+        help = "Directory with previously generated proto files, e.g. proto/v0_7_1"
+    )]
+    against: Option<PathBuf>,
+    #[clap(long, help = "Version of the specification to generate the previous files from")]
+    against_spec: Option<SpecVersion>,
+    #[clap(
+        long = "extension",
+        value_name = "[NAME=]PATH",
+        help = "Extension OpenRPC document generating its own service and package"
+    )]
+    extensions: Vec<String>,
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "wire",
+        help = "Kinds of breakage that fail the check"
+    )]
+    fail_on: Vec<BreakageKind>,
+    #[clap(long, value_enum, default_value = "markdown", help = "Output format")]
+    format: DiffFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BreakageKind {
+    Wire,
+    Json,
+    Source,
+}
+
+impl CheckBreaking {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|arg| RawExtension::load(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut generate = |version: SpecVersion| -> Result<Option<Vec<FileDescriptorProto>>> {
+            let profile = profiles
+                .iter()
+                .find(|profile| profile.version == version)
+                .expect("Unable to find profile");
+            let Some(specs) =
+                profile
+                    .raw_specs
+                    .parse_full(&extensions, ParseMode::Lenient, diagnostics)
+            else {
+                return Ok(None);
+            };
+            let config = ProtoConfig::new(&version.to_version_string());
+            let protos = ProtoGenerator::new(config).generate(&specs)?.protos;
+            Ok(Some(file_descriptors(&protos)?))
+        };
+
+        let old = match (&self.against, self.against_spec) {
+            (Some(dir), _) => match compile_proto_dir(dir) {
+                Ok(files) => Some(files),
+                Err(err) if err.chain().any(|cause| cause.is::<std::io::Error>()) => {
+                    return Err(err)
+                }
+                Err(err) => {
+                    diagnostics.push(Diagnostic::error(format!("{:#}", err)).code("invalid-proto"));
+                    return Ok(());
+                }
+            },
+            (None, Some(version)) => generate(version)?,
+            (None, None) => unreachable!("clap requires --against or --against-spec"),
+        };
+        let new = generate(self.spec)?;
+        let (Some(old), Some(new)) = (old, new) else {
+            return Ok(());
+        };
+
+        let diff = ProtoDiff::new(&old, &new);
+        match self.format {
+            DiffFormat::Markdown => print!("{}", diff.to_markdown()),
+            DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        }
+
+        for kind in self.fail_on {
+            let breakage = match kind {
+                BreakageKind::Wire => Breakage::Wire,
+                BreakageKind::Json => Breakage::Json,
+                BreakageKind::Source => Breakage::Source,
+            };
+            let count = diff.breaking(breakage).count();
+            if count > 0 {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "{} change(s) break {} compatibility",
+                        count, breakage
+                    ))
+                    .code("breaking-change"),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use clap::Parser;
+
+use starknet_grpc_codegen::{
+    diagnostics::Diagnostics,
+    proto_gen::{json_shape::JsonShapeReport, ProtoConfig, ProtoGenerator},
+    spec::ParseMode,
+    source::{GenerationProfile, RawExtension, SpecVersion},
+};
+
+use crate::subcommands::diff_spec::DiffFormat;
+
+#[derive(Debug, Parser)]
+pub struct CheckJson {
+    #[clap(long, env, help = "Version of the specification to generate")]
+    spec: SpecVersion,
+    #[clap(
+        long = "extension",
+        value_name = "[NAME=]PATH",
+        help = "Extension OpenRPC document generating its own service and package"
+    )]
+    extensions: Vec<String>,
+    #[clap(long, value_enum, default_value = "markdown", help = "Output format")]
+    format: DiffFormat,
+}
+
+impl CheckJson {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.version == self.spec)
+            .expect("Unable to find profile");
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|arg| RawExtension::load(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(specs) = profile
+            .raw_specs
+            .parse_full(&extensions, ParseMode::Lenient, diagnostics)
+        else {
+            return Ok(());
+        };
+
+        let config = ProtoConfig::new(&self.spec.to_version_string());
+        let result = ProtoGenerator::new(config.clone()).generate(&specs)?;
+
+        let report = JsonShapeReport::new(&specs, &config, &result.protos);
+        match self.format {
+            DiffFormat::Markdown => print!("{}", report.to_markdown()),
+            DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use starknet_grpc_codegen::{
+    diagnostics::Diagnostics,
+    source::{GenerationProfile, SpecVersion},
+    spec::ParseMode,
+    spec_diff::SpecDiff,
+};
+
+#[derive(Debug, Parser)]
+pub struct DiffSpec {
+    #[clap(long, help = "Version of the specification to compare from")]
+    from: SpecVersion,
+    #[clap(long, help = "Version of the specification to compare to")]
+    to: SpecVersion,
+    #[clap(long, value_enum, default_value = "markdown", help = "Output format")]
+    format: DiffFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum DiffFormat {
+    Markdown,
+    Json,
+}
+
+impl DiffSpec {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let mut parse = |version: SpecVersion| {
+            profiles
+                .iter()
+                .find(|profile| profile.version == version)
+                .expect("Unable to find profile")
+                .raw_specs
+                .parse_full(&[], ParseMode::Lenient, diagnostics)
+        };
+
+        let (Some(from), Some(to)) = (parse(self.from), parse(self.to)) else {
+            return Ok(());
+        };
+
+        let diff = SpecDiff::new(&from, &to);
+        match self.format {
+            DiffFormat::Markdown => print!("{}", diff.to_markdown()),
+            DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        }
+
+        Ok(())
+    }
+}
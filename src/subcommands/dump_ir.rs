@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use starknet_grpc_codegen::{
+    diagnostics::Diagnostics,
+    proto_gen::{
+        plugin::{PluginBackend, PluginFormat},
+        ProtoConfig, ProtoGenerator,
+    },
+    spec::ParseMode,
+    source::{GenerationProfile, RawExtension, SpecVersion},
+};
+
+#[derive(Debug, Parser)]
+pub struct DumpIr {
+    #[clap(long, env, help = "Version of the specification")]
+    spec: SpecVersion,
+    #[clap(
+        long = "extension",
+        value_name = "[NAME=]PATH",
+        help = "Extension OpenRPC document generating its own service and package"
+    )]
+    extensions: Vec<String>,
+    #[clap(
+        long,
+        value_name = "FORMAT",
+        default_value = "json",
+        help = "Format of the request: json or protobuf (protoc plugins)"
+    )]
+    format: PluginFormat,
+    #[clap(long, value_name = "PARAMETER", help = "Option passed to plugins along with the definitions")]
+    parameter: Option<String>,
+    #[clap(long, value_name = "PATH", help = "File to write the request to instead of standard output")]
+    output: Option<String>,
+}
+
+impl DumpIr {
+    /// Writes the request a plugin of `generate --target plugin` reads on its standard input
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.version == self.spec)
+            .expect("Unable to find profile");
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|arg| RawExtension::load(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(specs) = profile
+            .raw_specs
+            .parse_full(&extensions, ParseMode::Lenient, diagnostics)
+        else {
+            return Ok(());
+        };
+
+        let config = ProtoConfig::new(&self.spec.to_version_string());
+        let ir = ProtoGenerator::new(config).resolve(&specs)?;
+        let request = PluginBackend::new("", self.format)
+            .with_parameter(self.parameter)
+            .request(&ir)?;
+
+        match &self.output {
+            Some(path) => {
+                fs::write(path, &request).with_context(|| format!("failed to write {}", path))?
+            }
+            None => io::stdout().write_all(&request)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indexmap::IndexMap;
+
+use starknet_grpc_codegen::{
+    diagnostics::{Diagnostic, Diagnostics, UsageError},
+    proto_gen::{examples::{ExampleGenerator, MethodExamples}, ProtoConfig, ProtoGenerator},
+    spec::ParseMode,
+    source::{GenerationProfile, RawExtension, SpecVersion},
+};
+
+#[derive(Debug, Parser)]
+pub struct Examples {
+    #[clap(long, env, help = "Version of the specification to generate")]
+    spec: SpecVersion,
+    #[clap(
+        long = "extension",
+        value_name = "[NAME=]PATH",
+        help = "Extension OpenRPC document generating its own service and package"
+    )]
+    extensions: Vec<String>,
+    #[clap(
+        long = "method",
+        value_name = "NAME",
+        help = "Method to produce examples of, all methods if not set"
+    )]
+    methods: Vec<String>,
+    #[clap(
+        long,
+        value_name = "DIR",
+        help = "Directory to write one file per method to instead of standard output"
+    )]
+    output_dir: Option<String>,
+}
+
+impl Examples {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.version == self.spec)
+            .expect("Unable to find profile");
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|arg| RawExtension::load(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(specs) = profile
+            .raw_specs
+            .parse_full(&extensions, ParseMode::Lenient, diagnostics)
+        else {
+            return Ok(());
+        };
+
+        let config = ProtoConfig::new(&self.spec.to_version_string());
+        let result = ProtoGenerator::new(config.clone()).generate(&specs)?;
+
+        let methods = specs
+            .methods
+            .iter()
+            .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+            .collect::<Vec<_>>();
+        for name in &self.methods {
+            if !methods.iter().any(|method| &method.name == name) {
+                bail!(UsageError(format!("unknown method {}", name)));
+            }
+        }
+
+        let generator = ExampleGenerator::new(&specs, &config, &result.protos)?;
+        let mut examples = IndexMap::new();
+        for method in methods {
+            if self.methods.is_empty() || self.methods.contains(&method.name) {
+                let mut method_examples = MethodExamples {
+                    rpc: generator.rpc_path(method),
+                    examples: vec![],
+                };
+                for example in generator.examples(method) {
+                    match example {
+                        Ok(example) => method_examples.examples.push(example),
+                        Err(err) => diagnostics.push(
+                            Diagnostic::warning(format!("{:#}", err)).code("example"),
+                        ),
+                    }
+                }
+                examples.insert(method.name.as_str(), method_examples);
+            }
+        }
+
+        match &self.output_dir {
+            Some(output_dir) => {
+                let output_path = Path::new(output_dir).join(&config.version);
+                fs::create_dir_all(&output_path)?;
+                for (method, method_examples) in examples {
+                    let path = output_path.join(format!("{}.json", method));
+                    fs::write(&path, serde_json::to_string_pretty(&method_examples)? + "\n")
+                        .with_context(|| format!("failed to write examples {}", path.display()))?;
+                }
+            }
+            None => println!("{}", serde_json::to_string_pretty(&examples)?),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use clap::Parser;
+
+use starknet_grpc_codegen::{
+    diagnostics::{Diagnostic, Diagnostics},
+    refs::{escape_pointer_segment, DocumentSet},
+    spec::ParseMode,
+    source::{GenerationProfile, RawExtension, RawSpecs, SpecVersion},
+};
+
+#[derive(Debug, Parser)]
+pub struct LintSpec {
+    #[clap(long, env, help = "Version of the specification")]
+    spec: SpecVersion,
+    #[clap(
+        long = "extension",
+        value_name = "[NAME=]PATH",
+        help = "Extension OpenRPC document to check along with the specification"
+    )]
+    extensions: Vec<String>,
+}
+
+impl LintSpec {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.version == self.spec)
+            .expect("Unable to find profile");
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|arg| RawExtension::load(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        lint(&profile.raw_specs, &extensions, diagnostics);
+
+        if diagnostics.iter().next().is_none() {
+            println!("No problems found");
+        }
+
+        Ok(())
+    }
+}
+
+/// Reports the problems of `raw_specs` and `extensions` as diagnostics
+fn lint(raw_specs: &RawSpecs, extensions: &[RawExtension], diagnostics: &mut Diagnostics) {
+    // Dangling references, missing errors, duplicate methods and conflicting schemas are all
+    // reported while parsing
+    raw_specs.parse_full(extensions, ParseMode::Lenient, diagnostics);
+
+    // Invalid JSON was reported while parsing as well
+    let mut documents = DocumentSet::new();
+    for (document, content) in raw_specs.documents() {
+        let _ = documents.add(document, &content);
+    }
+    for extension in extensions.iter() {
+        let _ = documents.add(&extension.document, &extension.content);
+    }
+
+    for (document, name) in documents.unused_schemas() {
+        diagnostics.push(
+            Diagnostic::warning(format!("schema {} is never used", name))
+                .code("unused-schema")
+                .at(
+                    &document,
+                    &format!("#/components/schemas/{}", escape_pointer_segment(&name)),
+                ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    const MAIN: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.1.0", "title": "Main", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "starknet_getFoo",
+                "summary": "Returns a foo",
+                "params": [],
+                "result": { "name": "result", "schema": { "$ref": "#/components/schemas/FOO" } },
+                "errors": [{ "$ref": "#/components/errors/FOO_NOT_FOUND" }]
+            }
+        ],
+        "components": {
+            "contentDescriptors": {},
+            "schemas": {
+                "FELT": { "type": "string", "pattern": "^0x[a-fA-F0-9]+$" },
+                "FOO": {
+                    "type": "object",
+                    "properties": { "bar": { "$ref": "#/components/schemas/FELT" } },
+                    "required": []
+                }
+            },
+            "errors": {
+                "FOO_NOT_FOUND": { "code": 1, "message": "Foo not found" }
+            }
+        }
+    }"##;
+
+    const WRITE: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.1.0", "title": "Write", "license": {} },
+        "servers": [],
+        "methods": [
+            {
+                "name": "starknet_addFoo",
+                "summary": "Adds a foo",
+                "params": [
+                    { "name": "foo", "required": true, "schema": { "$ref": "./api/starknet_api_openrpc.json#/components/schemas/FOO" } }
+                ],
+                "errors": [{ "$ref": "./api/starknet_api_openrpc.json#/components/errors/FOO_NOT_FOUND" }]
+            }
+        ],
+        "components": { "contentDescriptors": {}, "schemas": {}, "errors": {} }
+    }"##;
+
+    const TRACE: &str = r##"{
+        "openrpc": "1.0.0",
+        "info": { "version": "0.1.0", "title": "Trace", "license": {} },
+        "servers": [],
+        "methods": [],
+        "components": { "contentDescriptors": {}, "schemas": {}, "errors": {} }
+    }"##;
+
+    fn lint_documents(main: &str, write: &str) -> Vec<String> {
+        let raw_specs = RawSpecs {
+            main: Cow::Owned(main.to_string()),
+            write: Cow::Owned(write.to_string()),
+            trace: Cow::Borrowed(TRACE),
+            ws: None,
+        };
+        let mut diagnostics = Diagnostics::new();
+        lint(&raw_specs, &[], &mut diagnostics);
+        diagnostics
+            .iter()
+            .map(|d| {
+                format!(
+                    "{} {}{}",
+                    d.code.as_deref().unwrap(),
+                    d.document.as_deref().unwrap(),
+                    d.path.as_deref().unwrap()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_documents() {
+        assert_eq!(lint_documents(MAIN, WRITE), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_duplicate_methods_across_documents() {
+        let write = WRITE.replace("starknet_addFoo", "starknet_getFoo");
+        assert_eq!(
+            lint_documents(MAIN, &write),
+            vec!["duplicate-method starknet_write_api.json#/methods/0"]
+        );
+    }
+
+    #[test]
+    fn test_unused_schemas() {
+        let main = MAIN.replace(
+            r##""FELT": {"##,
+            r##""UNUSED": { "type": "integer" }, "FELT": {"##,
+        );
+        assert_eq!(
+            lint_documents(&main, WRITE),
+            vec!["unused-schema starknet_api_openrpc.json#/components/schemas/UNUSED"]
+        );
+    }
+
+    #[test]
+    fn test_missing_errors() {
+        let write = WRITE.replace("errors/FOO_NOT_FOUND", "errors/BAR_NOT_FOUND");
+        assert_eq!(
+            lint_documents(MAIN, &write),
+            vec!["missing-error starknet_write_api.json#/methods/0/errors/0"]
+        );
+    }
+}
@@ -1,5 +1,23 @@
+mod check_breaking;
+pub use check_breaking::CheckBreaking;
+
+mod check_json;
+pub use check_json::CheckJson;
+
+mod diff_spec;
+pub use diff_spec::DiffSpec;
+
+mod dump_ir;
+pub use dump_ir::DumpIr;
+
+mod examples;
+pub use examples::Examples;
+
 mod generate;
 pub use generate::Generate;
 
+mod lint_spec;
+pub use lint_spec::LintSpec;
+
 mod print;
 pub use print::Print;
@@ -1,7 +1,11 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{spec::Specification, GenerationProfile, SpecVersion};
+use starknet_grpc_codegen::{
+    diagnostics::Diagnostics,
+    source::{GenerationProfile, SpecVersion, MAIN_DOCUMENT},
+    spec::Specification,
+};
 
 #[derive(Debug, Parser)]
 pub struct Print {
@@ -12,24 +16,28 @@ pub struct Print {
 }
 
 impl Print {
-    pub(crate) fn run(self, profiles: &[GenerationProfile]) -> Result<()> {
+    pub(crate) fn run(
+        self,
+        profiles: &[GenerationProfile],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<()> {
         let profile = profiles
             .iter()
             .find(|profile| profile.version == self.spec)
             .expect("Unable to find profile");
 
-        let mut main_specs: Specification =
-            serde_json::from_str(profile.raw_specs.main).expect("Failed to parse specification");
+        let Some(mut main_specs) =
+            Specification::from_document(MAIN_DOCUMENT, &profile.raw_specs.main, diagnostics)
+        else {
+            return Ok(());
+        };
 
         if self.sort {
             main_specs.components.schemas.sort_keys();
             main_specs.components.errors.sort_keys();
         }
 
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&main_specs).expect("Failed to serialize specification")
-        );
+        println!("{}", serde_json::to_string_pretty(&main_specs)?);
 
         Ok(())
     }
@@ -0,0 +1,763 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{anyhow, bail, Context, Result};
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    proto_gen::{
+        descriptor::file_descriptor_set,
+        json_mapping::JsonMapping,
+        service::{find_rpc, subscription_notifications},
+        types::{ProtoField, ProtoFieldType, ProtoMessage, ProtoRpc},
+        writer::to_proto_name,
+        ProtoConfig, ProtoFile, ProtoGenerator,
+    },
+    spec::{self, ErrorType, Method, Primitive, Schema, Specification},
+};
+
+/// Converts the params and results of JSON-RPC calls to the protobuf messages generated for
+/// them and back.
+///
+/// Values go through the proto3 JSON mapping: 64-bit integers become strings, unions the key
+/// of their variant, enum values their proto names and free-form objects JSON strings. Decoding
+/// undoes it, writing integers back as hex strings where the spec has them so and filling in
+/// required values the wire format leaves out.
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    mapping: JsonMapping,
+    routes: HashMap<String, Route>,
+    errors: HashMap<String, spec::Error>,
+}
+
+/// JSON-RPC error object, carried by the `error` field of response messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JSON-RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Messages of the rpc generated for a method
+#[derive(Debug, Clone)]
+struct Route {
+    method: Method,
+    rpc: ProtoRpc,
+    path: String,
+    request: ProtoMessage,
+    response: ProtoMessage,
+    request_descriptor: MessageDescriptor,
+    response_descriptor: MessageDescriptor,
+    /// Notifications of a subscription, with the name of their field in the response
+    notifications: Vec<(String, Method)>,
+}
+
+impl Transcoder {
+    /// Transcoder between the methods of `specs` and `files`, generated from it with `config`
+    pub fn new(specs: &Specification, config: &ProtoConfig, files: &[ProtoFile]) -> Result<Self> {
+        let mapping = JsonMapping::new(specs, files)?;
+        let descriptor_set = file_descriptor_set(files, |_| 0)?;
+        let pool = DescriptorPool::from_file_descriptor_set(descriptor_set)
+            .context("invalid generated files")?;
+
+        let mut routes = HashMap::new();
+        let methods = specs
+            .methods
+            .iter()
+            .chain(specs.extensions.iter().flat_map(|extension| &extension.methods))
+            .collect::<Vec<_>>();
+        for &method in &methods {
+            let Some((file, service, rpc)) = find_rpc(files, config, &method.name) else {
+                continue;
+            };
+            let message = |name: &str| -> Result<(ProtoMessage, MessageDescriptor)> {
+                let message = file
+                    .messages
+                    .iter()
+                    .find(|message| message.name == name)
+                    .ok_or_else(|| anyhow!("{} is not defined in {}", name, file.name))?;
+                let full_name = format!("{}.{}", file.package, name);
+                let descriptor = pool
+                    .get_message_by_name(&full_name)
+                    .ok_or_else(|| anyhow!("{} has no descriptor", full_name))?;
+                Ok((message.clone(), descriptor))
+            };
+            let (request, request_descriptor) = message(&rpc.request_type)?;
+            let (response, response_descriptor) = message(&rpc.response_type)?;
+            let route = Route {
+                method: method.clone(),
+                rpc: rpc.clone(),
+                path: format!("/{}.{}/{}", file.package, service.name, rpc.name),
+                request,
+                response,
+                request_descriptor,
+                response_descriptor,
+                notifications: subscription_notifications(method, &methods)
+                    .into_iter()
+                    .map(|(field, notification)| (field, notification.clone()))
+                    .collect(),
+            };
+            routes.insert(method.name.clone(), route);
+        }
+
+        let errors = specs
+            .components
+            .errors
+            .iter()
+            .filter_map(|(name, error)| match error {
+                ErrorType::Error(error) => Some((name.clone(), error.clone())),
+                ErrorType::Reference(_) => None,
+            })
+            .collect();
+
+        Ok(Self {
+            mapping,
+            routes,
+            errors,
+        })
+    }
+
+    /// Transcoder for the files `config` generates from `specs`
+    pub fn for_spec(specs: &Specification, config: &ProtoConfig) -> Result<Self> {
+        let result = ProtoGenerator::new(config.clone()).generate(specs)?;
+        Self::new(specs, config, &result.protos)
+    }
+
+    /// Transcoder for the files `config` generates from the merged specification `spec_json`,
+    /// e.g. the one `generate --bridge` writes
+    pub fn from_spec_json(spec_json: &str, config: &ProtoConfig) -> Result<Self> {
+        let specs = serde_json::from_str(spec_json).context("invalid specification")?;
+        Self::for_spec(&specs, config)
+    }
+
+    /// Method of the spec named `name`, if it has an rpc
+    pub fn method(&self, name: &str) -> Option<&Method> {
+        self.routes.get(name).map(|route| &route.method)
+    }
+
+    /// Rpc generated for `method`
+    pub fn rpc(&self, method: &str) -> Option<&ProtoRpc> {
+        self.routes.get(method).map(|route| &route.rpc)
+    }
+
+    /// Error `method` declares with `code`, if any
+    pub fn spec_error(&self, method: &str, code: i64) -> Option<&spec::Error> {
+        let route = self.routes.get(method)?;
+        route
+            .method
+            .errors
+            .iter()
+            .flatten()
+            .filter_map(|reference| self.errors.get(reference.name()))
+            .find(|error| i64::from(error.code) == code)
+    }
+
+    /// Names of the methods with an rpc
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.routes.keys().map(String::as_str)
+    }
+
+    /// gRPC path of the rpc generated for `method`, e.g.
+    /// `/starknet.v0_8_1.main.StarknetMainService/BlockNumber`
+    pub fn rpc_path(&self, method: &str) -> Option<&str> {
+        self.routes.get(method).map(|route| route.path.as_str())
+    }
+
+    /// Params by name of `method` called with `params`, by name or by position, checked against
+    /// the schemas of its params
+    pub fn params_by_name(&self, method: &str, params: &Value) -> Result<Map<String, Value>> {
+        let route = self.route(method)?;
+        let params: Map<String, Value> = match params {
+            Value::Object(params) => params.clone(),
+            Value::Array(values) => {
+                if values.len() > route.method.params.len() {
+                    bail!("{} takes at most {} params", method, route.method.params.len());
+                }
+                route
+                    .method
+                    .params
+                    .iter()
+                    .zip(values)
+                    .map(|(param, value)| (param.name.clone(), value.clone()))
+                    .collect()
+            }
+            Value::Null => Map::new(),
+            _ => bail!("params of {} must be an object or an array", method),
+        };
+        if let Some(param) = route
+            .method
+            .params
+            .iter()
+            .find(|param| param.required && !params.contains_key(&param.name))
+        {
+            bail!("missing required param `{}` of {}", param.name, method);
+        }
+        for (name, value) in &params {
+            let param = route
+                .method
+                .params
+                .iter()
+                .find(|param| &param.name == name)
+                .ok_or_else(|| anyhow!("`{}` is not a param of {}", name, method))?;
+            if !self.mapping.matches(value, &param.schema) {
+                bail!("param `{}` of {} doesn't match its schema", name, method);
+            }
+        }
+        Ok(params)
+    }
+
+    /// Request message of `method` called with `params`, by name or by position
+    pub fn params_to_message(&self, method: &str, params: &Value) -> Result<DynamicMessage> {
+        let route = self.route(method)?;
+        let params = self.params_by_name(method, params)?;
+        let json = self
+            .mapping
+            .request_to_proto_json(&route.request, &route.method, &params)
+            .with_context(|| format!("params of {}", method))?;
+        DynamicMessage::deserialize(route.request_descriptor.clone(), json)
+            .with_context(|| format!("params of {}", method))
+    }
+
+    /// Params by name of `method` called with `message`
+    pub fn message_to_params(
+        &self,
+        method: &str,
+        message: &DynamicMessage,
+    ) -> Result<Map<String, Value>> {
+        let route = self.route(method)?;
+        let json = serde_json::to_value(message)?;
+        let mut params = Map::new();
+        for param in &route.method.params {
+            let field = route
+                .request
+                .fields
+                .iter()
+                .find(|field| field.json_name() == param.name)
+                .ok_or_else(|| anyhow!("param `{}` has no field in {}", param.name, method))?;
+            let value = self
+                .field_value(field, &param.schema, json.get(field.json_name()), param.required)
+                .with_context(|| format!("{}.{}", route.request.name, field.name))?;
+            if let Some(value) = value {
+                params.insert(param.name.clone(), value);
+            }
+        }
+        Ok(params)
+    }
+
+    /// Response message of `method` returning `result`
+    pub fn result_to_message(&self, method: &str, result: &Value) -> Result<DynamicMessage> {
+        let route = self.route(method)?;
+        let json = self
+            .mapping
+            .response_to_proto_json(&route.response, &route.method, Some(result))
+            .with_context(|| format!("result of {}", method))?;
+        DynamicMessage::deserialize(route.response_descriptor.clone(), json)
+            .with_context(|| format!("result of {}", method))
+    }
+
+    /// Response message of `method` failing with `error`
+    pub fn error_to_message(&self, method: &str, error: &RpcError) -> Result<DynamicMessage> {
+        let route = self.route(method)?;
+        let mut json = json!({ "code": error.code, "message": error.message });
+        if let Some(data) = &error.data {
+            json["data"] = Value::String(data.to_string());
+        }
+        DynamicMessage::deserialize(route.response_descriptor.clone(), json!({ "error": json }))
+            .with_context(|| format!("error of {}", method))
+    }
+
+    /// Result of `method` returning `message`, `null` for methods without one. If the message
+    /// carries an error instead, it's returned as an [`RpcError`] to downcast to.
+    pub fn message_to_result(&self, method: &str, message: &DynamicMessage) -> Result<Value> {
+        let route = self.route(method)?;
+        let json = serde_json::to_value(message)?;
+        if let Some(error) = json.get("error") {
+            let data = match error.get("data").and_then(Value::as_str) {
+                Some(data) => Some(serde_json::from_str(data).context("invalid error data")?),
+                None => None,
+            };
+            return Err(RpcError {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                message: error.get("message").and_then(Value::as_str).unwrap_or_default().into(),
+                data,
+            }
+            .into());
+        }
+        let Some(result) = &route.method.result else {
+            return Ok(Value::Null);
+        };
+        let field = route
+            .response
+            .fields
+            .iter()
+            .find(|field| field.name == "result")
+            .ok_or_else(|| anyhow!("the result has no field in {}", route.response.name))?;
+        let value = self
+            .field_value(field, &result.schema, json.get(field.json_name()), true)
+            .with_context(|| format!("{}.{}", route.response.name, field.name))?;
+        value.ok_or_else(|| anyhow!("{} returned no result", method))
+    }
+
+    /// Names of the notifications the subscription `method` receives, none for other methods
+    pub fn notifications(&self, method: &str) -> impl Iterator<Item = &str> {
+        let notifications = self.routes.get(method).map(|route| &route.notifications);
+        notifications
+            .into_iter()
+            .flatten()
+            .map(|(_, notification)| notification.name.as_str())
+    }
+
+    /// Response message of the subscription `method` receiving `notification` with `result`
+    pub fn notification_to_message(
+        &self,
+        method: &str,
+        notification: &str,
+        result: &Value,
+    ) -> Result<DynamicMessage> {
+        let (route, field, schema) = self.notification_field(method, notification)?;
+        let converted = self
+            .mapping
+            .field_to_proto_json(field, schema, result)
+            .with_context(|| format!("result of {}", notification))?;
+        DynamicMessage::deserialize(
+            route.response_descriptor.clone(),
+            json!({ field.json_name(): converted }),
+        )
+        .with_context(|| format!("result of {}", notification))
+    }
+
+    /// Notification received by the subscription `method` and its result, if `message` carries
+    /// one
+    pub fn message_to_notification(
+        &self,
+        method: &str,
+        message: &DynamicMessage,
+    ) -> Result<Option<(String, Value)>> {
+        let route = self.route(method)?;
+        let json = serde_json::to_value(message)?;
+        for (_, notification) in &route.notifications {
+            let (_, field, schema) = self.notification_field(method, &notification.name)?;
+            let Some(value) = json.get(field.json_name()) else {
+                continue;
+            };
+            let result = self
+                .field_value(field, schema, Some(value), true)
+                .with_context(|| format!("{}.{}", route.response.name, field.name))?;
+            return Ok(Some((notification.name.clone(), result.unwrap_or_default())));
+        }
+        Ok(None)
+    }
+
+    /// Wire bytes of the request message of `method` called with `params`
+    pub fn encode_params(&self, method: &str, params: &Value) -> Result<Vec<u8>> {
+        Ok(self.params_to_message(method, params)?.encode_to_vec())
+    }
+
+    /// Params by name of `method` called with the request message in `bytes`
+    pub fn decode_params(&self, method: &str, bytes: &[u8]) -> Result<Map<String, Value>> {
+        let route = self.route(method)?;
+        let message = DynamicMessage::decode(route.request_descriptor.clone(), bytes)
+            .with_context(|| format!("invalid {}", route.request.name))?;
+        self.message_to_params(method, &message)
+    }
+
+    /// Wire bytes of the response message of `method` returning `result`
+    pub fn encode_result(&self, method: &str, result: &Value) -> Result<Vec<u8>> {
+        Ok(self.result_to_message(method, result)?.encode_to_vec())
+    }
+
+    /// Wire bytes of the response message of `method` failing with `error`
+    pub fn encode_error(&self, method: &str, error: &RpcError) -> Result<Vec<u8>> {
+        Ok(self.error_to_message(method, error)?.encode_to_vec())
+    }
+
+    /// Wire bytes of the response message of the subscription `method` receiving `notification`
+    pub fn encode_notification(
+        &self,
+        method: &str,
+        notification: &str,
+        result: &Value,
+    ) -> Result<Vec<u8>> {
+        Ok(self.notification_to_message(method, notification, result)?.encode_to_vec())
+    }
+
+    /// Notification and result carried by the response message of the subscription `method` in
+    /// `bytes`, if any
+    pub fn decode_notification(
+        &self,
+        method: &str,
+        bytes: &[u8],
+    ) -> Result<Option<(String, Value)>> {
+        let route = self.route(method)?;
+        let message = DynamicMessage::decode(route.response_descriptor.clone(), bytes)
+            .with_context(|| format!("invalid {}", route.response.name))?;
+        self.message_to_notification(method, &message)
+    }
+
+    /// Result of `method` returning the response message in `bytes`
+    pub fn decode_result(&self, method: &str, bytes: &[u8]) -> Result<Value> {
+        let route = self.route(method)?;
+        let message = DynamicMessage::decode(route.response_descriptor.clone(), bytes)
+            .with_context(|| format!("invalid {}", route.response.name))?;
+        self.message_to_result(method, &message)
+    }
+
+    fn route(&self, method: &str) -> Result<&Route> {
+        self.routes
+            .get(method)
+            .ok_or_else(|| anyhow!("no rpc is generated for {}", method))
+    }
+
+    /// Field of the response of the subscription `method` carrying `notification`, along with
+    /// the schema of its result
+    fn notification_field(
+        &self,
+        method: &str,
+        notification: &str,
+    ) -> Result<(&Route, &ProtoField, &Schema)> {
+        let route = self.route(method)?;
+        let (name, notification) = route
+            .notifications
+            .iter()
+            .find(|(_, candidate)| candidate.name == notification)
+            .ok_or_else(|| anyhow!("{} doesn't receive {}", method, notification))?;
+        let field = route
+            .response
+            .oneofs
+            .iter()
+            .flat_map(|oneof| &oneof.fields)
+            .find(|field| &field.name == name)
+            .ok_or_else(|| {
+                anyhow!("{} has no field in {}", notification.name, route.response.name)
+            })?;
+        let result = notification
+            .params
+            .iter()
+            .find(|param| param.name == "result")
+            .ok_or_else(|| anyhow!("{} has no result", notification.name))?;
+        Ok((route, field, &result.schema))
+    }
+
+    /// Spec JSON of `field` set to the proto3 JSON `value`, valid against `schema`. Fields left
+    /// out of the proto3 JSON are their default if `required`, or have no value.
+    fn field_value(
+        &self,
+        field: &ProtoField,
+        schema: &Schema,
+        value: Option<&Value>,
+        required: bool,
+    ) -> Result<Option<Value>> {
+        let default;
+        let value = match value {
+            Some(value) => value,
+            None if required && !field.optional && field.oneof_name.is_none() => {
+                match self.mapping.default_proto_json(field) {
+                    Some(value) => {
+                        default = value;
+                        &default
+                    }
+                    None => bail!("missing required value"),
+                }
+            }
+            None => return Ok(None),
+        };
+
+        if field.repeated {
+            let items = value.as_array().context("expected an array")?;
+            let items_schema = match self.mapping.resolve(schema) {
+                Schema::Primitive(Primitive::Array(array)) => &array.items,
+                _ => bail!("the list has no array schema"),
+            };
+            let items = items
+                .iter()
+                .map(|item| self.typed_value(&field.field_type, items_schema, item))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Some(Value::Array(items)));
+        }
+        self.typed_value(&field.field_type, schema, value).map(Some)
+    }
+
+    /// Spec JSON of a single proto3 JSON value of `field_type`
+    fn typed_value(
+        &self,
+        field_type: &ProtoFieldType,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<Value> {
+        match field_type {
+            ProtoFieldType::Int64 | ProtoFieldType::Uint64 => {
+                let number = match value {
+                    Value::String(number) => number.as_str(),
+                    Value::Number(number) => return Ok(Value::Number(number.clone())),
+                    _ => bail!("expected an integer, got {}", value),
+                };
+                let is_string = matches!(
+                    self.mapping.resolve(schema),
+                    Schema::Primitive(Primitive::String(_))
+                );
+                match (field_type, is_string) {
+                    (ProtoFieldType::Int64, false) => {
+                        Ok(Value::from(number.parse::<i64>().context("invalid int64")?))
+                    }
+                    (_, false) => Ok(Value::from(number.parse::<u64>().context("invalid uint64")?)),
+                    (_, true) => {
+                        let number = number.parse::<u64>().context("invalid uint64")?;
+                        Ok(Value::String(format!("{:#x}", number)))
+                    }
+                }
+            }
+            ProtoFieldType::Message(name) | ProtoFieldType::Enum(name) => {
+                if let Some(message) = self.mapping.message(name) {
+                    return self.message_value(message, schema, value);
+                }
+                let proto_enum = self
+                    .mapping
+                    .proto_enum(name)
+                    .ok_or_else(|| anyhow!("{} is not defined", name))?;
+                let index = proto_enum
+                    .values
+                    .iter()
+                    .position(|proto_value| value == proto_value.name.as_str())
+                    .ok_or_else(|| anyhow!("{} is not a value of {}", value, proto_enum.name))?;
+                let spec_value = self
+                    .mapping
+                    .spec_enum_values(schema)
+                    .and_then(|values| values.get(index))
+                    .ok_or_else(|| anyhow!("{} has no value in the spec", value))?;
+                Ok(Value::String(spec_value.clone()))
+            }
+            _ => Ok(value.clone()),
+        }
+    }
+
+    /// Spec JSON of the proto3 JSON of `message`, generated from `schema`
+    fn message_value(
+        &self,
+        message: &ProtoMessage,
+        schema: &Schema,
+        value: &Value,
+    ) -> Result<Value> {
+        if message.name == "Object" {
+            let data = value.get("data").and_then(Value::as_str).unwrap_or("null");
+            return serde_json::from_str(data).context("invalid JSON in Object.data");
+        }
+        let object = value.as_object().context("expected an object")?;
+
+        let schema = self.mapping.resolve(schema);
+        if let (Schema::OneOf(one_of), Some(oneof)) = (schema, message.declared_oneofs().next()) {
+            let (field, variant, value) = oneof
+                .fields
+                .iter()
+                .zip(&one_of.one_of)
+                .find_map(|(field, variant)| {
+                    Some((field, variant, object.get(&field.json_name())?))
+                })
+                .ok_or_else(|| anyhow!("no variant of {} is set", message.name))?;
+            return self
+                .field_value(field, variant, Some(value), true)
+                .map(Option::unwrap_or_default)
+                .with_context(|| format!("{}.{}", message.name, field.name));
+        }
+
+        let Some((properties, members)) = self.mapping.properties(schema) else {
+            let field = match message.fields.as_slice() {
+                [field] => field,
+                _ => bail!("{} doesn't wrap a single value", message.name),
+            };
+            return self
+                .field_value(field, schema, object.get(&field.json_name()), true)
+                .map(Option::unwrap_or_default)
+                .with_context(|| format!("{}.{}", message.name, field.name));
+        };
+
+        let mut spec_object = Map::new();
+        for field in &message.fields {
+            let json_name = field.json_name();
+            if let Some(property) = properties.iter().find(|property| property.name == json_name) {
+                let value = self
+                    .field_value(field, property.schema, object.get(&json_name), property.required)
+                    .with_context(|| format!("{}.{}", message.name, field.name))?;
+                if let Some(value) = value {
+                    spec_object.insert(property.name.to_owned(), value);
+                }
+            } else if let Some(member) =
+                members.iter().find(|member| to_proto_name(member) == field.name)
+            {
+                // Members hold properties of the object itself
+                let (Some(member_schema), Some(value)) =
+                    (self.mapping.schema(member), object.get(&json_name))
+                else {
+                    continue;
+                };
+                let value = self
+                    .field_value(field, member_schema, Some(value), true)
+                    .with_context(|| format!("{}.{}", message.name, field.name))?;
+                if let Some(Value::Object(member_object)) = value {
+                    spec_object.extend(member_object);
+                }
+            }
+        }
+        Ok(Value::Object(spec_object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto_gen::examples::ExampleGenerator;
+
+    fn create_test_transcoder() -> (Specification, ProtoConfig, Vec<ProtoFile>, Transcoder) {
+        let specs: Specification =
+            serde_json::from_str(include_str!("./specs/0.8.1/starknet_api_openrpc.json")).unwrap();
+        let config = ProtoConfig::new("v0_8_1");
+        let result = ProtoGenerator::new(config.clone()).generate(&specs).unwrap();
+        let transcoder = Transcoder::new(&specs, &config, &result.protos).unwrap();
+        (specs, config, result.protos, transcoder)
+    }
+
+    #[test]
+    fn test_params_by_position_are_decoded_by_name() {
+        let (_, _, _, transcoder) = create_test_transcoder();
+        let method = "starknet_getBlockWithTxHashes";
+        assert_eq!(
+            transcoder.rpc_path(method).unwrap(),
+            "/starknet.v0_8_1.main.StarknetMainService/GetBlockWithTxHashes"
+        );
+
+        let bytes = transcoder.encode_params(method, &json!([{ "block_number": 0 }])).unwrap();
+        let params = transcoder.decode_params(method, &bytes).unwrap();
+        assert_eq!(Value::Object(params), json!({ "block_id": { "block_number": 0 } }));
+
+        let bytes = transcoder.encode_params(method, &json!({ "block_id": "latest" })).unwrap();
+        let params = transcoder.decode_params(method, &bytes).unwrap();
+        assert_eq!(Value::Object(params), json!({ "block_id": "latest" }));
+
+        let err = transcoder.encode_params(method, &json!([])).unwrap_err();
+        assert_eq!(err.to_string(), format!("missing required param `block_id` of {}", method));
+        assert!(transcoder.encode_params("starknet_unknown", &json!([])).is_err());
+    }
+
+    #[test]
+    fn test_examples_round_trip_through_wire_bytes() {
+        let (specs, config, files, transcoder) = create_test_transcoder();
+        let generator = ExampleGenerator::new(&specs, &config, &files).unwrap();
+
+        let mut checked = 0;
+        for method in &specs.methods {
+            for example in generator.examples(method).into_iter().flatten() {
+                if !example.proto_errors.is_empty() {
+                    continue;
+                }
+                let context = format!("{} example of {}", example.name, method.name);
+                let params = Value::Object(example.params.clone());
+                let bytes = transcoder.encode_params(&method.name, &params).expect(&context);
+                let decoded = transcoder.decode_params(&method.name, &bytes).expect(&context);
+                assert_eq!(decoded, example.params, "{}", context);
+
+                if let Some(result) = &example.result {
+                    let bytes = transcoder.encode_result(&method.name, result).expect(&context);
+                    let decoded = transcoder.decode_result(&method.name, &bytes).expect(&context);
+                    assert_eq!(&decoded, result, "{}", context);
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_notifications_round_trip_through_wire_bytes() {
+        let specs: Specification = serde_json::from_str(
+            r##"{
+                "openrpc": "1.0.0",
+                "info": { "version": "0.8.1", "title": "Test", "license": {} },
+                "servers": [],
+                "methods": [
+                    {
+                        "name": "starknet_subscribeNewHeads",
+                        "summary": "New block headers subscription",
+                        "params": [],
+                        "result": { "name": "subscription_id", "schema": { "type": "string" } }
+                    },
+                    {
+                        "name": "starknet_subscriptionNewHeads",
+                        "summary": "New block headers notification",
+                        "params": [
+                            {
+                                "name": "subscription_id",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            {
+                                "name": "result",
+                                "required": true,
+                                "schema": { "$ref": "#/components/schemas/BLOCK_HEADER" }
+                            }
+                        ]
+                    },
+                    {
+                        "name": "starknet_subscriptionReorg",
+                        "summary": "Reorganization notification",
+                        "params": [
+                            {
+                                "name": "subscription_id",
+                                "required": true,
+                                "schema": { "type": "string" }
+                            },
+                            { "name": "result", "required": true, "schema": { "type": "integer" } }
+                        ]
+                    }
+                ],
+                "components": {
+                    "contentDescriptors": {},
+                    "schemas": {
+                        "BLOCK_HEADER": {
+                            "type": "object",
+                            "properties": {
+                                "block_hash": { "type": "string" },
+                                "block_number": { "type": "integer" }
+                            },
+                            "required": ["block_hash", "block_number"]
+                        }
+                    },
+                    "errors": {}
+                }
+            }"##,
+        )
+        .unwrap();
+        let transcoder = Transcoder::for_spec(&specs, &ProtoConfig::new("v0_8_1")).unwrap();
+        let method = "starknet_subscribeNewHeads";
+        assert_eq!(
+            transcoder.notifications(method).collect::<Vec<_>>(),
+            vec!["starknet_subscriptionNewHeads", "starknet_subscriptionReorg"]
+        );
+
+        let header = json!({ "block_hash": "0x1", "block_number": 7 });
+        let notifications = [
+            ("starknet_subscriptionNewHeads", header),
+            ("starknet_subscriptionReorg", json!(3)),
+        ];
+        for (notification, result) in notifications {
+            let bytes = transcoder.encode_notification(method, notification, &result).unwrap();
+            let decoded = transcoder.decode_notification(method, &bytes).unwrap();
+            assert_eq!(decoded, Some((notification.to_owned(), result)));
+        }
+
+        // The subscription id comes as the result
+        let bytes = transcoder.encode_result(method, &json!("0x42")).unwrap();
+        assert_eq!(transcoder.decode_notification(method, &bytes).unwrap(), None);
+        assert_eq!(transcoder.decode_result(method, &bytes).unwrap(), json!("0x42"));
+        assert!(transcoder.encode_notification(method, "starknet_unknown", &json!(1)).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! Generates the gRPC bridge of the 0.8.1 spec and runs the tests of `tests/bridge_fixture`,
+//! which compiles it with `tonic-prost-build` and calls it against a mock JSON-RPC node.
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn test_generated_bridge_forwards_calls_to_a_node() {
+    let dir = tempfile::tempdir().unwrap();
+    let version_dir = dir.path().join("v0_8_1");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--bridge"])
+        .arg("--output-dir")
+        .arg(dir.path())
+        .arg("--descriptor-set")
+        .arg(version_dir.join("starknet.binpb"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generate --bridge failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The fixture has its own target directory, kept between runs
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/bridge_fixture");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bridge-fixture");
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(fixture.join("Cargo.toml"))
+        .env("BRIDGE_FIXTURE_DIR", &version_dir)
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "bridge fixture failed:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,15 @@
+use std::{env, fs, path::Path};
+
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=BRIDGE_FIXTURE_DIR");
+    let dir = env::var("BRIDGE_FIXTURE_DIR").expect("BRIDGE_FIXTURE_DIR is not set");
+    let descriptor_set = Path::new(&dir).join("starknet.binpb");
+    println!("cargo:rerun-if-changed={}", descriptor_set.display());
+
+    let bytes = fs::read(&descriptor_set).unwrap();
+    let fds = FileDescriptorSet::decode(bytes.as_slice()).unwrap();
+    tonic_prost_build::configure().compile_fds(fds).unwrap();
+}
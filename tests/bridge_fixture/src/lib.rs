@@ -0,0 +1,22 @@
+pub mod starknet {
+    pub mod v0_8_1 {
+        pub mod common {
+            tonic::include_proto!("starknet.v0_8_1.common");
+        }
+        pub mod main {
+            tonic::include_proto!("starknet.v0_8_1.main");
+        }
+        pub mod write {
+            tonic::include_proto!("starknet.v0_8_1.write");
+        }
+        pub mod trace {
+            tonic::include_proto!("starknet.v0_8_1.trace");
+        }
+        pub mod ws {
+            tonic::include_proto!("starknet.v0_8_1.ws");
+        }
+        pub mod bridge {
+            include!(concat!(env!("BRIDGE_FIXTURE_DIR"), "/bridge.rs"));
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::sync::{Arc, Mutex};
+
+use bridge_fixture::starknet::v0_8_1::{
+    bridge::{bridge, json_rpc_server},
+    common::{block_id, BlockId, BlockNumberWrapper, BlockTag, TxnExecutionStatus, TxnStatus},
+    main::{
+        starknet_main_service_server::{StarknetMainService, StarknetMainServiceServer},
+        BlockNumberRequest, GetClassHashAtRequest,
+    },
+    ws::{
+        starknet_ws_service_server::StarknetWsService,
+        subscribe_transaction_status_response::Notification, SubscribeTransactionStatusRequest,
+    },
+};
+use futures_util::{SinkExt, StreamExt};
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use tonic::{
+    transport::{server::TcpIncoming, Channel, Server},
+    Request,
+};
+
+/// JSON-RPC node on a local port answering each method with the result or error `reply` gives,
+/// along with the requests it received
+async fn mock_node(reply: fn(&str) -> Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let received = received.clone();
+            let service = service_fn(move |request: hyper::Request<hyper::body::Incoming>| {
+                let received = received.clone();
+                async move {
+                    let body = request.into_body().collect().await?.to_bytes();
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    let mut response = reply(request["method"].as_str().unwrap());
+                    response["jsonrpc"] = json!("2.0");
+                    response["id"] = request["id"].clone();
+                    received.lock().unwrap().push(request);
+                    let body = Full::new(Bytes::from(response.to_string()));
+                    Ok::<_, hyper::Error>(hyper::Response::new(body))
+                }
+            });
+            tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        }
+    });
+    (url, requests)
+}
+
+#[tokio::test]
+async fn test_generated_services_forward_calls() {
+    let (url, requests) = mock_node(|method| match method {
+        "starknet_blockNumber" => json!({ "result": 42 }),
+        "starknet_getClassHashAt" => json!({ "result": "0x1234" }),
+        _ => json!({ "error": { "code": -32601, "message": "Method not found" } }),
+    })
+    .await;
+    let bridge = bridge(&url).unwrap();
+
+    let response = bridge.block_number(Request::new(BlockNumberRequest {})).await.unwrap();
+    assert_eq!(response.into_inner().result, 42);
+
+    let block_ids = [
+        block_id::Value::Variant2(BlockNumberWrapper { block_number: 7 }),
+        block_id::Value::Variant3(BlockTag::Pending.into()),
+    ];
+    for value in block_ids {
+        let request = GetClassHashAtRequest {
+            block_id: Some(BlockId { value: Some(value) }),
+            contract_address: "0x1".to_owned(),
+        };
+        let response = bridge.get_class_hash_at(Request::new(request)).await.unwrap();
+        assert_eq!(response.into_inner().result, "0x1234");
+    }
+
+    // Params are passed by name, in the JSON of the spec
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0]["method"], "starknet_blockNumber");
+    assert_eq!(requests[1]["method"], "starknet_getClassHashAt");
+    assert_eq!(
+        requests[1]["params"],
+        json!({ "block_id": { "block_number": 7 }, "contract_address": "0x1" })
+    );
+    assert_eq!(
+        requests[2]["params"],
+        json!({ "block_id": "pending", "contract_address": "0x1" })
+    );
+}
+
+#[tokio::test]
+async fn test_json_rpc_errors_are_returned_in_the_response() {
+    let (url, _) = mock_node(|_| {
+        json!({ "error": { "code": 20, "message": "Contract not found" } })
+    })
+    .await;
+    let bridge = bridge(&url).unwrap();
+
+    let request = GetClassHashAtRequest {
+        block_id: Some(BlockId {
+            value: Some(block_id::Value::Variant3(BlockTag::Latest.into())),
+        }),
+        contract_address: "0x1".to_owned(),
+    };
+    let response = bridge.get_class_hash_at(Request::new(request)).await.unwrap().into_inner();
+    let error = response.error.unwrap();
+    assert_eq!((error.code, error.message.as_str()), (20, "Contract not found"));
+}
+
+/// WebSocket JSON-RPC node on a local port answering each request with the messages `reply`
+/// gives, the first one being its response, along with the requests it received
+async fn mock_ws_node(reply: fn(&str) -> Vec<Value>) -> (String, mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                while let Some(Ok(Message::Text(text))) = socket.next().await {
+                    let request: Value = serde_json::from_str(text.as_str()).unwrap();
+                    let mut messages = reply(request["method"].as_str().unwrap());
+                    messages[0]["jsonrpc"] = json!("2.0");
+                    messages[0]["id"] = request["id"].clone();
+                    let _ = sender.send(request);
+                    for message in messages {
+                        socket.send(Message::text(message.to_string())).await.unwrap();
+                    }
+                }
+            });
+        }
+    });
+    (url, receiver)
+}
+
+#[tokio::test]
+async fn test_generated_subscriptions_stream_notifications() {
+    let (url, mut requests) = mock_ws_node(|method| match method {
+        "starknet_subscribeTransactionStatus" => vec![
+            json!({ "result": "0x42" }),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "starknet_subscriptionTransactionStatus",
+                "params": {
+                    "subscription_id": "0x42",
+                    "result": {
+                        "transaction_hash": "0x1",
+                        "status": {
+                            "finality_status": "ACCEPTED_ON_L2",
+                            "execution_status": "REVERTED"
+                        }
+                    }
+                }
+            }),
+        ],
+        _ => vec![json!({ "result": true })],
+    })
+    .await;
+    let bridge = bridge(&url).unwrap();
+
+    let request = SubscribeTransactionStatusRequest {
+        transaction_hash: "0x1".to_owned(),
+    };
+    let mut stream = bridge
+        .subscribe_transaction_status(Request::new(request))
+        .await
+        .unwrap()
+        .into_inner();
+    let response = stream.next().await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap().value, "0x42");
+
+    let response = stream.next().await.unwrap().unwrap();
+    let Some(Notification::TransactionStatus(status)) = response.notification else {
+        panic!("expected a transaction status, got {:?}", response.notification);
+    };
+    assert_eq!(status.transaction_hash, "0x1");
+    let status = status.status.unwrap();
+    assert_eq!(status.finality_status(), TxnStatus::AcceptedOnL2);
+    assert_eq!(status.execution_status(), TxnExecutionStatus::Reverted);
+
+    // Dropping the stream unsubscribes
+    drop(stream);
+    let subscribe = requests.recv().await.unwrap();
+    assert_eq!(subscribe["params"], json!({ "transaction_hash": "0x1" }));
+    let unsubscribe = requests.recv().await.unwrap();
+    assert_eq!(unsubscribe["method"], "starknet_unsubscribe");
+    assert_eq!(unsubscribe["params"], json!({ "subscription_id": "0x42" }));
+}
+
+/// Sends the JSON-RPC `request` to the server at `addr`, returning its response
+async fn post(addr: std::net::SocketAddr, request: Value) -> Value {
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(stream)).await.unwrap();
+    tokio::spawn(connection);
+    let request = hyper::Request::post("/")
+        .header(hyper::header::HOST, addr.to_string())
+        .body(Full::new(Bytes::from(request.to_string())))
+        .unwrap();
+    let response = sender.send_request(request).await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_json_rpc_server_calls_the_generated_services() {
+    // JSON-RPC server -> gRPC services of the bridge -> mock node
+    let (node_url, requests) = mock_node(|method| match method {
+        "starknet_getClassHashAt" => json!({ "result": "0x1234" }),
+        _ => json!({ "error": { "code": 24, "message": "Block not found" } }),
+    })
+    .await;
+    let grpc_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let grpc_url = format!("http://{}", grpc_listener.local_addr().unwrap());
+    let services = StarknetMainServiceServer::new(bridge(&node_url).unwrap());
+    tokio::spawn(
+        Server::builder()
+            .add_service(services)
+            .serve_with_incoming(TcpIncoming::from(grpc_listener)),
+    );
+
+    let channel = Channel::from_shared(grpc_url).unwrap().connect_lazy();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(json_rpc_server(channel).unwrap().serve(listener));
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "starknet_getClassHashAt",
+        "params": [{ "block_number": 7 }, "0x1"],
+    });
+    let response = post(addr, request).await;
+    assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 1, "result": "0x1234" }));
+    assert_eq!(
+        requests.lock().unwrap()[0]["params"],
+        json!({ "block_id": { "block_number": 7 }, "contract_address": "0x1" })
+    );
+
+    // Errors of the node keep their spec code, invalid params never reach it
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "starknet_getBlockWithTxHashes",
+        "params": { "block_id": "latest" },
+    });
+    let response = post(addr, request).await;
+    assert_eq!(response["error"], json!({ "code": 24, "message": "Block not found" }));
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "starknet_getBlockWithTxHashes",
+        "params": { "block_id": "earliest" },
+    });
+    let response = post(addr, request).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,148 @@
+//! Generates through the library API build scripts use, and compares its output with what the
+//! `generate` subcommand writes.
+
+use std::{fs, process::Command};
+
+use starknet_grpc_codegen::{
+    codegen::passes::{FilterMethods, Rename},
+    proto_gen::{
+        rust::{BridgeBackend, BRIDGE_SPEC_FILE},
+        rust_serde::RustSerdeBackend,
+        ProtoIr,
+    },
+    source::MAIN_DOCUMENT,
+    Builder, SpecVersion,
+};
+
+const PROTO_FILES: &[&str] =
+    &["common.proto", "main.proto", "write.proto", "trace.proto", "ws.proto"];
+
+#[test]
+fn test_builder_writes_what_generate_writes() {
+    let cli = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--output-dir"])
+        .arg(cli.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let library = tempfile::tempdir().unwrap();
+    let generated = Builder::bundled(SpecVersion::V0_8_1)
+        .with_output_dir(library.path())
+        .generate()
+        .unwrap();
+    assert_eq!(generated.dir, library.path().join("v0_8_1"));
+    assert_eq!(generated.files.len(), PROTO_FILES.len());
+
+    for file in PROTO_FILES.iter().chain(&["buf.yaml", "buf.gen.rust.yaml", "README.md"]) {
+        let expected = fs::read_to_string(cli.path().join("v0_8_1").join(file)).unwrap();
+        let written = fs::read_to_string(generated.dir.join(file)).unwrap();
+        assert!(written == expected, "{} differs from the one of generate", file);
+    }
+}
+
+#[test]
+fn test_passes_run_before_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let generated = Builder::bundled(SpecVersion::V0_8_1)
+        .with_pass(FilterMethods::new(|method| method.starts_with("starknet_getBlock")))
+        .with_pass(Rename::new("starknet.v0_8_1.common.BlockId", "BlockSelector"))
+        .with_pass(|_: &_, ir: &mut ProtoIr| {
+            // Passes see what the ones before them did
+            assert!(ir.methods.values().all(|method| method.starts_with("starknet_getBlock")));
+            Ok(())
+        })
+        .with_output_dir(dir.path())
+        .generate()
+        .unwrap();
+
+    let mut compiler = protox::Compiler::new([&generated.dir]).unwrap();
+    compiler.include_imports(true);
+    compiler.open_files(PROTO_FILES).unwrap();
+    let main = fs::read_to_string(generated.dir.join("main.proto")).unwrap();
+    assert!(main.contains("starknet.v0_8_1.common.BlockSelector block_id = 1"));
+    assert!(main.contains("rpc GetBlockWithTxHashes("));
+    assert!(!main.contains("rpc SpecVersion("));
+
+    // Broken definitions aren't written
+    let error = Builder::bundled(SpecVersion::V0_8_1)
+        .with_pass(|_: &_, ir: &mut ProtoIr| {
+            ir.files[0].imports.clear();
+            ir.files[1].imports.clear();
+            Ok(())
+        })
+        .with_output_dir(dir.path().join("broken"))
+        .generate()
+        .unwrap_err();
+    assert!(error.to_string().starts_with("generated proto files are invalid"));
+    assert!(!dir.path().join("broken").exists());
+}
+
+#[test]
+fn test_outputs_next_to_the_proto_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let descriptor_set = dir.path().join("descriptors.bin");
+    let builder = Builder::bundled(SpecVersion::V0_8_1)
+        .with_descriptor_set(&descriptor_set)
+        .with_output_dir(dir.path());
+    let backend = BridgeBackend::new(builder.config());
+    let generated = builder.with_output(backend).generate().unwrap();
+
+    assert_eq!(generated.files.len(), PROTO_FILES.len() + 3);
+    assert!(generated.files.contains(&generated.dir.join("bridge.rs")));
+    assert!(generated.dir.join(BRIDGE_SPEC_FILE).exists());
+    assert!(descriptor_set.exists());
+
+    // Checking writes nothing, and forcing writes broken definitions with warnings
+    let break_imports = |_: &_, ir: &mut ProtoIr| {
+        ir.files[0].imports.clear();
+        ir.files[1].imports.clear();
+        Ok(())
+    };
+    let checked = Builder::bundled(SpecVersion::V0_8_1)
+        .with_check(true)
+        .with_output_dir(dir.path().join("checked"))
+        .generate()
+        .unwrap();
+    assert!(checked.files.is_empty());
+    assert!(!dir.path().join("checked").exists());
+    let forced = Builder::bundled(SpecVersion::V0_8_1)
+        .with_pass(break_imports)
+        .with_force(true)
+        .with_output_dir(dir.path().join("forced"))
+        .generate()
+        .unwrap();
+    assert_eq!(forced.files.len(), PROTO_FILES.len());
+    assert!(!forced.warnings.is_empty());
+}
+
+#[test]
+fn test_documents_from_a_directory() {
+    let specs = tempfile::tempdir().unwrap();
+    let bundled = concat!(env!("CARGO_MANIFEST_DIR"), "/src/specs/0.7.1");
+    for entry in fs::read_dir(bundled).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, specs.path().join(path.file_name().unwrap())).unwrap();
+    }
+    let main = specs.path().join(MAIN_DOCUMENT);
+    let patched = fs::read_to_string(&main)
+        .unwrap()
+        .replace("\"name\": \"starknet_specVersion\"", "\"name\": \"starknet_apiVersion\"");
+    fs::write(&main, patched).unwrap();
+
+    let output = tempfile::tempdir().unwrap();
+    let builder = Builder::from_dir(SpecVersion::V0_7_1, specs.path())
+        .unwrap()
+        .with_output_dir(output.path());
+    let backend = RustSerdeBackend::new(builder.config(), builder.options().unwrap());
+    let generated = builder.with_backend(backend).generate().unwrap();
+
+    assert!(generated.files.contains(&output.path().join("v0_7_1/mod.rs")));
+    assert!(!output.path().join("v0_7_1/buf.yaml").exists());
+    let main = fs::read_to_string(output.path().join("v0_7_1/main.rs")).unwrap();
+    assert!(main.contains("ApiVersion"));
+
+    let missing = Builder::from_dir(SpecVersion::V0_7_1, output.path()).err().unwrap();
+    assert!(missing.to_string().starts_with("failed to read"));
+}
@@ -0,0 +1,162 @@
+//! Generates the conversions from the 0.7.1 messages into the 0.8.1 ones, builds them in a crate
+//! compiling the proto files of both versions with prost-build and runs a few of them.
+
+use std::{env, fs, path::Path, process::Command};
+
+const PROTO_FILES: &[&str] =
+    &["common.proto", "main.proto", "write.proto", "trace.proto", "ws.proto"];
+
+const PACKAGES: &[&str] = &["common", "main", "write", "trace", "ws"];
+
+/// Conversions the fixture runs
+const CHECKS: &str = r#"
+#[cfg(test)]
+mod tests {
+    use super::starknet::{v0_7_1, v0_7_1_to_v0_8_1::ConversionError, v0_8_1};
+
+    #[test]
+    fn test_conversions() {
+        let request = v0_7_1::main::GetBlockWithTxHashesRequest {
+            block_id: Some(Default::default()),
+        };
+        let converted = v0_8_1::main::GetBlockWithTxHashesRequest::from(request);
+        assert!(converted.block_id.is_some());
+
+        let error = v0_8_1::common::BlockHeader::try_from(v0_7_1::common::BlockHeader::default())
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ConversionError::MissingField {
+                message: "starknet.v0_8_1.common.BlockHeader",
+                field: "l2_gas_price",
+            }
+        );
+        assert!(v0_8_1::common::DeclareTxn::try_from(v0_7_1::common::DeclareTxn::default()).is_ok());
+    }
+}
+"#;
+
+fn check_converters(shared_types: bool) {
+    let dir = tempfile::tempdir().unwrap();
+    let proto = dir.path().join("proto");
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.7.1..", "--converters"])
+        .args(shared_types.then_some("--shared-types"))
+        .arg("--output-dir")
+        .arg(&proto)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(proto.join("convert/v0_7_1_to_v0_8_1.rs").exists());
+
+    let fixture = dir.path().join("fixture");
+    write_fixture(&fixture, &proto, shared_types);
+    // Shares the target directory of the bridge fixture, which has the same dependencies
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bridge-fixture");
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(fixture.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "conversions failed to build or run:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_generated_converters_build() {
+    check_converters(false);
+}
+
+#[test]
+fn test_generated_converters_build_with_shared_types() {
+    check_converters(true);
+
+    // Options other than the proto files of several versions don't get conversions
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--converters", "--output-dir"])
+        .arg(tempfile::tempdir().unwrap().path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    // Versions that aren't written get no conversions, which is reported
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.7.1..", "--converters", "--check", "--output-dir"])
+        .arg(tempfile::tempdir().unwrap().path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("no conversions were generated because v0_7_1, v0_8_1 were not written"));
+}
+
+/// Crate compiling each version, and the shared types if any, with the conversions as a module
+/// next to them
+fn write_fixture(fixture: &Path, proto: &Path, shared_types: bool) {
+    fs::create_dir_all(fixture.join("src")).unwrap();
+    fs::write(
+        fixture.join("Cargo.toml"),
+        "[package]\n\
+         name = \"converters-fixture\"\n\
+         version = \"0.1.0\"\n\
+         edition = \"2021\"\n\
+         \n\
+         [dependencies]\n\
+         prost = \"0.14.1\"\n\
+         tonic = \"0.14.2\"\n\
+         tonic-prost = \"0.14.2\"\n\
+         \n\
+         [build-dependencies]\n\
+         protox = \"0.9.1\"\n\
+         tonic-prost-build = \"0.14.2\"\n",
+    )
+    .unwrap();
+
+    let mut build = String::from("fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    for version in ["v0_7_1", "v0_8_1"] {
+        let version_dir = proto.join(version);
+        let files = PROTO_FILES
+            .iter()
+            .map(|file| format!("{:?}", version_dir.join(file)))
+            .collect::<Vec<_>>();
+        let mut includes = vec![format!("{:?}", version_dir)];
+        if shared_types {
+            includes.push(format!("{:?}", proto.join("types")));
+        }
+        build.push_str(&format!(
+            "    let fds = protox::compile([{}], [{}])?;\n",
+            files.join(", "),
+            includes.join(", ")
+        ));
+        build.push_str("    tonic_prost_build::configure().compile_fds(fds)?;\n");
+    }
+    build.push_str("    Ok(())\n}\n");
+    fs::write(fixture.join("build.rs"), build).unwrap();
+
+    let mut lib = String::from("#![allow(clippy::all)]\n\npub mod starknet {\n");
+    if shared_types {
+        lib.push_str("    pub mod types {\n        tonic::include_proto!(\"starknet.types\");\n    }\n");
+    }
+    for version in ["v0_7_1", "v0_8_1"] {
+        lib.push_str(&format!("    pub mod {} {{\n", version));
+        for package in PACKAGES {
+            lib.push_str(&format!(
+                "        pub mod {} {{\n            tonic::include_proto!(\"starknet.{}.{}\");\n        }}\n",
+                package, version, package
+            ));
+        }
+        lib.push_str("    }\n");
+    }
+    lib.push_str(&format!(
+        "    #[path = {:?}]\n    pub mod v0_7_1_to_v0_8_1;\n}}\n",
+        proto.join("convert/v0_7_1_to_v0_8_1.rs")
+    ));
+    lib.push_str(CHECKS);
+    fs::write(fixture.join("src/lib.rs"), lib).unwrap();
+}
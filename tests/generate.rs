@@ -0,0 +1,292 @@
+//! Runs `generate` for every bundled spec version, compiles the output and compares it and its
+//! proto3 JSON differences with the goldens in `tests/golden`. Set `UPDATE_GOLDENS=1` to rewrite
+//! them after an intended change.
+
+use std::{fs, path::Path, process::Command};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+
+const PROTO_FILES: &[&str] =
+    &["common.proto", "main.proto", "write.proto", "trace.proto", "ws.proto"];
+
+fn check_spec_version(spec: &str) {
+    let version = format!("v{}", spec.replace('.', "_"));
+    let dir = tempfile::tempdir().unwrap();
+    let descriptor_set = dir.path().join("descriptor_set.binpb");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .arg("generate")
+        .args(["--spec", spec])
+        .arg("--output-dir")
+        .arg(dir.path())
+        .arg("--descriptor-set")
+        .arg(&descriptor_set)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generate --spec {} failed:\n{}",
+        spec,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let proto_dir = dir.path().join(&version);
+
+    // The output has to compile without protoc
+    let mut compiler = protox::Compiler::new([&proto_dir]).unwrap();
+    compiler.include_imports(true);
+    if let Err(error) = compiler.open_files(PROTO_FILES) {
+        panic!("generated files of {} don't compile: {:?}", spec, error);
+    }
+
+    // And the descriptor set written along with it has to describe the same files
+    let compiled = compiler.file_descriptor_set();
+    let written = FileDescriptorSet::decode(&*fs::read(&descriptor_set).unwrap()).unwrap();
+    let without_source_info = |set: FileDescriptorSet| {
+        set.file
+            .into_iter()
+            .map(|file| FileDescriptorProto {
+                source_code_info: None,
+                ..file
+            })
+            .collect::<Vec<_>>()
+    };
+    pretty_assertions::assert_eq!(without_source_info(written), without_source_info(compiled));
+
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(&version);
+    for file in PROTO_FILES {
+        let generated = normalize(&fs::read_to_string(proto_dir.join(file)).unwrap());
+        compare_with_golden(&golden_dir.join(file), generated);
+    }
+
+    // New differences from the JSON of the spec show up as changes to the report
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .arg("check-json")
+        .args(["--spec", spec])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "check-json --spec {} failed", spec);
+    let report = String::from_utf8(output.stdout).unwrap();
+    compare_with_golden(&golden_dir.join("json-shape.md"), report);
+
+    check_examples(spec, &fs::read(&descriptor_set).unwrap());
+}
+
+/// Parses the proto3 JSON of every example with the messages of its rpc and checks it's what a
+/// protobuf library writes back
+fn check_examples(spec: &str, descriptor_set: &[u8]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .arg("examples")
+        .args(["--spec", spec])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "examples --spec {} failed", spec);
+    let examples: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&output.stdout).unwrap();
+
+    let pool = DescriptorPool::decode(descriptor_set).unwrap();
+    for (method, method_examples) in examples {
+        let rpc = method_examples["rpc"].as_str().unwrap();
+        let (service, rpc) = rpc.trim_start_matches('/').split_once('/').unwrap();
+        let rpc = pool
+            .get_service_by_name(service)
+            .and_then(|service| service.methods().find(|method| method.name() == rpc))
+            .unwrap_or_else(|| panic!("{} has no rpc {}", method, rpc));
+
+        for example in method_examples["examples"].as_array().unwrap() {
+            for (key, message) in [("request", rpc.input()), ("response", rpc.output())] {
+                let Some(json) = example.get(key) else {
+                    continue;
+                };
+                let parsed = DynamicMessage::deserialize(message, json)
+                    .unwrap_or_else(|err| panic!("{} of {}: {}", key, method, err));
+                let written = serde_json::to_value(&parsed).unwrap();
+                assert_eq!(&written, json, "{} {} of {}", example["name"], key, method);
+            }
+        }
+    }
+}
+
+fn compare_with_golden(golden_path: &Path, generated: String) {
+    if std::env::var_os("UPDATE_GOLDENS").is_some() {
+        fs::create_dir_all(golden_path.parent().unwrap()).unwrap();
+        fs::write(golden_path, generated).unwrap();
+        return;
+    }
+
+    let golden = fs::read_to_string(golden_path)
+        .unwrap_or_else(|_| panic!("missing golden {}", golden_path.display()));
+    if generated != golden {
+        pretty_assertions::assert_eq!(
+            generated,
+            golden,
+            "{} differs from its golden, run with UPDATE_GOLDENS=1 if the change is intended",
+            golden_path.display()
+        );
+    }
+}
+
+/// Drops the header line naming the commit the tool was built from
+fn normalize(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.starts_with("// Generated with commit:"))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[test]
+fn test_generate_0_1_0() {
+    check_spec_version("0.1.0");
+}
+
+#[test]
+fn test_generate_0_2_1() {
+    check_spec_version("0.2.1");
+}
+
+#[test]
+fn test_generate_0_3_0() {
+    check_spec_version("0.3.0");
+}
+
+#[test]
+fn test_generate_0_4_0() {
+    check_spec_version("0.4.0");
+}
+
+#[test]
+fn test_generate_0_5_1() {
+    check_spec_version("0.5.1");
+}
+
+#[test]
+fn test_generate_0_6_0() {
+    check_spec_version("0.6.0");
+}
+
+#[test]
+fn test_generate_0_7_1() {
+    check_spec_version("0.7.1");
+}
+
+#[test]
+fn test_generate_0_8_1() {
+    check_spec_version("0.8.1");
+}
+
+#[test]
+fn test_generate_subset() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--output-dir"])
+        .arg(dir.path())
+        .args(["--include-methods", "starknet_getBlockWithReceipts"])
+        .args(["--include-methods", "/^starknet_(getEvents|call)$/"])
+        .args(["--include-services", "main"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Only the files something is left in are written, and they still compile
+    let proto_dir = dir.path().join("v0_8_1");
+    assert!(!proto_dir.join("write.proto").exists());
+    let mut compiler = protox::Compiler::new([&proto_dir]).unwrap();
+    compiler.include_imports(true);
+    compiler.open_files(["common.proto", "main.proto"]).unwrap();
+
+    let main = fs::read_to_string(proto_dir.join("main.proto")).unwrap();
+    let rpcs = main.lines().filter(|line| line.trim_start().starts_with("rpc ")).count();
+    assert_eq!(rpcs, 3);
+    let common = fs::read_to_string(proto_dir.join("common.proto")).unwrap();
+    assert!(common.contains("message EmittedEvent {"));
+    assert!(!common.contains("message DeclareTxnV3 {"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--output-dir"])
+        .arg(dir.path())
+        .args(["--include-services", "admin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown service admin"));
+}
+
+#[test]
+fn test_generate_version_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.7.1..", "--output-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let workspace = fs::read_to_string(dir.path().join("buf.work.yaml")).unwrap();
+    assert_eq!(workspace, "version: v1\ndirectories:\n  - v0_7_1\n  - v0_8_1\n");
+    let readme = fs::read_to_string(dir.path().join("README.md")).unwrap();
+    assert!(readme.contains("| 0.7.1 | [v0_7_1](v0_7_1/README.md) |"));
+    assert!(readme.contains("| 0.8.1 | [v0_8_1](v0_8_1/README.md) |"));
+    for version in ["v0_7_1", "v0_8_1"] {
+        let proto_dir = dir.path().join(version);
+        assert!(proto_dir.join("buf.yaml").exists());
+        let mut compiler = protox::Compiler::new([&proto_dir]).unwrap();
+        compiler.include_imports(true);
+        compiler.open_files(PROTO_FILES).unwrap();
+    }
+
+    // A single descriptor set can't hold several versions
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "all", "--output-dir"])
+        .arg(dir.path())
+        .args(["--descriptor-set", "all.binpb"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--descriptor-set only applies to a single --spec version"));
+}
+
+#[test]
+fn test_generate_shared_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.6.0..", "--shared-types", "--output-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let workspace = fs::read_to_string(dir.path().join("buf.work.yaml")).unwrap();
+    assert!(workspace.starts_with("version: v1\ndirectories:\n  - types\n  - v0_6_0\n"));
+    let shared = dir.path().join("types");
+    assert!(shared.join("buf.yaml").exists());
+    let types = fs::read_to_string(shared.join("types.proto")).unwrap();
+    assert!(types.contains("package starknet.types;"));
+    assert!(types.contains("message BlockId {"));
+
+    for version in ["v0_6_0", "v0_7_1", "v0_8_1"] {
+        let proto_dir = dir.path().join(version);
+        let common = fs::read_to_string(proto_dir.join("common.proto")).unwrap();
+        assert!(common.contains("import \"types.proto\";"));
+        assert!(!common.contains("message BlockId {"));
+        // Files of a version resolve the shared types from the other module of the workspace
+        let mut compiler = protox::Compiler::new([&proto_dir, &shared]).unwrap();
+        compiler.include_imports(true);
+        compiler.open_files(PROTO_FILES).unwrap();
+        let pool = compiler.descriptor_pool();
+        assert!(pool.get_message_by_name("starknet.types.BlockId").is_some());
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--shared-types", "--output-dir"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--shared-types needs a range of --spec versions or all"));
+}
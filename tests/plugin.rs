@@ -0,0 +1,108 @@
+//! Runs `generate --target plugin` with a shell script as plugin, checking it reads the
+//! definitions `dump-ir` prints and that the files it answers with are written.
+
+#![cfg(unix)]
+
+use std::{fs, os::unix::fs::PermissionsExt, path::Path, process::Command};
+
+use serde_json::Value;
+
+/// Plugin saving its request next to itself and answering with `response`
+fn write_plugin(dir: &Path, response: &str) -> std::path::PathBuf {
+    let plugin = dir.join("plugin.sh");
+    let script = format!(
+        "#!/bin/sh\ncat > \"{}\"\nprintf '%s' '{}'\n",
+        dir.join("request").display(),
+        response
+    );
+    fs::write(&plugin, script).unwrap();
+    fs::set_permissions(&plugin, fs::Permissions::from_mode(0o755)).unwrap();
+    plugin
+}
+
+fn codegen(args: &[&str], dir: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_plugin_generates_files_from_the_ir() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugin = write_plugin(
+        dir.path(),
+        r#"{"files":[{"name":"kotlin/Types.kt","content":"data class BlockId()"}]}"#,
+    );
+
+    let output = codegen(
+        &[
+            "generate",
+            "--spec",
+            "0.8.1",
+            "--target",
+            "plugin",
+            "--plugin",
+            plugin.to_str().unwrap(),
+            "--plugin-parameter",
+            "package=com.example",
+            "--output-dir",
+            "out",
+        ],
+        dir.path(),
+    );
+    assert!(
+        output.status.success(),
+        "generate --target plugin failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written = fs::read_to_string(dir.path().join("out/v0_8_1/kotlin/Types.kt")).unwrap();
+    assert_eq!(written, "data class BlockId()");
+
+    // The plugin reads what dump-ir prints
+    let request: Value = serde_json::from_slice(&fs::read(dir.path().join("request")).unwrap()).unwrap();
+    assert_eq!(request["parameter"], "package=com.example");
+    assert_eq!(request["ir"]["spec_version"], "0.8.1");
+    assert_eq!(request["ir"]["schemas"]["starknet.v0_8_1.common.BlockId"], "BLOCK_ID");
+    assert_eq!(
+        request["ir"]["methods"]["/starknet.v0_8_1.main.StarknetMainService/GetBlockWithTxHashes"],
+        "starknet_getBlockWithTxHashes"
+    );
+    let output = codegen(
+        &["dump-ir", "--spec", "0.8.1", "--parameter", "package=com.example"],
+        dir.path(),
+    );
+    assert!(output.status.success());
+    let dumped: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(dumped, request);
+}
+
+#[test]
+fn test_plugin_errors_fail_generation() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugin = write_plugin(dir.path(), r#"{"error":"oneOf without discriminator"}"#);
+    let output = codegen(
+        &["generate", "--spec", "0.8.1", "--target", "plugin", "--plugin", plugin.to_str().unwrap()],
+        dir.path(),
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("oneOf without discriminator"));
+
+    // Like protoc, names outside the output directory are refused
+    let absolute = dir.path().join("absolute");
+    for name in ["../escaped", absolute.to_str().unwrap()] {
+        let response = format!(r#"{{"files":[{{"name":"{}","content":""}}]}}"#, name);
+        let plugin = write_plugin(dir.path(), &response);
+        let plugin = plugin.to_str().unwrap();
+        let output = codegen(
+            &["generate", "--spec", "0.8.1", "--target", "plugin", "--plugin", plugin],
+            dir.path(),
+        );
+        assert_eq!(output.status.code(), Some(4));
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains("which isn't a relative path inside the output directory"));
+    }
+    assert!(!dir.path().join("proto/escaped").exists());
+    assert!(!absolute.exists());
+}
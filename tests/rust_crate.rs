@@ -0,0 +1,128 @@
+//! Generates the Rust crate of the 0.8.1 spec and builds it, with every feature and with none,
+//! to check the protos compile without `protoc`, then checks its `serde` impls read and write
+//! back the examples `examples` produces for the spec.
+
+use std::{env, fmt::Write, fs, path::Path, process::Command};
+
+use serde_json::Value;
+
+/// Crate name of the generated crate, as written in Rust paths
+const CRATE: &str = "starknet_grpc_v0_8_1";
+
+fn generate_crate(dir: &Path) {
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["generate", "--spec", "0.8.1", "--rust-crate"])
+        .arg("--output-dir")
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generate --rust-crate failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn cargo(manifest: &Path, command: &str, features: &[&str]) -> std::process::Output {
+    // Shares the target directory of the bridge fixture, which has the same dependencies
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bridge-fixture");
+    Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .arg(command)
+        .arg("--manifest-path")
+        .arg(manifest)
+        .args(features)
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_generated_rust_crate_builds() {
+    let dir = tempfile::tempdir().unwrap();
+    generate_crate(dir.path());
+
+    let manifest = dir.path().join("v0_8_1/rust/Cargo.toml");
+    for features in [&["--all-features"][..], &["--no-default-features"]] {
+        let output = cargo(&manifest, "build", features);
+        assert!(
+            output.status.success(),
+            "generated crate failed to build with {:?}:\n{}",
+            features,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn test_generated_serde_impls_roundtrip_spec_examples() {
+    let dir = tempfile::tempdir().unwrap();
+    generate_crate(dir.path());
+    let examples_dir = dir.path().join("examples");
+    let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+        .args(["examples", "--spec", "0.8.1"])
+        .arg("--output-dir")
+        .arg(&examples_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "examples failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // One check per example with a proto form: its params as the request message and its
+    // result as the response message
+    let mut checks = String::new();
+    let mut count = 0;
+    let mut paths = fs::read_dir(examples_dir.join("v0_8_1"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        let method_examples: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let Some(rpc) = method_examples["rpc"].as_str() else {
+            continue;
+        };
+        // e.g. /starknet.v0_8_1.main.StarknetMainService/GetBlockWithTxHashes
+        let (service, rpc) = rpc.trim_start_matches('/').split_once('/').unwrap();
+        let package = service.rsplit_once('.').unwrap().0.replace('.', "::");
+        for example in method_examples["examples"].as_array().unwrap() {
+            if example.get("proto_errors").is_some() {
+                continue;
+            }
+            let name = format!("{} {}", rpc, example["name"].as_str().unwrap());
+            let mut check = |message: &str, json: Value| {
+                let _ = writeln!(
+                    checks,
+                    "    check::<{}::{}::{}{}>(&mut failures, {:?}, r#\"{}\"#);",
+                    CRATE, package, rpc, message, name, json
+                );
+                count += 1;
+            };
+            check("Request", example["params"].clone());
+            if let Some(result) = example.get("result") {
+                check("Response", serde_json::json!({ "result": result }));
+            }
+        }
+    }
+    assert!(count > 0, "no example has a proto form");
+
+    let crate_dir = dir.path().join("v0_8_1/rust");
+    fs::create_dir_all(crate_dir.join("tests")).unwrap();
+    let test = format!(
+        "{}\n#[test]\nfn spec_examples_roundtrip() {{\n    let mut failures = vec![];\n{}    \
+         assert!(failures.is_empty(), \"{{}}\", failures.join(\"\\n\"));\n}}\n",
+        include_str!("rust_crate/roundtrip.rs"),
+        checks
+    );
+    fs::write(crate_dir.join("tests/spec_json.rs"), test).unwrap();
+
+    let output = cargo(&crate_dir.join("Cargo.toml"), "test", &["--all-features"]);
+    assert!(
+        output.status.success(),
+        "generated serde impls failed on spec examples:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
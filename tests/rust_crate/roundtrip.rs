@@ -0,0 +1,19 @@
+// Checks the generated messages read the JSON of the spec and write it back unchanged. Written
+// into the generated crate by tests/rust_crate.rs, followed by a test calling `check` for each
+// example.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+fn check<T: Serialize + DeserializeOwned>(failures: &mut Vec<String>, name: &str, json: &str) {
+    let json: Value = serde_json::from_str(json).unwrap();
+    let message = match serde_json::from_value::<T>(json.clone()) {
+        Ok(message) => message,
+        Err(err) => return failures.push(format!("{}: failed to read {}: {}", name, json, err)),
+    };
+    match serde_json::to_value(&message) {
+        Ok(written) if written == json => {}
+        Ok(written) => failures.push(format!("{}: read {} but wrote {}", name, json, written)),
+        Err(err) => failures.push(format!("{}: failed to write: {}", name, err)),
+    }
+}
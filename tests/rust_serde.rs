@@ -0,0 +1,59 @@
+//! Generates the Rust serde types of every spec version into one crate and checks they build
+//! without warnings, then checks the types of the 0.8.1 spec read and write the JSON of nodes.
+
+use std::{env, fs, path::Path, process::Command};
+
+const VERSIONS: [&str; 8] = ["0.1.0", "0.2.1", "0.3.0", "0.4.0", "0.5.1", "0.6.0", "0.7.1", "0.8.1"];
+
+const MANIFEST: &str = r#"[package]
+name = "starknet-serde-types"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = { version = "1", features = ["derive", "rc"] }
+serde_json = "1"
+"#;
+
+#[test]
+fn test_generated_rust_serde_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut lib = String::new();
+    for version in VERSIONS {
+        let output = Command::new(env!("CARGO_BIN_EXE_starknet-grpc-codegen"))
+            .args(["generate", "--spec", version, "--target", "rust-serde"])
+            .arg("--output-dir")
+            .arg(dir.path().join("src"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "generate --target rust-serde failed for {}:\n{}",
+            version,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        lib.push_str(&format!("pub mod v{};\n", version.replace('.', "_")));
+    }
+    fs::write(dir.path().join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+    fs::create_dir_all(dir.path().join("tests")).unwrap();
+    fs::write(dir.path().join("tests/types.rs"), include_str!("rust_serde/types.rs")).unwrap();
+
+    // Shares the target directory of the bridge fixture, which has the same dependencies
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bridge-fixture");
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(dir.path().join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated serde types failed:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("warning"), "generated serde types have warnings:\n{}", stderr);
+}
@@ -0,0 +1,123 @@
+// Checks the generated types of the 0.8.1 spec read the JSON nodes write and write it back
+// unchanged, with the union variants and fixed values the profile of the version sets. Written
+// into the crate generated by tests/rust_serde.rs.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use starknet_serde_types::v0_8_1::common::*;
+
+fn roundtrip<T: Serialize + DeserializeOwned>(json: Value) -> T {
+    let read: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&read).unwrap(), json);
+    read
+}
+
+fn invoke_v3(version: &str) -> Value {
+    let bounds = json!({ "max_amount": "0x10", "max_price_per_unit": "0x20" });
+    json!({
+        "type": "INVOKE",
+        "version": version,
+        "sender_address": "0x1",
+        "calldata": ["0x2", "0x3"],
+        "signature": ["0x4"],
+        "nonce": "0x5",
+        "resource_bounds": { "l1_gas": bounds, "l1_data_gas": bounds, "l2_gas": bounds },
+        "tip": "0x0",
+        "paymaster_data": [],
+        "account_deployment_data": [],
+        "nonce_data_availability_mode": "L1",
+        "fee_data_availability_mode": "L2",
+    })
+}
+
+#[test]
+fn block_ids() {
+    assert!(matches!(roundtrip(json!("pending")), BlockId::BlockTag(BlockTag::Pending)));
+    assert!(matches!(
+        roundtrip(json!({ "block_number": 7 })),
+        BlockId::BlockNumberWrapper(BlockNumberWrapper { block_number: 7 })
+    ));
+    assert!(matches!(roundtrip(json!({ "block_hash": "0x7" })), BlockId::BlockHashWrapper(_)));
+}
+
+#[test]
+fn transactions_are_read_as_their_version() {
+    let mut json = invoke_v3("0x3");
+    json["transaction_hash"] = json!("0xabc");
+    let Txn::InvokeTxn(InvokeTxn::InvokeTxnV3(txn)) = roundtrip(json) else {
+        panic!("not an invoke v3");
+    };
+    assert_eq!(txn.transaction_hash, "0xabc");
+    assert_eq!(txn.invoke_txn_v3_content.resource_bounds.l2_gas.max_amount, 16);
+
+    let declare = json!({
+        "type": "DECLARE",
+        "version": "0x0",
+        "transaction_hash": "0x1",
+        "sender_address": "0x2",
+        "max_fee": "0x3",
+        "signature": ["0x4"],
+        "class_hash": "0x5",
+    });
+    assert!(matches!(roundtrip(declare.clone()), Txn::DeclareTxn(DeclareTxn::DeclareTxnV0(_))));
+    let mut declare_v1 = declare;
+    declare_v1["version"] = json!("0x1");
+    declare_v1["nonce"] = json!("0x6");
+    assert!(matches!(roundtrip(declare_v1.clone()), Txn::DeclareTxn(DeclareTxn::DeclareTxnV1(_))));
+    // The profile fixes the version of each variant
+    declare_v1["version"] = json!("0x2");
+    assert!(serde_json::from_value::<DeclareTxnV1>(declare_v1).is_err());
+}
+
+#[test]
+fn broadcasted_transactions_have_query_versions() {
+    let mut declare = invoke_v3("0x3");
+    let declare = declare.as_object_mut().unwrap();
+    declare.remove("calldata");
+    declare.insert("type".to_owned(), json!("DECLARE"));
+    declare.insert("compiled_class_hash".to_owned(), json!("0x6"));
+    declare.insert(
+        "contract_class".to_owned(),
+        json!({
+            "sierra_program": ["0x7"],
+            "contract_class_version": "0.1.0",
+            "entry_points_by_type": { "CONSTRUCTOR": [], "EXTERNAL": [], "L1_HANDLER": [] },
+            "abi": "[]",
+        }),
+    );
+    let mut declare = Value::Object(declare.clone());
+    let BroadcastedTxn::BroadcastedDeclareTxnV3(txn) = roundtrip(declare.clone()) else {
+        panic!("not a declare v3");
+    };
+    assert!(!txn.is_query);
+    assert_eq!(txn.contract_class.sierra_program, ["0x7"]);
+
+    declare["version"] = json!("0x100000000000000000000000000000003");
+    let BroadcastedTxn::BroadcastedDeclareTxnV3(txn) = roundtrip(declare.clone()) else {
+        panic!("not a declare v3");
+    };
+    assert!(txn.is_query);
+    declare["version"] = json!("0x1");
+    assert!(serde_json::from_value::<BroadcastedDeclareTxnV3>(declare).is_err());
+}
+
+#[test]
+fn receipts_with_block_info() {
+    let receipt = json!({
+        "type": "L1_HANDLER",
+        "transaction_hash": "0x1",
+        "message_hash": "0x2",
+        "actual_fee": { "amount": "0x3", "unit": "WEI" },
+        "finality_status": "ACCEPTED_ON_L1",
+        "execution_status": "REVERTED",
+        "revert_reason": "out of gas",
+        "messages_sent": [],
+        "events": [{ "from_address": "0x4", "keys": ["0x5"], "data": [] }],
+        "execution_resources": { "l1_gas": 1, "l1_data_gas": 2, "l2_gas": 3 },
+        "block_hash": "0x6",
+        "block_number": 8,
+    });
+    let receipt: TxnReceiptWithBlockInfo = roundtrip(receipt);
+    assert!(matches!(receipt.txn_receipt, TxnReceipt::L1HandlerTxnReceipt(_)));
+    assert_eq!(receipt.receipt_block.block_number, Some(8));
+}